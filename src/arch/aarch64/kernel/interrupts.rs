@@ -16,8 +16,9 @@ use crate::arch::aarch64::kernel::core_local::increment_irq_counter;
 use crate::arch::aarch64::kernel::scheduler::State;
 use crate::arch::aarch64::mm::paging::{self, BasePageSize, PageSize, PageTableEntryFlags};
 use crate::arch::aarch64::mm::{virtualmem, PhysAddr};
-use crate::core_scheduler;
+use crate::mm::mmap::Access;
 use crate::scheduler::{self, CoreId};
+use crate::{core_scheduler, mm};
 
 /// maximum number of interrupt handlers
 const MAX_HANDLERS: usize = 256;
@@ -189,7 +190,20 @@ pub(crate) extern "C" fn do_sync(state: &State) {
 			/* read far_el1 register, which holds the faulting virtual address */
 			let far = FAR_EL1.get();
 
-			// add page fault handler
+			/* translation or permission fault => first access to a page of a
+			 * task stack or a memory mapping or write to a page, which is
+			 * copied on write */
+			if matches!(iss & 0x3f, 0x04..=0x07 | 0x0c..=0x0f) {
+				let access = if (iss & (1 << 6)) != 0 {
+					Access::Write
+				} else {
+					Access::Read
+				};
+				if mm::handle_page_fault(far.try_into().unwrap(), access) {
+					GicV3::end_interrupt(irqid);
+					return;
+				}
+			}
 
 			error!("Current stack pointer {state:p}");
 			error!("Unable to handle page fault at {:#x}", far);
@@ -203,6 +217,23 @@ pub(crate) extern "C" fn do_sync(state: &State) {
 		} else {
 			error!("Unknown exception");
 		}
+	} else if (ec == 0b100000) || (ec == 0b100001) {
+		/* instruction abort from lower or current level */
+		let far = FAR_EL1.get();
+
+		if (0x04..=0x07).contains(&(iss & 0x3f))
+			&& mm::handle_page_fault(far.try_into().unwrap(), Access::Execute)
+		{
+			GicV3::end_interrupt(irqid);
+			return;
+		}
+
+		error!("Unable to handle instruction abort at {:#x}", far);
+		error!("Exception return address {:#x}", ELR_EL1.get());
+		error!("Exception Syndrome Register {:#x}", esr);
+
+		GicV3::end_interrupt(irqid);
+		scheduler::abort()
 	} else if ec == 0x3c {
 		error!("Trap to debugger, PC={:#x}", pc);
 	} else {
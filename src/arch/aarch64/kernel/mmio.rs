@@ -1,7 +1,15 @@
 use hermit_sync::InterruptTicketMutex;
 
+#[cfg(feature = "blk")]
+use crate::drivers::block::virtio_blk::VirtioBlkDriver;
 use crate::drivers::net::virtio_net::VirtioNetDriver;
 
-pub(crate) fn get_network_driver() -> Option<&'static InterruptTicketMutex<VirtioNetDriver>> {
+pub(crate) fn get_network_drivers(
+) -> impl Iterator<Item = &'static InterruptTicketMutex<VirtioNetDriver>> {
+	core::iter::empty()
+}
+
+#[cfg(feature = "blk")]
+pub(crate) fn get_block_driver() -> Option<&'static InterruptTicketMutex<VirtioBlkDriver>> {
 	None
 }
@@ -165,7 +165,7 @@ pub fn supports_1gib_pages() -> bool {
 
 #[inline]
 pub fn supports_2mib_pages() -> bool {
-	false
+	true
 }
 
 pub fn configure() {
@@ -127,7 +127,7 @@ impl TaskStacks {
 		let virt_addr =
 			crate::arch::mm::virtualmem::allocate(total_size + 3 * BasePageSize::SIZE as usize)
 				.expect("Failed to allocate Virtual Memory for TaskStacks");
-		let phys_addr = crate::arch::mm::physicalmem::allocate(total_size)
+		let phys_addr = crate::arch::mm::physicalmem::allocate(DEFAULT_STACK_SIZE)
 			.expect("Failed to allocate Physical Memory for TaskStacks");
 
 		debug!(
@@ -147,24 +147,12 @@ impl TaskStacks {
 			flags,
 		);
 
-		// map user stack into the address space
-		crate::arch::mm::paging::map::<BasePageSize>(
-			virt_addr + DEFAULT_STACK_SIZE + 2 * BasePageSize::SIZE,
-			phys_addr + DEFAULT_STACK_SIZE,
-			user_stack_size / BasePageSize::SIZE as usize,
-			flags,
+		// reserve user stack, which is mapped on demand
+		crate::mm::stack::reserve(
+			virt_addr + DEFAULT_STACK_SIZE + BasePageSize::SIZE,
+			user_stack_size,
 		);
 
-		// clear user stack
-		unsafe {
-			ptr::write_bytes(
-				(virt_addr + DEFAULT_STACK_SIZE + 2 * BasePageSize::SIZE as usize)
-					.as_mut_ptr::<u8>(),
-				0xAC,
-				user_stack_size,
-			);
-		}
-
 		TaskStacks::Common(CommonStack {
 			virt_addr,
 			phys_addr,
@@ -222,15 +210,19 @@ impl Drop for TaskStacks {
 					stacks.total_size >> 10,
 				);
 
+				crate::mm::stack::release(
+					stacks.virt_addr + DEFAULT_STACK_SIZE + BasePageSize::SIZE,
+					stacks.total_size - DEFAULT_STACK_SIZE,
+				);
 				crate::arch::mm::paging::unmap::<BasePageSize>(
 					stacks.virt_addr,
-					stacks.total_size / BasePageSize::SIZE as usize + 3,
+					DEFAULT_STACK_SIZE / BasePageSize::SIZE as usize + 1,
 				);
 				crate::arch::mm::virtualmem::deallocate(
 					stacks.virt_addr,
 					stacks.total_size + 3 * BasePageSize::SIZE as usize,
 				);
-				crate::arch::mm::physicalmem::deallocate(stacks.phys_addr, stacks.total_size);
+				crate::arch::mm::physicalmem::deallocate(stacks.phys_addr, DEFAULT_STACK_SIZE);
 			}
 		}
 	}
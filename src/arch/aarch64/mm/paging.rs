@@ -141,6 +141,11 @@ impl PageTableEntry {
 		(self.physical_address_and_flags & PageTableEntryFlags::PRESENT.bits()) != 0
 	}
 
+	/// Returns whether this entry points to a table or a 4 KiB page instead of a block.
+	fn is_table(&self) -> bool {
+		(self.physical_address_and_flags & PageTableEntryFlags::TABLE_OR_4KIB_PAGE.bits()) != 0
+	}
+
 	/// Mark this as a valid (present) entry and set address translation and flags.
 	///
 	/// # Arguments
@@ -450,9 +455,16 @@ where
 		let index = page.table_index::<L>();
 
 		if self.entries[index].is_present() {
+			// a block or a table maps pages of another size than S
 			if L::LEVEL < S::MAP_LEVEL {
-				let subtable = self.subtable::<S>(page);
-				subtable.get_page_table_entry::<S>(page)
+				if self.entries[index].is_table() {
+					let subtable = self.subtable::<S>(page);
+					subtable.get_page_table_entry::<S>(page)
+				} else {
+					None
+				}
+			} else if self.entries[index].is_table() {
+				None
 			} else {
 				Some(self.entries[index])
 			}
@@ -472,9 +484,15 @@ where
 		flags: PageTableEntryFlags,
 	) {
 		assert!(L::LEVEL <= S::MAP_LEVEL);
+		let index = page.table_index::<L>();
 
 		if L::LEVEL < S::MAP_LEVEL {
-			let index = page.table_index::<L>();
+			// the frame of a block isn't a subtable
+			assert!(
+				!self.entries[index].is_present() || self.entries[index].is_table(),
+				"Page {:p} is part of a larger page",
+				page.address()
+			);
 
 			// Does the table exist yet?
 			if !self.entries[index].is_present() {
@@ -495,6 +513,21 @@ where
 
 			let subtable = self.subtable::<S>(page);
 			subtable.map_page::<S>(page, physical_address, flags)
+		} else if self.entries[index].is_present() && self.entries[index].is_table() {
+			// a table of smaller pages, which is left over from an earlier
+			// mapping, covers the page => fall back to 4 KiB pages
+			debug!("Map {:p} with 4 KiB pages", page.address());
+			let first = Page::<BasePageSize>::including_address(page.address());
+			let last = Page::<BasePageSize>::including_address(
+				page.address() + (S::SIZE - BasePageSize::SIZE),
+			);
+			let mut current_physical_address = physical_address;
+			for base_page in Page::range(first, last) {
+				self.map_page::<BasePageSize>(base_page, current_physical_address, flags);
+				if flags != PageTableEntryFlags::BLANK {
+					current_physical_address += BasePageSize::SIZE;
+				}
+			}
 		} else {
 			// Calling the default implementation from a specialized one is not supported (yet),
 			// so we have to resort to an extra function.
@@ -574,8 +607,9 @@ pub fn get_physical_address<S: PageSize>(virtual_address: VirtAddr) -> Option<Ph
 /// Translate a virtual memory address to a physical one.
 /// Just like get_physical_address, but automatically uses the correct page size for the respective memory address.
 pub fn virtual_to_physical(virtual_address: VirtAddr) -> Option<PhysAddr> {
-	// Currently, we use only 4K pages.
 	get_physical_address::<BasePageSize>(virtual_address)
+		.or_else(|| get_physical_address::<LargePageSize>(virtual_address))
+		.or_else(|| get_physical_address::<HugePageSize>(virtual_address))
 }
 
 #[no_mangle]
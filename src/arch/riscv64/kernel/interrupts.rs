@@ -5,7 +5,8 @@ use riscv::asm::wfi;
 use riscv::register::{scause, sie, sip, sstatus, stval};
 use trapframe::TrapFrame;
 
-use crate::scheduler;
+use crate::mm::mmap::Access;
+use crate::{mm, scheduler};
 
 /// base address of the PLIC, only one access at the same time is allowed
 static PLIC_BASE: SpinMutex<usize> = SpinMutex::new(0x0);
@@ -140,7 +141,7 @@ pub fn irq_install_handler(irq_number: u8, handler: fn()) {
 /// This function is called from `trap.S` which is in the trapframe crate.
 #[no_mangle]
 pub extern "C" fn trap_handler(tf: &mut TrapFrame) {
-	use self::scause::{Interrupt as I, Trap};
+	use self::scause::{Exception as E, Interrupt as I, Trap};
 	let scause = scause::read();
 	let cause = scause.cause();
 	let stval = stval::read();
@@ -158,6 +159,10 @@ pub extern "C" fn trap_handler(tf: &mut TrapFrame) {
 		Trap::Interrupt(I::SupervisorTimer) => {
 			crate::arch::riscv64::kernel::scheduler::timer_handler()
 		}
+		Trap::Exception(E::LoadPageFault) if mm::handle_page_fault(stval, Access::Read) => {}
+		Trap::Exception(E::StorePageFault) if mm::handle_page_fault(stval, Access::Write) => {}
+		Trap::Exception(E::InstructionPageFault)
+			if mm::handle_page_fault(stval, Access::Execute) => {}
 		cause => {
 			error!("Interrupt: {cause:?}");
 			error!("tf = {tf:x?} ");
@@ -1,7 +1,11 @@
 use alloc::vec::Vec;
 
 use hermit_sync::InterruptSpinMutex;
+#[cfg(feature = "blk")]
+use hermit_sync::InterruptTicketMutex;
 
+#[cfg(feature = "blk")]
+use crate::drivers::block::virtio_blk::VirtioBlkDriver;
 #[cfg(feature = "gem-net")]
 use crate::drivers::net::gem::GEMDriver;
 #[cfg(not(feature = "gem-net"))]
@@ -38,11 +42,26 @@ pub(crate) fn register_driver(drv: MmioDriver) {
 }
 
 #[cfg(feature = "gem-net")]
-pub(crate) fn get_network_driver() -> Option<&'static InterruptSpinMutex<GEMDriver>> {
-	unsafe { MMIO_DRIVERS.iter().find_map(|drv| drv.get_network_driver()) }
+pub(crate) fn get_network_drivers() -> impl Iterator<Item = &'static InterruptSpinMutex<GEMDriver>>
+{
+	unsafe {
+		MMIO_DRIVERS
+			.iter()
+			.filter_map(|drv| drv.get_network_driver())
+	}
 }
 
 #[cfg(not(feature = "gem-net"))]
-pub(crate) fn get_network_driver() -> Option<&'static InterruptSpinMutex<VirtioNetDriver>> {
-	unsafe { MMIO_DRIVERS.iter().find_map(|drv| drv.get_network_driver()) }
+pub(crate) fn get_network_drivers(
+) -> impl Iterator<Item = &'static InterruptSpinMutex<VirtioNetDriver>> {
+	unsafe {
+		MMIO_DRIVERS
+			.iter()
+			.filter_map(|drv| drv.get_network_driver())
+	}
+}
+
+#[cfg(feature = "blk")]
+pub(crate) fn get_block_driver() -> Option<&'static InterruptTicketMutex<VirtioBlkDriver>> {
+	None
 }
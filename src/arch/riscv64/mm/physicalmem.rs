@@ -37,6 +37,11 @@ pub fn total_memory_size() -> usize {
 	TOTAL_MEMORY.load(Ordering::SeqCst)
 }
 
+/// Returns the number of bytes in the free list
+pub fn free_memory_size() -> usize {
+	PHYSICAL_FREE_LIST.lock().free_space()
+}
+
 pub fn allocate(size: usize) -> Result<PhysAddr, AllocError> {
 	assert!(size > 0);
 	assert_eq!(
@@ -81,8 +86,34 @@ pub fn allocate_aligned(size: usize, alignment: usize) -> Result<PhysAddr, Alloc
 	))
 }
 
-/// This function must only be called from mm::deallocate!
-/// Otherwise, it may fail due to an empty node pool (POOL.maintain() is called in virtualmem::deallocate)
+/// Allocates `size` bytes, which are aligned to `alignment`, at the lowest
+/// free address, which isn't below `min_address`.
+pub fn allocate_above(
+	min_address: PhysAddr,
+	size: usize,
+	alignment: usize,
+) -> Result<PhysAddr, AllocError> {
+	assert!(size > 0);
+	assert_eq!(
+		alignment % BasePageSize::SIZE as usize,
+		0,
+		"Alignment {:#X} is not a multiple of {:#X}",
+		alignment,
+		BasePageSize::SIZE as usize
+	);
+
+	Ok(PhysAddr(
+		PHYSICAL_FREE_LIST
+			.lock()
+			.allocate_above(min_address.as_usize(), size, alignment)?
+			.try_into()
+			.unwrap(),
+	))
+}
+
+/// Returns frames, which have been taken by one of the `allocate` functions,
+/// to the free list. The frames must not be mapped anymore, which
+/// mm::deallocate ensures for the kernel heap.
 pub fn deallocate(physical_address: PhysAddr, size: usize) {
 	assert!(
 		physical_address >= PhysAddr(mm::kernel_end_address().as_u64()),
@@ -7,6 +7,8 @@ use core::arch::x86_64::_mm_mfence;
 use core::fmt;
 use core::hint::spin_loop;
 use core::sync::atomic::Ordering;
+#[cfg(feature = "smp")]
+use core::sync::atomic::{fence, AtomicU64};
 use core::{cmp, mem, ptr};
 
 use align_address::Align;
@@ -95,6 +97,15 @@ static IOAPIC_ADDRESS: OnceCell<VirtAddr> = OnceCell::new();
 /// Both numbers often match, but don't need to (e.g. when a core has been disabled).
 static CPU_LOCAL_APIC_IDS: SpinMutex<Vec<u8>> = SpinMutex::new(Vec::new());
 
+/// Number of TLB flushes, which each core has performed on request of another
+/// core. The index equals the Core ID, which is bounded by the 8-bit APIC IDs.
+#[cfg(feature = "smp")]
+static TLB_FLUSHES: [AtomicU64; 256] = {
+	#[allow(clippy::declare_interior_mutable_const)]
+	const ZERO: AtomicU64 = AtomicU64::new(0);
+	[ZERO; 256]
+};
+
 /// After calibration, initialize the APIC Timer with this counter value to let it fire an interrupt
 /// after 1 microsecond.
 static CALIBRATED_COUNTER_VALUE: OnceCell<u64> = OnceCell::new();
@@ -215,6 +226,7 @@ extern "x86-interrupt" fn tlb_flush_handler(stack_frame: interrupts::ExceptionSt
 	unsafe {
 		cr3_write(cr3());
 	}
+	TLB_FLUSHES[core_id() as usize].fetch_add(1, Ordering::Release);
 	eoi();
 	swapgs(&stack_frame);
 }
@@ -835,6 +847,34 @@ pub fn ipi_tlb_flush() {
 	}
 }
 
+/// Flushes the TLBs of all other cores and waits, until they are flushed.
+/// A flush, which started after the page tables were changed, is sufficient.
+/// The other cores have to be able to receive interrupts, so the caller must
+/// not hold locks, which disable interrupts and which other cores may wait for.
+#[cfg(feature = "smp")]
+pub fn ipi_tlb_shootdown() {
+	let count = arch::get_processor_count();
+	if count <= 1 {
+		return;
+	}
+
+	// order the changes of the page tables before the snapshot of the flushes
+	fence(Ordering::SeqCst);
+	let own_id = core_id() as usize;
+	let flushes: Vec<(usize, u64)> = (0..count as usize)
+		.filter(|id| *id != own_id)
+		.map(|id| (id, TLB_FLUSHES[id].load(Ordering::Acquire)))
+		.collect();
+
+	ipi_tlb_flush();
+
+	for (id, previous) in flushes {
+		while TLB_FLUSHES[id].load(Ordering::Acquire) == previous {
+			spin_loop();
+		}
+	}
+}
+
 /// Send an inter-processor interrupt to wake up a CPU Core that is in a HALT state.
 #[allow(unused_variables)]
 pub fn wakeup_core(core_id_to_wakeup: CoreId) {
@@ -9,6 +9,9 @@ use crate::arch::x86_64::mm::paging::{
 	BasePageSize, PageSize, PageTableEntryFlags, PageTableEntryFlagsExt,
 };
 use crate::arch::x86_64::mm::{paging, PhysAddr};
+#[cfg(feature = "blk")]
+use crate::drivers::block::virtio_blk::VirtioBlkDriver;
+#[cfg(any(feature = "tcp", feature = "udp"))]
 use crate::drivers::net::virtio_net::VirtioNetDriver;
 use crate::drivers::virtio::transport::mmio as mmio_virtio;
 use crate::drivers::virtio::transport::mmio::{DevId, MmioRegisterLayout, VirtioDriver};
@@ -23,10 +26,14 @@ const IRQ_NUMBER: u8 = 23;
 static mut MMIO_DRIVERS: Vec<MmioDriver> = Vec::new();
 
 pub(crate) enum MmioDriver {
+	#[cfg(any(feature = "tcp", feature = "udp"))]
 	VirtioNet(InterruptTicketMutex<VirtioNetDriver>),
+	#[cfg(feature = "blk")]
+	VirtioBlk(InterruptTicketMutex<VirtioBlkDriver>),
 }
 
 impl MmioDriver {
+	#[cfg(any(feature = "tcp", feature = "udp"))]
 	#[allow(unreachable_patterns)]
 	fn get_network_driver(&self) -> Option<&InterruptTicketMutex<VirtioNetDriver>> {
 		match self {
@@ -34,10 +41,20 @@ impl MmioDriver {
 			_ => None,
 		}
 	}
+
+	#[cfg(feature = "blk")]
+	#[allow(unreachable_patterns)]
+	fn get_block_driver(&self) -> Option<&InterruptTicketMutex<VirtioBlkDriver>> {
+		match self {
+			Self::VirtioBlk(drv) => Some(drv),
+			_ => None,
+		}
+	}
 }
 
 fn check_linux_args(
 	linux_mmio: &'static [String],
+	device_id: DevId,
 ) -> Result<(&'static mut MmioRegisterLayout, u8), &'static str> {
 	let virtual_address =
 		crate::arch::mm::virtualmem::allocate(BasePageSize::SIZE as usize).unwrap();
@@ -90,11 +107,11 @@ fn check_linux_args(
 				// We found a MMIO-device (whose 512-bit address in this structure).
 				trace!("Found a MMIO-device at {mmio:p}");
 
-				// Verify the device-ID to find the network card
+				// Verify the device-ID to find the requested device
 				let id = mmio.get_device_id();
 
-				if id != DevId::VIRTIO_DEV_ID_NET {
-					trace!("It's not a network card at {mmio:p}");
+				if id != device_id {
+					trace!("It's not a {device_id:?} device at {mmio:p}");
 					continue;
 				}
 
@@ -114,10 +131,10 @@ fn check_linux_args(
 	// frees obsolete virtual memory region for MMIO devices
 	crate::arch::mm::virtualmem::deallocate(virtual_address, BasePageSize::SIZE as usize);
 
-	Err("Network card not found!")
+	Err("Device not found!")
 }
 
-fn guess_device() -> Result<(&'static mut MmioRegisterLayout, u8), &'static str> {
+fn guess_device(device_id: DevId) -> Result<(&'static mut MmioRegisterLayout, u8), &'static str> {
 	// Trigger page mapping in the first iteration!
 	let mut current_page = 0;
 	let virtual_address =
@@ -167,15 +184,15 @@ fn guess_device() -> Result<(&'static mut MmioRegisterLayout, u8), &'static str>
 		// We found a MMIO-device (whose 512-bit address in this structure).
 		trace!("Found a MMIO-device at {mmio:p}");
 
-		// Verify the device-ID to find the network card
+		// Verify the device-ID to find the requested device
 		let id = mmio.get_device_id();
 
-		if id != DevId::VIRTIO_DEV_ID_NET {
-			trace!("It's not a network card at {mmio:p}");
+		if id != device_id {
+			trace!("It's not a {device_id:?} device at {mmio:p}");
 			continue;
 		}
 
-		info!("Found network card at {mmio:p}");
+		info!("Found {device_id:?} device at {mmio:p}");
 
 		crate::arch::mm::physicalmem::reserve(
 			PhysAddr::from(current_address.align_down(BasePageSize::SIZE as usize)),
@@ -188,18 +205,18 @@ fn guess_device() -> Result<(&'static mut MmioRegisterLayout, u8), &'static str>
 	// frees obsolete virtual memory region for MMIO devices
 	crate::arch::mm::virtualmem::deallocate(virtual_address, BasePageSize::SIZE as usize);
 
-	Err("Network card not found!")
+	Err("Device not found!")
 }
 
-/// Tries to find the network device within the specified address range.
+/// Tries to find the device with the given id within the specified address range.
 /// Returns a reference to it within the Ok() if successful or an Err() on failure.
-fn detect_network() -> Result<(&'static mut MmioRegisterLayout, u8), &'static str> {
+fn detect_device(device_id: DevId) -> Result<(&'static mut MmioRegisterLayout, u8), &'static str> {
 	let linux_mmio = env::mmio();
 
 	if linux_mmio.len() > 0 {
-		check_linux_args(linux_mmio)
+		check_linux_args(linux_mmio, device_id)
 	} else {
-		guess_device()
+		guess_device(device_id)
 	}
 }
 
@@ -209,26 +226,49 @@ pub(crate) fn register_driver(drv: MmioDriver) {
 	}
 }
 
-pub(crate) fn get_network_driver() -> Option<&'static InterruptTicketMutex<VirtioNetDriver>> {
-	unsafe { MMIO_DRIVERS.iter().find_map(|drv| drv.get_network_driver()) }
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub(crate) fn get_network_drivers(
+) -> impl Iterator<Item = &'static InterruptTicketMutex<VirtioNetDriver>> {
+	unsafe {
+		MMIO_DRIVERS
+			.iter()
+			.filter_map(|drv| drv.get_network_driver())
+	}
+}
+
+#[cfg(feature = "blk")]
+pub(crate) fn get_block_driver() -> Option<&'static InterruptTicketMutex<VirtioBlkDriver>> {
+	unsafe { MMIO_DRIVERS.iter().find_map(|drv| drv.get_block_driver()) }
+}
+
+fn init_device(device_id: DevId) {
+	if let Ok((mmio, irq)) = detect_device(device_id) {
+		warn!(
+			"Found MMIO device, but we guess the interrupt number {}!",
+			irq
+		);
+		match mmio_virtio::init_device(mmio, irq) {
+			#[cfg(any(feature = "tcp", feature = "udp"))]
+			Ok(VirtioDriver::Network(drv)) => {
+				register_driver(MmioDriver::VirtioNet(InterruptTicketMutex::new(drv)))
+			}
+			#[cfg(feature = "blk")]
+			Ok(VirtioDriver::Block(drv)) => {
+				register_driver(MmioDriver::VirtioBlk(InterruptTicketMutex::new(drv)))
+			}
+			Err(err) => error!("Could not initialize virtio-mmio device: {err}"),
+		}
+	} else {
+		warn!("Unable to find mmio device {device_id:?}");
+	}
 }
 
 pub(crate) fn init_drivers() {
 	// virtio: MMIO Device Discovery
 	without_interrupts(|| {
-		if let Ok((mmio, irq)) = detect_network() {
-			warn!(
-				"Found MMIO device, but we guess the interrupt number {}!",
-				irq
-			);
-			match mmio_virtio::init_device(mmio, irq) {
-				Ok(VirtioDriver::Network(drv)) => {
-					register_driver(MmioDriver::VirtioNet(InterruptTicketMutex::new(drv)))
-				}
-				Err(err) => error!("Could not initialize virtio-mmio device: {err}"),
-			}
-		} else {
-			warn!("Unable to find mmio device");
-		}
+		#[cfg(any(feature = "tcp", feature = "udp"))]
+		init_device(DevId::VIRTIO_DEV_ID_NET);
+		#[cfg(feature = "blk")]
+		init_device(DevId::VIRTIO_DEV_ID_BLK);
 	});
 }
@@ -21,7 +21,10 @@ pub mod apic;
 pub mod core_local;
 pub mod gdt;
 pub mod interrupts;
-#[cfg(all(not(feature = "pci"), any(feature = "tcp", feature = "udp")))]
+#[cfg(all(
+	not(feature = "pci"),
+	any(feature = "tcp", feature = "udp", feature = "blk")
+))]
 pub mod mmio;
 #[cfg(feature = "pci")]
 pub mod pci;
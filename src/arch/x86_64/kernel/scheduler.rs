@@ -104,7 +104,7 @@ impl TaskStacks {
 		let virt_addr =
 			crate::arch::mm::virtualmem::allocate(total_size + 4 * BasePageSize::SIZE as usize)
 				.expect("Failed to allocate Virtual Memory for TaskStacks");
-		let phys_addr = crate::arch::mm::physicalmem::allocate(total_size)
+		let phys_addr = crate::arch::mm::physicalmem::allocate(DEFAULT_STACK_SIZE + IST_SIZE)
 			.expect("Failed to allocate Physical Memory for TaskStacks");
 
 		debug!(
@@ -132,24 +132,12 @@ impl TaskStacks {
 			flags,
 		);
 
-		// map user stack into the address space
-		crate::arch::mm::paging::map::<BasePageSize>(
-			virt_addr + IST_SIZE + DEFAULT_STACK_SIZE + 3 * BasePageSize::SIZE,
-			phys_addr + IST_SIZE + DEFAULT_STACK_SIZE,
-			user_stack_size / BasePageSize::SIZE as usize,
-			flags,
+		// reserve user stack, which is mapped on demand
+		crate::mm::stack::reserve(
+			virt_addr + IST_SIZE + DEFAULT_STACK_SIZE + 2 * BasePageSize::SIZE,
+			user_stack_size,
 		);
 
-		// clear user stack
-		unsafe {
-			ptr::write_bytes(
-				(virt_addr + IST_SIZE + DEFAULT_STACK_SIZE + 3 * BasePageSize::SIZE as usize)
-					.as_mut_ptr::<u8>(),
-				0xAC,
-				user_stack_size,
-			);
-		}
-
 		TaskStacks::Common(CommonStack {
 			virt_addr,
 			phys_addr,
@@ -225,15 +213,22 @@ impl Drop for TaskStacks {
 					stacks.total_size >> 10,
 				);
 
+				crate::mm::stack::release(
+					stacks.virt_addr + IST_SIZE + DEFAULT_STACK_SIZE + 2 * BasePageSize::SIZE,
+					stacks.total_size - DEFAULT_STACK_SIZE - IST_SIZE,
+				);
 				crate::arch::mm::paging::unmap::<BasePageSize>(
 					stacks.virt_addr,
-					stacks.total_size / BasePageSize::SIZE as usize + 4,
+					(IST_SIZE + DEFAULT_STACK_SIZE) / BasePageSize::SIZE as usize + 2,
 				);
 				crate::arch::mm::virtualmem::deallocate(
 					stacks.virt_addr,
 					stacks.total_size + 4 * BasePageSize::SIZE as usize,
 				);
-				crate::arch::mm::physicalmem::deallocate(stacks.phys_addr, stacks.total_size);
+				crate::arch::mm::physicalmem::deallocate(
+					stacks.phys_addr,
+					DEFAULT_STACK_SIZE + IST_SIZE,
+				);
 			}
 		}
 	}
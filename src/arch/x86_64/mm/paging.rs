@@ -7,7 +7,7 @@ use x86_64::registers::control::Cr2;
 use x86_64::registers::segmentation::SegmentSelector;
 pub use x86_64::structures::idt::InterruptStackFrame as ExceptionStackFrame;
 use x86_64::structures::idt::PageFaultErrorCode;
-use x86_64::structures::paging::mapper::{TranslateResult, UnmapError};
+use x86_64::structures::paging::mapper::{MapToError, TranslateResult, UnmapError};
 pub use x86_64::structures::paging::PageTableFlags as PageTableEntryFlags;
 use x86_64::structures::paging::{
 	Mapper, Page, PageTableIndex, PhysFrame, RecursivePageTable, Size2MiB, Translate,
@@ -15,6 +15,7 @@ use x86_64::structures::paging::{
 
 use crate::arch::x86_64::kernel::processor;
 use crate::arch::x86_64::mm::{physicalmem, PhysAddr, VirtAddr};
+use crate::mm::mmap::Access;
 use crate::{env, mm, scheduler};
 
 pub trait PageTableEntryFlagsExt {
@@ -22,7 +23,6 @@ pub trait PageTableEntryFlagsExt {
 
 	fn normal(&mut self) -> &mut Self;
 
-	#[cfg(feature = "acpi")]
 	fn read_only(&mut self) -> &mut Self;
 
 	fn writable(&mut self) -> &mut Self;
@@ -50,7 +50,6 @@ impl PageTableEntryFlagsExt for PageTableEntryFlags {
 		self
 	}
 
-	#[cfg(feature = "acpi")]
 	fn read_only(&mut self) -> &mut Self {
 		self.remove(PageTableEntryFlags::WRITABLE);
 		self
@@ -118,6 +117,20 @@ pub fn virtual_to_physical(virtual_address: VirtAddr) -> Option<PhysAddr> {
 	}
 }
 
+/// Returns the frame of the page of size `S` at `virtual_address`, if it is mapped
+pub fn get_physical_address<S>(virtual_address: VirtAddr) -> Option<PhysAddr>
+where
+	S: PageSize + Debug,
+	RecursivePageTable<'static>: Mapper<S>,
+{
+	let page = Page::<S>::containing_address(x86_64::VirtAddr::new(virtual_address.0));
+	let frame = unsafe { recursive_page_table() }
+		.translate_page(page)
+		.ok()?;
+
+	Some(PhysAddr(frame.start_address().as_u64()))
+}
+
 #[no_mangle]
 pub extern "C" fn virt_to_phys(virtual_address: VirtAddr) -> PhysAddr {
 	virtual_to_physical(virtual_address).unwrap()
@@ -169,10 +182,21 @@ pub fn map<S>(
 				flush.flush();
 				debug!("Had to unmap page {page:?} before mapping.");
 			}
-			recursive_page_table()
-				.map_to(page, frame, flags, &mut physicalmem::FrameAlloc)
-				.unwrap()
-				.flush();
+			match recursive_page_table().map_to(page, frame, flags, &mut physicalmem::FrameAlloc) {
+				Ok(flush) => flush.flush(),
+				// a page table of smaller pages, which is left over from an
+				// earlier mapping, covers the page => fall back to 4 KiB pages
+				Err(MapToError::PageAlreadyMapped(_)) if S::SIZE != BasePageSize::SIZE => {
+					debug!("Map {page:?} with 4 KiB pages");
+					map::<BasePageSize>(
+						VirtAddr(page.start_address().as_u64()),
+						PhysAddr(frame.start_address().as_u64()),
+						(S::SIZE / BasePageSize::SIZE) as usize,
+						flags,
+					);
+				}
+				Err(err) => panic!("{err:?}"),
+			}
 		}
 	}
 
@@ -264,11 +288,30 @@ pub fn get_application_page_size() -> usize {
 	LargePageSize::SIZE as usize
 }
 
+/// Maps the page of a task stack or a memory mapping, which is accessed for
+/// the first time or copied on the first write
+fn resolve_page_fault(error_code: PageFaultErrorCode) -> bool {
+	let access = if error_code.contains(PageFaultErrorCode::INSTRUCTION_FETCH) {
+		Access::Execute
+	} else if error_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE) {
+		Access::Write
+	} else {
+		Access::Read
+	};
+	let addr = Cr2::read().unwrap().as_u64();
+
+	mm::handle_page_fault(addr.try_into().unwrap(), access)
+}
+
 #[cfg(not(feature = "common-os"))]
 pub(crate) extern "x86-interrupt" fn page_fault_handler(
 	stack_frame: ExceptionStackFrame,
 	error_code: PageFaultErrorCode,
 ) {
+	if resolve_page_fault(error_code) {
+		return;
+	}
+
 	error!("Page fault (#PF)!");
 	error!("page_fault_linear_address = {:p}", Cr2::read().unwrap());
 	error!("error_code = {error_code:?}");
@@ -288,6 +331,16 @@ pub(crate) extern "x86-interrupt" fn page_fault_handler(
 			core::arch::asm!("swapgs", options(nostack));
 		}
 	}
+
+	if resolve_page_fault(error_code) {
+		unsafe {
+			if stack_frame.as_mut().read().code_segment != SegmentSelector(0x08) {
+				core::arch::asm!("swapgs", options(nostack));
+			}
+		}
+		return;
+	}
+
 	error!("Page fault (#PF)!");
 	error!("page_fault_linear_address = {:p}", Cr2::read().unwrap());
 	error!("error_code = {error_code:?}");
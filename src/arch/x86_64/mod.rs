@@ -37,6 +37,9 @@ pub fn init_drivers() {
 	// Initialize PCI Drivers
 	#[cfg(feature = "pci")]
 	crate::drivers::pci::init_drivers();
-	#[cfg(all(not(feature = "pci"), any(feature = "tcp", feature = "udp")))]
+	#[cfg(all(
+		not(feature = "pci"),
+		any(feature = "tcp", feature = "udp", feature = "blk")
+	))]
 	crate::arch::x86_64::kernel::mmio::init_drivers();
 }
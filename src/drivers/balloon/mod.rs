@@ -0,0 +1,34 @@
+//! A module containing the memory balloon driver, which hands free memory
+//! of the guest back to the host.
+
+pub mod virtio_balloon;
+pub mod virtio_pci;
+
+use core::future;
+use core::task::Poll;
+
+use crate::drivers::pci::get_balloon_driver;
+use crate::executor::spawn;
+
+async fn balloon_run() {
+	future::poll_fn(|_cx| {
+		let Some(driver) = get_balloon_driver() else {
+			return Poll::Ready(());
+		};
+
+		// another task is already using the driver => don't check
+		if let Some(mut guard) = driver.try_lock() {
+			guard.poll();
+		}
+
+		Poll::Pending
+	})
+	.await
+}
+
+/// Spawns the task, which resizes the balloon on request of the host
+pub(crate) fn init() {
+	if get_balloon_driver().is_some() {
+		spawn(balloon_run());
+	}
+}
@@ -0,0 +1,662 @@
+//! A module containing a virtio memory balloon driver.
+//!
+//! The host announces the number of pages, which the balloon should contain.
+//! The driver inflates the balloon by taking free frames from the physical
+//! memory allocator and handing them over to the host. It deflates the
+//! balloon by returning the frames to the allocator, after the host has been
+//! told about them. With free page reporting, the driver additionally sweeps
+//! over the free memory and reports it in chunks, which the host may discard.
+//! A single chunk is only held while it is reported.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::slice;
+
+use pci_types::InterruptLine;
+
+use self::constants::{
+	FeatureSet, Features, BALLOON_PAGE_SIZE, DEFLATE_QUEUE, INFLATE_QUEUE, MAX_PFNS_PER_REQUEST,
+	MIN_FREE_MEMORY, MIN_FREE_MEMORY_DIVISOR, PFN_SHIFT, REPORTING_CHUNK_SIZE, REPORTING_INTERVAL,
+	REPORTING_QUEUE,
+};
+use self::error::VirtioBalloonError;
+use crate::arch::mm::{physicalmem, PhysAddr};
+use crate::config::VIRTIO_MAX_QUEUE_SIZE;
+use crate::drivers::balloon::virtio_pci::BalloonDevCfgRaw;
+use crate::drivers::virtio::transport::pci::{ComCfg, IsrStatus, NotifCfg};
+use crate::drivers::virtio::virtqueue::error::VirtqError;
+use crate::drivers::virtio::virtqueue::split::SplitVq;
+use crate::drivers::virtio::virtqueue::{BuffSpec, Bytes, Virtq, VqIndex, VqSize};
+use crate::executor::now;
+use crate::mm;
+
+/// A wrapper struct for the raw configuration structure.
+/// Handling the right access to fields, as some are read-only
+/// for the driver.
+pub(crate) struct BalloonDevCfg {
+	pub raw: &'static mut BalloonDevCfgRaw,
+	pub dev_id: u16,
+	pub features: FeatureSet,
+}
+
+/// Virtio memory balloon driver struct.
+///
+/// Struct allows to control devices virtqueues as also
+/// the device itself.
+#[allow(dead_code)]
+pub(crate) struct VirtioBalloonDriver {
+	pub(super) dev_cfg: BalloonDevCfg,
+	pub(super) com_cfg: ComCfg,
+	pub(super) isr_stat: IsrStatus,
+	pub(super) notif_cfg: NotifCfg,
+	pub(super) vqueues: Vec<Rc<dyn Virtq>>,
+	pub(super) irq: InterruptLine,
+	/// Frames, which have been handed over to the host
+	pub(super) frames: Vec<PhysAddr>,
+	/// Number of pages, which has been requested by the host
+	pub(super) target: usize,
+	/// Address, above which the next chunk of free memory is reported
+	pub(super) report_cursor: PhysAddr,
+	/// Time of the next sweep over the free memory in microseconds
+	pub(super) next_report: u64,
+}
+
+impl VirtioBalloonDriver {
+	pub fn get_dev_id(&self) -> u16 {
+		self.dev_cfg.dev_id
+	}
+
+	pub fn set_failed(&mut self) {
+		self.com_cfg.set_failed();
+	}
+
+	/// Returns the number of pages in the balloon
+	pub fn size(&self) -> usize {
+		self.frames.len()
+	}
+
+	/// Negotiates a subset of features, understood and wanted by both the OS
+	/// and the device.
+	fn negotiate_features(&mut self, wanted_feats: &[Features]) -> Result<(), VirtioBalloonError> {
+		let mut drv_feats = FeatureSet::new(0);
+
+		for feat in wanted_feats.iter() {
+			drv_feats |= *feat;
+		}
+
+		let dev_feats = FeatureSet::new(self.com_cfg.dev_features());
+
+		if (dev_feats & drv_feats) == drv_feats {
+			// If device supports subset of features write feature set to common config
+			self.com_cfg.set_drv_features(drv_feats.into());
+			Ok(())
+		} else {
+			Err(VirtioBalloonError::IncompFeatsSet(drv_feats, dev_feats))
+		}
+	}
+
+	/// Initializes the device in adherence to specification. Returns Some(VirtioBalloonError)
+	/// upon failure and None in case everything worked as expected.
+	///
+	/// See Virtio specification v1.1. - 3.1.1.
+	///                      and v1.2. - 5.5.5
+	pub(crate) fn init_dev(&mut self) -> Result<(), VirtioBalloonError> {
+		// Reset
+		self.com_cfg.reset_dev();
+
+		// Indiacte device, that OS noticed it
+		self.com_cfg.ack_dev();
+
+		// Indicate device, that driver is able to handle it
+		self.com_cfg.set_drv();
+
+		// Frames are always announced before they are reused and free page
+		// reporting is optional. Hence, both are only requested, if the device
+		// offers them.
+		let dev_feats = FeatureSet::new(self.com_cfg.dev_features());
+		let mut feats: Vec<Features> = vec![Features::VIRTIO_F_VERSION_1];
+		for feat in [
+			Features::VIRTIO_BALLOON_F_MUST_TELL_HOST,
+			Features::VIRTIO_BALLOON_F_PAGE_REPORTING,
+		] {
+			if dev_feats.is_feature(feat) {
+				feats.push(feat);
+			}
+		}
+		self.negotiate_features(&feats)?;
+
+		// Indicates the device, that the current feature set is final for the driver
+		// and will not be changed.
+		self.com_cfg.features_ok();
+
+		// Checks if the device has accepted final set. This finishes feature negotiation.
+		if self.com_cfg.check_features() {
+			info!(
+				"Features have been negotiated between virtio balloon device {:x} and driver.",
+				self.dev_cfg.dev_id
+			);
+			// Set feature set in device config fur future use.
+			self.dev_cfg.features.set_features(&feats);
+		} else {
+			return Err(VirtioBalloonError::FailFeatureNeg(self.dev_cfg.dev_id));
+		}
+
+		// The inflate and the deflate queue always exist, the reporting
+		// queue only with free page reporting.
+		let vqnum = if self.is_reporting() {
+			REPORTING_QUEUE + 1
+		} else {
+			DEFLATE_QUEUE + 1
+		};
+		for i in 0..vqnum as u16 {
+			let vq = SplitVq::new(
+				&mut self.com_cfg,
+				&self.notif_cfg,
+				VqSize::from(VIRTIO_MAX_QUEUE_SIZE),
+				VqIndex::from(i),
+				self.dev_cfg.features.into(),
+			)
+			.map_err(|_| VirtioBalloonError::Unknown)?;
+			self.vqueues.push(Rc::new(vq));
+		}
+
+		// At this point the device is "live"
+		self.com_cfg.drv_ok();
+
+		info!(
+			"Virtio balloon device {:x} {} free page reporting",
+			self.dev_cfg.dev_id,
+			if self.is_reporting() {
+				"supports"
+			} else {
+				"doesn't support"
+			}
+		);
+
+		Ok(())
+	}
+
+	/// Returns true, if free memory is reported to the host
+	fn is_reporting(&self) -> bool {
+		self.dev_cfg
+			.features
+			.is_feature(Features::VIRTIO_BALLOON_F_PAGE_REPORTING)
+	}
+
+	/// Sends the page frame numbers of `frames` to the device by the queue `index`
+	fn tell_host(&self, index: usize, frames: &[PhysAddr]) -> Result<(), VirtqError> {
+		let pfns: Vec<u8> = frames
+			.iter()
+			.flat_map(|addr| {
+				u32::try_from(addr.as_usize() >> PFN_SHIFT)
+					.unwrap()
+					.to_le_bytes()
+			})
+			.collect();
+		let send = (
+			pfns.as_slice(),
+			BuffSpec::Single(Bytes::new(pfns.len()).ok_or(VirtqError::BufferToLarge)?),
+		);
+
+		let transfer_tkn = self.vqueues[index]
+			.clone()
+			.prep_transfer_from_raw(Some(send), None)?;
+		transfer_tkn.dispatch_blocking()?;
+
+		Ok(())
+	}
+
+	/// Takes up to `count` free frames from the physical memory allocator and
+	/// hands them over to the host. The balloon never takes the free memory
+	/// below a low-water mark, which is left for the kernel, e.g., for page
+	/// faults on stacks and mappings. Returns false, if no frame has been added.
+	fn inflate(&mut self, count: usize) -> bool {
+		let low_water_mark =
+			MIN_FREE_MEMORY.max(physicalmem::total_memory_size() / MIN_FREE_MEMORY_DIVISOR);
+		let available =
+			physicalmem::free_memory_size().saturating_sub(low_water_mark) / BALLOON_PAGE_SIZE;
+
+		let frames: Vec<PhysAddr> = (0..count.min(available).min(MAX_PFNS_PER_REQUEST))
+			.map_while(|_| physicalmem::allocate(BALLOON_PAGE_SIZE).ok())
+			.collect();
+		if frames.is_empty() {
+			// the free memory has reached the low-water mark => try again later
+			return false;
+		}
+
+		if let Err(err) = self.tell_host(INFLATE_QUEUE, &frames) {
+			error!("Unable to inflate the balloon: {:?}", err);
+			for addr in frames {
+				physicalmem::deallocate(addr, BALLOON_PAGE_SIZE);
+			}
+			return false;
+		}
+
+		self.frames.extend(frames);
+		true
+	}
+
+	/// Returns up to `count` frames of the balloon to the physical memory
+	/// allocator. Returns false, if no frame has been removed.
+	fn deflate(&mut self, count: usize) -> bool {
+		let frames = self
+			.frames
+			.split_off(self.frames.len() - count.min(MAX_PFNS_PER_REQUEST));
+
+		// the host is always told before the frames are reused, which is
+		// required by VIRTIO_BALLOON_F_MUST_TELL_HOST
+		if let Err(err) = self.tell_host(DEFLATE_QUEUE, &frames) {
+			error!("Unable to deflate the balloon: {:?}", err);
+			self.frames.extend(frames);
+			return false;
+		}
+
+		for addr in frames {
+			physicalmem::deallocate(addr, BALLOON_PAGE_SIZE);
+		}
+		true
+	}
+
+	/// Reports the free chunk at `addr` to the host
+	fn report(&self, addr: PhysAddr) -> Result<(), VirtqError> {
+		let virt_addr = mm::map(addr, REPORTING_CHUNK_SIZE, true, true, false);
+		let chunk = unsafe {
+			slice::from_raw_parts_mut(virt_addr.as_mut_ptr::<u8>(), REPORTING_CHUNK_SIZE)
+		};
+		let recv = (
+			chunk,
+			BuffSpec::Single(Bytes::new(REPORTING_CHUNK_SIZE).ok_or(VirtqError::BufferToLarge)?),
+		);
+
+		let result = self.vqueues[REPORTING_QUEUE]
+			.clone()
+			.prep_transfer_from_raw(None, Some(recv))
+			.and_then(|transfer_tkn| transfer_tkn.dispatch_blocking());
+		mm::unmap(virt_addr, REPORTING_CHUNK_SIZE);
+
+		result.map(|_| ())
+	}
+
+	/// Reports the next chunk of free memory to the host. The chunk is taken
+	/// from the physical memory allocator above the last reported chunk and
+	/// returned afterwards, so that only one chunk is held at a time. If no
+	/// chunk is left or the chunk couldn't be reported, the sweep over the
+	/// free memory continues after [`REPORTING_INTERVAL`].
+	fn report_free_pages(&mut self) {
+		let Ok(addr) = physicalmem::allocate_above(
+			self.report_cursor,
+			REPORTING_CHUNK_SIZE,
+			REPORTING_CHUNK_SIZE,
+		) else {
+			self.report_cursor = PhysAddr(0);
+			self.next_report = now() + REPORTING_INTERVAL;
+			return;
+		};
+
+		match self.report(addr) {
+			Ok(()) => self.report_cursor = addr + REPORTING_CHUNK_SIZE,
+			Err(err) => {
+				// keep the cursor to report the chunk with the next sweep
+				error!("Unable to report free memory: {:?}", err);
+				self.next_report = now() + REPORTING_INTERVAL;
+			}
+		}
+
+		physicalmem::deallocate(addr, REPORTING_CHUNK_SIZE);
+	}
+
+	/// Inflates or deflates the balloon towards the size, which is requested
+	/// by the host, and reports free memory. Every call handles a limited
+	/// number of pages and at most one chunk of free memory, so that neither
+	/// the executor nor the driver lock is blocked for long.
+	pub(crate) fn poll(&mut self) {
+		let target = self.dev_cfg.raw.get_num_pages() as usize;
+		if target != self.target {
+			info!(
+				"Resize virtio balloon from {} to {} pages",
+				self.size(),
+				target
+			);
+			self.target = target;
+		}
+
+		let changed = match target.cmp(&self.size()) {
+			Ordering::Greater => self.inflate(target - self.size()),
+			Ordering::Less => self.deflate(self.size() - target),
+			Ordering::Equal => false,
+		};
+		if changed {
+			let size = self.size().try_into().unwrap();
+			self.dev_cfg.raw.set_actual(size);
+		}
+
+		if self.is_reporting() && now() >= self.next_report {
+			self.report_free_pages();
+		}
+	}
+}
+
+pub mod constants {
+	use alloc::vec::Vec;
+	use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+
+	use crate::arch::mm::paging::{LargePageSize, PageSize};
+
+	/// Size of the pages, which are described by page frame numbers.
+	/// See Virtio specification v1.2. - 5.5.6
+	pub const BALLOON_PAGE_SIZE: usize = 4096;
+	/// Shift of a physical address to its page frame number.
+	pub const PFN_SHIFT: usize = 12;
+	/// Maximum number of page frame numbers, which are sent with a single request.
+	pub const MAX_PFNS_PER_REQUEST: usize = 256;
+	/// Free memory in bytes, which the balloon leaves to the kernel.
+	pub const MIN_FREE_MEMORY: usize = 0x1000000;
+	/// Fraction of the total memory, which the balloon leaves to the kernel,
+	/// if it is larger than [`MIN_FREE_MEMORY`].
+	pub const MIN_FREE_MEMORY_DIVISOR: usize = 32;
+
+	/// Index of the queue, which receives the frames of the inflated balloon.
+	pub const INFLATE_QUEUE: usize = 0;
+	/// Index of the queue, which receives the frames of the deflated balloon.
+	pub const DEFLATE_QUEUE: usize = 1;
+	/// Index of the queue, which receives free memory. Queues of features,
+	/// which aren't negotiated, don't occupy an index.
+	/// See Virtio specification v1.2. - 5.5.2
+	pub const REPORTING_QUEUE: usize = 2;
+
+	/// Size of the chunks of free memory, which are reported to the host.
+	pub const REPORTING_CHUNK_SIZE: usize = LargePageSize::SIZE as usize;
+	/// Interval between two sweeps over the free memory in microseconds.
+	pub const REPORTING_INTERVAL: u64 = 2_000_000;
+
+	/// Enum contains virtio's memory balloon device features and general features of Virtio.
+	///
+	/// See Virtio specification v1.2. - 5.5.3
+	///
+	/// See Virtio specification v1.1. - 6
+	//
+	// WARN: In case the enum is changed, the static function of features `into_features(feat: u64) ->
+	// Option<Vec<Features>>` must also be adjusted to return a correct vector of features.
+	#[allow(dead_code, non_camel_case_types)]
+	#[derive(Copy, Clone, Debug)]
+	#[repr(u64)]
+	pub enum Features {
+		VIRTIO_BALLOON_F_MUST_TELL_HOST = 1 << 0,
+		VIRTIO_BALLOON_F_STATS_VQ = 1 << 1,
+		VIRTIO_BALLOON_F_DEFLATE_ON_OOM = 1 << 2,
+		VIRTIO_BALLOON_F_FREE_PAGE_HINT = 1 << 3,
+		VIRTIO_BALLOON_F_PAGE_POISON = 1 << 4,
+		VIRTIO_BALLOON_F_PAGE_REPORTING = 1 << 5,
+		VIRTIO_F_RING_INDIRECT_DESC = 1 << 28,
+		VIRTIO_F_RING_EVENT_IDX = 1 << 29,
+		VIRTIO_F_VERSION_1 = 1 << 32,
+		VIRTIO_F_ACCESS_PLATFORM = 1 << 33,
+		VIRTIO_F_RING_PACKED = 1 << 34,
+		VIRTIO_F_IN_ORDER = 1 << 35,
+		VIRTIO_F_ORDER_PLATFORM = 1 << 36,
+		VIRTIO_F_SR_IOV = 1 << 37,
+		VIRTIO_F_NOTIFICATION_DATA = 1 << 38,
+	}
+
+	impl From<Features> for u64 {
+		fn from(val: Features) -> Self {
+			match val {
+				Features::VIRTIO_BALLOON_F_MUST_TELL_HOST => 1 << 0,
+				Features::VIRTIO_BALLOON_F_STATS_VQ => 1 << 1,
+				Features::VIRTIO_BALLOON_F_DEFLATE_ON_OOM => 1 << 2,
+				Features::VIRTIO_BALLOON_F_FREE_PAGE_HINT => 1 << 3,
+				Features::VIRTIO_BALLOON_F_PAGE_POISON => 1 << 4,
+				Features::VIRTIO_BALLOON_F_PAGE_REPORTING => 1 << 5,
+				Features::VIRTIO_F_RING_INDIRECT_DESC => 1 << 28,
+				Features::VIRTIO_F_RING_EVENT_IDX => 1 << 29,
+				Features::VIRTIO_F_VERSION_1 => 1 << 32,
+				Features::VIRTIO_F_ACCESS_PLATFORM => 1 << 33,
+				Features::VIRTIO_F_RING_PACKED => 1 << 34,
+				Features::VIRTIO_F_IN_ORDER => 1 << 35,
+				Features::VIRTIO_F_ORDER_PLATFORM => 1 << 36,
+				Features::VIRTIO_F_SR_IOV => 1 << 37,
+				Features::VIRTIO_F_NOTIFICATION_DATA => 1 << 38,
+			}
+		}
+	}
+
+	impl BitOr for Features {
+		type Output = u64;
+
+		fn bitor(self, rhs: Self) -> Self::Output {
+			u64::from(self) | u64::from(rhs)
+		}
+	}
+
+	impl BitOr<Features> for u64 {
+		type Output = u64;
+
+		fn bitor(self, rhs: Features) -> Self::Output {
+			self | u64::from(rhs)
+		}
+	}
+
+	impl BitOrAssign<Features> for u64 {
+		fn bitor_assign(&mut self, rhs: Features) {
+			*self |= u64::from(rhs);
+		}
+	}
+
+	impl BitAnd for Features {
+		type Output = u64;
+
+		fn bitand(self, rhs: Features) -> Self::Output {
+			u64::from(self) & u64::from(rhs)
+		}
+	}
+
+	impl BitAnd<Features> for u64 {
+		type Output = u64;
+
+		fn bitand(self, rhs: Features) -> Self::Output {
+			self & u64::from(rhs)
+		}
+	}
+
+	impl BitAndAssign<Features> for u64 {
+		fn bitand_assign(&mut self, rhs: Features) {
+			*self &= u64::from(rhs);
+		}
+	}
+
+	impl core::fmt::Display for Features {
+		fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+			match *self {
+				Features::VIRTIO_BALLOON_F_MUST_TELL_HOST => {
+					write!(f, "VIRTIO_BALLOON_F_MUST_TELL_HOST")
+				}
+				Features::VIRTIO_BALLOON_F_STATS_VQ => write!(f, "VIRTIO_BALLOON_F_STATS_VQ"),
+				Features::VIRTIO_BALLOON_F_DEFLATE_ON_OOM => {
+					write!(f, "VIRTIO_BALLOON_F_DEFLATE_ON_OOM")
+				}
+				Features::VIRTIO_BALLOON_F_FREE_PAGE_HINT => {
+					write!(f, "VIRTIO_BALLOON_F_FREE_PAGE_HINT")
+				}
+				Features::VIRTIO_BALLOON_F_PAGE_POISON => write!(f, "VIRTIO_BALLOON_F_PAGE_POISON"),
+				Features::VIRTIO_BALLOON_F_PAGE_REPORTING => {
+					write!(f, "VIRTIO_BALLOON_F_PAGE_REPORTING")
+				}
+				Features::VIRTIO_F_RING_INDIRECT_DESC => write!(f, "VIRTIO_F_RING_INDIRECT_DESC"),
+				Features::VIRTIO_F_RING_EVENT_IDX => write!(f, "VIRTIO_F_RING_EVENT_IDX"),
+				Features::VIRTIO_F_VERSION_1 => write!(f, "VIRTIO_F_VERSION_1"),
+				Features::VIRTIO_F_ACCESS_PLATFORM => write!(f, "VIRTIO_F_ACCESS_PLATFORM"),
+				Features::VIRTIO_F_RING_PACKED => write!(f, "VIRTIO_F_RING_PACKED"),
+				Features::VIRTIO_F_IN_ORDER => write!(f, "VIRTIO_F_IN_ORDER"),
+				Features::VIRTIO_F_ORDER_PLATFORM => write!(f, "VIRTIO_F_ORDER_PLATFORM"),
+				Features::VIRTIO_F_SR_IOV => write!(f, "VIRTIO_F_SR_IOV"),
+				Features::VIRTIO_F_NOTIFICATION_DATA => write!(f, "VIRTIO_F_NOTIFICATION_DATA"),
+			}
+		}
+	}
+
+	impl Features {
+		/// Return a vector of [Features] for a given input of a u64 representation.
+		///
+		/// INFO: In case the FEATURES enum is changed, this function MUST also be adjusted to the new set!
+		pub fn from_set(feat_set: FeatureSet) -> Option<Vec<Features>> {
+			let mut vec_of_feats: Vec<Features> = Vec::new();
+			let feats = feat_set.0;
+
+			if feats & (1 << 0) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BALLOON_F_MUST_TELL_HOST)
+			}
+			if feats & (1 << 1) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BALLOON_F_STATS_VQ)
+			}
+			if feats & (1 << 2) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BALLOON_F_DEFLATE_ON_OOM)
+			}
+			if feats & (1 << 3) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BALLOON_F_FREE_PAGE_HINT)
+			}
+			if feats & (1 << 4) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BALLOON_F_PAGE_POISON)
+			}
+			if feats & (1 << 5) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BALLOON_F_PAGE_REPORTING)
+			}
+			if feats & (1 << 28) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_RING_INDIRECT_DESC)
+			}
+			if feats & (1 << 29) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_RING_EVENT_IDX)
+			}
+			if feats & (1 << 32) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_VERSION_1)
+			}
+			if feats & (1 << 33) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_ACCESS_PLATFORM)
+			}
+			if feats & (1 << 34) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_RING_PACKED)
+			}
+			if feats & (1 << 35) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_IN_ORDER)
+			}
+			if feats & (1 << 36) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_ORDER_PLATFORM)
+			}
+			if feats & (1 << 37) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_SR_IOV)
+			}
+			if feats & (1 << 38) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_NOTIFICATION_DATA)
+			}
+
+			if vec_of_feats.is_empty() {
+				None
+			} else {
+				Some(vec_of_feats)
+			}
+		}
+	}
+
+	/// FeatureSet is a new type which holds features for virtio memory balloon devices indicated by the virtio specification
+	/// v1.2. - 5.5.3. and all General Features defined in Virtio specification v1.1. - 6
+	/// wrapping a u64.
+	///
+	/// The main functionality of this type are functions implemented on it.
+	#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+	pub struct FeatureSet(u64);
+
+	impl BitOr for FeatureSet {
+		type Output = FeatureSet;
+
+		fn bitor(self, rhs: Self) -> Self::Output {
+			FeatureSet(self.0 | rhs.0)
+		}
+	}
+
+	impl BitOr<FeatureSet> for u64 {
+		type Output = u64;
+
+		fn bitor(self, rhs: FeatureSet) -> Self::Output {
+			self | u64::from(rhs)
+		}
+	}
+
+	impl BitOrAssign<FeatureSet> for u64 {
+		fn bitor_assign(&mut self, rhs: FeatureSet) {
+			*self |= u64::from(rhs);
+		}
+	}
+
+	impl BitOrAssign<Features> for FeatureSet {
+		fn bitor_assign(&mut self, rhs: Features) {
+			self.0 = self.0 | u64::from(rhs);
+		}
+	}
+
+	impl BitAnd for FeatureSet {
+		type Output = FeatureSet;
+
+		fn bitand(self, rhs: FeatureSet) -> Self::Output {
+			FeatureSet(self.0 & rhs.0)
+		}
+	}
+
+	impl BitAnd<FeatureSet> for u64 {
+		type Output = u64;
+
+		fn bitand(self, rhs: FeatureSet) -> Self::Output {
+			self & u64::from(rhs)
+		}
+	}
+
+	impl BitAndAssign<FeatureSet> for u64 {
+		fn bitand_assign(&mut self, rhs: FeatureSet) {
+			*self &= u64::from(rhs);
+		}
+	}
+
+	impl From<FeatureSet> for u64 {
+		fn from(feature_set: FeatureSet) -> Self {
+			feature_set.0
+		}
+	}
+
+	impl FeatureSet {
+		/// Checks if a given feature is set.
+		pub fn is_feature(self, feat: Features) -> bool {
+			self.0 & feat != 0
+		}
+
+		/// Sets features contained in feats to true.
+		pub fn set_features(&mut self, feats: &[Features]) {
+			for feat in feats {
+				self.0 |= *feat;
+			}
+		}
+
+		/// Returns a new instance of (FeatureSet)[FeatureSet] with all features
+		/// initialized to false.
+		pub fn new(val: u64) -> Self {
+			FeatureSet(val)
+		}
+	}
+}
+
+/// Error module of virtios memory balloon driver.
+pub mod error {
+	use super::constants::FeatureSet;
+
+	/// Virtio memory balloon device error enum.
+	#[derive(Debug, Copy, Clone)]
+	pub enum VirtioBalloonError {
+		NoDevCfg(u16),
+		NoComCfg(u16),
+		NoIsrCfg(u16),
+		NoNotifCfg(u16),
+		FailFeatureNeg(u16),
+		/// The first u64 contains the feature bits wanted by the driver.
+		/// but which are incompatible with the device feature set, second u64.
+		IncompFeatsSet(FeatureSet, FeatureSet),
+		Unknown,
+	}
+}
@@ -0,0 +1,137 @@
+use alloc::vec::Vec;
+use core::ptr::{read_volatile, write_volatile};
+
+use crate::arch::mm::PhysAddr;
+use crate::arch::pci::PciConfigRegion;
+use crate::drivers::balloon::virtio_balloon::constants::FeatureSet;
+use crate::drivers::balloon::virtio_balloon::{BalloonDevCfg, VirtioBalloonDriver};
+use crate::drivers::pci::PciDevice;
+use crate::drivers::virtio::error::{self, VirtioError};
+use crate::drivers::virtio::transport::pci;
+use crate::drivers::virtio::transport::pci::{PciCap, UniCapsColl};
+
+/// Virtio's memory balloon device configuration structure.
+/// See specification v1.2. - 5.5.4
+///
+/// The fields `free_page_hint_cmd_id` and `poison_val` are omitted, as the
+/// device only provides them with features, which the driver doesn't negotiate.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub(crate) struct BalloonDevCfgRaw {
+	/// Number of pages, which the host wants the balloon to contain.
+	num_pages: u32,
+	/// Number of pages, which the driver has placed in the balloon.
+	actual: u32,
+}
+
+impl BalloonDevCfgRaw {
+	pub fn get_num_pages(&self) -> u32 {
+		u32::from_le(unsafe { read_volatile(&self.num_pages) })
+	}
+
+	pub fn set_actual(&mut self, pages: u32) {
+		unsafe { write_volatile(&mut self.actual, pages.to_le()) }
+	}
+}
+
+impl VirtioBalloonDriver {
+	fn map_cfg(cap: &PciCap) -> Option<BalloonDevCfg> {
+		let dev_cfg = pci::map_dev_cfg::<BalloonDevCfgRaw>(cap)?;
+
+		Some(BalloonDevCfg {
+			raw: dev_cfg,
+			dev_id: cap.dev_id(),
+			features: FeatureSet::new(0),
+		})
+	}
+
+	/// Instantiates a new (VirtioBalloonDriver)[VirtioBalloonDriver] struct, by checking the available
+	/// configuration structures and moving them into the struct.
+	pub fn new(
+		mut caps_coll: UniCapsColl,
+		device: &PciDevice<PciConfigRegion>,
+	) -> Result<Self, error::VirtioBalloonError> {
+		let device_id = device.device_id();
+
+		let com_cfg = match caps_coll.get_com_cfg() {
+			Some(com_cfg) => com_cfg,
+			None => {
+				error!("No common config. Aborting!");
+				return Err(error::VirtioBalloonError::NoComCfg(device_id));
+			}
+		};
+
+		let isr_stat = match caps_coll.get_isr_cfg() {
+			Some(isr_stat) => isr_stat,
+			None => {
+				error!("No ISR status config. Aborting!");
+				return Err(error::VirtioBalloonError::NoIsrCfg(device_id));
+			}
+		};
+
+		let notif_cfg = match caps_coll.get_notif_cfg() {
+			Some(notif_cfg) => notif_cfg,
+			None => {
+				error!("No notif config. Aborting!");
+				return Err(error::VirtioBalloonError::NoNotifCfg(device_id));
+			}
+		};
+
+		let dev_cfg = loop {
+			match caps_coll.get_dev_cfg() {
+				Some(cfg) => {
+					if let Some(dev_cfg) = VirtioBalloonDriver::map_cfg(&cfg) {
+						break dev_cfg;
+					}
+				}
+				None => {
+					error!("No dev config. Aborting!");
+					return Err(error::VirtioBalloonError::NoDevCfg(device_id));
+				}
+			}
+		};
+
+		Ok(VirtioBalloonDriver {
+			dev_cfg,
+			com_cfg,
+			isr_stat,
+			notif_cfg,
+			vqueues: Vec::new(),
+			irq: device.get_irq().unwrap(),
+			frames: Vec::new(),
+			target: 0,
+			report_cursor: PhysAddr(0),
+			next_report: 0,
+		})
+	}
+
+	/// Initializes virtio memory balloon device
+	pub fn init(device: &PciDevice<PciConfigRegion>) -> Result<VirtioBalloonDriver, VirtioError> {
+		let mut drv = match pci::map_caps(device) {
+			Ok(caps) => match VirtioBalloonDriver::new(caps, device) {
+				Ok(driver) => driver,
+				Err(balloon_err) => {
+					error!("Initializing new balloon driver failed. Aborting!");
+					return Err(VirtioError::BalloonDriver(balloon_err));
+				}
+			},
+			Err(pci_error) => {
+				error!("Mapping capabilities failed. Aborting!");
+				return Err(VirtioError::FromPci(pci_error));
+			}
+		};
+
+		match drv.init_dev() {
+			Ok(_) => info!(
+				"Balloon device with id {:x}, has been initialized by driver!",
+				drv.get_dev_id()
+			),
+			Err(balloon_err) => {
+				drv.set_failed();
+				return Err(VirtioError::BalloonDriver(balloon_err));
+			}
+		}
+
+		Ok(drv)
+	}
+}
@@ -0,0 +1,30 @@
+//! A module containing block device drivers and the block device interface,
+//! which is used by file systems to access the underlying storage.
+
+pub mod virtio_blk;
+#[cfg(not(feature = "pci"))]
+pub mod virtio_mmio;
+#[cfg(feature = "pci")]
+pub mod virtio_pci;
+
+use crate::fd::IoError;
+
+/// Size of a sector in bytes. All block devices are addressed in
+/// units of this size, independent of their physical block size.
+pub(crate) const SECTOR_SIZE: usize = 512;
+
+/// A trait for accessing a block device
+pub(crate) trait BlockDevice {
+	/// Returns the capacity of the device in sectors.
+	fn capacity(&self) -> u64;
+	/// Returns true, if the device rejects write requests.
+	fn is_read_only(&self) -> bool;
+	/// Reads `buf.len() / SECTOR_SIZE` sectors starting at `sector` into `buf`.
+	/// The length of `buf` must be a multiple of [`SECTOR_SIZE`].
+	fn read(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), IoError>;
+	/// Writes `buf.len() / SECTOR_SIZE` sectors starting at `sector`.
+	/// The length of `buf` must be a multiple of [`SECTOR_SIZE`].
+	fn write(&mut self, sector: u64, buf: &[u8]) -> Result<(), IoError>;
+	/// Flushes the write cache of the device.
+	fn flush(&mut self) -> Result<(), IoError>;
+}
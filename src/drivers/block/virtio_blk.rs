@@ -0,0 +1,611 @@
+//! A module containing a virtio block device driver.
+//!
+//! The driver uses a single request queue and processes one request at a time.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use pci_types::InterruptLine;
+
+use self::constants::{FeatureSet, Features, ReqType, Status, MAX_SECTORS_PER_REQUEST};
+use self::error::VirtioBlkError;
+use crate::config::VIRTIO_MAX_QUEUE_SIZE;
+#[cfg(not(feature = "pci"))]
+use crate::drivers::block::virtio_mmio::BlkDevCfgRaw;
+#[cfg(feature = "pci")]
+use crate::drivers::block::virtio_pci::BlkDevCfgRaw;
+use crate::drivers::block::{BlockDevice, SECTOR_SIZE};
+#[cfg(not(feature = "pci"))]
+use crate::drivers::virtio::transport::mmio::{ComCfg, IsrStatus, NotifCfg};
+#[cfg(feature = "pci")]
+use crate::drivers::virtio::transport::pci::{ComCfg, IsrStatus, NotifCfg};
+use crate::drivers::virtio::virtqueue::split::SplitVq;
+use crate::drivers::virtio::virtqueue::{AsSliceU8, BuffSpec, Bytes, Virtq, VqIndex, VqSize};
+use crate::fd::IoError;
+
+/// A wrapper struct for the raw configuration structure.
+/// Handling the right access to fields, as some are read-only
+/// for the driver.
+pub(crate) struct BlkDevCfg {
+	pub raw: &'static BlkDevCfgRaw,
+	pub dev_id: u16,
+	pub features: FeatureSet,
+}
+
+/// Header of a request to the device.
+/// See Virtio specification v1.1. - 5.2.6
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+struct BlkReqHeader {
+	req_type: u32,
+	reserved: u32,
+	sector: u64,
+}
+
+impl BlkReqHeader {
+	fn new(req_type: ReqType, sector: u64) -> Self {
+		Self {
+			req_type: u32::from(req_type).to_le(),
+			reserved: 0,
+			sector: sector.to_le(),
+		}
+	}
+}
+
+impl AsSliceU8 for BlkReqHeader {}
+
+/// Virtio block driver struct.
+///
+/// Struct allows to control devices virtqueues as also
+/// the device itself.
+#[allow(dead_code)]
+pub(crate) struct VirtioBlkDriver {
+	pub(super) dev_cfg: BlkDevCfg,
+	pub(super) com_cfg: ComCfg,
+	pub(super) isr_stat: IsrStatus,
+	pub(super) notif_cfg: NotifCfg,
+	pub(super) vqueues: Vec<Rc<dyn Virtq>>,
+	pub(super) irq: InterruptLine,
+}
+
+// Backend-independent interface for Virtio block driver
+impl VirtioBlkDriver {
+	pub fn get_dev_id(&self) -> u16 {
+		self.dev_cfg.dev_id
+	}
+
+	pub fn set_failed(&mut self) {
+		self.com_cfg.set_failed();
+	}
+
+	/// Negotiates a subset of features, understood and wanted by both the OS
+	/// and the device.
+	fn negotiate_features(&mut self, wanted_feats: &[Features]) -> Result<(), VirtioBlkError> {
+		let mut drv_feats = FeatureSet::new(0);
+
+		for feat in wanted_feats.iter() {
+			drv_feats |= *feat;
+		}
+
+		let dev_feats = FeatureSet::new(self.com_cfg.dev_features());
+
+		if (dev_feats & drv_feats) == drv_feats {
+			// If device supports subset of features write feature set to common config
+			self.com_cfg.set_drv_features(drv_feats.into());
+			Ok(())
+		} else {
+			Err(VirtioBlkError::IncompFeatsSet(drv_feats, dev_feats))
+		}
+	}
+
+	/// Initializes the device in adherence to specification. Returns Some(VirtioBlkError)
+	/// upon failure and None in case everything worked as expected.
+	///
+	/// See Virtio specification v1.1. - 3.1.1.
+	///                      and v1.1. - 5.2.5
+	pub(crate) fn init_dev(&mut self) -> Result<(), VirtioBlkError> {
+		// Reset
+		self.com_cfg.reset_dev();
+
+		// Indiacte device, that OS noticed it
+		self.com_cfg.ack_dev();
+
+		// Indicate device, that driver is able to handle it
+		self.com_cfg.set_drv();
+
+		// The read-only flag and the flush command are optional and
+		// only requested, if the device offers them.
+		let dev_feats = FeatureSet::new(self.com_cfg.dev_features());
+		let mut feats: Vec<Features> = vec![Features::VIRTIO_F_VERSION_1];
+		for feat in [Features::VIRTIO_BLK_F_RO, Features::VIRTIO_BLK_F_FLUSH] {
+			if dev_feats.is_feature(feat) {
+				feats.push(feat);
+			}
+		}
+		self.negotiate_features(&feats)?;
+
+		// Indicates the device, that the current feature set is final for the driver
+		// and will not be changed.
+		self.com_cfg.features_ok();
+
+		// Checks if the device has accepted final set. This finishes feature negotiation.
+		if self.com_cfg.check_features() {
+			info!(
+				"Features have been negotiated between virtio block device {:x} and driver.",
+				self.dev_cfg.dev_id
+			);
+			// Set feature set in device config fur future use.
+			self.dev_cfg.features.set_features(&feats);
+		} else {
+			return Err(VirtioBlkError::FailFeatureNeg(self.dev_cfg.dev_id));
+		}
+
+		// A single request queue is sufficient, as requests are processed synchronously
+		let vq = SplitVq::new(
+			&mut self.com_cfg,
+			&self.notif_cfg,
+			VqSize::from(VIRTIO_MAX_QUEUE_SIZE),
+			VqIndex::from(0u16),
+			self.dev_cfg.features.into(),
+		)
+		.map_err(|_| VirtioBlkError::Unknown)?;
+		self.vqueues.push(Rc::new(vq));
+
+		// At this point the device is "live"
+		self.com_cfg.drv_ok();
+
+		info!(
+			"Virtio block device {:x} has a capacity of {} sectors{}",
+			self.dev_cfg.dev_id,
+			self.capacity(),
+			if self.is_read_only() {
+				" and is read-only"
+			} else {
+				""
+			}
+		);
+
+		Ok(())
+	}
+
+	/// Sends `send` to the device and receives the answer into `recv`.
+	/// The last byte of `recv` holds the request status written by the device.
+	fn transfer(&mut self, send: &[u8], recv: &mut [u8]) -> Result<(), IoError> {
+		let send_spec = BuffSpec::Single(Bytes::new(send.len()).ok_or(IoError::EINVAL)?);
+		let recv_len = recv.len();
+		let recv_spec = BuffSpec::Single(Bytes::new(recv_len).ok_or(IoError::EINVAL)?);
+
+		let transfer_tkn = self.vqueues[0]
+			.clone()
+			.prep_transfer_from_raw(Some((send, send_spec)), Some((&mut *recv, recv_spec)))
+			.map_err(|_| IoError::EIO)?;
+		transfer_tkn.dispatch_blocking().map_err(|_| IoError::EIO)?;
+
+		match Status::from(recv[recv_len - 1]) {
+			Status::VIRTIO_BLK_S_OK => Ok(()),
+			Status::VIRTIO_BLK_S_UNSUPP => Err(IoError::ENOSYS),
+			Status::VIRTIO_BLK_S_IOERR => Err(IoError::EIO),
+		}
+	}
+
+	/// Checks that a request of `len` bytes starting at `sector` fits on the device.
+	fn check_request(&self, sector: u64, len: usize) -> Result<(), IoError> {
+		if len % SECTOR_SIZE != 0 {
+			return Err(IoError::EINVAL);
+		}
+
+		let end = sector
+			.checked_add((len / SECTOR_SIZE) as u64)
+			.ok_or(IoError::EINVAL)?;
+		if end > self.capacity() {
+			return Err(IoError::EINVAL);
+		}
+
+		Ok(())
+	}
+}
+
+impl BlockDevice for VirtioBlkDriver {
+	fn capacity(&self) -> u64 {
+		self.dev_cfg.raw.get_capacity()
+	}
+
+	fn is_read_only(&self) -> bool {
+		self.dev_cfg.features.is_feature(Features::VIRTIO_BLK_F_RO)
+	}
+
+	fn read(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), IoError> {
+		self.check_request(sector, buf.len())?;
+
+		let mut sector = sector;
+		for chunk in buf.chunks_mut(MAX_SECTORS_PER_REQUEST * SECTOR_SIZE) {
+			let header = BlkReqHeader::new(ReqType::VIRTIO_BLK_T_IN, sector);
+			// data and the status byte are written by the device
+			let mut recv = vec![0u8; chunk.len() + 1];
+			self.transfer(header.as_slice_u8(), &mut recv)?;
+			chunk.copy_from_slice(&recv[..chunk.len()]);
+			sector += (chunk.len() / SECTOR_SIZE) as u64;
+		}
+
+		Ok(())
+	}
+
+	fn write(&mut self, sector: u64, buf: &[u8]) -> Result<(), IoError> {
+		if self.is_read_only() {
+			return Err(IoError::EROFS);
+		}
+		self.check_request(sector, buf.len())?;
+
+		let mut sector = sector;
+		for chunk in buf.chunks(MAX_SECTORS_PER_REQUEST * SECTOR_SIZE) {
+			let header = BlkReqHeader::new(ReqType::VIRTIO_BLK_T_OUT, sector);
+			let mut send = Vec::with_capacity(header.len() + chunk.len());
+			send.extend_from_slice(header.as_slice_u8());
+			send.extend_from_slice(chunk);
+			let mut status = [0u8; 1];
+			self.transfer(&send, &mut status)?;
+			sector += (chunk.len() / SECTOR_SIZE) as u64;
+		}
+
+		Ok(())
+	}
+
+	fn flush(&mut self) -> Result<(), IoError> {
+		// Without VIRTIO_BLK_F_FLUSH the device does not cache writes
+		if !self
+			.dev_cfg
+			.features
+			.is_feature(Features::VIRTIO_BLK_F_FLUSH)
+		{
+			return Ok(());
+		}
+
+		let header = BlkReqHeader::new(ReqType::VIRTIO_BLK_T_FLUSH, 0);
+		let mut status = [0u8; 1];
+		self.transfer(header.as_slice_u8(), &mut status)
+	}
+}
+
+pub mod constants {
+	use alloc::vec::Vec;
+	use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+
+	/// Maximum number of sectors, which are transferred with a single request.
+	pub const MAX_SECTORS_PER_REQUEST: usize = 256;
+
+	/// Request types of the block device.
+	/// See Virtio specification v1.1. - 5.2.6
+	#[allow(dead_code, non_camel_case_types)]
+	#[derive(Copy, Clone, Debug)]
+	#[repr(u32)]
+	pub enum ReqType {
+		VIRTIO_BLK_T_IN = 0,
+		VIRTIO_BLK_T_OUT = 1,
+		VIRTIO_BLK_T_FLUSH = 4,
+	}
+
+	impl From<ReqType> for u32 {
+		fn from(val: ReqType) -> Self {
+			match val {
+				ReqType::VIRTIO_BLK_T_IN => 0,
+				ReqType::VIRTIO_BLK_T_OUT => 1,
+				ReqType::VIRTIO_BLK_T_FLUSH => 4,
+			}
+		}
+	}
+
+	/// Status of a finished request.
+	/// See Virtio specification v1.1. - 5.2.6
+	#[allow(dead_code, non_camel_case_types)]
+	#[derive(Copy, Clone, Debug)]
+	#[repr(u8)]
+	pub enum Status {
+		VIRTIO_BLK_S_OK = 0,
+		VIRTIO_BLK_S_IOERR = 1,
+		VIRTIO_BLK_S_UNSUPP = 2,
+	}
+
+	impl From<u8> for Status {
+		fn from(val: u8) -> Self {
+			match val {
+				0 => Status::VIRTIO_BLK_S_OK,
+				2 => Status::VIRTIO_BLK_S_UNSUPP,
+				_ => Status::VIRTIO_BLK_S_IOERR,
+			}
+		}
+	}
+
+	/// Enum contains virtio's block device features and general features of Virtio.
+	///
+	/// See Virtio specification v1.1. - 5.2.3
+	///
+	/// See Virtio specification v1.1. - 6
+	//
+	// WARN: In case the enum is changed, the static function of features `into_features(feat: u64) ->
+	// Option<Vec<Features>>` must also be adjusted to return a correct vector of features.
+	#[allow(dead_code, non_camel_case_types)]
+	#[derive(Copy, Clone, Debug)]
+	#[repr(u64)]
+	pub enum Features {
+		VIRTIO_BLK_F_SIZE_MAX = 1 << 1,
+		VIRTIO_BLK_F_SEG_MAX = 1 << 2,
+		VIRTIO_BLK_F_GEOMETRY = 1 << 4,
+		VIRTIO_BLK_F_RO = 1 << 5,
+		VIRTIO_BLK_F_BLK_SIZE = 1 << 6,
+		VIRTIO_BLK_F_FLUSH = 1 << 9,
+		VIRTIO_BLK_F_TOPOLOGY = 1 << 10,
+		VIRTIO_BLK_F_CONFIG_WCE = 1 << 11,
+		VIRTIO_F_RING_INDIRECT_DESC = 1 << 28,
+		VIRTIO_F_RING_EVENT_IDX = 1 << 29,
+		VIRTIO_F_VERSION_1 = 1 << 32,
+		VIRTIO_F_ACCESS_PLATFORM = 1 << 33,
+		VIRTIO_F_RING_PACKED = 1 << 34,
+		VIRTIO_F_IN_ORDER = 1 << 35,
+		VIRTIO_F_ORDER_PLATFORM = 1 << 36,
+		VIRTIO_F_SR_IOV = 1 << 37,
+		VIRTIO_F_NOTIFICATION_DATA = 1 << 38,
+	}
+
+	impl From<Features> for u64 {
+		fn from(val: Features) -> Self {
+			match val {
+				Features::VIRTIO_BLK_F_SIZE_MAX => 1 << 1,
+				Features::VIRTIO_BLK_F_SEG_MAX => 1 << 2,
+				Features::VIRTIO_BLK_F_GEOMETRY => 1 << 4,
+				Features::VIRTIO_BLK_F_RO => 1 << 5,
+				Features::VIRTIO_BLK_F_BLK_SIZE => 1 << 6,
+				Features::VIRTIO_BLK_F_FLUSH => 1 << 9,
+				Features::VIRTIO_BLK_F_TOPOLOGY => 1 << 10,
+				Features::VIRTIO_BLK_F_CONFIG_WCE => 1 << 11,
+				Features::VIRTIO_F_RING_INDIRECT_DESC => 1 << 28,
+				Features::VIRTIO_F_RING_EVENT_IDX => 1 << 29,
+				Features::VIRTIO_F_VERSION_1 => 1 << 32,
+				Features::VIRTIO_F_ACCESS_PLATFORM => 1 << 33,
+				Features::VIRTIO_F_RING_PACKED => 1 << 34,
+				Features::VIRTIO_F_IN_ORDER => 1 << 35,
+				Features::VIRTIO_F_ORDER_PLATFORM => 1 << 36,
+				Features::VIRTIO_F_SR_IOV => 1 << 37,
+				Features::VIRTIO_F_NOTIFICATION_DATA => 1 << 38,
+			}
+		}
+	}
+
+	impl BitOr for Features {
+		type Output = u64;
+
+		fn bitor(self, rhs: Self) -> Self::Output {
+			u64::from(self) | u64::from(rhs)
+		}
+	}
+
+	impl BitOr<Features> for u64 {
+		type Output = u64;
+
+		fn bitor(self, rhs: Features) -> Self::Output {
+			self | u64::from(rhs)
+		}
+	}
+
+	impl BitOrAssign<Features> for u64 {
+		fn bitor_assign(&mut self, rhs: Features) {
+			*self |= u64::from(rhs);
+		}
+	}
+
+	impl BitAnd for Features {
+		type Output = u64;
+
+		fn bitand(self, rhs: Features) -> Self::Output {
+			u64::from(self) & u64::from(rhs)
+		}
+	}
+
+	impl BitAnd<Features> for u64 {
+		type Output = u64;
+
+		fn bitand(self, rhs: Features) -> Self::Output {
+			self & u64::from(rhs)
+		}
+	}
+
+	impl BitAndAssign<Features> for u64 {
+		fn bitand_assign(&mut self, rhs: Features) {
+			*self &= u64::from(rhs);
+		}
+	}
+
+	impl core::fmt::Display for Features {
+		fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+			match *self {
+				Features::VIRTIO_BLK_F_SIZE_MAX => write!(f, "VIRTIO_BLK_F_SIZE_MAX"),
+				Features::VIRTIO_BLK_F_SEG_MAX => write!(f, "VIRTIO_BLK_F_SEG_MAX"),
+				Features::VIRTIO_BLK_F_GEOMETRY => write!(f, "VIRTIO_BLK_F_GEOMETRY"),
+				Features::VIRTIO_BLK_F_RO => write!(f, "VIRTIO_BLK_F_RO"),
+				Features::VIRTIO_BLK_F_BLK_SIZE => write!(f, "VIRTIO_BLK_F_BLK_SIZE"),
+				Features::VIRTIO_BLK_F_FLUSH => write!(f, "VIRTIO_BLK_F_FLUSH"),
+				Features::VIRTIO_BLK_F_TOPOLOGY => write!(f, "VIRTIO_BLK_F_TOPOLOGY"),
+				Features::VIRTIO_BLK_F_CONFIG_WCE => write!(f, "VIRTIO_BLK_F_CONFIG_WCE"),
+				Features::VIRTIO_F_RING_INDIRECT_DESC => write!(f, "VIRTIO_F_RING_INDIRECT_DESC"),
+				Features::VIRTIO_F_RING_EVENT_IDX => write!(f, "VIRTIO_F_RING_EVENT_IDX"),
+				Features::VIRTIO_F_VERSION_1 => write!(f, "VIRTIO_F_VERSION_1"),
+				Features::VIRTIO_F_ACCESS_PLATFORM => write!(f, "VIRTIO_F_ACCESS_PLATFORM"),
+				Features::VIRTIO_F_RING_PACKED => write!(f, "VIRTIO_F_RING_PACKED"),
+				Features::VIRTIO_F_IN_ORDER => write!(f, "VIRTIO_F_IN_ORDER"),
+				Features::VIRTIO_F_ORDER_PLATFORM => write!(f, "VIRTIO_F_ORDER_PLATFORM"),
+				Features::VIRTIO_F_SR_IOV => write!(f, "VIRTIO_F_SR_IOV"),
+				Features::VIRTIO_F_NOTIFICATION_DATA => write!(f, "VIRTIO_F_NOTIFICATION_DATA"),
+			}
+		}
+	}
+
+	impl Features {
+		/// Return a vector of [Features] for a given input of a u64 representation.
+		///
+		/// INFO: In case the FEATURES enum is changed, this function MUST also be adjusted to the new set!
+		pub fn from_set(feat_set: FeatureSet) -> Option<Vec<Features>> {
+			let mut vec_of_feats: Vec<Features> = Vec::new();
+			let feats = feat_set.0;
+
+			if feats & (1 << 1) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BLK_F_SIZE_MAX)
+			}
+			if feats & (1 << 2) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BLK_F_SEG_MAX)
+			}
+			if feats & (1 << 4) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BLK_F_GEOMETRY)
+			}
+			if feats & (1 << 5) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BLK_F_RO)
+			}
+			if feats & (1 << 6) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BLK_F_BLK_SIZE)
+			}
+			if feats & (1 << 9) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BLK_F_FLUSH)
+			}
+			if feats & (1 << 10) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BLK_F_TOPOLOGY)
+			}
+			if feats & (1 << 11) != 0 {
+				vec_of_feats.push(Features::VIRTIO_BLK_F_CONFIG_WCE)
+			}
+			if feats & (1 << 28) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_RING_INDIRECT_DESC)
+			}
+			if feats & (1 << 29) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_RING_EVENT_IDX)
+			}
+			if feats & (1 << 32) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_VERSION_1)
+			}
+			if feats & (1 << 33) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_ACCESS_PLATFORM)
+			}
+			if feats & (1 << 34) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_RING_PACKED)
+			}
+			if feats & (1 << 35) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_IN_ORDER)
+			}
+			if feats & (1 << 36) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_ORDER_PLATFORM)
+			}
+			if feats & (1 << 37) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_SR_IOV)
+			}
+			if feats & (1 << 38) != 0 {
+				vec_of_feats.push(Features::VIRTIO_F_NOTIFICATION_DATA)
+			}
+
+			if vec_of_feats.is_empty() {
+				None
+			} else {
+				Some(vec_of_feats)
+			}
+		}
+	}
+
+	/// FeatureSet is a new type which holds features for virtio block devices indicated by the virtio specification
+	/// v1.1. - 5.2.3. and all General Features defined in Virtio specification v1.1. - 6
+	/// wrapping a u64.
+	///
+	/// The main functionality of this type are functions implemented on it.
+	#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+	pub struct FeatureSet(u64);
+
+	impl BitOr for FeatureSet {
+		type Output = FeatureSet;
+
+		fn bitor(self, rhs: Self) -> Self::Output {
+			FeatureSet(self.0 | rhs.0)
+		}
+	}
+
+	impl BitOr<FeatureSet> for u64 {
+		type Output = u64;
+
+		fn bitor(self, rhs: FeatureSet) -> Self::Output {
+			self | u64::from(rhs)
+		}
+	}
+
+	impl BitOrAssign<FeatureSet> for u64 {
+		fn bitor_assign(&mut self, rhs: FeatureSet) {
+			*self |= u64::from(rhs);
+		}
+	}
+
+	impl BitOrAssign<Features> for FeatureSet {
+		fn bitor_assign(&mut self, rhs: Features) {
+			self.0 = self.0 | u64::from(rhs);
+		}
+	}
+
+	impl BitAnd for FeatureSet {
+		type Output = FeatureSet;
+
+		fn bitand(self, rhs: FeatureSet) -> Self::Output {
+			FeatureSet(self.0 & rhs.0)
+		}
+	}
+
+	impl BitAnd<FeatureSet> for u64 {
+		type Output = u64;
+
+		fn bitand(self, rhs: FeatureSet) -> Self::Output {
+			self & u64::from(rhs)
+		}
+	}
+
+	impl BitAndAssign<FeatureSet> for u64 {
+		fn bitand_assign(&mut self, rhs: FeatureSet) {
+			*self &= u64::from(rhs);
+		}
+	}
+
+	impl From<FeatureSet> for u64 {
+		fn from(feature_set: FeatureSet) -> Self {
+			feature_set.0
+		}
+	}
+
+	impl FeatureSet {
+		/// Checks if a given feature is set.
+		pub fn is_feature(self, feat: Features) -> bool {
+			self.0 & feat != 0
+		}
+
+		/// Sets features contained in feats to true.
+		pub fn set_features(&mut self, feats: &[Features]) {
+			for feat in feats {
+				self.0 |= *feat;
+			}
+		}
+
+		/// Returns a new instance of (FeatureSet)[FeatureSet] with all features
+		/// initialized to false.
+		pub fn new(val: u64) -> Self {
+			FeatureSet(val)
+		}
+	}
+}
+
+/// Error module of virtios block driver.
+pub mod error {
+	use super::constants::FeatureSet;
+
+	/// Virtio block device error enum.
+	#[derive(Debug, Copy, Clone)]
+	pub enum VirtioBlkError {
+		NoDevCfg(u16),
+		NoComCfg(u16),
+		NoIsrCfg(u16),
+		NoNotifCfg(u16),
+		FailFeatureNeg(u16),
+		/// The first u64 contains the feature bits wanted by the driver.
+		/// but which are incompatible with the device feature set, second u64.
+		IncompFeatsSet(FeatureSet, FeatureSet),
+		Unknown,
+	}
+}
@@ -0,0 +1,107 @@
+//! A module containing the MMIO specific parts of the virtio block driver.
+
+use alloc::vec::Vec;
+use core::ptr;
+use core::ptr::read_volatile;
+use core::sync::atomic::{fence, Ordering};
+
+use crate::drivers::block::virtio_blk::constants::FeatureSet;
+use crate::drivers::block::virtio_blk::error::VirtioBlkError;
+use crate::drivers::block::virtio_blk::{BlkDevCfg, VirtioBlkDriver};
+use crate::drivers::virtio::error::VirtioError;
+use crate::drivers::virtio::transport::mmio::{ComCfg, IsrStatus, MmioRegisterLayout, NotifCfg};
+
+/// Virtio's block device configuration structure.
+/// See specification v1.1. - 5.2.4
+///
+/// The capacity is split into two halves, because the structure starts
+/// with the config generation of the MMIO register layout.
+#[repr(C)]
+pub struct BlkDevCfgRaw {
+	config_generation: u32,
+	// Capacity of the device in 512-byte sectors
+	capacity_low: u32,
+	capacity_high: u32,
+	// Maximum size of any single segment. Only valid if VIRTIO_BLK_F_SIZE_MAX is set.
+	size_max: u32,
+	// Maximum number of segments in a request. Only valid if VIRTIO_BLK_F_SEG_MAX is set.
+	seg_max: u32,
+	// Geometry of the device. Only valid if VIRTIO_BLK_F_GEOMETRY is set.
+	cylinders: u16,
+	heads: u8,
+	sectors: u8,
+	// Block size of the device. Only valid if VIRTIO_BLK_F_BLK_SIZE is set.
+	blk_size: u32,
+}
+
+impl BlkDevCfgRaw {
+	pub fn get_capacity(&self) -> u64 {
+		// see Virtio specification v1.1 -  2.4.1
+		unsafe {
+			loop {
+				let before = read_volatile(&self.config_generation);
+				fence(Ordering::SeqCst);
+				let low = u32::from_le(read_volatile(&self.capacity_low));
+				let high = u32::from_le(read_volatile(&self.capacity_high));
+				fence(Ordering::SeqCst);
+				let after = read_volatile(&self.config_generation);
+
+				if before == after {
+					return (u64::from(high) << 32) | u64::from(low);
+				}
+			}
+		}
+	}
+}
+
+// Backend-dependent interface for Virtio block driver
+impl VirtioBlkDriver {
+	pub fn new(
+		dev_id: u16,
+		registers: &'static mut MmioRegisterLayout,
+		irq: u8,
+	) -> Result<Self, VirtioBlkError> {
+		let dev_cfg_raw: &'static BlkDevCfgRaw =
+			unsafe { &*(ptr::with_exposed_provenance(ptr::from_ref(registers).addr() + 0xFC)) };
+		let dev_cfg = BlkDevCfg {
+			raw: dev_cfg_raw,
+			dev_id,
+			features: FeatureSet::new(0),
+		};
+		let isr_stat = IsrStatus::new(registers);
+		let notif_cfg = NotifCfg::new(registers);
+
+		Ok(VirtioBlkDriver {
+			dev_cfg,
+			com_cfg: ComCfg::new(registers, 1),
+			isr_stat,
+			notif_cfg,
+			vqueues: Vec::new(),
+			irq,
+		})
+	}
+
+	/// Initializes virtio block device by mapping configuration layout to
+	/// respective structs.
+	///
+	/// Returns a driver instance of
+	/// [VirtioBlkDriver](structs.virtioblkdriver.html) or an [VirtioError](enums.virtioerror.html).
+	pub fn init(
+		dev_id: u16,
+		registers: &'static mut MmioRegisterLayout,
+		irq_no: u8,
+	) -> Result<VirtioBlkDriver, VirtioError> {
+		if let Ok(mut drv) = VirtioBlkDriver::new(dev_id, registers, irq_no) {
+			match drv.init_dev() {
+				Err(error_code) => {
+					drv.set_failed();
+					Err(VirtioError::BlkDriver(error_code))
+				}
+				_ => Ok(drv),
+			}
+		} else {
+			error!("Unable to create Driver. Aborting!");
+			Err(VirtioError::Unknown)
+		}
+	}
+}
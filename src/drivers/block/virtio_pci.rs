@@ -0,0 +1,137 @@
+use alloc::vec::Vec;
+use core::ptr::read_volatile;
+
+use crate::arch::pci::PciConfigRegion;
+use crate::drivers::block::virtio_blk::constants::FeatureSet;
+use crate::drivers::block::virtio_blk::{BlkDevCfg, VirtioBlkDriver};
+use crate::drivers::pci::PciDevice;
+use crate::drivers::virtio::error::{self, VirtioError};
+use crate::drivers::virtio::transport::pci;
+use crate::drivers::virtio::transport::pci::{PciCap, UniCapsColl};
+
+/// Virtio's block device configuration structure.
+/// See specification v1.1. - 5.2.4
+///
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub(crate) struct BlkDevCfgRaw {
+	/// Capacity of the device in 512-byte sectors.
+	capacity: u64,
+	/// Maximum size of any single segment. Only valid if VIRTIO_BLK_F_SIZE_MAX is set.
+	size_max: u32,
+	/// Maximum number of segments in a request. Only valid if VIRTIO_BLK_F_SEG_MAX is set.
+	seg_max: u32,
+	/// Geometry of the device. Only valid if VIRTIO_BLK_F_GEOMETRY is set.
+	cylinders: u16,
+	heads: u8,
+	sectors: u8,
+	/// Block size of the device. Only valid if VIRTIO_BLK_F_BLK_SIZE is set.
+	blk_size: u32,
+}
+
+impl BlkDevCfgRaw {
+	pub fn get_capacity(&self) -> u64 {
+		u64::from_le(unsafe { read_volatile(&self.capacity) })
+	}
+}
+
+impl VirtioBlkDriver {
+	fn map_cfg(cap: &PciCap) -> Option<BlkDevCfg> {
+		let dev_cfg: &'static BlkDevCfgRaw = match pci::map_dev_cfg::<BlkDevCfgRaw>(cap) {
+			Some(cfg) => cfg,
+			None => return None,
+		};
+
+		Some(BlkDevCfg {
+			raw: dev_cfg,
+			dev_id: cap.dev_id(),
+			features: FeatureSet::new(0),
+		})
+	}
+
+	/// Instantiates a new (VirtioBlkDriver)[VirtioBlkDriver] struct, by checking the available
+	/// configuration structures and moving them into the struct.
+	pub fn new(
+		mut caps_coll: UniCapsColl,
+		device: &PciDevice<PciConfigRegion>,
+	) -> Result<Self, error::VirtioBlkError> {
+		let device_id = device.device_id();
+
+		let com_cfg = match caps_coll.get_com_cfg() {
+			Some(com_cfg) => com_cfg,
+			None => {
+				error!("No common config. Aborting!");
+				return Err(error::VirtioBlkError::NoComCfg(device_id));
+			}
+		};
+
+		let isr_stat = match caps_coll.get_isr_cfg() {
+			Some(isr_stat) => isr_stat,
+			None => {
+				error!("No ISR status config. Aborting!");
+				return Err(error::VirtioBlkError::NoIsrCfg(device_id));
+			}
+		};
+
+		let notif_cfg = match caps_coll.get_notif_cfg() {
+			Some(notif_cfg) => notif_cfg,
+			None => {
+				error!("No notif config. Aborting!");
+				return Err(error::VirtioBlkError::NoNotifCfg(device_id));
+			}
+		};
+
+		let dev_cfg = loop {
+			match caps_coll.get_dev_cfg() {
+				Some(cfg) => {
+					if let Some(dev_cfg) = VirtioBlkDriver::map_cfg(&cfg) {
+						break dev_cfg;
+					}
+				}
+				None => {
+					error!("No dev config. Aborting!");
+					return Err(error::VirtioBlkError::NoDevCfg(device_id));
+				}
+			}
+		};
+
+		Ok(VirtioBlkDriver {
+			dev_cfg,
+			com_cfg,
+			isr_stat,
+			notif_cfg,
+			vqueues: Vec::new(),
+			irq: device.get_irq().unwrap(),
+		})
+	}
+
+	/// Initializes virtio block device
+	pub fn init(device: &PciDevice<PciConfigRegion>) -> Result<VirtioBlkDriver, VirtioError> {
+		let mut drv = match pci::map_caps(device) {
+			Ok(caps) => match VirtioBlkDriver::new(caps, device) {
+				Ok(driver) => driver,
+				Err(blk_err) => {
+					error!("Initializing new block driver failed. Aborting!");
+					return Err(VirtioError::BlkDriver(blk_err));
+				}
+			},
+			Err(pci_error) => {
+				error!("Mapping capabilities failed. Aborting!");
+				return Err(VirtioError::FromPci(pci_error));
+			}
+		};
+
+		match drv.init_dev() {
+			Ok(_) => info!(
+				"Block device with id {:x}, has been initialized by driver!",
+				drv.get_dev_id()
+			),
+			Err(blk_err) => {
+				drv.set_failed();
+				return Err(VirtioError::BlkDriver(blk_err));
+			}
+		}
+
+		Ok(drv)
+	}
+}
@@ -5,6 +5,7 @@ use alloc::vec::Vec;
 use pci_types::InterruptLine;
 
 use self::constants::{FeatureSet, Features};
+use crate::arch::mm::PhysAddr;
 use crate::config::VIRTIO_MAX_QUEUE_SIZE;
 #[cfg(feature = "pci")]
 use crate::drivers::fs::virtio_pci::FsDevCfgRaw;
@@ -17,6 +18,45 @@ use crate::drivers::virtio::virtqueue::error::VirtqError;
 use crate::drivers::virtio::virtqueue::split::SplitVq;
 use crate::drivers::virtio::virtqueue::{AsSliceU8, BuffSpec, Bytes, Virtq, VqIndex, VqSize};
 use crate::fs::fuse::{self, FuseInterface};
+use crate::mm::freelist::{FreeList, FreeListEntry};
+
+/// Id of the shared memory region, which is used as DAX window.
+/// See Virtio specification v1.2. - 5.11.6.4
+pub(crate) const VIRTIO_FS_SHMCAP_ID_CACHE: u8 = 0;
+
+/// Window of the device memory, in which the host maps the content of files
+/// (DAX). Mappings are allocated from the window and set up by FUSE requests.
+pub(crate) struct DaxWindow {
+	/// Physical address of the window
+	addr: PhysAddr,
+	/// Free ranges, given as offsets within the window
+	free: FreeList,
+}
+
+impl DaxWindow {
+	pub fn new(addr: PhysAddr, len: usize) -> Self {
+		let mut free = FreeList::new();
+		free.push(FreeListEntry::new(0, len));
+
+		Self { addr, free }
+	}
+
+	/// Allocates `len` bytes of the window at an offset, which is aligned
+	/// to `alignment`, and returns the offset
+	pub fn allocate(&mut self, len: usize, alignment: usize) -> Option<usize> {
+		self.free.allocate(len, Some(alignment)).ok()
+	}
+
+	/// Releases `len` bytes at `offset` of the window
+	pub fn deallocate(&mut self, offset: usize, len: usize) {
+		self.free.deallocate(offset, len);
+	}
+
+	/// Returns the physical address of the window
+	pub fn addr(&self) -> PhysAddr {
+		self.addr
+	}
+}
 
 /// A wrapper struct for the raw configuration structure.
 /// Handling the right access to fields, as some are read-only
@@ -39,6 +79,7 @@ pub(crate) struct VirtioFsDriver {
 	pub(super) notif_cfg: NotifCfg,
 	pub(super) vqueues: Vec<Rc<dyn Virtq>>,
 	pub(super) irq: InterruptLine,
+	pub(super) dax: Option<DaxWindow>,
 }
 
 // Backend-independent interface for Virtio network driver
@@ -169,6 +210,10 @@ impl FuseInterface for VirtioFsDriver {
 	fn get_mount_point(&self) -> String {
 		self.dev_cfg.raw.get_tag().to_string()
 	}
+
+	fn get_dax_window(&mut self) -> Option<&mut DaxWindow> {
+		self.dax.as_mut()
+	}
 }
 
 pub mod constants {
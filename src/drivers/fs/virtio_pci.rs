@@ -1,8 +1,13 @@
 use alloc::vec::Vec;
+use core::mem;
 
+use crate::arch::mm::paging::virtual_to_physical;
+use crate::arch::mm::VirtAddr;
 use crate::arch::pci::PciConfigRegion;
 use crate::drivers::fs::virtio_fs::constants::FeatureSet;
-use crate::drivers::fs::virtio_fs::{FsDevCfg, VirtioFsDriver};
+use crate::drivers::fs::virtio_fs::{
+	DaxWindow, FsDevCfg, VirtioFsDriver, VIRTIO_FS_SHMCAP_ID_CACHE,
+};
 use crate::drivers::pci::PciDevice;
 use crate::drivers::virtio::error::{self, VirtioError};
 use crate::drivers::virtio::transport::pci;
@@ -106,6 +111,20 @@ impl VirtioFsDriver {
 			}
 		};
 
+		let dax = caps_coll
+			.get_sh_mem_cfg(VIRTIO_FS_SHMCAP_ID_CACHE)
+			.and_then(|cfg| {
+				let addr = VirtAddr(usize::from(cfg.mem_addr()).try_into().unwrap());
+				let len = usize::from(cfg.length());
+				// The window is only accessible, if the host has set up a
+				// mapping. Hence, it must not be cleared on drop.
+				mem::forget(cfg);
+
+				let addr = virtual_to_physical(addr)?;
+				info!("DAX window of {:#x} bytes at {:p}", len, addr);
+				Some(DaxWindow::new(addr, len))
+			});
+
 		Ok(VirtioFsDriver {
 			dev_cfg,
 			com_cfg,
@@ -113,6 +132,7 @@ impl VirtioFsDriver {
 			notif_cfg,
 			vqueues: Vec::new(),
 			irq: device.get_irq().unwrap(),
+			dax,
 		})
 	}
 
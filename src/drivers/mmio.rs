@@ -1,2 +1,4 @@
+#[cfg(feature = "blk")]
+pub(crate) use crate::arch::kernel::mmio::get_block_driver;
 #[cfg(any(feature = "tcp", feature = "udp"))]
-pub(crate) use crate::arch::kernel::mmio::get_network_driver;
+pub(crate) use crate::arch::kernel::mmio::get_network_drivers;
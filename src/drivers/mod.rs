@@ -1,5 +1,9 @@
 //! A module containing hermit-rs driver, hermit-rs driver trait and driver specific errors.
 
+#[cfg(feature = "balloon")]
+pub mod balloon;
+#[cfg(feature = "blk")]
+pub mod block;
 #[cfg(feature = "fuse")]
 pub mod fs;
 #[cfg(not(feature = "pci"))]
@@ -10,7 +14,9 @@ pub mod net;
 pub mod pci;
 #[cfg(any(
 	all(any(feature = "tcp", feature = "udp"), not(feature = "rtl8139")),
-	feature = "fuse"
+	feature = "fuse",
+	feature = "blk",
+	feature = "balloon"
 ))]
 pub mod virtio;
 
@@ -26,7 +32,9 @@ pub mod error {
 	use crate::drivers::net::rtl8139::RTL8139Error;
 	#[cfg(any(
 		all(any(feature = "tcp", feature = "udp"), not(feature = "rtl8139")),
-		feature = "fuse"
+		feature = "fuse",
+		feature = "blk",
+		feature = "balloon"
 	))]
 	use crate::drivers::virtio::error::VirtioError;
 
@@ -34,7 +42,9 @@ pub mod error {
 	pub enum DriverError {
 		#[cfg(any(
 			all(any(feature = "tcp", feature = "udp"), not(feature = "rtl8139")),
-			feature = "fuse"
+			feature = "fuse",
+			feature = "blk",
+			feature = "balloon"
 		))]
 		InitVirtioDevFail(VirtioError),
 		#[cfg(feature = "rtl8139")]
@@ -45,7 +55,9 @@ pub mod error {
 
 	#[cfg(any(
 		all(any(feature = "tcp", feature = "udp"), not(feature = "rtl8139")),
-		feature = "fuse"
+		feature = "fuse",
+		feature = "blk",
+		feature = "balloon"
 	))]
 	impl From<VirtioError> for DriverError {
 		fn from(err: VirtioError) -> Self {
@@ -73,7 +85,9 @@ pub mod error {
 			match *self {
 				#[cfg(any(
 					all(any(feature = "tcp", feature = "udp"), not(feature = "rtl8139")),
-					feature = "fuse"
+					feature = "fuse",
+					feature = "blk",
+					feature = "balloon"
 				))]
 				DriverError::InitVirtioDevFail(ref err) => {
 					write!(f, "Virtio driver failed: {err:?}")
@@ -19,8 +19,8 @@ use crate::arch::kernel::interrupts::*;
 use crate::arch::mm::paging::virt_to_phys;
 use crate::arch::mm::VirtAddr;
 use crate::drivers::error::DriverError;
-use crate::drivers::net::{network_irqhandler, NetworkDriver};
-use crate::executor::device::{RxToken, TxToken};
+use crate::drivers::net::{network_irqhandler, NetworkDriver, NetworkStats};
+use crate::executor::device::RxToken;
 
 //Base address of the control registers
 //const GEM: *mut Registers = 0x1009_0000 as *mut Registers; //For Sifive FU540
@@ -213,6 +213,7 @@ pub struct GEMDriver {
 	tx_counter: u32,
 	txbuffer: VirtAddr,
 	txbuffer_list: VirtAddr,
+	stats: NetworkStats,
 }
 
 impl NetworkDriver for GEMDriver {
@@ -289,6 +290,7 @@ impl NetworkDriver for GEMDriver {
 				unsafe {
 					core::ptr::write_volatile(word1_addr, word1 | TX_DESC_USED);
 				}
+				self.stats.count_tx(len);
 
 				return result;
 			}
@@ -297,13 +299,17 @@ impl NetworkDriver for GEMDriver {
 		panic!("Unable to get TX buffer")
 	}
 
+	fn get_stats(&self) -> NetworkStats {
+		self.stats
+	}
+
 	fn has_packet(&self) -> bool {
 		debug!("has_packet");
 
 		self.next_rx_index().is_some()
 	}
 
-	fn receive_packet(&mut self) -> Option<(RxToken, TxToken)> {
+	fn receive_packet(&mut self) -> Option<RxToken> {
 		debug!("receive_rx_buffer");
 
 		// Scan the buffer descriptor queue starting from rx_count
@@ -328,7 +334,8 @@ impl NetworkDriver for GEMDriver {
 				};
 				trace!("BUFFER: {:x?}", buffer);
 				self.rx_buffer_consumed(index as usize);
-				Some((RxToken::new(buffer.to_vec()), TxToken::new()))
+				self.stats.count_rx(buffer.len());
+				Some(RxToken::new(buffer.to_vec()))
 			}
 			None => None,
 		}
@@ -702,6 +709,7 @@ pub fn init_device(
 		tx_counter: 0,
 		txbuffer,
 		txbuffer_list,
+		stats: NetworkStats::default(),
 	})
 }
 
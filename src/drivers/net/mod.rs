@@ -23,7 +23,34 @@ use crate::arch::kernel::mmio as hardware;
 use crate::arch::scheduler::State;
 #[cfg(feature = "pci")]
 use crate::drivers::pci as hardware;
-use crate::executor::device::{RxToken, TxToken};
+use crate::executor::device::RxToken;
+
+/// Packet, byte and error counters of a network device
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NetworkStats {
+	pub rx_packets: u64,
+	pub rx_bytes: u64,
+	pub rx_errors: u64,
+	pub rx_dropped: u64,
+	pub tx_packets: u64,
+	pub tx_bytes: u64,
+	pub tx_errors: u64,
+	pub tx_dropped: u64,
+}
+
+impl NetworkStats {
+	/// Counts a received packet with the size `len`
+	pub(crate) fn count_rx(&mut self, len: usize) {
+		self.rx_packets += 1;
+		self.rx_bytes += len as u64;
+	}
+
+	/// Counts a sent packet with the size `len`
+	pub(crate) fn count_tx(&mut self, len: usize) {
+		self.tx_packets += 1;
+		self.tx_bytes += len as u64;
+	}
+}
 
 /// A trait for accessing the network interface
 pub(crate) trait NetworkDriver {
@@ -36,11 +63,13 @@ pub(crate) trait NetworkDriver {
 	/// Returns the current MTU of the device.
 	fn get_mtu(&self) -> u16;
 	/// Get buffer with the received packet
-	fn receive_packet(&mut self) -> Option<(RxToken, TxToken)>;
+	fn receive_packet(&mut self) -> Option<RxToken>;
 	/// Send packet with the size `len`
 	fn send_packet<R, F>(&mut self, len: usize, f: F) -> R
 	where
 		F: FnOnce(&mut [u8]) -> R;
+	/// Returns the packet and error counters of the device
+	fn get_stats(&self) -> NetworkStats;
 	/// Check if a packet is available
 	#[allow(dead_code)]
 	fn has_packet(&self) -> bool;
@@ -52,12 +81,10 @@ pub(crate) trait NetworkDriver {
 
 #[inline]
 fn _irqhandler() -> bool {
-	let result = if let Some(driver) = hardware::get_network_driver() {
-		driver.lock().handle_interrupt()
-	} else {
-		debug!("Unable to handle interrupt!");
-		false
-	};
+	// the devices can share an interrupt line => ask every device
+	let result = hardware::get_network_drivers().fold(false, |result, driver| {
+		driver.lock().handle_interrupt() || result
+	});
 
 	// TODO: do we need it?
 	crate::executor::run();
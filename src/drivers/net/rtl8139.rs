@@ -14,9 +14,9 @@ use crate::arch::mm::paging::virt_to_phys;
 use crate::arch::mm::VirtAddr;
 use crate::arch::pci::PciConfigRegion;
 use crate::drivers::error::DriverError;
-use crate::drivers::net::{network_irqhandler, NetworkDriver};
+use crate::drivers::net::{network_irqhandler, NetworkDriver, NetworkStats};
 use crate::drivers::pci::{PciCommand, PciDevice};
-use crate::executor::device::{RxToken, TxToken};
+use crate::executor::device::RxToken;
 
 /// size of the receive buffer
 const RX_BUF_LEN: usize = 8192;
@@ -211,6 +211,7 @@ pub(crate) struct RTL8139Driver {
 	rxbuffer: Box<[u8]>,
 	rxpos: usize,
 	txbuffer: Box<[u8]>,
+	stats: NetworkStats,
 }
 
 impl NetworkDriver for RTL8139Driver {
@@ -247,11 +248,16 @@ impl NetworkDriver for RTL8139Driver {
 					len.try_into().unwrap(),
 				); //|0x3A0000);
 			}
+			self.stats.count_tx(len);
 
 			result
 		}
 	}
 
+	fn get_stats(&self) -> NetworkStats {
+		self.stats
+	}
+
 	fn has_packet(&self) -> bool {
 		let cmd = unsafe { inb(self.iobase + CR) };
 
@@ -267,7 +273,7 @@ impl NetworkDriver for RTL8139Driver {
 	}
 
 	/// Get buffer with the received packet
-	fn receive_packet(&mut self) -> Option<(RxToken, TxToken)> {
+	fn receive_packet(&mut self) -> Option<RxToken> {
 		let cmd = unsafe { inb(self.iobase + CR) };
 
 		if (cmd & CR_BUFE) != CR_BUFE {
@@ -289,13 +295,15 @@ impl NetworkDriver for RTL8139Driver {
 				};
 
 				self.consume_current_buffer();
+				self.stats.count_rx(vec_data.len());
 
-				Some((RxToken::new(vec_data), TxToken::new()))
+				Some(RxToken::new(vec_data))
 			} else {
 				warn!(
 					"RTL8192: invalid header {:#x}, rx_pos {}\n",
 					header, self.rxpos
 				);
+				self.stats.rx_errors += 1;
 
 				None
 			}
@@ -328,14 +336,17 @@ impl NetworkDriver for RTL8139Driver {
 
 		if (isr_contents & ISR_RER) == ISR_RER {
 			error!("RTL88139: RX error detected!\n");
+			self.stats.rx_errors += 1;
 		}
 
 		if (isr_contents & ISR_TER) == ISR_TER {
 			trace!("RTL88139r: TX error detected!\n");
+			self.stats.tx_errors += 1;
 		}
 
 		if (isr_contents & ISR_RXOVW) == ISR_RXOVW {
 			trace!("RTL88139: RX overflow detected!\n");
+			self.stats.rx_dropped += 1;
 		}
 
 		let ret = (isr_contents & ISR_ROK) == ISR_ROK;
@@ -586,5 +597,6 @@ pub(crate) fn init_device(
 		rxbuffer,
 		rxpos: 0,
 		txbuffer,
+		stats: NetworkStats::default(),
 	})
 }
@@ -15,6 +15,7 @@ use smoltcp::phy::ChecksumCapabilities;
 
 use crate::drivers::net::virtio_net::constants::{FeatureSet, Status};
 use crate::drivers::net::virtio_net::{CtrlQueue, NetDevCfg, RxQueues, TxQueues, VirtioNetDriver};
+use crate::drivers::net::NetworkStats;
 use crate::drivers::virtio::error::{VirtioError, VirtioNetError};
 use crate::drivers::virtio::transport::mmio::{ComCfg, IsrStatus, MmioRegisterLayout, NotifCfg};
 use crate::drivers::virtio::virtqueue::Virtq;
@@ -153,6 +154,7 @@ impl VirtioNetDriver {
 			irq,
 			mtu,
 			checksums: ChecksumCapabilities::default(),
+			stats: NetworkStats::default(),
 		})
 	}
 
@@ -24,7 +24,7 @@ use crate::config::VIRTIO_MAX_QUEUE_SIZE;
 use crate::drivers::net::virtio_mmio::NetDevCfgRaw;
 #[cfg(feature = "pci")]
 use crate::drivers::net::virtio_pci::NetDevCfgRaw;
-use crate::drivers::net::NetworkDriver;
+use crate::drivers::net::{NetworkDriver, NetworkStats};
 #[cfg(not(feature = "pci"))]
 use crate::drivers::virtio::transport::mmio::{ComCfg, IsrStatus, NotifCfg};
 #[cfg(feature = "pci")]
@@ -34,7 +34,7 @@ use crate::drivers::virtio::virtqueue::split::SplitVq;
 use crate::drivers::virtio::virtqueue::{
 	BuffSpec, BufferToken, Bytes, Transfer, Virtq, VqIndex, VqSize,
 };
-use crate::executor::device::{RxToken, TxToken};
+use crate::executor::device::RxToken;
 
 /// A wrapper struct for the raw configuration structure.
 /// Handling the right access to fields, as some are read-only
@@ -466,6 +466,7 @@ pub(crate) struct VirtioNetDriver {
 	pub(super) irq: InterruptLine,
 	pub(super) mtu: u16,
 	pub(super) checksums: ChecksumCapabilities,
+	pub(super) stats: NetworkStats,
 }
 
 impl NetworkDriver for VirtioNetDriver {
@@ -488,6 +489,10 @@ impl NetworkDriver for VirtioNetDriver {
 		self.checksums.clone()
 	}
 
+	fn get_stats(&self) -> NetworkStats {
+		self.stats
+	}
+
 	#[allow(dead_code)]
 	fn has_packet(&self) -> bool {
 		self.recv_vqs.poll();
@@ -555,6 +560,7 @@ impl NetworkDriver for VirtioNetDriver {
 			buff_tkn
 				.provide()
 				.dispatch_await(Rc::clone(&self.send_vqs.poll_queue), false);
+			self.stats.count_tx(len);
 
 			result
 		} else {
@@ -562,13 +568,14 @@ impl NetworkDriver for VirtioNetDriver {
 		}
 	}
 
-	fn receive_packet(&mut self) -> Option<(RxToken, TxToken)> {
+	fn receive_packet(&mut self) -> Option<RxToken> {
 		match self.recv_vqs.get_next() {
 			Some(transfer) => {
 				let transfer = match RxQueues::post_processing(transfer) {
 					Ok(trf) => trf,
 					Err(vnet_err) => {
 						warn!("Post processing failed. Err: {:?}", vnet_err);
+						self.stats.rx_errors += 1;
 						return None;
 					}
 				};
@@ -590,6 +597,7 @@ impl NetworkDriver for VirtioNetDriver {
 								.unwrap()
 								.provide()
 								.dispatch_await(Rc::clone(&self.recv_vqs.poll_queue), false);
+							self.stats.rx_dropped += 1;
 
 							return None;
 						}
@@ -618,6 +626,7 @@ impl NetworkDriver for VirtioNetDriver {
 								Ok(trf) => trf,
 								Err(vnet_err) => {
 									warn!("Post processing failed. Err: {:?}", vnet_err);
+									self.stats.rx_errors += 1;
 									return None;
 								}
 							};
@@ -633,7 +642,8 @@ impl NetworkDriver for VirtioNetDriver {
 							.dispatch_await(Rc::clone(&self.recv_vqs.poll_queue), false);
 					}
 
-					Some((RxToken::new(vec_data), TxToken::new()))
+					self.stats.count_rx(vec_data.len());
+					Some(RxToken::new(vec_data))
 				} else {
 					error!("Empty transfer, or with wrong buffer layout. Reusing and returning error to user-space network driver...");
 					transfer
@@ -643,6 +653,7 @@ impl NetworkDriver for VirtioNetDriver {
 						.unwrap()
 						.provide()
 						.dispatch_await(Rc::clone(&self.recv_vqs.poll_queue), false);
+					self.stats.rx_errors += 1;
 
 					None
 				}
@@ -13,6 +13,7 @@ use smoltcp::phy::ChecksumCapabilities;
 use crate::arch::pci::PciConfigRegion;
 use crate::drivers::net::virtio_net::constants::FeatureSet;
 use crate::drivers::net::virtio_net::{CtrlQueue, NetDevCfg, RxQueues, TxQueues, VirtioNetDriver};
+use crate::drivers::net::NetworkStats;
 use crate::drivers::pci::{PciCommand, PciDevice};
 use crate::drivers::virtio::error::{self, VirtioError};
 use crate::drivers::virtio::transport::pci;
@@ -157,6 +158,7 @@ impl VirtioNetDriver {
 			irq: device.get_irq().unwrap(),
 			mtu,
 			checksums: ChecksumCapabilities::default(),
+			stats: NetworkStats::default(),
 		})
 	}
 
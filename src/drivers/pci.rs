@@ -5,7 +5,13 @@ use core::fmt;
 
 use bitflags::bitflags;
 use hermit_sync::without_interrupts;
-#[cfg(any(feature = "tcp", feature = "udp", feature = "fuse"))]
+#[cfg(any(
+	feature = "tcp",
+	feature = "udp",
+	feature = "fuse",
+	feature = "blk",
+	feature = "balloon"
+))]
 use hermit_sync::InterruptTicketMutex;
 use pci_types::{
 	Bar, ConfigRegionAccess, DeviceId, EndpointHeader, InterruptLine, InterruptPin, PciAddress,
@@ -14,6 +20,10 @@ use pci_types::{
 
 use crate::arch::mm::{PhysAddr, VirtAddr};
 use crate::arch::pci::PciConfigRegion;
+#[cfg(feature = "balloon")]
+use crate::drivers::balloon::virtio_balloon::VirtioBalloonDriver;
+#[cfg(feature = "blk")]
+use crate::drivers::block::virtio_blk::VirtioBlkDriver;
 #[cfg(feature = "fuse")]
 use crate::drivers::fs::virtio_fs::VirtioFsDriver;
 #[cfg(feature = "rtl8139")]
@@ -22,12 +32,16 @@ use crate::drivers::net::rtl8139::{self, RTL8139Driver};
 use crate::drivers::net::virtio_net::VirtioNetDriver;
 #[cfg(any(
 	all(any(feature = "tcp", feature = "udp"), not(feature = "rtl8139")),
-	feature = "fuse"
+	feature = "fuse",
+	feature = "blk",
+	feature = "balloon"
 ))]
 use crate::drivers::virtio::transport::pci as pci_virtio;
 #[cfg(any(
 	all(any(feature = "tcp", feature = "udp"), not(feature = "rtl8139")),
-	feature = "fuse"
+	feature = "fuse",
+	feature = "blk",
+	feature = "balloon"
 ))]
 use crate::drivers::virtio::transport::pci::VirtioDriver;
 
@@ -468,6 +482,10 @@ pub(crate) fn print_information() {
 pub(crate) enum PciDriver {
 	#[cfg(feature = "fuse")]
 	VirtioFs(InterruptTicketMutex<VirtioFsDriver>),
+	#[cfg(feature = "blk")]
+	VirtioBlk(InterruptTicketMutex<VirtioBlkDriver>),
+	#[cfg(feature = "balloon")]
+	VirtioBalloon(InterruptTicketMutex<VirtioBalloonDriver>),
 	#[cfg(all(not(feature = "rtl8139"), any(feature = "tcp", feature = "udp")))]
 	VirtioNet(InterruptTicketMutex<VirtioNetDriver>),
 	#[cfg(all(feature = "rtl8139", any(feature = "tcp", feature = "udp")))]
@@ -501,6 +519,24 @@ impl PciDriver {
 			_ => None,
 		}
 	}
+
+	#[cfg(feature = "blk")]
+	fn get_block_driver(&self) -> Option<&InterruptTicketMutex<VirtioBlkDriver>> {
+		match self {
+			Self::VirtioBlk(drv) => Some(drv),
+			#[allow(unreachable_patterns)]
+			_ => None,
+		}
+	}
+
+	#[cfg(feature = "balloon")]
+	fn get_balloon_driver(&self) -> Option<&InterruptTicketMutex<VirtioBalloonDriver>> {
+		match self {
+			Self::VirtioBalloon(drv) => Some(drv),
+			#[allow(unreachable_patterns)]
+			_ => None,
+		}
+	}
 }
 
 pub(crate) fn register_driver(drv: PciDriver) {
@@ -510,13 +546,23 @@ pub(crate) fn register_driver(drv: PciDriver) {
 }
 
 #[cfg(all(not(feature = "rtl8139"), any(feature = "tcp", feature = "udp")))]
-pub(crate) fn get_network_driver() -> Option<&'static InterruptTicketMutex<VirtioNetDriver>> {
-	unsafe { PCI_DRIVERS.iter().find_map(|drv| drv.get_network_driver()) }
+pub(crate) fn get_network_drivers(
+) -> impl Iterator<Item = &'static InterruptTicketMutex<VirtioNetDriver>> {
+	unsafe {
+		PCI_DRIVERS
+			.iter()
+			.filter_map(|drv| drv.get_network_driver())
+	}
 }
 
 #[cfg(all(feature = "rtl8139", any(feature = "tcp", feature = "udp")))]
-pub(crate) fn get_network_driver() -> Option<&'static InterruptTicketMutex<RTL8139Driver>> {
-	unsafe { PCI_DRIVERS.iter().find_map(|drv| drv.get_network_driver()) }
+pub(crate) fn get_network_drivers(
+) -> impl Iterator<Item = &'static InterruptTicketMutex<RTL8139Driver>> {
+	unsafe {
+		PCI_DRIVERS
+			.iter()
+			.filter_map(|drv| drv.get_network_driver())
+	}
 }
 
 #[cfg(feature = "fuse")]
@@ -528,6 +574,16 @@ pub(crate) fn get_filesystem_driver() -> Option<&'static InterruptTicketMutex<Vi
 	}
 }
 
+#[cfg(feature = "blk")]
+pub(crate) fn get_block_driver() -> Option<&'static InterruptTicketMutex<VirtioBlkDriver>> {
+	unsafe { PCI_DRIVERS.iter().find_map(|drv| drv.get_block_driver()) }
+}
+
+#[cfg(feature = "balloon")]
+pub(crate) fn get_balloon_driver() -> Option<&'static InterruptTicketMutex<VirtioBalloonDriver>> {
+	unsafe { PCI_DRIVERS.iter().find_map(|drv| drv.get_balloon_driver()) }
+}
+
 pub(crate) fn init_drivers() {
 	// virtio: 4.1.2 PCI Device Discovery
 	without_interrupts(|| {
@@ -544,7 +600,9 @@ pub(crate) fn init_drivers() {
 
 			#[cfg(any(
 				all(any(feature = "tcp", feature = "udp"), not(feature = "rtl8139")),
-				feature = "fuse"
+				feature = "fuse",
+				feature = "blk",
+				feature = "balloon"
 			))]
 			match pci_virtio::init_device(adapter) {
 				#[cfg(all(not(feature = "rtl8139"), any(feature = "tcp", feature = "udp")))]
@@ -555,6 +613,14 @@ pub(crate) fn init_drivers() {
 				Ok(VirtioDriver::FileSystem(drv)) => {
 					register_driver(PciDriver::VirtioFs(InterruptTicketMutex::new(drv)))
 				}
+				#[cfg(feature = "blk")]
+				Ok(VirtioDriver::Block(drv)) => {
+					register_driver(PciDriver::VirtioBlk(InterruptTicketMutex::new(drv)))
+				}
+				#[cfg(feature = "balloon")]
+				Ok(VirtioDriver::Balloon(drv)) => {
+					register_driver(PciDriver::VirtioBalloon(InterruptTicketMutex::new(drv)))
+				}
 				_ => {}
 			}
 		}
@@ -8,6 +8,10 @@ pub mod virtqueue;
 pub mod error {
 	use core::fmt;
 
+	#[cfg(feature = "balloon")]
+	pub use crate::drivers::balloon::virtio_balloon::error::VirtioBalloonError;
+	#[cfg(feature = "blk")]
+	pub use crate::drivers::block::virtio_blk::error::VirtioBlkError;
 	#[cfg(feature = "fuse")]
 	pub use crate::drivers::fs::virtio_fs::error::VirtioFsError;
 	#[cfg(all(not(feature = "rtl8139"), any(feature = "tcp", feature = "udp")))]
@@ -25,6 +29,10 @@ pub mod error {
 		NetDriver(VirtioNetError),
 		#[cfg(feature = "fuse")]
 		FsDriver(VirtioFsError),
+		#[cfg(feature = "blk")]
+		BlkDriver(VirtioBlkError),
+		#[cfg(feature = "balloon")]
+		BalloonDriver(VirtioBalloonError),
 		#[cfg(not(feature = "pci"))]
 		Unknown,
 	}
@@ -66,6 +74,26 @@ pub mod error {
 					VirtioFsError::IncompFeatsSet(drv_feats, dev_feats) => write!(f, "Feature set: {:x} , is incompatible with the device features: {:x}", u64::from(*drv_feats), u64::from(*dev_feats)),
 					VirtioFsError::Unknown => write!(f, "Virtio filesystem failed, driver failed due unknown reason!"),
 				},
+				#[cfg(feature = "blk")]
+				VirtioError::BlkDriver(blk_error) => match blk_error {
+					VirtioBlkError::NoDevCfg(id) => write!(f, "Virtio block driver failed, for device {id:x}, due to a missing or malformed device config!"),
+					VirtioBlkError::NoComCfg(id) =>  write!(f, "Virtio block driver failed, for device {id:x}, due to a missing or malformed common config!"),
+					VirtioBlkError::NoIsrCfg(id) =>  write!(f, "Virtio block driver failed, for device {id:x}, due to a missing or malformed ISR status config!"),
+					VirtioBlkError::NoNotifCfg(id) =>  write!(f, "Virtio block driver failed, for device {id:x}, due to a missing or malformed notification config!"),
+					VirtioBlkError::FailFeatureNeg(id) => write!(f, "Virtio block driver failed, for device {id:x}, device did not acknowledge negotiated feature set!"),
+					VirtioBlkError::IncompFeatsSet(drv_feats, dev_feats) => write!(f, "Feature set: {:x} , is incompatible with the device features: {:x}", u64::from(*drv_feats), u64::from(*dev_feats)),
+					VirtioBlkError::Unknown => write!(f, "Virtio block driver failed due unknown reason!"),
+				},
+				#[cfg(feature = "balloon")]
+				VirtioError::BalloonDriver(balloon_error) => match balloon_error {
+					VirtioBalloonError::NoDevCfg(id) => write!(f, "Virtio balloon driver failed, for device {id:x}, due to a missing or malformed device config!"),
+					VirtioBalloonError::NoComCfg(id) =>  write!(f, "Virtio balloon driver failed, for device {id:x}, due to a missing or malformed common config!"),
+					VirtioBalloonError::NoIsrCfg(id) =>  write!(f, "Virtio balloon driver failed, for device {id:x}, due to a missing or malformed ISR status config!"),
+					VirtioBalloonError::NoNotifCfg(id) =>  write!(f, "Virtio balloon driver failed, for device {id:x}, due to a missing or malformed notification config!"),
+					VirtioBalloonError::FailFeatureNeg(id) => write!(f, "Virtio balloon driver failed, for device {id:x}, device did not acknowledge negotiated feature set!"),
+					VirtioBalloonError::IncompFeatsSet(drv_feats, dev_feats) => write!(f, "Feature set: {:x} , is incompatible with the device features: {:x}", u64::from(*drv_feats), u64::from(*dev_feats)),
+					VirtioBalloonError::Unknown => write!(f, "Virtio balloon driver failed due unknown reason!"),
+				},
             }
 		}
 	}
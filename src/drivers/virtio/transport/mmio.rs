@@ -12,6 +12,8 @@ use virtio_def::DeviceStatus;
 #[cfg(any(feature = "tcp", feature = "udp"))]
 use crate::arch::kernel::interrupts::*;
 use crate::arch::mm::PhysAddr;
+#[cfg(feature = "blk")]
+use crate::drivers::block::virtio_blk::VirtioBlkDriver;
 use crate::drivers::error::DriverError;
 #[cfg(any(feature = "tcp", feature = "udp"))]
 use crate::drivers::net::network_irqhandler;
@@ -368,6 +370,8 @@ struct IsrStatusRaw {
 pub(crate) enum VirtioDriver {
 	#[cfg(any(feature = "tcp", feature = "udp"))]
 	Network(VirtioNetDriver),
+	#[cfg(feature = "blk")]
+	Block(VirtioBlkDriver),
 }
 
 #[allow(unused_variables)]
@@ -384,7 +388,7 @@ pub(crate) fn init_device(
 		));
 	}
 
-	// Verify the device-ID to find the network card
+	// Verify the device-ID to find a supported device
 	match registers.device_id {
 		#[cfg(any(feature = "tcp", feature = "udp"))]
 		DevId::VIRTIO_DEV_ID_NET => {
@@ -404,6 +408,17 @@ pub(crate) fn init_device(
 				}
 			}
 		}
+		#[cfg(feature = "blk")]
+		DevId::VIRTIO_DEV_ID_BLK => match VirtioBlkDriver::init(dev_id, registers, irq_no) {
+			Ok(virt_blk_drv) => {
+				info!("Virtio block driver initialized.");
+				Ok(VirtioDriver::Block(virt_blk_drv))
+			}
+			Err(virtio_error) => {
+				error!("Virtio block driver could not be initialized with device");
+				Err(DriverError::InitVirtioDevFail(virtio_error))
+			}
+		},
 		_ => {
 			error!(
 				"Device with id {:?} is currently not supported!",
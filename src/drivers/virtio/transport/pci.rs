@@ -17,6 +17,10 @@ use crate::arch::kernel::interrupts::*;
 use crate::arch::memory_barrier;
 use crate::arch::mm::PhysAddr;
 use crate::arch::pci::PciConfigRegion;
+#[cfg(feature = "balloon")]
+use crate::drivers::balloon::virtio_balloon::VirtioBalloonDriver;
+#[cfg(feature = "blk")]
+use crate::drivers::block::virtio_blk::VirtioBlkDriver;
 use crate::drivers::error::DriverError;
 #[cfg(feature = "fuse")]
 use crate::drivers::fs::virtio_fs::VirtioFsDriver;
@@ -49,6 +53,8 @@ pub enum DevId {
 	VIRTIO_TRANS_DEV_ID_ENTROPY = 0x1005,
 	VIRTIO_TRANS_DEV_ID_9P = 0x1009,
 	VIRTIO_DEV_ID_NET = 0x1041,
+	VIRTIO_DEV_ID_BLK = 0x1042,
+	VIRTIO_DEV_ID_BALLOON = 0x1045,
 	VIRTIO_DEV_ID_FS = 0x105A,
 }
 
@@ -63,6 +69,8 @@ impl From<DevId> for u16 {
 			DevId::VIRTIO_TRANS_DEV_ID_ENTROPY => 0x1005,
 			DevId::VIRTIO_TRANS_DEV_ID_9P => 0x1009,
 			DevId::VIRTIO_DEV_ID_NET => 0x1041,
+			DevId::VIRTIO_DEV_ID_BLK => 0x1042,
+			DevId::VIRTIO_DEV_ID_BALLOON => 0x1045,
 			DevId::VIRTIO_DEV_ID_FS => 0x105A,
 			DevId::INVALID => 0x0,
 		}
@@ -80,6 +88,8 @@ impl From<u16> for DevId {
 			0x1005 => DevId::VIRTIO_TRANS_DEV_ID_ENTROPY,
 			0x1009 => DevId::VIRTIO_TRANS_DEV_ID_9P,
 			0x1041 => DevId::VIRTIO_DEV_ID_NET,
+			0x1042 => DevId::VIRTIO_DEV_ID_BLK,
+			0x1045 => DevId::VIRTIO_DEV_ID_BALLOON,
 			0x105A => DevId::VIRTIO_DEV_ID_FS,
 			_ => DevId::INVALID,
 		}
@@ -408,6 +418,14 @@ impl UniCapsColl {
 	pub fn get_notif_cfg(&mut self) -> Option<NotifCfg> {
 		self.notif_cfg_list.pop()
 	}
+
+	/// Returns the shared memory region with the given id.
+	///
+	/// INFO: This function removes the Capability and returns ownership.
+	pub fn get_sh_mem_cfg(&mut self, id: u8) -> Option<ShMemCfg> {
+		let index = self.sh_mem_cfg_list.iter().position(|cfg| cfg.id == id)?;
+		Some(self.sh_mem_cfg_list.remove(index))
+	}
 }
 
 /// Wraps a [`CommonCfg`] in order to preserve
@@ -968,12 +986,9 @@ impl ShMemCfg {
 		let virt_addr_raw = cap.bar.mem_addr + offset;
 		let raw_ptr = ptr::with_exposed_provenance_mut::<u8>(virt_addr_raw.into());
 
-		// Zero initialize shared memory area
-		unsafe {
-			for i in 0..usize::from(length) {
-				*(raw_ptr.add(i)) = 0;
-			}
-		};
+		// The shared memory area isn't initialized, because some regions,
+		// e.g., the DAX window of virtio-fs, aren't accessible until the
+		// device has set up a mapping.
 
 		// Currently in place in order to ensure a safe cast below
 		// "len: cap.bar.length as usize"
@@ -983,14 +998,29 @@ impl ShMemCfg {
 
 		Some(ShMemCfg {
 			mem_addr: virt_addr_raw,
-			length: cap.length,
+			length,
 			sh_mem: ShMem {
 				ptr: raw_ptr,
-				len: cap.bar.length as usize,
+				len: length.into(),
 			},
 			id: cap.id,
 		})
 	}
+
+	/// Returns the id of the shared memory region
+	pub fn id(&self) -> u8 {
+		self.id
+	}
+
+	/// Returns the virtual address, at which the region is mapped
+	pub fn mem_addr(&self) -> VirtMemAddr {
+		self.mem_addr
+	}
+
+	/// Returns the length of the region in bytes
+	pub fn length(&self) -> MemLen {
+		self.length
+	}
 }
 
 /// Defines a shared memory locate at location ptr with a length of len.
@@ -1314,6 +1344,34 @@ pub(crate) fn init_device(
 				Err(DriverError::InitVirtioDevFail(virtio_error))
 			}
 		},
+		#[cfg(feature = "blk")]
+		DevId::VIRTIO_DEV_ID_BLK => match VirtioBlkDriver::init(device) {
+			Ok(virt_blk_drv) => {
+				info!("Virtio block driver initialized.");
+				Ok(VirtioDriver::Block(virt_blk_drv))
+			}
+			Err(virtio_error) => {
+				error!(
+					"Virtio block driver could not be initialized with device: {:x}",
+					device_id
+				);
+				Err(DriverError::InitVirtioDevFail(virtio_error))
+			}
+		},
+		#[cfg(feature = "balloon")]
+		DevId::VIRTIO_DEV_ID_BALLOON => match VirtioBalloonDriver::init(device) {
+			Ok(virt_balloon_drv) => {
+				info!("Virtio balloon driver initialized.");
+				Ok(VirtioDriver::Balloon(virt_balloon_drv))
+			}
+			Err(virtio_error) => {
+				error!(
+					"Virtio balloon driver could not be initialized with device: {:x}",
+					device_id
+				);
+				Err(DriverError::InitVirtioDevFail(virtio_error))
+			}
+		},
 		#[cfg(feature = "fuse")]
 		DevId::VIRTIO_DEV_ID_FS => {
 			// TODO: check subclass
@@ -1360,6 +1418,10 @@ pub(crate) fn init_device(
 				}
 				#[cfg(feature = "fuse")]
 				VirtioDriver::FileSystem(_) => Ok(drv),
+				#[cfg(feature = "blk")]
+				VirtioDriver::Block(_) => Ok(drv),
+				#[cfg(feature = "balloon")]
+				VirtioDriver::Balloon(_) => Ok(drv),
 			}
 		}
 		Err(virt_err) => Err(virt_err),
@@ -1371,4 +1433,8 @@ pub(crate) enum VirtioDriver {
 	Network(VirtioNetDriver),
 	#[cfg(feature = "fuse")]
 	FileSystem(VirtioFsDriver),
+	#[cfg(feature = "blk")]
+	Block(VirtioBlkDriver),
+	#[cfg(feature = "balloon")]
+	Balloon(VirtioBalloonDriver),
 }
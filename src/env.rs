@@ -36,6 +36,17 @@ pub fn is_uhyve() -> bool {
 	matches!(boot_info().platform_info, PlatformInfo::Uhyve { .. })
 }
 
+/// Appends `value` to a comma-separated list, so that an argument can be used several times
+fn append_var(env_vars: &mut HashMap<String, String, RandomState>, key: &str, value: String) {
+	env_vars
+		.entry(String::from(key))
+		.and_modify(|list| {
+			list.push(',');
+			list.push_str(&value);
+		})
+		.or_insert(value);
+}
+
 impl Default for Cli {
 	fn default() -> Self {
 		let mut image_path = None;
@@ -71,15 +82,43 @@ impl Default for Cli {
 				}
 				"-ip" => {
 					let ip = expect_arg(words.next(), word.as_str());
-					env_vars.insert(String::from("HERMIT_IP"), ip);
+					append_var(&mut env_vars, "HERMIT_IP", ip);
 				}
 				"-mask" => {
 					let mask = expect_arg(words.next(), word.as_str());
-					env_vars.insert(String::from("HERMIT_MASK"), mask);
+					append_var(&mut env_vars, "HERMIT_MASK", mask);
 				}
 				"-gateway" => {
 					let gateway = expect_arg(words.next(), word.as_str());
-					env_vars.insert(String::from("HERMIT_GATEWAY"), gateway);
+					append_var(&mut env_vars, "HERMIT_GATEWAY", gateway);
+				}
+				"-ip6" => {
+					let ip = expect_arg(words.next(), word.as_str());
+					append_var(&mut env_vars, "HERMIT_IP6", ip);
+				}
+				"-gateway6" => {
+					let gateway = expect_arg(words.next(), word.as_str());
+					append_var(&mut env_vars, "HERMIT_GATEWAY6", gateway);
+				}
+				"-route" => {
+					let route = expect_arg(words.next(), word.as_str());
+					if route.contains('=') {
+						append_var(&mut env_vars, "HERMIT_ROUTES", route);
+					} else {
+						warn!("The argument '-route' expects an entry of the form cidr=gateway");
+					}
+				}
+				"-dns" => {
+					let server = expect_arg(words.next(), word.as_str());
+					append_var(&mut env_vars, "HERMIT_DNS", server);
+				}
+				"-host" => {
+					let host = expect_arg(words.next(), word.as_str());
+					if host.contains('=') {
+						append_var(&mut env_vars, "HERMIT_HOSTS", host);
+					} else {
+						warn!("The argument '-host' expects an entry of the form name=ip");
+					}
 				}
 				"-mount" => {
 					let gateway = expect_arg(words.next(), word.as_str());
@@ -1,4 +1,7 @@
+use alloc::borrow::Cow;
 use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 #[cfg(not(feature = "dhcpv4"))]
 use core::str::FromStr;
@@ -8,153 +11,252 @@ use smoltcp::phy::{self, ChecksumCapabilities, Device, DeviceCapabilities, Mediu
 #[cfg(feature = "dhcpv4")]
 use smoltcp::socket::dhcpv4;
 use smoltcp::time::Instant;
-use smoltcp::wire::{EthernetAddress, HardwareAddress};
 #[cfg(not(feature = "dhcpv4"))]
-use smoltcp::wire::{IpAddress, IpCidr, Ipv4Address};
-
-use super::network::{NetworkInterface, NetworkState};
+use smoltcp::wire::Ipv4Address;
+use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr, Ipv6Address};
+
+#[cfg(feature = "dhcpv6")]
+use super::dhcpv6::Dhcpv6;
+#[cfg(feature = "dns")]
+use super::network::Handle;
+use super::network::{NetworkDevice, NetworkInterface, NetworkState};
+#[cfg(feature = "slaac")]
+use super::slaac::Slaac;
 use crate::arch;
 #[cfg(not(feature = "pci"))]
 use crate::arch::kernel::mmio as hardware;
-use crate::drivers::net::NetworkDriver;
+use crate::drivers::net::{NetworkDriver, NetworkStats};
 #[cfg(feature = "pci")]
 use crate::drivers::pci as hardware;
 
+/// MTU of the loopback device
+const LOOPBACK_MTU: usize = 65535;
+
 /// Data type to determine the mac address
 #[derive(Debug, Clone)]
 #[repr(C)]
 pub(crate) struct HermitNet {
+	/// Position of the driver in the list of network drivers
+	index: usize,
 	mtu: u16,
 	checksums: ChecksumCapabilities,
 }
 
 impl HermitNet {
-	pub(crate) const fn new(mtu: u16, checksums: ChecksumCapabilities) -> Self {
-		Self { mtu, checksums }
+	pub(crate) const fn new(index: usize, mtu: u16, checksums: ChecksumCapabilities) -> Self {
+		Self {
+			index,
+			mtu,
+			checksums,
+		}
+	}
+
+	/// Returns the counters of the network driver
+	pub(crate) fn stats(&self) -> NetworkStats {
+		hardware::get_network_drivers()
+			.nth(self.index)
+			.map(|driver| driver.lock().get_stats())
+			.unwrap_or_default()
 	}
 }
 
-impl<'a> NetworkInterface<'a> {
-	#[cfg(feature = "dhcpv4")]
-	pub(crate) fn create() -> NetworkState<'a> {
-		let (mtu, mac, checksums) = if let Some(driver) = hardware::get_network_driver() {
-			let guard = driver.lock();
+/// Loopback device, which receives every packet that it sends
+#[derive(Debug, Default)]
+pub(crate) struct LoopbackNet {
+	queue: VecDeque<Vec<u8>>,
+	pub(crate) stats: NetworkStats,
+}
+
+/// Physical layer of a network device
+#[derive(Debug)]
+pub(crate) enum NetworkPhy {
+	Driver(HermitNet),
+	Loopback(LoopbackNet),
+}
+
+/// Returns the value of the `index`-th device from a comma-separated list,
+/// e.g., `-ip 10.0.5.3 -ip 10.0.6.3` configures the first two devices.
+pub(crate) fn device_var(value: Option<Cow<'_, str>>, index: usize) -> Option<String> {
+	value?
+		.split(',')
+		.nth(index)
+		.map(str::trim)
+		.filter(|s| !s.is_empty())
+		.map(ToString::to_string)
+}
+
+/// Returns the static IPv4 configuration of the `index`-th device. Only the
+/// first device has a default configuration.
+#[cfg(not(feature = "dhcpv4"))]
+fn static_config(index: usize) -> Option<(Ipv4Address, Ipv4Address, Option<Ipv4Address>)> {
+	let defaults = (index == 0).then_some(("10.0.5.3", "10.0.5.1"));
+
+	let myip = device_var(hermit_var!("HERMIT_IP"), index)
+		.or_else(|| defaults.map(|(ip, _)| ip.to_string()))?;
+	let mymask = device_var(hermit_var!("HERMIT_MASK"), index)
+		.unwrap_or_else(|| String::from("255.255.255.0"));
+	let mygw = device_var(hermit_var!("HERMIT_GATEWAY"), index)
+		.or_else(|| defaults.map(|(_, gw)| gw.to_string()));
+
+	Some((
+		Ipv4Address::from_str(&myip).unwrap(),
+		Ipv4Address::from_str(&mymask).unwrap(),
+		mygw.map(|gw| Ipv4Address::from_str(&gw).unwrap()),
+	))
+}
+
+impl<'a> NetworkDevice<'a> {
+	/// Creates the interface of the `index`-th network driver, if it exists
+	pub(crate) fn create(index: usize) -> Option<Self> {
+		let (mtu, mac, checksums) = {
+			let guard = hardware::get_network_drivers().nth(index)?.lock();
 			(
 				guard.get_mtu(),
 				guard.get_mac_address(),
 				guard.get_checksums(),
 			)
-		} else {
-			return NetworkState::InitializationFailed;
 		};
 
-		let mut device = HermitNet::new(mtu, checksums);
+		let mut device = HermitNet::new(index, mtu, checksums);
 
 		let ethernet_addr = EthernetAddress([mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]]);
 		let hardware_addr = HardwareAddress::Ethernet(ethernet_addr);
 
+		info!("Network device {}", index);
 		info!("MAC address {}", hardware_addr);
 		info!("MTU: {} bytes", mtu);
 
-		let dhcp = dhcpv4::Socket::new();
-
 		// use the current time based on the wall-clock time as seed
 		let mut config = Config::new(hardware_addr);
-		config.random_seed = (arch::kernel::systemtime::now_micros()) / 1000000;
+		config.random_seed = (arch::kernel::systemtime::now_micros()) / 1000000 + index as u64;
 		if device.capabilities().medium == Medium::Ethernet {
 			config.hardware_addr = hardware_addr;
 		}
 
-		let iface = Interface::new(config, &mut device, crate::executor::network::now());
-		let mut sockets = SocketSet::new(vec![]);
-		let dhcp_handle = sockets.add(dhcp);
+		let mut iface = Interface::new(config, &mut device, crate::executor::network::now());
+		let device = NetworkPhy::Driver(device);
+
+		#[cfg(not(feature = "dhcpv4"))]
+		if let Some((myip, mymask, mygw)) = static_config(index) {
+			// calculate the netmask length
+			// => count the number of contiguous 1 bits,
+			// starting at the most significant bit in the first octet
+			let mut prefix_len = (!mymask.as_bytes()[0]).trailing_zeros();
+			if prefix_len == 8 {
+				prefix_len += (!mymask.as_bytes()[1]).trailing_zeros();
+			}
+			if prefix_len == 16 {
+				prefix_len += (!mymask.as_bytes()[2]).trailing_zeros();
+			}
+			if prefix_len == 24 {
+				prefix_len += (!mymask.as_bytes()[3]).trailing_zeros();
+			}
+
+			let ip_addr = IpCidr::new(IpAddress::Ipv4(myip), prefix_len.try_into().unwrap());
+			info!("Configure network interface with address {}", ip_addr);
+			iface.update_ip_addrs(|ip_addrs| {
+				ip_addrs.push(ip_addr).unwrap();
+			});
+
+			if let Some(mygw) = mygw {
+				info!("Configure gateway with address {}", mygw);
+				iface.routes_mut().add_default_ipv4_route(mygw).unwrap();
+			}
+		}
+		super::ipv6::configure(&mut iface, ethernet_addr, index);
+		super::route::add_static_routes(&mut iface);
 
-		NetworkState::Initialized(Box::new(Self {
+		let mut sockets = SocketSet::new(vec![]);
+		#[cfg(feature = "dhcpv4")]
+		let dhcp_handle = Some(sockets.add(dhcpv4::Socket::new()));
+		#[cfg(feature = "slaac")]
+		let slaac = Some(Slaac::new(
+			&mut sockets,
+			&iface,
+			ethernet_addr,
+			crate::executor::network::now(),
+		));
+		#[cfg(feature = "dhcpv6")]
+		let dhcpv6 = Some(Dhcpv6::new(&mut sockets, ethernet_addr));
+
+		Some(Self {
 			iface,
 			sockets,
 			device,
+			#[cfg(feature = "tcp")]
+			retransmissions: Default::default(),
+			#[cfg(feature = "dhcpv4")]
 			dhcp_handle,
-		}))
+			#[cfg(feature = "dns")]
+			dns_servers: None,
+			#[cfg(feature = "slaac")]
+			slaac,
+			#[cfg(feature = "dhcpv6")]
+			dhcpv6,
+		})
 	}
 
-	#[cfg(not(feature = "dhcpv4"))]
-	pub(crate) fn create() -> NetworkState<'a> {
-		let (mtu, mac, checksums) = if let Some(driver) = hardware::get_network_driver() {
-			let guard = driver.lock();
-			(
-				guard.get_mtu(),
-				guard.get_mac_address(),
-				guard.get_checksums(),
-			)
-		} else {
-			return NetworkState::InitializationFailed;
-		};
-
-		let mut device = HermitNet::new(mtu, checksums);
-
-		let myip = Ipv4Address::from_str(hermit_var_or!("HERMIT_IP", "10.0.5.3")).unwrap();
-		let mygw = Ipv4Address::from_str(hermit_var_or!("HERMIT_GATEWAY", "10.0.5.1")).unwrap();
-		let mymask = Ipv4Address::from_str(hermit_var_or!("HERMIT_MASK", "255.255.255.0")).unwrap();
-
-		// calculate the netmask length
-		// => count the number of contiguous 1 bits,
-		// starting at the most significant bit in the first octet
-		let mut prefix_len = (!mymask.as_bytes()[0]).trailing_zeros();
-		if prefix_len == 8 {
-			prefix_len += (!mymask.as_bytes()[1]).trailing_zeros();
-		}
-		if prefix_len == 16 {
-			prefix_len += (!mymask.as_bytes()[2]).trailing_zeros();
-		}
-		if prefix_len == 24 {
-			prefix_len += (!mymask.as_bytes()[3]).trailing_zeros();
-		}
+	/// Creates the loopback device, which owns 127.0.0.1/8 and ::1
+	pub(crate) fn loopback() -> Self {
+		let mut device = NetworkPhy::Loopback(LoopbackNet::default());
 
-		let ethernet_addr = EthernetAddress([mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]]);
-		let hardware_addr = HardwareAddress::Ethernet(ethernet_addr);
-		let ip_addrs = [IpCidr::new(
-			IpAddress::v4(
-				myip.as_bytes()[0],
-				myip.as_bytes()[1],
-				myip.as_bytes()[2],
-				myip.as_bytes()[3],
-			),
-			prefix_len.try_into().unwrap(),
-		)];
-
-		info!("MAC address {}", hardware_addr);
-		info!("Configure network interface with address {}", ip_addrs[0]);
-		info!("Configure gateway with address {}", mygw);
-		info!("MTU: {} bytes", mtu);
-
-		// use the current time based on the wall-clock time as seed
-		let mut config = Config::new(hardware_addr);
+		let mut config = Config::new(HardwareAddress::Ip);
 		config.random_seed = (arch::kernel::systemtime::now_micros()) / 1000000;
-		if device.capabilities().medium == Medium::Ethernet {
-			config.hardware_addr = hardware_addr;
-		}
 
 		let mut iface = Interface::new(config, &mut device, crate::executor::network::now());
 		iface.update_ip_addrs(|ip_addrs| {
 			ip_addrs
-				.push(IpCidr::new(
-					IpAddress::v4(
-						myip.as_bytes()[0],
-						myip.as_bytes()[1],
-						myip.as_bytes()[2],
-						myip.as_bytes()[3],
-					),
-					prefix_len.try_into().unwrap(),
-				))
+				.push(IpCidr::new(IpAddress::v4(127, 0, 0, 1), 8))
+				.unwrap();
+			ip_addrs
+				.push(IpCidr::new(IpAddress::Ipv6(Ipv6Address::LOOPBACK), 128))
 				.unwrap();
 		});
-		iface.routes_mut().add_default_ipv4_route(mygw).unwrap();
+		info!("Configure loopback device with addresses 127.0.0.1/8 and ::1/128");
 
-		NetworkState::Initialized(Box::new(Self {
+		Self {
 			iface,
 			sockets: SocketSet::new(vec![]),
 			device,
-		}))
+			#[cfg(feature = "tcp")]
+			retransmissions: Default::default(),
+			#[cfg(feature = "dhcpv4")]
+			dhcp_handle: None,
+			#[cfg(feature = "dns")]
+			dns_servers: None,
+			#[cfg(feature = "slaac")]
+			slaac: None,
+			#[cfg(feature = "dhcpv6")]
+			dhcpv6: None,
+		}
+	}
+}
+
+impl<'a> NetworkInterface<'a> {
+	/// Brings up every network device. The loopback device follows the
+	/// devices of the network drivers, which keeps the index of a device
+	/// and of its driver the same. Without a network driver, the loopback
+	/// device is the only device.
+	pub(crate) fn create() -> NetworkState<'a> {
+		let mut devices: Vec<NetworkDevice<'a>> = (0..).map_while(NetworkDevice::create).collect();
+		devices.push(NetworkDevice::loopback());
+
+		#[cfg(feature = "dns")]
+		let dns_handle = Handle {
+			iface: 0,
+			socket: super::dns::create_socket(&mut devices[0].sockets),
+		};
+
+		#[allow(unused_mut)]
+		let mut nic = Self {
+			devices,
+			#[cfg(feature = "dns")]
+			dns_handle,
+		};
+		#[cfg(feature = "dns")]
+		nic.route_dns_socket();
+
+		NetworkState::Initialized(Box::new(nic))
 	}
 }
 
@@ -171,15 +273,69 @@ impl Device for HermitNet {
 	}
 
 	fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
-		if let Some(driver) = hardware::get_network_driver() {
-			driver.lock().receive_packet()
-		} else {
-			None
-		}
+		hardware::get_network_drivers()
+			.nth(self.index)?
+			.lock()
+			.receive_packet()
+			.map(|rx| (rx, TxToken::new(self.index)))
+	}
+
+	fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+		Some(TxToken::new(self.index))
+	}
+}
+
+impl Device for LoopbackNet {
+	type RxToken<'a> = RxToken;
+	type TxToken<'a> = LoopbackTxToken<'a>;
+
+	fn capabilities(&self) -> DeviceCapabilities {
+		let mut cap = DeviceCapabilities::default();
+		cap.medium = Medium::Ip;
+		cap.max_transmission_unit = LOOPBACK_MTU;
+		// the packets don't leave the kernel => checksums are useless
+		cap.checksum = ChecksumCapabilities::ignored();
+		cap
+	}
+
+	fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+		let buffer = self.queue.pop_front()?;
+		self.stats.count_rx(buffer.len());
+		Some((RxToken::new(buffer), LoopbackTxToken { device: self }))
 	}
 
 	fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
-		Some(TxToken::new())
+		Some(LoopbackTxToken { device: self })
+	}
+}
+
+impl Device for NetworkPhy {
+	type RxToken<'a> = RxToken;
+	type TxToken<'a> = NetworkPhyTxToken<'a>;
+
+	fn capabilities(&self) -> DeviceCapabilities {
+		match self {
+			Self::Driver(device) => device.capabilities(),
+			Self::Loopback(device) => device.capabilities(),
+		}
+	}
+
+	fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+		match self {
+			Self::Driver(device) => device
+				.receive(timestamp)
+				.map(|(rx, tx)| (rx, NetworkPhyTxToken::Driver(tx))),
+			Self::Loopback(device) => device
+				.receive(timestamp)
+				.map(|(rx, tx)| (rx, NetworkPhyTxToken::Loopback(tx))),
+		}
+	}
+
+	fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+		match self {
+			Self::Driver(device) => device.transmit(timestamp).map(NetworkPhyTxToken::Driver),
+			Self::Loopback(device) => device.transmit(timestamp).map(NetworkPhyTxToken::Loopback),
+		}
 	}
 }
 
@@ -207,11 +363,13 @@ impl phy::RxToken for RxToken {
 }
 
 #[doc(hidden)]
-pub(crate) struct TxToken;
+pub(crate) struct TxToken {
+	index: usize,
+}
 
 impl TxToken {
-	pub(crate) fn new() -> Self {
-		Self {}
+	pub(crate) fn new(index: usize) -> Self {
+		Self { index }
 	}
 }
 
@@ -220,9 +378,47 @@ impl phy::TxToken for TxToken {
 	where
 		F: FnOnce(&mut [u8]) -> R,
 	{
-		hardware::get_network_driver()
+		hardware::get_network_drivers()
+			.nth(self.index)
 			.unwrap()
 			.lock()
 			.send_packet(len, f)
 	}
 }
+
+/// Queues a sent packet as received packet of the loopback device
+#[doc(hidden)]
+pub(crate) struct LoopbackTxToken<'a> {
+	device: &'a mut LoopbackNet,
+}
+
+impl phy::TxToken for LoopbackTxToken<'_> {
+	fn consume<R, F>(self, len: usize, f: F) -> R
+	where
+		F: FnOnce(&mut [u8]) -> R,
+	{
+		let mut buffer = vec![0; len];
+		let result = f(&mut buffer);
+		self.device.stats.count_tx(len);
+		self.device.queue.push_back(buffer);
+		result
+	}
+}
+
+#[doc(hidden)]
+pub(crate) enum NetworkPhyTxToken<'a> {
+	Driver(TxToken),
+	Loopback(LoopbackTxToken<'a>),
+}
+
+impl phy::TxToken for NetworkPhyTxToken<'_> {
+	fn consume<R, F>(self, len: usize, f: F) -> R
+	where
+		F: FnOnce(&mut [u8]) -> R,
+	{
+		match self {
+			Self::Driver(token) => phy::TxToken::consume(token, len, f),
+			Self::Loopback(token) => phy::TxToken::consume(token, len, f),
+		}
+	}
+}
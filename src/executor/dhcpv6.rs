@@ -0,0 +1,538 @@
+//! Stateful DHCPv6 client (RFC 8415).
+//!
+//! The client is started by SLAAC, if a router advertisement has the
+//! managed flag. It leases a single non-temporary address and learns the
+//! name servers of the network. The lease is renewed at T1 and the address
+//! is removed, if the lease expires.
+
+use alloc::vec::Vec;
+
+use smoltcp::iface::{Interface, SocketHandle, SocketSet};
+use smoltcp::socket::udp;
+use smoltcp::time::{Duration, Instant};
+#[cfg(feature = "dns")]
+use smoltcp::wire::IpAddress;
+use smoltcp::wire::{EthernetAddress, IpCidr, IpEndpoint, IpListenEndpoint, Ipv6Address, Ipv6Cidr};
+
+use crate::executor::network::NetworkDevice;
+
+const CLIENT_PORT: u16 = 546;
+const SERVER_PORT: u16 = 547;
+/// All_DHCP_Relay_Agents_and_Servers
+const ALL_SERVERS: Ipv6Address = Ipv6Address::new(0xff02, 0, 0, 0, 0, 0, 1, 2);
+
+const SOLICIT: u8 = 1;
+const ADVERTISE: u8 = 2;
+const REQUEST: u8 = 3;
+const RENEW: u8 = 5;
+const REPLY: u8 = 7;
+
+const OPTION_CLIENTID: u16 = 1;
+const OPTION_SERVERID: u16 = 2;
+const OPTION_IA_NA: u16 = 3;
+const OPTION_IAADDR: u16 = 5;
+const OPTION_ORO: u16 = 6;
+const OPTION_ELAPSED_TIME: u16 = 8;
+const OPTION_STATUS_CODE: u16 = 13;
+const OPTION_RAPID_COMMIT: u16 = 14;
+const OPTION_DNS_SERVERS: u16 = 23;
+
+/// Identifier of the only IA_NA of the client
+const IAID: u32 = 1;
+/// Initial retransmission timeout
+const INITIAL_TIMEOUT: Duration = Duration::from_secs(1);
+/// Upper bound of the retransmission timeout
+const MAX_TIMEOUT: Duration = Duration::from_secs(120);
+/// Number of requests, before the client solicits servers again
+const MAX_REQUESTS: u8 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+	Idle,
+	Soliciting,
+	Requesting {
+		server_id: Vec<u8>,
+		requests: u8,
+	},
+	Bound {
+		server_id: Vec<u8>,
+		renew_at: Instant,
+	},
+	Renewing {
+		server_id: Vec<u8>,
+		requests: u8,
+	},
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Lease {
+	cidr: Ipv6Cidr,
+	valid_until: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) struct Dhcpv6 {
+	handle: SocketHandle,
+	/// DUID-LL, which is derived from the MAC address
+	duid: [u8; 10],
+	state: State,
+	xid: [u8; 3],
+	/// Start of the current exchange
+	started: Instant,
+	timeout: Duration,
+	next_transmission: Option<Instant>,
+	lease: Option<Lease>,
+}
+
+impl Dhcpv6 {
+	/// Adds the UDP socket of the client to the socket set of the interface
+	pub(crate) fn new(sockets: &mut SocketSet<'_>, mac: EthernetAddress) -> Self {
+		let rx_buffer = udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 4096]);
+		let tx_buffer = udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 1024]);
+		let mut socket = udp::Socket::new(rx_buffer, tx_buffer);
+		socket
+			.bind(IpListenEndpoint {
+				addr: None,
+				port: CLIENT_PORT,
+			})
+			.unwrap();
+
+		let mut duid = [0u8; 10];
+		// DUID-LL with the hardware type Ethernet
+		duid[..4].copy_from_slice(&[0, 3, 0, 1]);
+		duid[4..].copy_from_slice(mac.as_bytes());
+
+		Self {
+			handle: sockets.add(socket),
+			duid,
+			state: State::Idle,
+			xid: [0; 3],
+			started: Instant::ZERO,
+			timeout: INITIAL_TIMEOUT,
+			next_transmission: None,
+			lease: None,
+		}
+	}
+
+	/// Returns the time of the next transmission or expiry
+	pub(crate) fn poll_at(&self) -> Option<Instant> {
+		let renew_at = match self.state {
+			State::Bound { renew_at, .. } => Some(renew_at),
+			_ => None,
+		};
+
+		self.next_transmission
+			.into_iter()
+			.chain(renew_at)
+			.chain(self.lease.map(|lease| lease.valid_until))
+			.min()
+	}
+
+	/// Starts a new exchange in the state `state`
+	fn transition(&mut self, state: State, timestamp: Instant) {
+		// every exchange uses a new transaction id
+		let previous = u32::from_be_bytes([0, self.xid[0], self.xid[1], self.xid[2]]);
+		let xid = (timestamp.total_micros() as u32) ^ previous.rotate_left(7);
+		self.xid.copy_from_slice(&xid.to_be_bytes()[1..]);
+		self.state = state;
+		self.started = timestamp;
+		self.timeout = INITIAL_TIMEOUT;
+		self.next_transmission = Some(timestamp);
+	}
+
+	/// Builds the next message of the current exchange
+	fn message(&self, timestamp: Instant) -> Option<Vec<u8>> {
+		let (msg_type, server_id) = match &self.state {
+			State::Soliciting => (SOLICIT, None),
+			State::Requesting { server_id, .. } => (REQUEST, Some(server_id)),
+			State::Renewing { server_id, .. } => (RENEW, Some(server_id)),
+			State::Idle | State::Bound { .. } => return None,
+		};
+
+		let mut msg = Vec::with_capacity(128);
+		msg.push(msg_type);
+		msg.extend_from_slice(&self.xid);
+
+		put_option(&mut msg, OPTION_CLIENTID, &self.duid);
+		if let Some(server_id) = server_id {
+			put_option(&mut msg, OPTION_SERVERID, server_id);
+		}
+
+		// the elapsed time is measured in hundredths of a second
+		let elapsed = ((timestamp - self.started).total_millis() / 10).min(u16::MAX.into()) as u16;
+		put_option(&mut msg, OPTION_ELAPSED_TIME, &elapsed.to_be_bytes());
+		put_option(&mut msg, OPTION_ORO, &OPTION_DNS_SERVERS.to_be_bytes());
+		if msg_type == SOLICIT {
+			put_option(&mut msg, OPTION_RAPID_COMMIT, &[]);
+		}
+
+		// IA_NA with T1 and T2 left to the server
+		let mut ia_na = Vec::with_capacity(40);
+		ia_na.extend_from_slice(&IAID.to_be_bytes());
+		ia_na.extend_from_slice(&[0; 8]);
+		if let Some(lease) = self.lease.filter(|_| msg_type != SOLICIT) {
+			let mut iaaddr = [0u8; 24];
+			iaaddr[..16].copy_from_slice(lease.cidr.address().as_bytes());
+			put_option(&mut ia_na, OPTION_IAADDR, &iaaddr);
+		}
+		put_option(&mut msg, OPTION_IA_NA, &ia_na);
+
+		Some(msg)
+	}
+}
+
+fn put_option(msg: &mut Vec<u8>, code: u16, data: &[u8]) {
+	msg.extend_from_slice(&code.to_be_bytes());
+	msg.extend_from_slice(&u16::try_from(data.len()).unwrap().to_be_bytes());
+	msg.extend_from_slice(data);
+}
+
+/// Iterates over the options in `data`
+fn options(mut data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+	core::iter::from_fn(move || {
+		if data.len() < 4 {
+			return None;
+		}
+		let code = u16::from_be_bytes([data[0], data[1]]);
+		let len = usize::from(u16::from_be_bytes([data[2], data[3]]));
+		let value = data.get(4..4 + len)?;
+		data = &data[4 + len..];
+		Some((code, value))
+	})
+}
+
+fn status_code(data: &[u8]) -> u16 {
+	options(data)
+		.find(|(code, _)| *code == OPTION_STATUS_CODE)
+		.and_then(|(_, value)| Some(u16::from_be_bytes(value.get(..2)?.try_into().unwrap())))
+		.unwrap_or(0)
+}
+
+/// Content of an advertise or reply message
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Reply {
+	msg_type: u8,
+	rapid_commit: bool,
+	server_id: Vec<u8>,
+	/// Leased address with its valid lifetime in seconds
+	address: Option<(Ipv6Address, u32)>,
+	t1: u32,
+	dns_servers: Vec<Ipv6Address>,
+}
+
+/// Parses a message of a server, which answers the transaction `xid` of
+/// the client `duid`
+fn parse_reply(data: &[u8], xid: &[u8; 3], duid: &[u8]) -> Option<Reply> {
+	let msg_type = *data.first()?;
+	if (msg_type != ADVERTISE && msg_type != REPLY) || data.get(1..4)? != xid {
+		return None;
+	}
+
+	let mut reply = Reply {
+		msg_type,
+		..Default::default()
+	};
+	let mut client_id = None;
+	for (code, value) in options(&data[4..]) {
+		match code {
+			OPTION_CLIENTID => client_id = Some(value),
+			OPTION_SERVERID => reply.server_id = value.to_vec(),
+			OPTION_RAPID_COMMIT => reply.rapid_commit = true,
+			OPTION_STATUS_CODE if value.get(..2).is_some_and(|code| code != [0, 0]) => return None,
+			OPTION_IA_NA if value.len() >= 12 => {
+				if u32::from_be_bytes(value[..4].try_into().unwrap()) != IAID
+					|| status_code(&value[12..]) != 0
+				{
+					continue;
+				}
+				reply.t1 = u32::from_be_bytes(value[4..8].try_into().unwrap());
+				reply.address = options(&value[12..])
+					.filter(|(code, value)| *code == OPTION_IAADDR && value.len() >= 24)
+					.map(|(_, value)| {
+						(
+							Ipv6Address::from_bytes(&value[..16]),
+							u32::from_be_bytes(value[20..24].try_into().unwrap()),
+						)
+					})
+					.find(|(_, valid)| *valid > 0);
+			}
+			OPTION_DNS_SERVERS => {
+				reply.dns_servers = value
+					.chunks_exact(16)
+					.map(Ipv6Address::from_bytes)
+					.collect();
+			}
+			_ => {}
+		}
+	}
+
+	if client_id != Some(duid) || reply.server_id.is_empty() {
+		return None;
+	}
+
+	Some(reply)
+}
+
+impl Dhcpv6 {
+	/// Solicits a lease, if the client isn't already running
+	fn start(&mut self, timestamp: Instant) {
+		if self.state == State::Idle {
+			info!("Start DHCPv6");
+			self.transition(State::Soliciting, timestamp);
+		}
+	}
+
+	/// Processes the messages of servers, retransmits messages and expires
+	/// the lease. Returns the name servers of a new lease.
+	fn poll(
+		&mut self,
+		iface: &mut Interface,
+		sockets: &mut SocketSet<'_>,
+		timestamp: Instant,
+	) -> Option<Vec<Ipv6Address>> {
+		let mut dns_servers = None;
+
+		loop {
+			let socket = sockets.get_mut::<udp::Socket<'_>>(self.handle);
+			let Ok((data, _)) = socket.recv() else {
+				break;
+			};
+			if let Some(reply) = parse_reply(data, &self.xid, &self.duid) {
+				if let Some(servers) = self.process_reply(iface, reply, timestamp) {
+					dns_servers = Some(servers);
+				}
+			}
+		}
+
+		if let State::Bound {
+			server_id,
+			renew_at,
+		} = &self.state
+		{
+			if *renew_at <= timestamp {
+				let state = State::Renewing {
+					server_id: server_id.clone(),
+					requests: 0,
+				};
+				self.transition(state, timestamp);
+			}
+		}
+
+		if let Some(lease) = self.lease {
+			if lease.valid_until <= timestamp {
+				info!("DHCPv6 address {} expired", lease.cidr);
+				self.remove_address(iface);
+				if matches!(self.state, State::Bound { .. }) {
+					self.transition(State::Soliciting, timestamp);
+				}
+			}
+		}
+
+		self.retransmit(sockets, timestamp);
+
+		dns_servers
+	}
+
+	fn retransmit(&mut self, sockets: &mut SocketSet<'_>, timestamp: Instant) {
+		match self.next_transmission {
+			Some(next_transmission) if next_transmission <= timestamp => {}
+			_ => return,
+		}
+
+		// a server, which doesn't answer requests, is given up
+		match &mut self.state {
+			State::Requesting { requests, .. } | State::Renewing { requests, .. } => {
+				if *requests == MAX_REQUESTS {
+					self.transition(State::Soliciting, timestamp);
+				} else {
+					*requests += 1;
+				}
+			}
+			_ => {}
+		}
+
+		let Some(msg) = self.message(timestamp) else {
+			self.next_transmission = None;
+			return;
+		};
+		let socket = sockets.get_mut::<udp::Socket<'_>>(self.handle);
+		if socket
+			.send_slice(&msg, IpEndpoint::new(ALL_SERVERS.into(), SERVER_PORT))
+			.is_err()
+		{
+			warn!("Unable to send DHCPv6 message");
+		}
+
+		self.next_transmission = Some(timestamp + self.timeout);
+		self.timeout = (self.timeout * 2).min(MAX_TIMEOUT);
+	}
+
+	fn process_reply(
+		&mut self,
+		iface: &mut Interface,
+		reply: Reply,
+		timestamp: Instant,
+	) -> Option<Vec<Ipv6Address>> {
+		match (&self.state, reply.msg_type) {
+			(State::Soliciting, ADVERTISE) if reply.address.is_some() => {
+				let state = State::Requesting {
+					server_id: reply.server_id,
+					requests: 0,
+				};
+				self.transition(state, timestamp);
+				None
+			}
+			(State::Soliciting, REPLY) if reply.rapid_commit => self.bind(iface, reply, timestamp),
+			(State::Requesting { .. } | State::Renewing { .. }, REPLY) => {
+				self.bind(iface, reply, timestamp)
+			}
+			_ => None,
+		}
+	}
+
+	/// Adds the leased address to the interface and returns the name servers
+	/// of the lease
+	fn bind(
+		&mut self,
+		iface: &mut Interface,
+		reply: Reply,
+		timestamp: Instant,
+	) -> Option<Vec<Ipv6Address>> {
+		let Some((address, valid_lifetime)) = reply.address else {
+			self.transition(State::Soliciting, timestamp);
+			return None;
+		};
+
+		// DHCPv6 doesn't announce prefixes, on-link prefixes are learned by SLAAC
+		let cidr = Ipv6Cidr::new(address, 128);
+		let valid_lifetime = Duration::from_secs(valid_lifetime.into());
+		// without T1, the lease is renewed after half of its lifetime
+		let t1 = if reply.t1 == 0 || reply.t1 == u32::MAX {
+			valid_lifetime / 2
+		} else {
+			Duration::from_secs(reply.t1.into()).min(valid_lifetime)
+		};
+
+		if self.lease.map(|lease| lease.cidr) != Some(cidr) {
+			self.remove_address(iface);
+			info!("DHCPv6 address:  {}", cidr);
+			let mut added = false;
+			iface.update_ip_addrs(|addrs| {
+				added = addrs.push(IpCidr::Ipv6(cidr)).is_ok();
+			});
+			if !added {
+				warn!("Unable to add IPv6 address {}", cidr);
+			}
+		}
+		self.lease = Some(Lease {
+			cidr,
+			valid_until: timestamp + valid_lifetime,
+		});
+
+		for (i, s) in reply.dns_servers.iter().enumerate() {
+			info!("DNS server {}:    {}", i, s);
+		}
+
+		self.state = State::Bound {
+			server_id: reply.server_id,
+			renew_at: timestamp + t1,
+		};
+		self.next_transmission = None;
+
+		(!reply.dns_servers.is_empty()).then_some(reply.dns_servers)
+	}
+
+	fn remove_address(&mut self, iface: &mut Interface) {
+		if let Some(lease) = self.lease.take() {
+			iface.update_ip_addrs(|addrs| {
+				addrs.retain(|addr| *addr != IpCidr::Ipv6(lease.cidr));
+			});
+		}
+	}
+}
+
+impl<'a> NetworkDevice<'a> {
+	/// Solicits a lease, if the device has a DHCPv6 client
+	pub(crate) fn start_dhcpv6(&mut self, timestamp: Instant) {
+		if let Some(dhcpv6) = self.dhcpv6.as_mut() {
+			dhcpv6.start(timestamp);
+		}
+	}
+
+	pub(crate) fn poll_dhcpv6(&mut self, timestamp: Instant) {
+		let Some(dhcpv6) = self.dhcpv6.as_mut() else {
+			return;
+		};
+
+		let dns_servers = dhcpv6.poll(&mut self.iface, &mut self.sockets, timestamp);
+		#[cfg(feature = "dns")]
+		if let Some(servers) = dns_servers {
+			self.dns_servers = Some(servers.into_iter().map(IpAddress::Ipv6).collect());
+		}
+		#[cfg(not(feature = "dns"))]
+		let _ = dns_servers;
+	}
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	const XID: [u8; 3] = [0x12, 0x34, 0x56];
+	const DUID: [u8; 10] = [0, 3, 0, 1, 0x52, 0x54, 0, 0x12, 0x34, 0x56];
+	const ADDRESS: Ipv6Address = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x100);
+
+	fn reply(msg_type: u8, ia_status: u16) -> Vec<u8> {
+		let mut iaaddr = Vec::new();
+		iaaddr.extend_from_slice(ADDRESS.as_bytes());
+		iaaddr.extend_from_slice(&3600u32.to_be_bytes());
+		iaaddr.extend_from_slice(&7200u32.to_be_bytes());
+
+		let mut ia_na = Vec::new();
+		ia_na.extend_from_slice(&IAID.to_be_bytes());
+		ia_na.extend_from_slice(&1800u32.to_be_bytes());
+		ia_na.extend_from_slice(&2880u32.to_be_bytes());
+		put_option(&mut ia_na, OPTION_IAADDR, &iaaddr);
+		if ia_status != 0 {
+			put_option(&mut ia_na, OPTION_STATUS_CODE, &ia_status.to_be_bytes());
+		}
+
+		let mut msg = vec![msg_type];
+		msg.extend_from_slice(&XID);
+		put_option(&mut msg, OPTION_CLIENTID, &DUID);
+		put_option(&mut msg, OPTION_SERVERID, &[0, 3, 0, 1, 1, 2, 3, 4, 5, 6]);
+		put_option(&mut msg, OPTION_IA_NA, &ia_na);
+		put_option(
+			&mut msg,
+			OPTION_DNS_SERVERS,
+			Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53).as_bytes(),
+		);
+		msg
+	}
+
+	#[test]
+	fn lease() {
+		let reply = parse_reply(&reply(REPLY, 0), &XID, &DUID).unwrap();
+		assert_eq!(reply.msg_type, REPLY);
+		assert_eq!(reply.address, Some((ADDRESS, 7200)));
+		assert_eq!(reply.t1, 1800);
+		assert_eq!(
+			reply.dns_servers,
+			[Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53)]
+		);
+	}
+
+	#[test]
+	fn foreign_and_failed_replies() {
+		assert!(parse_reply(&reply(REPLY, 0), &[0, 0, 0], &DUID).is_none());
+		assert!(parse_reply(&reply(REPLY, 0), &XID, &[0, 3, 0, 1, 0, 0, 0, 0, 0, 0]).is_none());
+		assert!(parse_reply(&reply(SOLICIT, 0), &XID, &DUID).is_none());
+		// NoAddrsAvail
+		assert_eq!(
+			parse_reply(&reply(ADVERTISE, 2), &XID, &DUID)
+				.unwrap()
+				.address,
+			None
+		);
+	}
+}
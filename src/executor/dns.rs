@@ -0,0 +1,152 @@
+//! Stub resolver on top of the DNS socket of smoltcp.
+//!
+//! Name servers are either passed by `-dns` on the kernel command line or
+//! learned from DHCPv4 and DHCPv6. Statically configured servers take precedence over
+//! the servers of a DHCP lease. The DNS socket belongs to the device, which
+//! routes the first name server.
+
+use alloc::vec::Vec;
+use core::future;
+use core::str::FromStr;
+use core::task::Poll;
+
+use smoltcp::config::DNS_MAX_SERVER_COUNT;
+use smoltcp::iface::{SocketHandle, SocketSet};
+use smoltcp::socket::dns::{self, GetQueryResultError, QueryHandle, StartQueryError};
+use smoltcp::wire::{DnsQueryType, IpAddress};
+
+use crate::executor::network::{NetworkInterface, NIC};
+use crate::fd::IoError;
+
+/// Returns the name servers, which are passed by `-dns`
+fn static_servers() -> Option<Vec<IpAddress>> {
+	hermit_var!("HERMIT_DNS").map(|servers| {
+		servers
+			.split(',')
+			.filter_map(|s| match IpAddress::from_str(s.trim()) {
+				Ok(addr) => Some(addr),
+				Err(_) => {
+					warn!("Ignore invalid DNS server {}", s);
+					None
+				}
+			})
+			.take(DNS_MAX_SERVER_COUNT)
+			.collect()
+	})
+}
+
+/// Adds the DNS socket to the socket set of a device
+pub(crate) fn create_socket(sockets: &mut SocketSet<'_>) -> SocketHandle {
+	let servers = static_servers().unwrap_or_default();
+	for (i, s) in servers.iter().enumerate() {
+		info!("DNS server {}:    {}", i, s);
+	}
+
+	sockets.add(dns::Socket::new(&servers, vec![]))
+}
+
+impl<'a> NetworkInterface<'a> {
+	/// Moves the DNS socket to the device, which routes `server`
+	fn move_dns_socket(&mut self, server: IpAddress) {
+		if let Some(iface) = self.route(server) {
+			self.dns_handle = self.move_socket(self.dns_handle, iface);
+		}
+	}
+
+	/// Moves the DNS socket to the device of the first server, which is passed by `-dns`
+	pub(crate) fn route_dns_socket(&mut self) {
+		if let Some(server) = static_servers().and_then(|servers| servers.first().copied()) {
+			self.move_dns_socket(server);
+		}
+	}
+
+	/// Uses the name servers of a DHCP lease, if no servers are passed by `-dns`
+	#[cfg(any(feature = "dhcpv4", feature = "dhcpv6"))]
+	pub(crate) fn set_dhcp_dns_servers(&mut self, servers: &[IpAddress]) {
+		if static_servers().is_some() {
+			return;
+		}
+
+		let servers = &servers[..servers.len().min(DNS_MAX_SERVER_COUNT)];
+		let dns_handle = self.dns_handle;
+		self.get_mut_socket::<dns::Socket<'_>>(dns_handle)
+			.update_servers(servers);
+		if let Some(server) = servers.first() {
+			self.move_dns_socket(*server);
+		}
+	}
+
+	fn start_query(
+		&mut self,
+		name: &str,
+		query_type: DnsQueryType,
+	) -> Result<QueryHandle, IoError> {
+		let (socket, cx) = self.get_socket_and_context::<dns::Socket<'_>>(self.dns_handle);
+		socket
+			.start_query(cx, name, query_type)
+			.map_err(|err| match err {
+				StartQueryError::NoFreeSlot => IoError::ENOBUFS,
+				StartQueryError::InvalidName | StartQueryError::NameTooLong => IoError::EINVAL,
+			})
+	}
+}
+
+/// Query, which is cancelled if the resolver gives up before it is answered
+struct Query {
+	handle: QueryHandle,
+	finished: bool,
+}
+
+impl Drop for Query {
+	fn drop(&mut self) {
+		if self.finished {
+			return;
+		}
+
+		if let Ok(nic) = NIC.lock().as_nic_mut() {
+			let dns_handle = nic.dns_handle;
+			nic.get_mut_socket::<dns::Socket<'_>>(dns_handle)
+				.cancel_query(self.handle);
+		}
+	}
+}
+
+/// Asks the name servers for the addresses of `name`. Returns `ENOENT`,
+/// if the name cannot be resolved.
+pub(crate) async fn resolve(
+	name: &str,
+	query_type: DnsQueryType,
+) -> Result<Vec<IpAddress>, IoError> {
+	let handle = NIC
+		.lock()
+		.as_nic_mut()
+		.map_err(|_| IoError::EIO)?
+		.start_query(name, query_type)?;
+	let mut query = Query {
+		handle,
+		finished: false,
+	};
+
+	future::poll_fn(|cx| {
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().map_err(|_| IoError::EIO)?;
+		let dns_handle = nic.dns_handle;
+		let socket = nic.get_mut_socket::<dns::Socket<'_>>(dns_handle);
+
+		match socket.get_query_result(query.handle) {
+			Ok(addrs) => {
+				query.finished = true;
+				Poll::Ready(Ok(addrs.into_iter().collect()))
+			}
+			Err(GetQueryResultError::Pending) => {
+				socket.register_query_waker(query.handle, cx.waker());
+				Poll::Pending
+			}
+			Err(GetQueryResultError::Failed) => {
+				query.finished = true;
+				Poll::Ready(Err(IoError::ENOENT))
+			}
+		}
+	})
+	.await
+}
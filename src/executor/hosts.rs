@@ -0,0 +1,119 @@
+//! Static name table in the format of `/etc/hosts`.
+//!
+//! Entries are either passed by `-host name=ip` on the kernel command line
+//! or read from `/etc/hosts`, e.g., from an initramfs. Entries of the command
+//! line take precedence over the file.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use smoltcp::wire::IpAddress;
+
+use crate::executor::block_on;
+use crate::fd::{AccessPermission, OpenOption};
+use crate::fs;
+
+const HOSTS_FILE: &str = "/etc/hosts";
+
+/// Returns the addresses of `name` in a table, where each line consists of
+/// an address followed by the host name and its aliases.
+fn find(table: &str, name: &str) -> Vec<IpAddress> {
+	table
+		.lines()
+		.filter_map(|line| {
+			let line = line.split('#').next().unwrap();
+			let mut fields = line.split_whitespace();
+			let addr = IpAddress::from_str(fields.next()?).ok()?;
+
+			fields
+				.any(|alias| alias.eq_ignore_ascii_case(name))
+				.then_some(addr)
+		})
+		.collect()
+}
+
+/// Returns the entries of `-host` in the format of a hosts table
+fn static_table() -> String {
+	hermit_var!("HERMIT_HOSTS")
+		.map(|hosts| {
+			hosts
+				.split(',')
+				.filter_map(|entry| entry.split_once('='))
+				.map(|(name, addr)| format!("{addr} {name}\n"))
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+fn read_hosts_file() -> Option<String> {
+	let file = fs::FILESYSTEM
+		.get()?
+		.open(HOSTS_FILE, OpenOption::O_RDONLY, AccessPermission::empty())
+		.ok()?;
+
+	let mut content = Vec::new();
+	let mut buf = [0u8; 512];
+	loop {
+		match block_on(file.async_read(&mut buf), None) {
+			Ok(0) => break,
+			Ok(len) => content.extend_from_slice(&buf[..len]),
+			Err(_) => return None,
+		}
+	}
+
+	String::from_utf8(content).ok()
+}
+
+/// Returns the addresses of the host `name` in the hosts table
+pub(crate) fn lookup(name: &str) -> Vec<IpAddress> {
+	let addrs = find(&static_table(), name);
+	if !addrs.is_empty() {
+		return addrs;
+	}
+
+	read_hosts_file()
+		.map(|table| find(&table, name))
+		.unwrap_or_default()
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use smoltcp::wire::{Ipv4Address, Ipv6Address};
+
+	use super::*;
+
+	const TABLE: &str = "# static entries
+127.0.0.1	localhost
+::1		localhost ip6-localhost
+10.0.5.2	server.example server	# file server
+
+invalid		broken
+";
+
+	#[test]
+	fn names_and_aliases() {
+		assert_eq!(
+			find(TABLE, "localhost"),
+			[
+				IpAddress::Ipv4(Ipv4Address::new(127, 0, 0, 1)),
+				IpAddress::Ipv6(Ipv6Address::LOOPBACK)
+			]
+		);
+		assert_eq!(
+			find(TABLE, "SERVER"),
+			[IpAddress::Ipv4(Ipv4Address::new(10, 0, 5, 2))]
+		);
+		assert_eq!(
+			find(TABLE, "ip6-localhost"),
+			[IpAddress::Ipv6(Ipv6Address::LOOPBACK)]
+		);
+	}
+
+	#[test]
+	fn comments_and_invalid_lines() {
+		assert!(find(TABLE, "file").is_empty());
+		assert!(find(TABLE, "broken").is_empty());
+		assert!(find(TABLE, "static").is_empty());
+	}
+}
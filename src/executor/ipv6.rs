@@ -0,0 +1,148 @@
+//! IPv6 configuration of the network interface.
+//!
+//! Every interface gets a link-local address, which is derived from the MAC
+//! address. Global addresses are either passed by `-ip6` and `-gateway6` on
+//! the kernel command line or learned by SLAAC and DHCPv6. If the arguments
+//! are used several times, the n-th value belongs to the n-th device.
+
+use core::str::FromStr;
+
+use smoltcp::iface::Interface;
+use smoltcp::wire::{EthernetAddress, IpCidr, Ipv6Address, Ipv6Cidr};
+
+use crate::executor::device::device_var;
+
+/// Prefix length of an address, which consists of a prefix and an interface identifier
+pub(crate) const INTERFACE_PREFIX_LEN: u8 = 64;
+
+/// Returns the modified EUI-64 interface identifier of a MAC address (RFC 4291)
+pub(crate) fn interface_id(mac: EthernetAddress) -> [u8; 8] {
+	let mac = mac.as_bytes();
+	[
+		mac[0] ^ 0x02,
+		mac[1],
+		mac[2],
+		0xff,
+		0xfe,
+		mac[3],
+		mac[4],
+		mac[5],
+	]
+}
+
+/// Combines the upper 64 bits of `prefix` with the interface identifier of `mac`
+pub(crate) fn address_from_prefix(prefix: Ipv6Address, mac: EthernetAddress) -> Ipv6Address {
+	let mut bytes = [0u8; 16];
+	bytes[..8].copy_from_slice(&prefix.as_bytes()[..8]);
+	bytes[8..].copy_from_slice(&interface_id(mac));
+	Ipv6Address::from_bytes(&bytes)
+}
+
+/// Returns the link-local address of the interface with the MAC address `mac`
+pub(crate) fn link_local_address(mac: EthernetAddress) -> Ipv6Address {
+	address_from_prefix(Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0), mac)
+}
+
+/// Parses an address with an optional prefix length, e.g., `2001:db8::5/64`.
+/// Without a prefix length, the address is part of a /64 network.
+fn parse_cidr(s: &str) -> Option<Ipv6Cidr> {
+	let (addr, prefix_len) = match s.trim().split_once('/') {
+		Some((addr, prefix_len)) => (addr, prefix_len.parse().ok()?),
+		None => (s.trim(), INTERFACE_PREFIX_LEN),
+	};
+	if prefix_len > 128 {
+		return None;
+	}
+
+	Some(Ipv6Cidr::new(Ipv6Address::from_str(addr).ok()?, prefix_len))
+}
+
+/// Returns the static address and gateway of the `index`-th device, which
+/// are passed by `-ip6` and `-gateway6`
+fn static_config(index: usize) -> (Option<Ipv6Cidr>, Option<Ipv6Address>) {
+	let cidr = device_var(hermit_var!("HERMIT_IP6"), index).and_then(|s| {
+		let cidr = parse_cidr(&s);
+		if cidr.is_none() {
+			warn!("Ignore invalid IPv6 address {}", s);
+		}
+		cidr
+	});
+	let gateway = device_var(hermit_var!("HERMIT_GATEWAY6"), index).and_then(|s| {
+		let gateway = Ipv6Address::from_str(s.trim()).ok();
+		if gateway.is_none() {
+			warn!("Ignore invalid IPv6 gateway {}", s);
+		}
+		gateway
+	});
+
+	(cidr, gateway)
+}
+
+/// Adds the link-local address and the static configuration to the
+/// interface of the `index`-th device
+pub(crate) fn configure(iface: &mut Interface, mac: EthernetAddress, index: usize) {
+	let link_local = Ipv6Cidr::new(link_local_address(mac), INTERFACE_PREFIX_LEN);
+	let (cidr, gateway) = static_config(index);
+
+	info!("IPv6 link-local address {}", link_local);
+	iface.update_ip_addrs(|addrs| {
+		if addrs.push(IpCidr::Ipv6(link_local)).is_err() {
+			warn!("Unable to add IPv6 link-local address");
+		}
+		if let Some(cidr) = cidr {
+			info!("Configure network interface with address {}", cidr);
+			if addrs.push(IpCidr::Ipv6(cidr)).is_err() {
+				warn!("Unable to add IPv6 address {}", cidr);
+			}
+		}
+	});
+
+	if let Some(gateway) = gateway {
+		info!("Configure IPv6 gateway with address {}", gateway);
+		if iface.routes_mut().add_default_ipv6_route(gateway).is_err() {
+			warn!("Unable to add IPv6 default route");
+		}
+	}
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	const MAC: EthernetAddress = EthernetAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+
+	#[test]
+	fn eui64() {
+		assert_eq!(
+			link_local_address(MAC),
+			Ipv6Address::new(0xfe80, 0, 0, 0, 0x5054, 0x00ff, 0xfe12, 0x3456)
+		);
+		assert_eq!(
+			address_from_prefix(
+				Ipv6Address::new(0x2001, 0xdb8, 0, 1, 0xa, 0xb, 0xc, 0xd),
+				MAC
+			),
+			Ipv6Address::new(0x2001, 0xdb8, 0, 1, 0x5054, 0x00ff, 0xfe12, 0x3456)
+		);
+	}
+
+	#[test]
+	fn static_address() {
+		assert_eq!(
+			parse_cidr("2001:db8::5/48"),
+			Some(Ipv6Cidr::new(
+				Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 5),
+				48
+			))
+		);
+		assert_eq!(
+			parse_cidr("2001:db8::5"),
+			Some(Ipv6Cidr::new(
+				Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 5),
+				64
+			))
+		);
+		assert_eq!(parse_cidr("2001:db8::5/129"), None);
+		assert_eq!(parse_cidr("10.0.5.3"), None);
+	}
+}
@@ -2,20 +2,37 @@
 
 #[cfg(any(feature = "tcp", feature = "udp"))]
 pub(crate) mod device;
+#[cfg(feature = "dhcpv6")]
+pub(crate) mod dhcpv6;
+#[cfg(feature = "dns")]
+pub(crate) mod dns;
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub(crate) mod hosts;
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub(crate) mod ipv6;
 #[cfg(any(feature = "tcp", feature = "udp"))]
 pub(crate) mod network;
+#[cfg(feature = "tcp")]
+pub(crate) mod retransmit;
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub(crate) mod route;
+#[cfg(feature = "slaac")]
+pub(crate) mod slaac;
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub(crate) mod stats;
 pub(crate) mod task;
 
 use alloc::sync::Arc;
 use alloc::task::Wake;
-use core::future::Future;
+use alloc::vec::Vec;
+use core::future::{self, Future};
 use core::pin::pin;
 use core::sync::atomic::AtomicU32;
 use core::task::{Context, Poll, Waker};
 use core::time::Duration;
 
 use crossbeam_utils::Backoff;
-use hermit_sync::without_interrupts;
+use hermit_sync::{without_interrupts, InterruptTicketMutex};
 #[cfg(any(feature = "tcp", feature = "udp"))]
 use smoltcp::time::Instant;
 
@@ -25,7 +42,7 @@ use crate::arch::core_local::*;
 	not(feature = "pci"),
 	not(feature = "newlib")
 ))]
-use crate::drivers::mmio::get_network_driver;
+use crate::drivers::mmio::get_network_drivers;
 #[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
 use crate::drivers::net::NetworkDriver;
 #[cfg(all(
@@ -33,7 +50,7 @@ use crate::drivers::net::NetworkDriver;
 	feature = "pci",
 	not(feature = "newlib")
 ))]
-use crate::drivers::pci::get_network_driver;
+use crate::drivers::pci::get_network_drivers;
 #[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
 use crate::executor::network::network_delay;
 use crate::executor::task::AsyncTask;
@@ -73,7 +90,55 @@ impl Wake for TaskNotify {
 	}
 }
 
+/// Wakers of futures, which wait for a point in time, with their deadline
+static TIMERS: InterruptTicketMutex<Vec<(u64, Waker)>> = InterruptTicketMutex::new(Vec::new());
+
+/// Returns a future, which completes after `duration`
+pub(crate) fn sleep(duration: Duration) -> impl Future<Output = ()> {
+	let deadline = now() + u64::try_from(duration.as_micros()).unwrap();
+
+	future::poll_fn(move |cx| {
+		if now() >= deadline {
+			return Poll::Ready(());
+		}
+
+		let mut timers = TIMERS.lock();
+		if !timers
+			.iter()
+			.any(|(time, waker)| *time == deadline && waker.will_wake(cx.waker()))
+		{
+			timers.push((deadline, cx.waker().clone()));
+		}
+		Poll::Pending
+	})
+}
+
+/// Wakes the futures, whose deadline has passed
+fn wake_timers() {
+	let now = now();
+	TIMERS.lock().retain(|(deadline, waker)| {
+		if *deadline <= now {
+			waker.wake_by_ref();
+			false
+		} else {
+			true
+		}
+	});
+}
+
+/// Returns the time in microseconds until the next deadline of a timer
+fn next_timer() -> Option<u64> {
+	let now = now();
+	TIMERS
+		.lock()
+		.iter()
+		.map(|(deadline, _)| deadline.saturating_sub(now))
+		.min()
+}
+
 pub(crate) fn run() {
+	wake_timers();
+
 	let mut cx = Context::from_waker(Waker::noop());
 
 	without_interrupts(|| {
@@ -101,6 +166,14 @@ pub fn init() {
 	crate::executor::network::init();
 }
 
+/// Enables or disables the polling mode of all network devices
+#[cfg(any(feature = "tcp", feature = "udp"))]
+fn set_polling_mode(value: bool) {
+	for nic in get_network_drivers() {
+		nic.lock().set_polling_mode(value);
+	}
+}
+
 #[inline]
 pub(crate) fn now() -> u64 {
 	crate::arch::kernel::systemtime::now_micros()
@@ -111,17 +184,13 @@ pub(crate) fn poll_on<F, T>(future: F, timeout: Option<Duration>) -> Result<T, I
 where
 	F: Future<Output = Result<T, IoError>>,
 {
-	#[cfg(any(feature = "tcp", feature = "udp"))]
-	let nic = get_network_driver();
-
 	// disable network interrupts
 	#[cfg(any(feature = "tcp", feature = "udp"))]
-	let no_retransmission = if let Some(nic) = nic {
-		let mut guard = nic.lock();
-		guard.set_polling_mode(true);
-		guard.get_checksums().tcp.tx()
-	} else {
-		true
+	let no_retransmission = {
+		set_polling_mode(true);
+		get_network_drivers()
+			.next()
+			.map_or(true, |nic| nic.lock().get_checksums().tcp.tx())
 	};
 
 	let start = now();
@@ -143,9 +212,7 @@ where
 
 			// allow network interrupts
 			#[cfg(any(feature = "tcp", feature = "udp"))]
-			if let Some(nic) = nic {
-				nic.lock().set_polling_mode(false);
-			}
+			set_polling_mode(false);
 
 			return t;
 		}
@@ -162,9 +229,7 @@ where
 
 				// allow network interrupts
 				#[cfg(any(feature = "tcp", feature = "udp"))]
-				if let Some(nic) = nic {
-					nic.lock().set_polling_mode(false);
-				}
+				set_polling_mode(false);
 
 				return Err(IoError::ETIME);
 			}
@@ -177,17 +242,13 @@ pub(crate) fn block_on<F, T>(future: F, timeout: Option<Duration>) -> Result<T,
 where
 	F: Future<Output = Result<T, IoError>>,
 {
-	#[cfg(any(feature = "tcp", feature = "udp"))]
-	let nic = get_network_driver();
-
 	// disable network interrupts
 	#[cfg(any(feature = "tcp", feature = "udp"))]
-	let no_retransmission = if let Some(nic) = nic {
-		let mut guard = nic.lock();
-		guard.set_polling_mode(true);
-		!guard.get_checksums().tcp.tx()
-	} else {
-		true
+	let no_retransmission = {
+		set_polling_mode(true);
+		get_network_drivers()
+			.next()
+			.map_or(true, |nic| !nic.lock().get_checksums().tcp.tx())
 	};
 
 	let backoff = Backoff::new();
@@ -213,9 +274,7 @@ where
 
 			// allow network interrupts
 			#[cfg(any(feature = "tcp", feature = "udp"))]
-			if let Some(nic) = nic {
-				nic.lock().set_polling_mode(false);
-			}
+			set_polling_mode(false);
 
 			return t;
 		}
@@ -232,9 +291,7 @@ where
 
 				// allow network interrupts
 				#[cfg(any(feature = "tcp", feature = "udp"))]
-				if let Some(nic) = nic {
-					nic.lock().set_polling_mode(false);
-				}
+				set_polling_mode(false);
 
 				return Err(IoError::ETIME);
 			}
@@ -248,6 +305,10 @@ where
 			if backoff.is_completed() && delay.unwrap_or(10_000_000) > 10_000 {
 				let wakeup_time =
 					timeout.map(|duration| start + u64::try_from(duration.as_micros()).unwrap());
+				let wakeup_time = match (wakeup_time, next_timer()) {
+					(Some(time), Some(timer)) => Some(time.min(timer)),
+					(time, timer) => time.or(timer),
+				};
 				if !no_retransmission {
 					let ticks = crate::arch::processor::get_timer_ticks();
 					let network_timer = delay.map(|d| ticks + d);
@@ -255,17 +316,13 @@ where
 				}
 
 				// allow network interrupts
-				if let Some(nic) = nic {
-					nic.lock().set_polling_mode(false);
-				}
+				set_polling_mode(false);
 
 				// switch to another task
 				task_notify.wait(wakeup_time);
 
 				// restore default values
-				if let Some(nic) = nic {
-					nic.lock().set_polling_mode(true);
-				}
+				set_polling_mode(true);
 				backoff.reset();
 			} else {
 				backoff.snooze();
@@ -277,6 +334,10 @@ where
 			if backoff.is_completed() {
 				let wakeup_time =
 					timeout.map(|duration| start + u64::try_from(duration.as_micros()).unwrap());
+				let wakeup_time = match (wakeup_time, next_timer()) {
+					(Some(time), Some(timer)) => Some(time.min(timer)),
+					(time, timer) => time.or(timer),
+				};
 
 				// switch to another task
 				task_notify.wait(wakeup_time);
@@ -1,4 +1,5 @@
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::future;
 use core::ops::DerefMut;
 use core::sync::atomic::{AtomicU16, Ordering};
@@ -8,18 +9,34 @@ use hermit_sync::InterruptTicketMutex;
 use smoltcp::iface::{SocketHandle, SocketSet};
 #[cfg(feature = "dhcpv4")]
 use smoltcp::socket::dhcpv4;
+#[cfg(feature = "icmp")]
+use smoltcp::socket::icmp;
+#[cfg(feature = "raw")]
+use smoltcp::socket::raw;
 #[cfg(feature = "tcp")]
 use smoltcp::socket::tcp;
 #[cfg(feature = "udp")]
 use smoltcp::socket::udp;
-use smoltcp::socket::AnySocket;
+use smoltcp::socket::{AnySocket, Socket};
 use smoltcp::time::{Duration, Instant};
+#[cfg(any(feature = "udp", feature = "dns"))]
+use smoltcp::wire::IpAddress;
 #[cfg(feature = "dhcpv4")]
 use smoltcp::wire::{IpCidr, Ipv4Address, Ipv4Cidr};
+#[cfg(feature = "raw")]
+use smoltcp::wire::{IpProtocol, IpVersion};
 
 use crate::arch;
-use crate::executor::device::HermitNet;
+use crate::executor::device::NetworkPhy;
+#[cfg(feature = "dhcpv6")]
+use crate::executor::dhcpv6::Dhcpv6;
+#[cfg(feature = "tcp")]
+use crate::executor::retransmit::{CountingPhy, Retransmissions};
+#[cfg(feature = "slaac")]
+use crate::executor::slaac::Slaac;
 use crate::executor::spawn;
+#[cfg(feature = "udp")]
+use crate::fd::IoError;
 use crate::scheduler::PerCoreSchedulerExt;
 
 pub(crate) enum NetworkState<'a> {
@@ -37,18 +54,42 @@ impl<'a> NetworkState<'a> {
 	}
 }
 
-pub(crate) type Handle = SocketHandle;
+/// Identifies a socket and the network device, which owns the socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Handle {
+	/// Index of the device in the list of network devices
+	pub(super) iface: usize,
+	pub(super) socket: SocketHandle,
+}
 
 static LOCAL_ENDPOINT: AtomicU16 = AtomicU16::new(0);
 pub(crate) static NIC: InterruptTicketMutex<NetworkState<'_>> =
 	InterruptTicketMutex::new(NetworkState::Missing);
 
-pub(crate) struct NetworkInterface<'a> {
+/// Interface of a network driver or of the loopback device with its own socket set
+pub(crate) struct NetworkDevice<'a> {
 	pub(super) iface: smoltcp::iface::Interface,
 	pub(super) sockets: SocketSet<'a>,
-	pub(super) device: HermitNet,
+	pub(super) device: NetworkPhy,
+	/// Counter of the retransmitted TCP segments, which smoltcp doesn't provide
+	#[cfg(feature = "tcp")]
+	pub(super) retransmissions: Retransmissions,
 	#[cfg(feature = "dhcpv4")]
-	pub(super) dhcp_handle: SocketHandle,
+	pub(super) dhcp_handle: Option<SocketHandle>,
+	/// Name servers of a DHCP lease, which aren't yet passed to the resolver
+	#[cfg(feature = "dns")]
+	pub(super) dns_servers: Option<Vec<IpAddress>>,
+	/// Autoconfiguration of a device with a MAC address
+	#[cfg(feature = "slaac")]
+	pub(super) slaac: Option<Slaac>,
+	#[cfg(feature = "dhcpv6")]
+	pub(super) dhcpv6: Option<Dhcpv6>,
+}
+
+pub(crate) struct NetworkInterface<'a> {
+	pub(super) devices: Vec<NetworkDevice<'a>>,
+	#[cfg(feature = "dns")]
+	pub(super) dns_handle: Handle,
 }
 
 #[cfg(target_arch = "x86_64")]
@@ -126,15 +167,121 @@ pub(crate) fn init() {
 	}
 }
 
+impl<'a> NetworkDevice<'a> {
+	pub(crate) fn poll(&mut self, timestamp: Instant) {
+		#[cfg(feature = "tcp")]
+		{
+			let mut device = CountingPhy::new(&mut self.device, &mut self.retransmissions);
+			let _ = self.iface.poll(timestamp, &mut device, &mut self.sockets);
+			self.retransmissions.prune(&self.sockets);
+		}
+		#[cfg(not(feature = "tcp"))]
+		let _ = self
+			.iface
+			.poll(timestamp, &mut self.device, &mut self.sockets);
+
+		// the loopback device isn't configured by DHCP
+		#[cfg(feature = "dhcpv4")]
+		if let Some(dhcp_handle) = self.dhcp_handle {
+			match self
+				.sockets
+				.get_mut::<dhcpv4::Socket<'_>>(dhcp_handle)
+				.poll()
+			{
+				None => {}
+				Some(dhcpv4::Event::Configured(config)) => {
+					info!("DHCP config acquired!");
+					info!("IP address:      {}", config.address);
+					self.iface.update_ip_addrs(|addrs| {
+						if let Some(dest) = addrs
+							.iter_mut()
+							.find(|addr| matches!(addr, IpCidr::Ipv4(_)))
+						{
+							*dest = IpCidr::Ipv4(config.address);
+						} else if addrs.push(IpCidr::Ipv4(config.address)).is_err() {
+							info!("Unable to update IP address");
+						}
+					});
+					if let Some(router) = config.router {
+						info!("Default gateway: {}", router);
+						self.iface
+							.routes_mut()
+							.add_default_ipv4_route(router)
+							.unwrap();
+					} else {
+						info!("Default gateway: None");
+						self.iface.routes_mut().remove_default_ipv4_route();
+					}
+					// the gateways of static routes may be reachable by the new address
+					super::route::add_static_routes(&mut self.iface);
+
+					for (i, s) in config.dns_servers.iter().enumerate() {
+						info!("DNS server {}:    {}", i, s);
+					}
+					#[cfg(feature = "dns")]
+					{
+						self.dns_servers = Some(
+							config
+								.dns_servers
+								.iter()
+								.map(|s| IpAddress::Ipv4(*s))
+								.collect(),
+						);
+					}
+				}
+				Some(dhcpv4::Event::Deconfigured) => {
+					info!("DHCP lost config!");
+					let cidr = Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0);
+					self.iface.update_ip_addrs(|addrs| {
+						if let Some(dest) = addrs
+							.iter_mut()
+							.find(|addr| matches!(addr, IpCidr::Ipv4(_)))
+						{
+							*dest = IpCidr::Ipv4(cidr);
+						}
+					});
+					self.iface.routes_mut().remove_default_ipv4_route();
+					#[cfg(feature = "dns")]
+					{
+						self.dns_servers = Some(Vec::new());
+					}
+				}
+			}
+		}
+
+		#[cfg(feature = "slaac")]
+		self.poll_slaac(timestamp);
+		#[cfg(feature = "dhcpv6")]
+		self.poll_dhcpv6(timestamp);
+	}
+
+	pub(crate) fn poll_delay(&mut self, timestamp: Instant) -> Option<Duration> {
+		// timers of the IPv6 autoconfiguration aren't known by smoltcp
+		#[cfg(feature = "slaac")]
+		if let Some(at) = self.autoconf_poll_at() {
+			let delay = if at > timestamp {
+				at - timestamp
+			} else {
+				Duration::ZERO
+			};
+			return Some(
+				self.iface
+					.poll_delay(timestamp, &self.sockets)
+					.map_or(delay, |d| d.min(delay)),
+			);
+		}
+
+		self.iface.poll_delay(timestamp, &self.sockets)
+	}
+}
+
 impl<'a> NetworkInterface<'a> {
 	#[cfg(feature = "udp")]
 	pub(crate) fn create_udp_handle(&mut self) -> Result<Handle, ()> {
-		let udp_rx_buffer =
-			udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 65535]);
-		let udp_tx_buffer =
-			udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 65535]);
+		let udp_rx_buffer = crate::fd::socket::udp::packet_buffer(65535);
+		let udp_tx_buffer = crate::fd::socket::udp::packet_buffer(65535);
 		let udp_socket = udp::Socket::new(udp_rx_buffer, udp_tx_buffer);
-		let udp_handle = self.sockets.add(udp_socket);
+		let udp_handle = self.add_socket(0, udp_socket);
 
 		Ok(udp_handle)
 	}
@@ -145,84 +292,161 @@ impl<'a> NetworkInterface<'a> {
 		let tcp_tx_buffer = tcp::SocketBuffer::new(vec![0; 65535]);
 		let mut tcp_socket = tcp::Socket::new(tcp_rx_buffer, tcp_tx_buffer);
 		tcp_socket.set_nagle_enabled(true);
-		let tcp_handle = self.sockets.add(tcp_socket);
+		let tcp_handle = self.add_socket(0, tcp_socket);
 
 		Ok(tcp_handle)
 	}
 
+	#[cfg(feature = "icmp")]
+	pub(crate) fn create_icmp_handle(&mut self) -> Result<Handle, ()> {
+		let icmp_rx_buffer =
+			icmp::PacketBuffer::new(vec![icmp::PacketMetadata::EMPTY; 4], vec![0; 65535]);
+		let icmp_tx_buffer =
+			icmp::PacketBuffer::new(vec![icmp::PacketMetadata::EMPTY; 4], vec![0; 65535]);
+		let icmp_socket = icmp::Socket::new(icmp_rx_buffer, icmp_tx_buffer);
+		let icmp_handle = self.add_socket(0, icmp_socket);
+
+		Ok(icmp_handle)
+	}
+
+	#[cfg(feature = "raw")]
+	pub(crate) fn create_raw_handle(
+		&mut self,
+		version: IpVersion,
+		protocol: IpProtocol,
+	) -> Result<Handle, ()> {
+		let raw_rx_buffer =
+			raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY; 4], vec![0; 65535]);
+		let raw_tx_buffer =
+			raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY; 4], vec![0; 65535]);
+		let raw_socket = raw::Socket::new(version, protocol, raw_rx_buffer, raw_tx_buffer);
+		let raw_handle = self.add_socket(0, raw_socket);
+
+		Ok(raw_handle)
+	}
+
 	pub(crate) fn poll_common(&mut self, timestamp: Instant) {
-		let _ = self
-			.iface
-			.poll(timestamp, &mut self.device, &mut self.sockets);
+		for device in self.devices.iter_mut() {
+			device.poll(timestamp);
+		}
 
-		#[cfg(feature = "dhcpv4")]
-		match self
-			.sockets
-			.get_mut::<dhcpv4::Socket<'_>>(self.dhcp_handle)
-			.poll()
+		#[cfg(feature = "dns")]
 		{
-			None => {}
-			Some(dhcpv4::Event::Configured(config)) => {
-				info!("DHCP config acquired!");
-				info!("IP address:      {}", config.address);
-				self.iface.update_ip_addrs(|addrs| {
-					if let Some(dest) = addrs.iter_mut().next() {
-						*dest = IpCidr::Ipv4(config.address);
-					} else if addrs.push(IpCidr::Ipv4(config.address)).is_err() {
-						info!("Unable to update IP address");
-					}
-				});
-				if let Some(router) = config.router {
-					info!("Default gateway: {}", router);
-					self.iface
-						.routes_mut()
-						.add_default_ipv4_route(router)
-						.unwrap();
-				} else {
-					info!("Default gateway: None");
-					self.iface.routes_mut().remove_default_ipv4_route();
-				}
-
-				for (i, s) in config.dns_servers.iter().enumerate() {
-					info!("DNS server {}:    {}", i, s);
-				}
+			let leases: Vec<Vec<IpAddress>> = self
+				.devices
+				.iter_mut()
+				.filter_map(|device| device.dns_servers.take())
+				.collect();
+			for servers in leases {
+				self.set_dhcp_dns_servers(&servers);
 			}
-			Some(dhcpv4::Event::Deconfigured) => {
-				info!("DHCP lost config!");
-				let cidr = Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0);
-				self.iface.update_ip_addrs(|addrs| {
-					if let Some(dest) = addrs.iter_mut().next() {
-						*dest = IpCidr::Ipv4(cidr);
-					}
-				});
-				self.iface.routes_mut().remove_default_ipv4_route();
-			}
-		};
+		}
 	}
 
 	pub(crate) fn poll_delay(&mut self, timestamp: Instant) -> Option<Duration> {
-		self.iface.poll_delay(timestamp, &self.sockets)
+		self.devices
+			.iter_mut()
+			.filter_map(|device| device.poll_delay(timestamp))
+			.min()
+	}
+
+	/// Returns the number of network devices
+	pub(crate) fn device_count(&self) -> usize {
+		self.devices.len()
 	}
 
 	#[allow(dead_code)]
-	pub(crate) fn get_socket<T: AnySocket<'a>>(&self, handle: SocketHandle) -> &T {
-		self.sockets.get(handle)
+	pub(crate) fn get_socket<T: AnySocket<'a>>(&self, handle: Handle) -> &T {
+		self.devices[handle.iface].sockets.get(handle.socket)
 	}
 
-	pub(crate) fn get_mut_socket<T: AnySocket<'a>>(&mut self, handle: SocketHandle) -> &mut T {
-		self.sockets.get_mut(handle)
+	pub(crate) fn get_mut_socket<T: AnySocket<'a>>(&mut self, handle: Handle) -> &mut T {
+		self.devices[handle.iface].sockets.get_mut(handle.socket)
 	}
 
 	pub(crate) fn get_socket_and_context<T: AnySocket<'a>>(
 		&mut self,
-		handle: SocketHandle,
+		handle: Handle,
 	) -> (&mut T, &mut smoltcp::iface::Context) {
-		(self.sockets.get_mut(handle), self.iface.context())
+		let device = &mut self.devices[handle.iface];
+		(
+			device.sockets.get_mut(handle.socket),
+			device.iface.context(),
+		)
+	}
+
+	fn add_socket<T: AnySocket<'a>>(&mut self, iface: usize, socket: T) -> Handle {
+		Handle {
+			iface,
+			socket: self.devices[iface].sockets.add(socket),
+		}
+	}
+
+	/// Adds a TCP socket, whose buffers and options are prepared by the caller
+	#[cfg(feature = "tcp")]
+	pub(crate) fn add_tcp_socket(&mut self, iface: usize, socket: tcp::Socket<'a>) -> Handle {
+		self.add_socket(iface, socket)
+	}
+
+	/// Adds a UDP socket, whose buffers and options are prepared by the caller
+	#[cfg(feature = "udp")]
+	pub(crate) fn add_udp_socket(&mut self, iface: usize, socket: udp::Socket<'a>) -> Handle {
+		self.add_socket(iface, socket)
 	}
 
 	pub(crate) fn destroy_socket(&mut self, handle: Handle) {
 		// This deallocates the socket's buffers
-		self.sockets.remove(handle);
+		self.devices[handle.iface].sockets.remove(handle.socket);
+	}
+
+	/// Moves a socket with its state and buffers to the device `iface`.
+	/// Returns the new handle of the socket.
+	pub(crate) fn move_socket(&mut self, handle: Handle, iface: usize) -> Handle {
+		if handle.iface == iface {
+			return handle;
+		}
+
+		match self.devices[handle.iface].sockets.remove(handle.socket) {
+			#[cfg(feature = "tcp")]
+			Socket::Tcp(socket) => self.add_socket(iface, socket),
+			#[cfg(feature = "udp")]
+			Socket::Udp(socket) => self.add_socket(iface, socket),
+			#[cfg(feature = "icmp")]
+			Socket::Icmp(socket) => self.add_socket(iface, socket),
+			#[cfg(any(feature = "raw", feature = "slaac"))]
+			Socket::Raw(socket) => self.add_socket(iface, socket),
+			#[cfg(feature = "dns")]
+			Socket::Dns(socket) => self.add_socket(iface, socket),
+			#[allow(unreachable_patterns)]
+			_ => unreachable!("socket cannot be moved"),
+		}
+	}
+
+	/// Joins the multicast group `addr` on every device and announces the
+	/// membership by IGMP
+	#[cfg(feature = "udp")]
+	pub(crate) fn join_multicast_group(&mut self, addr: IpAddress) -> Result<(), IoError> {
+		for device in self.devices.iter_mut() {
+			device
+				.iface
+				.join_multicast_group(&mut device.device, addr, now())
+				.map_err(|_| IoError::ENOBUFS)?;
+		}
+
+		Ok(())
+	}
+
+	/// Leaves the multicast group `addr` on every device
+	#[cfg(feature = "udp")]
+	pub(crate) fn leave_multicast_group(&mut self, addr: IpAddress) -> Result<(), IoError> {
+		for device in self.devices.iter_mut() {
+			device
+				.iface
+				.leave_multicast_group(&mut device.device, addr, now())
+				.map_err(|_| IoError::EINVAL)?;
+		}
+
+		Ok(())
 	}
 }
 
@@ -0,0 +1,299 @@
+//! Counter of retransmitted TCP segments.
+//!
+//! smoltcp doesn't count retransmissions. Therefore, the sent packets of a
+//! device are inspected while the interface is polled. A segment, which
+//! starts below the highest sequence number that its connection has already
+//! sent, is counted as retransmission. Keep-alive probes repeat the last
+//! sent byte and are counted as well.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use smoltcp::iface::SocketSet;
+use smoltcp::phy::{self, Device, DeviceCapabilities, Medium};
+use smoltcp::socket::Socket;
+use smoltcp::time::Instant;
+use smoltcp::wire::{
+	EthernetFrame, EthernetProtocol, IpAddress, IpEndpoint, IpProtocol, Ipv4Packet, Ipv6Packet,
+	TcpPacket, TcpSeqNumber,
+};
+
+use crate::executor::device::{NetworkPhy, NetworkPhyTxToken, RxToken};
+
+/// Local and remote endpoint of a TCP connection
+type Connection = (IpEndpoint, IpEndpoint);
+
+/// Sequence space of a sent TCP segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+	connection: Connection,
+	seq_number: TcpSeqNumber,
+	/// Length in the sequence space, including SYN and FIN
+	len: usize,
+	syn: bool,
+	rst: bool,
+}
+
+/// Returns the TCP segment of a sent packet, which starts with a header of `medium`
+fn tcp_segment(medium: Medium, packet: &[u8]) -> Option<Segment> {
+	let packet = match medium {
+		Medium::Ethernet => {
+			let frame = EthernetFrame::new_checked(packet).ok()?;
+			match frame.ethertype() {
+				EthernetProtocol::Ipv4 | EthernetProtocol::Ipv6 => frame.payload(),
+				_ => return None,
+			}
+		}
+		Medium::Ip => packet,
+		#[allow(unreachable_patterns)]
+		_ => return None,
+	};
+
+	let (src_addr, dst_addr, payload) = match packet.first()? >> 4 {
+		4 => {
+			let packet = Ipv4Packet::new_checked(packet).ok()?;
+			// only the first fragment contains the TCP header
+			if packet.next_header() != IpProtocol::Tcp || packet.frag_offset() != 0 {
+				return None;
+			}
+			(
+				IpAddress::Ipv4(packet.src_addr()),
+				IpAddress::Ipv4(packet.dst_addr()),
+				packet.payload(),
+			)
+		}
+		6 => {
+			let packet = Ipv6Packet::new_checked(packet).ok()?;
+			// smoltcp doesn't add extension headers to TCP segments
+			if packet.next_header() != IpProtocol::Tcp {
+				return None;
+			}
+			(
+				IpAddress::Ipv6(packet.src_addr()),
+				IpAddress::Ipv6(packet.dst_addr()),
+				packet.payload(),
+			)
+		}
+		_ => return None,
+	};
+
+	let segment = TcpPacket::new_checked(payload).ok()?;
+	Some(Segment {
+		connection: (
+			IpEndpoint::new(src_addr, segment.src_port()),
+			IpEndpoint::new(dst_addr, segment.dst_port()),
+		),
+		seq_number: segment.seq_number(),
+		len: segment.segment_len(),
+		syn: segment.syn(),
+		rst: segment.rst(),
+	})
+}
+
+/// Retransmitted TCP segments of a network device
+#[derive(Debug, Default)]
+pub(crate) struct Retransmissions {
+	/// End of the sent sequence space of every connection
+	sent: BTreeMap<Connection, TcpSeqNumber>,
+	/// Connections were added since the last call of `prune`
+	added: bool,
+	/// Number of retransmitted segments
+	count: u64,
+}
+
+impl Retransmissions {
+	/// Returns the number of retransmitted segments
+	pub(crate) fn count(&self) -> u64 {
+		self.count
+	}
+
+	/// Counts `segment`, if its connection has already sent its sequence space
+	fn inspect(&mut self, segment: Segment) {
+		if segment.rst {
+			self.sent.remove(&segment.connection);
+			return;
+		}
+		// pure acknowledgements don't occupy sequence space
+		if segment.len == 0 {
+			return;
+		}
+
+		let end = segment.seq_number + segment.len;
+		match self.sent.get_mut(&segment.connection) {
+			Some(sent) if segment.syn => {
+				if end == *sent {
+					self.count += 1;
+				} else {
+					// a new connection reuses the endpoints
+					*sent = end;
+				}
+			}
+			Some(sent) => {
+				if segment.seq_number < *sent {
+					self.count += 1;
+				}
+				if end > *sent {
+					*sent = end;
+				}
+			}
+			None => {
+				self.sent.insert(segment.connection, end);
+				self.added = true;
+			}
+		}
+	}
+
+	/// Forgets the connections, which are closed without a reset
+	pub(crate) fn prune(&mut self, sockets: &SocketSet<'_>) {
+		if !self.added {
+			return;
+		}
+		self.added = false;
+
+		let open: Vec<Connection> = sockets
+			.iter()
+			.filter_map(|(_, socket)| match socket {
+				Socket::Tcp(socket) => Some((socket.local_endpoint()?, socket.remote_endpoint()?)),
+				#[allow(unreachable_patterns)]
+				_ => None,
+			})
+			.collect();
+		self.sent.retain(|connection, _| open.contains(connection));
+	}
+}
+
+/// Network device, which counts the retransmissions of its sent packets
+pub(crate) struct CountingPhy<'d> {
+	device: &'d mut NetworkPhy,
+	retransmissions: &'d mut Retransmissions,
+	medium: Medium,
+}
+
+impl<'d> CountingPhy<'d> {
+	pub(crate) fn new(
+		device: &'d mut NetworkPhy,
+		retransmissions: &'d mut Retransmissions,
+	) -> Self {
+		let medium = device.capabilities().medium;
+		Self {
+			device,
+			retransmissions,
+			medium,
+		}
+	}
+}
+
+impl Device for CountingPhy<'_> {
+	type RxToken<'a> = RxToken where Self: 'a;
+	type TxToken<'a> = CountingTxToken<'a> where Self: 'a;
+
+	fn capabilities(&self) -> DeviceCapabilities {
+		self.device.capabilities()
+	}
+
+	fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+		let (rx, token) = self.device.receive(timestamp)?;
+		Some((
+			rx,
+			CountingTxToken {
+				token,
+				retransmissions: self.retransmissions,
+				medium: self.medium,
+			},
+		))
+	}
+
+	fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+		let token = self.device.transmit(timestamp)?;
+		Some(CountingTxToken {
+			token,
+			retransmissions: self.retransmissions,
+			medium: self.medium,
+		})
+	}
+}
+
+#[doc(hidden)]
+pub(crate) struct CountingTxToken<'a> {
+	token: NetworkPhyTxToken<'a>,
+	retransmissions: &'a mut Retransmissions,
+	medium: Medium,
+}
+
+impl phy::TxToken for CountingTxToken<'_> {
+	fn consume<R, F>(self, len: usize, f: F) -> R
+	where
+		F: FnOnce(&mut [u8]) -> R,
+	{
+		let Self {
+			token,
+			retransmissions,
+			medium,
+		} = self;
+		phy::TxToken::consume(token, len, |buffer| {
+			let result = f(buffer);
+			if let Some(segment) = tcp_segment(medium, buffer) {
+				retransmissions.inspect(segment);
+			}
+			result
+		})
+	}
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	fn segment(seq_number: i32, len: usize) -> Segment {
+		Segment {
+			connection: (
+				IpEndpoint::new(IpAddress::v4(10, 0, 5, 3), 49152),
+				IpEndpoint::new(IpAddress::v4(10, 0, 5, 1), 80),
+			),
+			seq_number: TcpSeqNumber(seq_number),
+			len,
+			syn: false,
+			rst: false,
+		}
+	}
+
+	#[test]
+	fn retransmitted_segments() {
+		let mut retransmissions = Retransmissions::default();
+		retransmissions.inspect(Segment {
+			syn: true,
+			..segment(100, 1)
+		});
+		retransmissions.inspect(Segment {
+			syn: true,
+			..segment(100, 1)
+		});
+		assert_eq!(retransmissions.count(), 1);
+
+		retransmissions.inspect(segment(101, 100));
+		retransmissions.inspect(segment(201, 100));
+		// an acknowledgement without data
+		retransmissions.inspect(segment(301, 0));
+		assert_eq!(retransmissions.count(), 1);
+
+		retransmissions.inspect(segment(101, 200));
+		assert_eq!(retransmissions.count(), 2);
+
+		retransmissions.inspect(Segment {
+			rst: true,
+			..segment(301, 0)
+		});
+		assert!(retransmissions.sent.is_empty());
+	}
+
+	#[test]
+	fn sequence_number_wraps() {
+		let mut retransmissions = Retransmissions::default();
+		retransmissions.inspect(segment(i32::MAX - 49, 100));
+		retransmissions.inspect(segment(i32::MIN + 50, 100));
+		assert_eq!(retransmissions.count(), 0);
+
+		retransmissions.inspect(segment(i32::MAX - 49, 100));
+		assert_eq!(retransmissions.count(), 1);
+	}
+}
@@ -0,0 +1,151 @@
+//! Selection of the outgoing network device.
+//!
+//! Every device has its own routing table with the default gateways of the
+//! device. Static routes are passed by `-route <cidr>=<gateway>` on the kernel
+//! command line and belong to the device, whose network contains the gateway.
+//! A destination is sent by the device with the longest matching prefix of
+//! an address or a route. On a tie, the device with the lower index wins.
+
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use smoltcp::iface::{Interface, Route};
+use smoltcp::wire::{IpAddress, IpCidr};
+
+use crate::executor::network::NetworkInterface;
+
+/// Parses a route of the form `10.0.6.0/24=10.0.5.1`
+fn parse_route(s: &str) -> Option<(IpCidr, IpAddress)> {
+	let (cidr, gateway) = s.split_once('=')?;
+	let cidr = IpCidr::from_str(cidr.trim()).ok()?;
+	let gateway = IpAddress::from_str(gateway.trim()).ok()?;
+
+	(cidr.address().version() == gateway.version()).then_some((cidr, gateway))
+}
+
+/// Returns the routes, which are passed by `-route`
+fn static_routes() -> Vec<(IpCidr, IpAddress)> {
+	hermit_var!("HERMIT_ROUTES")
+		.map(|routes| {
+			routes
+				.split(',')
+				.filter_map(|s| {
+					let route = parse_route(s);
+					if route.is_none() {
+						warn!("Ignore invalid route {}", s);
+					}
+					route
+				})
+				.collect()
+		})
+		.unwrap_or_default()
+}
+
+/// Adds the static routes, whose gateway is part of a network of the interface
+pub(crate) fn add_static_routes(iface: &mut Interface) {
+	let routes: Vec<_> = static_routes()
+		.into_iter()
+		.filter(|(_, gateway)| {
+			iface
+				.ip_addrs()
+				.iter()
+				.any(|addr| !addr.address().is_unspecified() && addr.contains_addr(gateway))
+		})
+		.collect();
+
+	iface.routes_mut().update(|storage| {
+		for (cidr, gateway) in routes {
+			if storage.iter().any(|route| route.cidr == cidr) {
+				continue;
+			}
+
+			info!("Route {} via {}", cidr, gateway);
+			let route = Route {
+				cidr,
+				via_router: gateway,
+				preferred_until: None,
+				expires_at: None,
+			};
+			if storage.push(route).is_err() {
+				warn!("Unable to add route {}", cidr);
+			}
+		}
+	});
+}
+
+impl<'a> NetworkInterface<'a> {
+	/// Returns the index of the device, which sends packets to `dst`
+	pub(crate) fn route(&mut self, dst: IpAddress) -> Option<usize> {
+		let mut best: Option<(usize, u8)> = None;
+
+		for (i, device) in self.devices.iter_mut().enumerate() {
+			let local = device
+				.iface
+				.ip_addrs()
+				.iter()
+				.filter(|cidr| !cidr.address().is_unspecified() && cidr.contains_addr(&dst))
+				.map(|cidr| cidr.prefix_len())
+				.max();
+			let mut routed = None;
+			device.iface.routes_mut().update(|routes| {
+				routed = routes
+					.iter()
+					.filter(|route| route.cidr.contains_addr(&dst))
+					.map(|route| route.cidr.prefix_len())
+					.max();
+			});
+
+			if let Some(prefix_len) = local.max(routed) {
+				if best.map_or(true, |(_, best_len)| prefix_len > best_len) {
+					best = Some((i, prefix_len));
+				}
+			}
+		}
+
+		best.map(|(i, _)| i)
+	}
+
+	/// Returns the index of the device, which owns the address `addr`
+	pub(crate) fn device_of_address(&self, addr: IpAddress) -> Option<usize> {
+		self.devices
+			.iter()
+			.position(|device| device.iface.has_ip_addr(addr))
+	}
+
+	/// Returns an address of the device `iface`, which has the IP version of `peer`
+	#[cfg(feature = "udp")]
+	pub(crate) fn address_of_device(&self, iface: usize, peer: IpAddress) -> Option<IpAddress> {
+		self.devices[iface]
+			.iface
+			.ip_addrs()
+			.iter()
+			.map(|cidr| cidr.address())
+			.find(|addr| !addr.is_unspecified() && addr.version() == peer.version())
+	}
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn static_route() {
+		assert_eq!(
+			parse_route("10.0.6.0/24=10.0.5.1"),
+			Some((
+				IpCidr::new(IpAddress::v4(10, 0, 6, 0), 24),
+				IpAddress::v4(10, 0, 5, 1)
+			))
+		);
+		assert_eq!(
+			parse_route(" 2001:db8:1::/48 = fe80::1 "),
+			Some((
+				IpCidr::new(IpAddress::v6(0x2001, 0xdb8, 1, 0, 0, 0, 0, 0), 48),
+				IpAddress::v6(0xfe80, 0, 0, 0, 0, 0, 0, 1)
+			))
+		);
+		assert_eq!(parse_route("10.0.6.0/24=fe80::1"), None);
+		assert_eq!(parse_route("10.0.6.0/24"), None);
+		assert_eq!(parse_route("10.0.6.0/33=10.0.5.1"), None);
+	}
+}
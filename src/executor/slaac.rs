@@ -0,0 +1,332 @@
+//! Stateless address autoconfiguration (RFC 4862).
+//!
+//! The interface solicits router advertisements on a raw ICMPv6 socket.
+//! Advertised prefixes with the autonomous flag are combined with the
+//! interface identifier, and an advertising router becomes the default
+//! gateway. If a router announces managed addresses, DHCPv6 is started.
+//! SLAAC is disabled by a static address, which is passed by `-ip6`.
+
+use alloc::vec::Vec;
+
+use smoltcp::iface::{Interface, SocketHandle, SocketSet};
+use smoltcp::phy::ChecksumCapabilities;
+use smoltcp::socket::raw;
+use smoltcp::time::{Duration, Instant};
+use smoltcp::wire::{
+	EthernetAddress, Icmpv6Packet, Icmpv6Repr, IpAddress, IpCidr, IpProtocol, IpVersion,
+	Ipv6Address, Ipv6Cidr, Ipv6Packet, Ipv6Repr, NdiscPrefixInfoFlags, NdiscRepr, NdiscRouterFlags,
+	RawHardwareAddress,
+};
+
+use crate::executor::ipv6::{address_from_prefix, link_local_address, INTERFACE_PREFIX_LEN};
+use crate::executor::network::NetworkDevice;
+
+/// Maximum number of router solicitations (RFC 4861)
+const MAX_RTR_SOLICITATIONS: u8 = 3;
+/// Interval between router solicitations (RFC 4861)
+const RTR_SOLICITATION_INTERVAL: Duration = Duration::from_secs(4);
+/// Hop limit of neighbor discovery messages
+const NDISC_HOP_LIMIT: u8 = 255;
+
+#[derive(Debug)]
+struct Prefix {
+	cidr: Ipv6Cidr,
+	valid_until: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) struct Slaac {
+	handle: SocketHandle,
+	mac: EthernetAddress,
+	enabled: bool,
+	solicitations: u8,
+	next_solicitation: Option<Instant>,
+	/// Addresses, which are derived from advertised prefixes
+	prefixes: Vec<Prefix>,
+	router: Option<(Ipv6Address, Instant)>,
+}
+
+impl Slaac {
+	/// Adds the raw ICMPv6 socket to the socket set of the interface. SLAAC
+	/// is only enabled, if the interface doesn't have a global address.
+	pub(crate) fn new(
+		sockets: &mut SocketSet<'_>,
+		iface: &Interface,
+		mac: EthernetAddress,
+		now: Instant,
+	) -> Self {
+		let enabled = !iface
+			.ip_addrs()
+			.iter()
+			.any(|addr| matches!(addr, IpCidr::Ipv6(cidr) if !cidr.address().is_link_local()));
+
+		let rx_buffer = raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY; 4], vec![0; 4096]);
+		let tx_buffer = raw::PacketBuffer::new(vec![raw::PacketMetadata::EMPTY; 1], vec![0; 256]);
+		let socket = raw::Socket::new(IpVersion::Ipv6, IpProtocol::Icmpv6, rx_buffer, tx_buffer);
+
+		Self {
+			handle: sockets.add(socket),
+			mac,
+			enabled,
+			solicitations: 0,
+			next_solicitation: enabled.then_some(now),
+			prefixes: Vec::new(),
+			router: None,
+		}
+	}
+
+	/// Returns the time of the next solicitation or expiry
+	pub(crate) fn poll_at(&self) -> Option<Instant> {
+		self.prefixes
+			.iter()
+			.map(|prefix| prefix.valid_until)
+			.chain(self.router.map(|(_, valid_until)| valid_until))
+			.chain(self.next_solicitation)
+			.min()
+	}
+
+	/// Builds a router solicitation, which is sent to all routers
+	fn router_solicitation(&self) -> Vec<u8> {
+		let src_addr = link_local_address(self.mac);
+		let dst_addr = Ipv6Address::LINK_LOCAL_ALL_ROUTERS;
+		let icmp_repr = Icmpv6Repr::Ndisc(NdiscRepr::RouterSolicit {
+			lladdr: Some(RawHardwareAddress::from_bytes(self.mac.as_bytes())),
+		});
+		let ip_repr = Ipv6Repr {
+			src_addr,
+			dst_addr,
+			next_header: IpProtocol::Icmpv6,
+			payload_len: icmp_repr.buffer_len(),
+			hop_limit: NDISC_HOP_LIMIT,
+		};
+
+		let mut buffer = vec![0; ip_repr.buffer_len() + icmp_repr.buffer_len()];
+		let mut packet = Ipv6Packet::new_unchecked(&mut buffer[..]);
+		ip_repr.emit(&mut packet);
+		icmp_repr.emit(
+			&IpAddress::Ipv6(src_addr),
+			&IpAddress::Ipv6(dst_addr),
+			&mut Icmpv6Packet::new_unchecked(packet.payload_mut()),
+			&ChecksumCapabilities::default(),
+		);
+
+		buffer
+	}
+}
+
+/// Configuration, which is announced by a router advertisement
+struct Advertisement {
+	router: Ipv6Address,
+	router_lifetime: Duration,
+	flags: NdiscRouterFlags,
+	prefix: Option<(Ipv6Address, Duration)>,
+}
+
+/// Parses a router advertisement, which includes the IPv6 header
+fn parse_advertisement(data: &[u8]) -> Option<Advertisement> {
+	let packet = Ipv6Packet::new_checked(data).ok()?;
+	// routers send advertisements from their link-local address with
+	// the maximum hop limit, which proves that they are on the link
+	if packet.hop_limit() != NDISC_HOP_LIMIT || !packet.src_addr().is_link_local() {
+		return None;
+	}
+
+	let src_addr = IpAddress::Ipv6(packet.src_addr());
+	let dst_addr = IpAddress::Ipv6(packet.dst_addr());
+	let icmp_packet = Icmpv6Packet::new_checked(packet.payload()).ok()?;
+	let repr = Icmpv6Repr::parse(
+		&src_addr,
+		&dst_addr,
+		&icmp_packet,
+		&ChecksumCapabilities::default(),
+	)
+	.ok()?;
+
+	if let Icmpv6Repr::Ndisc(NdiscRepr::RouterAdvert {
+		flags,
+		router_lifetime,
+		prefix_info,
+		..
+	}) = repr
+	{
+		let prefix = prefix_info
+			.filter(|info| {
+				info.flags.contains(NdiscPrefixInfoFlags::ADDRCONF)
+					&& info.prefix_len == INTERFACE_PREFIX_LEN
+					&& !info.prefix.is_link_local()
+			})
+			.map(|info| (info.prefix, info.valid_lifetime));
+
+		Some(Advertisement {
+			router: packet.src_addr(),
+			router_lifetime,
+			flags,
+			prefix,
+		})
+	} else {
+		None
+	}
+}
+
+impl Slaac {
+	/// Solicits routers, processes their advertisements and expires
+	/// addresses, whose lifetime is over. Returns true, if a router
+	/// announces that addresses are managed by DHCPv6.
+	fn poll(
+		&mut self,
+		iface: &mut Interface,
+		sockets: &mut SocketSet<'_>,
+		timestamp: Instant,
+	) -> bool {
+		let mut managed = false;
+
+		while let Ok(data) = sockets.get_mut::<raw::Socket<'_>>(self.handle).recv() {
+			if !self.enabled {
+				continue;
+			}
+			if let Some(advertisement) = parse_advertisement(data) {
+				managed |= self.process_advertisement(iface, advertisement, timestamp);
+			}
+		}
+
+		self.expire(iface, timestamp);
+
+		if let Some(next_solicitation) = self.next_solicitation {
+			if next_solicitation <= timestamp {
+				let packet = self.router_solicitation();
+				let socket = sockets.get_mut::<raw::Socket<'_>>(self.handle);
+				if socket.send_slice(&packet).is_err() {
+					warn!("Unable to send router solicitation");
+				}
+
+				self.solicitations += 1;
+				self.next_solicitation = (self.solicitations < MAX_RTR_SOLICITATIONS)
+					.then(|| timestamp + RTR_SOLICITATION_INTERVAL);
+			}
+		}
+
+		managed
+	}
+
+	fn process_advertisement(
+		&mut self,
+		iface: &mut Interface,
+		advertisement: Advertisement,
+		timestamp: Instant,
+	) -> bool {
+		// a router answered => stop soliciting
+		self.next_solicitation = None;
+
+		if let Some((prefix, valid_lifetime)) = advertisement.prefix {
+			let cidr = Ipv6Cidr::new(address_from_prefix(prefix, self.mac), INTERFACE_PREFIX_LEN);
+			let valid_until = timestamp + valid_lifetime;
+
+			if let Some(entry) = self.prefixes.iter_mut().find(|p| p.cidr == cidr) {
+				entry.valid_until = valid_until;
+			} else if valid_lifetime > Duration::ZERO {
+				info!("SLAAC address:   {}", cidr);
+				let mut added = false;
+				iface.update_ip_addrs(|addrs| {
+					added = addrs.iter().any(|addr| *addr == IpCidr::Ipv6(cidr))
+						|| addrs.push(IpCidr::Ipv6(cidr)).is_ok();
+				});
+				if added {
+					self.prefixes.push(Prefix { cidr, valid_until });
+				} else {
+					warn!("Unable to add IPv6 address {}", cidr);
+				}
+			}
+		}
+
+		if advertisement.router_lifetime > Duration::ZERO {
+			if self.router.map(|(router, _)| router) != Some(advertisement.router) {
+				info!("IPv6 gateway:    {}", advertisement.router);
+			}
+			match iface
+				.routes_mut()
+				.add_default_ipv6_route(advertisement.router)
+			{
+				Ok(_) => {
+					self.router = Some((
+						advertisement.router,
+						timestamp + advertisement.router_lifetime,
+					));
+				}
+				Err(err) => warn!(
+					"Unable to add IPv6 gateway {}: {:?}",
+					advertisement.router, err
+				),
+			}
+		} else if self.router.map(|(router, _)| router) == Some(advertisement.router) {
+			// the router is no longer a default router
+			self.router = Some((advertisement.router, timestamp));
+		}
+
+		advertisement.flags.contains(NdiscRouterFlags::MANAGED)
+	}
+
+	/// Removes the addresses and the default route, whose lifetime is over
+	fn expire(&mut self, iface: &mut Interface, timestamp: Instant) {
+		if let Some((router, valid_until)) = self.router {
+			if valid_until <= timestamp {
+				info!("IPv6 gateway {} expired", router);
+				iface.routes_mut().remove_default_ipv6_route();
+				self.router = None;
+				// look for another router
+				self.solicitations = 0;
+				self.next_solicitation = Some(timestamp);
+			}
+		}
+
+		let expired: Vec<Ipv6Cidr> = self
+			.prefixes
+			.iter()
+			.filter(|prefix| prefix.valid_until <= timestamp)
+			.map(|prefix| prefix.cidr)
+			.collect();
+		if expired.is_empty() {
+			return;
+		}
+
+		for cidr in expired.iter() {
+			info!("SLAAC address {} expired", cidr);
+		}
+		self.prefixes
+			.retain(|prefix| prefix.valid_until > timestamp);
+		iface.update_ip_addrs(|addrs| {
+			addrs.retain(|addr| !matches!(addr, IpCidr::Ipv6(cidr) if expired.contains(cidr)));
+		});
+	}
+}
+
+impl<'a> NetworkDevice<'a> {
+	/// Returns the next time, at which SLAAC or DHCPv6 has to be polled
+	pub(crate) fn autoconf_poll_at(&self) -> Option<Instant> {
+		#[cfg(not(feature = "dhcpv6"))]
+		let dhcpv6 = None;
+		#[cfg(feature = "dhcpv6")]
+		let dhcpv6 = self.dhcpv6.as_ref().and_then(|dhcpv6| dhcpv6.poll_at());
+
+		self.slaac
+			.as_ref()
+			.and_then(Slaac::poll_at)
+			.into_iter()
+			.chain(dhcpv6)
+			.min()
+	}
+
+	/// Runs SLAAC on a device with autoconfiguration and starts DHCPv6,
+	/// if a router announces managed addresses
+	pub(crate) fn poll_slaac(&mut self, timestamp: Instant) {
+		let Some(slaac) = self.slaac.as_mut() else {
+			return;
+		};
+
+		if slaac.poll(&mut self.iface, &mut self.sockets, timestamp) {
+			#[cfg(feature = "dhcpv6")]
+			self.start_dhcpv6(timestamp);
+			#[cfg(not(feature = "dhcpv6"))]
+			debug!("Router announces DHCPv6, which isn't enabled");
+		}
+	}
+}
@@ -0,0 +1,161 @@
+//! Counters and configuration of the network devices.
+//!
+//! The report lists every device with its addresses, routes and counters,
+//! including the retransmitted TCP segments, followed by the open TCP and
+//! UDP sockets, similar to `netstat`. It is printed by the shell command
+//! `netstat` and returned by `sys_netstat`.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use smoltcp::phy::Device;
+use smoltcp::socket::Socket;
+use smoltcp::wire::{HardwareAddress, IpAddress, IpCidr};
+
+use crate::drivers::net::NetworkStats;
+use crate::executor::device::NetworkPhy;
+use crate::executor::network::{NetworkDevice, NetworkInterface, NIC};
+
+impl<'a> NetworkDevice<'a> {
+	/// Returns the name of the device, e.g., `eth0` for the first network
+	/// driver or `lo` for the loopback device
+	pub(crate) fn name(&self, index: usize) -> String {
+		match self.device {
+			NetworkPhy::Driver(_) => format!("eth{index}"),
+			NetworkPhy::Loopback(_) => String::from("lo"),
+		}
+	}
+
+	/// Returns the MTU of the device
+	pub(crate) fn mtu(&self) -> usize {
+		self.device.capabilities().max_transmission_unit
+	}
+
+	/// Returns the counters of the device
+	pub(crate) fn stats(&self) -> NetworkStats {
+		match &self.device {
+			NetworkPhy::Driver(device) => device.stats(),
+			NetworkPhy::Loopback(device) => device.stats,
+		}
+	}
+
+	/// Writes the addresses, routes and counters of the device
+	fn write_device(&mut self, name: &str, f: &mut impl Write) -> fmt::Result {
+		writeln!(f, "{}: mtu {}", name, self.mtu())?;
+		if let HardwareAddress::Ethernet(mac) = self.iface.hardware_addr() {
+			writeln!(f, "\tether {}", mac)?;
+		}
+		for cidr in self.iface.ip_addrs() {
+			match cidr {
+				IpCidr::Ipv4(cidr) => writeln!(f, "\tinet {}", cidr)?,
+				IpCidr::Ipv6(cidr) => writeln!(f, "\tinet6 {}", cidr)?,
+			}
+		}
+
+		let mut routes: Vec<(IpCidr, IpAddress)> = Vec::new();
+		self.iface.routes_mut().update(|storage| {
+			routes.extend(storage.iter().map(|route| (route.cidr, route.via_router)));
+		});
+		for (cidr, gateway) in routes {
+			writeln!(f, "\troute {} via {}", cidr, gateway)?;
+		}
+
+		let stats = self.stats();
+		writeln!(
+			f,
+			"\tRX packets {} bytes {} errors {} dropped {}",
+			stats.rx_packets, stats.rx_bytes, stats.rx_errors, stats.rx_dropped
+		)?;
+		writeln!(
+			f,
+			"\tTX packets {} bytes {} errors {} dropped {}",
+			stats.tx_packets, stats.tx_bytes, stats.tx_errors, stats.tx_dropped
+		)?;
+		#[cfg(feature = "tcp")]
+		writeln!(f, "\tTCP retransmits {}", self.retransmissions.count())?;
+
+		Ok(())
+	}
+
+	/// Writes a line for every TCP and UDP socket of the device
+	fn write_sockets(&self, name: &str, f: &mut impl Write) -> fmt::Result {
+		for (_, socket) in self.sockets.iter() {
+			match socket {
+				#[cfg(feature = "tcp")]
+				Socket::Tcp(socket) => {
+					let local = socket.local_endpoint().map_or_else(
+						|| socket.listen_endpoint().to_string(),
+						|endpoint| endpoint.to_string(),
+					);
+					let remote = socket
+						.remote_endpoint()
+						.map_or_else(|| String::from("*:*"), |endpoint| endpoint.to_string());
+					writeln!(
+						f,
+						"tcp   {:<5} {:>6} {:>6} {:<40} {:<40} {}",
+						name,
+						socket.recv_queue(),
+						socket.send_queue(),
+						local,
+						remote,
+						socket.state()
+					)?;
+				}
+				#[cfg(feature = "udp")]
+				Socket::Udp(socket) => {
+					writeln!(
+						f,
+						"udp   {:<5} {:>6} {:>6} {:<40} {:<40}",
+						name,
+						socket.recv_queue(),
+						socket.send_queue(),
+						socket.endpoint().to_string(),
+						"*:*"
+					)?;
+				}
+				_ => {}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl<'a> NetworkInterface<'a> {
+	/// Returns the name, the MTU and the counters of the `index`-th device
+	pub(crate) fn device_stats(&self, index: usize) -> Option<(String, usize, NetworkStats)> {
+		let device = self.devices.get(index)?;
+		Some((device.name(index), device.mtu(), device.stats()))
+	}
+
+	/// Writes the devices and the open sockets in the style of `netstat`
+	pub(crate) fn write_report(&mut self, f: &mut impl Write) -> fmt::Result {
+		for (i, device) in self.devices.iter_mut().enumerate() {
+			let name = device.name(i);
+			device.write_device(&name, f)?;
+		}
+
+		writeln!(
+			f,
+			"\nProto Iface Recv-Q Send-Q {:<40} {:<40} State",
+			"Local Address", "Foreign Address"
+		)?;
+		for (i, device) in self.devices.iter().enumerate() {
+			device.write_sockets(&device.name(i), f)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Returns the report of the network devices and sockets
+pub(crate) fn report() -> String {
+	let mut report = String::new();
+	if let Ok(nic) = NIC.lock().as_nic_mut() {
+		// writing to a string cannot fail
+		nic.write_report(&mut report).unwrap();
+	}
+
+	report
+}
@@ -0,0 +1,296 @@
+//! Linux-like `epoll` instances with persistent interest sets.
+//!
+//! Every registered file descriptor owns a `Notifier`, which is passed as
+//! waker to the `poll` function of the object. If a socket or an eventfd
+//! signals a state change, the notifier enqueues itself into the ready list
+//! of the instance. Consequently, `epoll_wait` checks only these objects
+//! instead of all registered ones.
+//!
+//! Objects, which are ready, are checked again by the next `epoll_wait`.
+//! In level-triggered mode, they are reported as long as they are ready.
+//! In edge-triggered mode, only events are reported, which weren't
+//! available at the previous check or since a call on the descriptor
+//! returned `EAGAIN`.
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::{Arc, Weak};
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use core::future::{self, Future};
+use core::mem;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::task::{ready, Context, Poll, Waker};
+
+use async_lock::Mutex;
+use async_trait::async_trait;
+use hermit_sync::InterruptTicketMutex;
+
+use crate::arch::kernel::core_local::core_scheduler;
+use crate::fd::{FileDescriptor, IoError, ObjectInterface, PollEvent};
+
+/// Requests edge-triggered notification
+pub(crate) const EPOLLET: u32 = 1 << 31;
+/// Disables the file descriptor after the first reported event
+pub(crate) const EPOLLONESHOT: u32 = 1 << 30;
+
+/// Operations of `epoll_ctl`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+pub(crate) enum EpollCtl {
+	Add = 1,
+	Del = 2,
+	Mod = 3,
+}
+
+/// Event of `epoll_ctl` and `epoll_wait`, which is packed on x86_64 like on Linux
+#[repr(C)]
+#[cfg_attr(target_arch = "x86_64", repr(packed))]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct EpollEvent {
+	pub events: u32,
+	pub data: u64,
+}
+
+#[derive(Debug, Default)]
+struct ReadyList {
+	notifiers: VecDeque<Arc<Notifier>>,
+	/// Tasks, which are blocked in `epoll_wait`
+	waiters: Vec<Waker>,
+	/// Is set, if a notifier was enqueued since the last check
+	notified: bool,
+}
+
+#[derive(Debug)]
+struct Notifier {
+	fd: FileDescriptor,
+	/// Is set, if the notifier is part of the ready list
+	queued: AtomicBool,
+	ready: Weak<InterruptTicketMutex<ReadyList>>,
+}
+
+impl Notifier {
+	fn new(fd: FileDescriptor, ready: Weak<InterruptTicketMutex<ReadyList>>) -> Self {
+		Self {
+			fd,
+			queued: AtomicBool::new(false),
+			ready,
+		}
+	}
+
+	/// Adds the notifier to the ready list and wakes up waiting tasks, if `wakeup` is set
+	fn enqueue(self: &Arc<Self>, wakeup: bool) {
+		let Some(ready) = self.ready.upgrade() else {
+			return;
+		};
+
+		let waiters = {
+			let mut guard = ready.lock();
+			if !self.queued.swap(true, Ordering::AcqRel) {
+				guard.notifiers.push_back(self.clone());
+			}
+			if wakeup {
+				guard.notified = true;
+				mem::take(&mut guard.waiters)
+			} else {
+				Vec::new()
+			}
+		};
+
+		for waker in waiters {
+			waker.wake();
+		}
+	}
+}
+
+impl Wake for Notifier {
+	fn wake(self: Arc<Self>) {
+		self.enqueue(true);
+	}
+
+	fn wake_by_ref(self: &Arc<Self>) {
+		self.enqueue(true);
+	}
+}
+
+#[derive(Debug)]
+struct Interest {
+	/// The object isn't kept alive by the interest set
+	obj: Weak<dyn ObjectInterface>,
+	event: EpollEvent,
+	notifier: Arc<Notifier>,
+	/// Events of the previous check, which aren't reported again in edge-triggered mode
+	previous: PollEvent,
+	/// Counter of calls on the descriptor, which returned `EAGAIN`. Such a
+	/// call ends an edge, so that the next available event is reported again.
+	would_block: Arc<AtomicUsize>,
+	/// Value of `would_block` at the previous check
+	previous_would_block: usize,
+	/// Is set, if an one-shot event was already reported
+	disabled: bool,
+}
+
+#[derive(Debug)]
+struct EpollInner {
+	interests: Mutex<BTreeMap<FileDescriptor, Interest>>,
+	ready: Arc<InterruptTicketMutex<ReadyList>>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Epoll(Arc<EpollInner>);
+
+impl Epoll {
+	pub fn new() -> Self {
+		Self(Arc::new(EpollInner {
+			interests: Mutex::new(BTreeMap::new()),
+			ready: Arc::new(InterruptTicketMutex::new(ReadyList::default())),
+		}))
+	}
+}
+
+#[async_trait]
+impl ObjectInterface for Epoll {
+	async fn epoll_ctl(
+		&self,
+		op: EpollCtl,
+		fd: FileDescriptor,
+		event: EpollEvent,
+	) -> Result<(), IoError> {
+		let mut interests = self.0.interests.lock().await;
+
+		match op {
+			EpollCtl::Add => {
+				if interests.contains_key(&fd) {
+					return Err(IoError::EEXIST);
+				}
+
+				let entry = core_scheduler().get_entry(fd).await?;
+				let notifier = Arc::new(Notifier::new(fd, Arc::downgrade(&self.0.ready)));
+				interests.insert(
+					fd,
+					Interest {
+						obj: Arc::downgrade(&entry.object),
+						event,
+						notifier: notifier.clone(),
+						previous: PollEvent::empty(),
+						previous_would_block: entry.would_block.load(Ordering::Acquire),
+						would_block: entry.would_block,
+						disabled: false,
+					},
+				);
+
+				// the current state is checked by the next `epoll_wait`
+				notifier.enqueue(true);
+			}
+			EpollCtl::Mod => {
+				let interest = interests.get_mut(&fd).ok_or(IoError::ENOENT)?;
+				interest.event = event;
+				interest.previous = PollEvent::empty();
+				interest.disabled = false;
+				interest.notifier.enqueue(true);
+			}
+			EpollCtl::Del => {
+				interests.remove(&fd).ok_or(IoError::ENOENT)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	async fn epoll_wait(&self, events: &mut [EpollEvent]) -> Result<usize, IoError> {
+		future::poll_fn(|cx| {
+			let mut pinned = core::pin::pin!(self.0.interests.lock());
+			let mut interests = ready!(pinned.as_mut().poll(cx));
+
+			self.0.ready.lock().notified = false;
+
+			let mut count = 0;
+			let mut recheck = Vec::new();
+			while count < events.len() {
+				let Some(notifier) = self.0.ready.lock().notifiers.pop_front() else {
+					break;
+				};
+				notifier.queued.store(false, Ordering::Release);
+
+				let Some(interest) = interests
+					.get_mut(&notifier.fd)
+					.filter(|i| Arc::ptr_eq(&i.notifier, &notifier))
+				else {
+					// the file descriptor was removed from the interest set
+					continue;
+				};
+				if interest.disabled {
+					continue;
+				}
+				let Some(obj) = interest.obj.upgrade() else {
+					// the file descriptor was closed
+					interests.remove(&notifier.fd);
+					continue;
+				};
+
+				let flags = interest.event.events;
+				let requested = PollEvent::from_bits_truncate(flags as u16 as i16);
+				let waker = Waker::from(notifier.clone());
+				let mut obj_cx = Context::from_waker(&waker);
+
+				match obj.poll(requested).as_mut().poll(&mut obj_cx) {
+					Poll::Pending => {
+						// the object will wake up the notifier
+						interest.previous = PollEvent::empty();
+					}
+					Poll::Ready(result) => {
+						let revents = result.map_or(PollEvent::POLLERR, |e| {
+							e & (requested | PollEvent::POLLERR | PollEvent::POLLHUP)
+						});
+
+						let would_block = interest.would_block.load(Ordering::Acquire);
+						if would_block != interest.previous_would_block {
+							// the descriptor was drained since the previous check
+							interest.previous_would_block = would_block;
+							interest.previous = PollEvent::empty();
+						}
+
+						let new_events = if flags & EPOLLET == 0 {
+							revents
+						} else {
+							revents.difference(interest.previous)
+						};
+						interest.previous = revents;
+
+						if !new_events.is_empty() {
+							events[count] = EpollEvent {
+								events: revents.bits() as u16 as u32,
+								data: interest.event.data,
+							};
+							count += 1;
+
+							if flags & EPOLLONESHOT != 0 {
+								interest.disabled = true;
+								continue;
+							}
+						}
+
+						recheck.push(notifier);
+					}
+				}
+			}
+
+			for notifier in recheck {
+				notifier.enqueue(false);
+			}
+
+			if count > 0 {
+				Poll::Ready(Ok(count))
+			} else {
+				let mut guard = self.0.ready.lock();
+				if guard.notified {
+					// a notifier was enqueued during the check
+					cx.waker().wake_by_ref();
+				} else {
+					guard.waiters.push(cx.waker().clone());
+				}
+				Poll::Pending
+			}
+		})
+		.await
+	}
+}
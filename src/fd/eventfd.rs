@@ -111,16 +111,10 @@ impl ObjectInterface for EventFd {
 			let mut guard = ready!(pinned.as_mut().poll(cx));
 			if u64::MAX - guard.counter > c {
 				guard.counter += c;
-				if self.flags.contains(EventFlags::EFD_SEMAPHORE) {
-					for _i in 0..c {
-						if let Some(cx) = guard.read_queue.pop_front() {
-							cx.wake_by_ref();
-						} else {
-							break;
-						}
-					}
-				} else if let Some(cx) = guard.read_queue.pop_front() {
-					cx.wake_by_ref();
+				// wake up all waiting tasks, because epoll instances are
+				// also registered as readers
+				while let Some(cx) = guard.read_queue.pop_front() {
+					cx.wake();
 				}
 
 				Poll::Ready(Ok(len))
@@ -0,0 +1,216 @@
+//! Advisory record locks, which are owned by open file descriptions.
+//!
+//! Locks of `flock` and of `fcntl(F_SETLK)` don't interact, so each file
+//! keeps them in separate lock tables. A lock is only released by its owner
+//! or if the owning open file description is closed.
+
+use alloc::collections::vec_deque::VecDeque;
+use alloc::vec::Vec;
+use core::task::Waker;
+
+use crate::fd::IoError;
+
+/// Type of a record lock, the values correspond to `F_RDLCK`, `F_WRLCK`
+/// and `F_UNLCK`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+pub(crate) enum LockKind {
+	Read = 0,
+	Write = 1,
+	Unlock = 2,
+}
+
+/// Interface, which places a lock
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LockType {
+	/// Record locks of `fcntl`
+	Posix,
+	/// Locks of the whole file of `flock`
+	Flock,
+}
+
+/// Lock of the byte range `start..=end`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct FileLock {
+	pub kind: LockKind,
+	pub start: u64,
+	/// Last byte of the range, `u64::MAX` extends the lock to the end of the file
+	pub end: u64,
+}
+
+impl FileLock {
+	/// Returns a lock, which covers the whole file
+	pub const fn whole_file(kind: LockKind) -> Self {
+		Self {
+			kind,
+			start: 0,
+			end: u64::MAX,
+		}
+	}
+
+	fn overlaps(&self, other: &FileLock) -> bool {
+		self.start <= other.end && other.start <= self.end
+	}
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Record {
+	owner: usize,
+	lock: FileLock,
+}
+
+/// Locks of a single file
+#[derive(Debug, Default)]
+pub(crate) struct LockTable {
+	records: Vec<Record>,
+	/// Tasks, which wait for the release of a lock
+	waiters: VecDeque<Waker>,
+}
+
+impl LockTable {
+	pub const fn new() -> Self {
+		Self {
+			records: Vec::new(),
+			waiters: VecDeque::new(),
+		}
+	}
+
+	/// Returns a lock of another owner, which prevents placing `lock`.
+	pub fn conflict(&self, owner: usize, lock: &FileLock) -> Option<FileLock> {
+		self.records
+			.iter()
+			.find(|r| {
+				r.owner != owner
+					&& r.lock.overlaps(lock)
+					&& (r.lock.kind == LockKind::Write || lock.kind == LockKind::Write)
+			})
+			.map(|r| r.lock)
+	}
+
+	/// Places or removes `lock` on behalf of `owner`. Existing locks of the
+	/// owner are replaced in the given range. Returns `EAGAIN`, if a lock of
+	/// another owner conflicts.
+	pub fn set(&mut self, owner: usize, lock: FileLock) -> Result<(), IoError> {
+		if lock.kind != LockKind::Unlock && self.conflict(owner, &lock).is_some() {
+			return Err(IoError::EAGAIN);
+		}
+
+		let mut records = Vec::with_capacity(self.records.len() + 2);
+		for r in self.records.drain(..) {
+			if r.owner != owner || !r.lock.overlaps(&lock) {
+				records.push(r);
+				continue;
+			}
+
+			// keep the parts of the old lock, which are outside of the new range
+			if r.lock.start < lock.start {
+				records.push(Record {
+					owner,
+					lock: FileLock {
+						end: lock.start - 1,
+						..r.lock
+					},
+				});
+			}
+			if r.lock.end > lock.end {
+				records.push(Record {
+					owner,
+					lock: FileLock {
+						start: lock.end + 1,
+						..r.lock
+					},
+				});
+			}
+		}
+
+		if lock.kind != LockKind::Unlock {
+			records.push(Record { owner, lock });
+		}
+		self.records = records;
+
+		// unlocking or downgrading a lock may allow waiting tasks to proceed
+		self.wakeup();
+
+		Ok(())
+	}
+
+	/// Removes all locks of `owner`
+	pub fn release(&mut self, owner: usize) {
+		self.records.retain(|r| r.owner != owner);
+		self.wakeup();
+	}
+
+	/// Returns true, if the table neither holds locks nor waiting tasks
+	pub fn is_unused(&self) -> bool {
+		self.records.is_empty() && self.waiters.is_empty()
+	}
+
+	/// Registers a task, which will be woken up after the next change of the table
+	pub fn wait(&mut self, waker: &Waker) {
+		self.waiters.push_back(waker.clone());
+	}
+
+	fn wakeup(&mut self) {
+		while let Some(waker) = self.waiters.pop_front() {
+			waker.wake();
+		}
+	}
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	fn lock(kind: LockKind, start: u64, end: u64) -> FileLock {
+		FileLock { kind, start, end }
+	}
+
+	#[test]
+	fn shared_and_exclusive() {
+		let mut table = LockTable::new();
+
+		table.set(1, lock(LockKind::Read, 0, 99)).unwrap();
+		table.set(2, lock(LockKind::Read, 50, 149)).unwrap();
+		assert_eq!(
+			table.set(3, lock(LockKind::Write, 90, 99)),
+			Err(IoError::EAGAIN)
+		);
+		assert!(table.set(3, lock(LockKind::Write, 150, 199)).is_ok());
+		assert_eq!(
+			table.conflict(1, &lock(LockKind::Write, 0, 59)),
+			Some(lock(LockKind::Read, 50, 149))
+		);
+
+		table.release(2);
+		assert!(table.conflict(1, &lock(LockKind::Write, 0, 99)).is_none());
+	}
+
+	#[test]
+	fn split_on_unlock() {
+		let mut table = LockTable::new();
+
+		table.set(1, FileLock::whole_file(LockKind::Write)).unwrap();
+		table.set(1, lock(LockKind::Unlock, 10, 19)).unwrap();
+
+		assert!(table.set(2, lock(LockKind::Write, 10, 19)).is_ok());
+		assert_eq!(
+			table.conflict(2, &lock(LockKind::Read, 0, 9)),
+			Some(lock(LockKind::Write, 0, 9))
+		);
+		assert_eq!(
+			table.conflict(2, &lock(LockKind::Read, 20, 20)),
+			Some(lock(LockKind::Write, 20, u64::MAX))
+		);
+	}
+
+	#[test]
+	fn upgrade_own_lock() {
+		let mut table = LockTable::new();
+
+		table.set(1, lock(LockKind::Read, 0, 9)).unwrap();
+		table.set(1, lock(LockKind::Write, 0, 9)).unwrap();
+		assert_eq!(
+			table.conflict(2, &lock(LockKind::Read, 5, 5)),
+			Some(lock(LockKind::Write, 0, 9))
+		);
+	}
+}
@@ -1,20 +1,28 @@
 use alloc::boxed::Box;
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::future::{self, Future};
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
 use core::task::Poll::{Pending, Ready};
 use core::time::Duration;
 
 use async_trait::async_trait;
 use dyn_clone::DynClone;
 #[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
-use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
+use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
 use crate::arch::kernel::core_local::core_scheduler;
 use crate::executor::{block_on, poll_on};
+use crate::fd::epoll::{EpollCtl, EpollEvent};
+use crate::fd::lock::{FileLock, LockType};
 use crate::fs::{self, DirectoryEntry, FileAttr, SeekWhence};
+use crate::mm::mmap::MappedObject;
 
+pub(crate) mod epoll;
 mod eventfd;
+pub(crate) mod lock;
+mod pipe;
 #[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
 pub(crate) mod socket;
 pub(crate) mod stdio;
@@ -43,12 +51,54 @@ pub enum IoError {
 	EEXIST = crate::errno::EEXIST as isize,
 	EADDRINUSE = crate::errno::EADDRINUSE as isize,
 	EOVERFLOW = crate::errno::EOVERFLOW as isize,
+	EROFS = crate::errno::EROFS as isize,
+	EPERM = crate::errno::EPERM as isize,
+	EXDEV = crate::errno::EXDEV as isize,
+	ENOTEMPTY = crate::errno::ENOTEMPTY as isize,
+	ELOOP = crate::errno::ELOOP as isize,
+	ESPIPE = crate::errno::ESPIPE as isize,
+	EPIPE = crate::errno::EPIPE as isize,
+	ENOPROTOOPT = crate::errno::ENOPROTOOPT as isize,
+	EADDRNOTAVAIL = crate::errno::EADDRNOTAVAIL as isize,
+	ENETUNREACH = crate::errno::ENETUNREACH as isize,
+	ENOMEM = crate::errno::ENOMEM as isize,
+	ENODEV = crate::errno::ENODEV as isize,
+	EACCES = crate::errno::EACCES as isize,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) enum SocketOption {
+	/// `TCP_NODELAY`
 	TcpNoDelay,
+	/// `SO_KEEPALIVE`
+	KeepAlive,
+	/// `SO_LINGER`
+	Linger,
+	/// `SO_RCVBUF`
+	RecvBufferSize,
+	/// `SO_SNDBUF`
+	SendBufferSize,
+	/// `SO_RCVTIMEO`
+	RecvTimeout,
+	/// `SO_SNDTIMEO`
+	SendTimeout,
+	/// `SO_ERROR`
+	Error,
+	/// `IP_TTL` and `IPV6_UNICAST_HOPS`
+	HopLimit,
+	/// `IP_PKTINFO` and `IPV6_RECVPKTINFO`
+	RecvPacketInfo,
+}
+
+/// Value of a socket option. Timeouts and the linger time are `None`,
+/// if they are disabled.
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum SocketOptionValue {
+	Bool(bool),
+	Int(i32),
+	Duration(Option<Duration>),
 }
 
 #[allow(dead_code)]
@@ -70,7 +120,120 @@ bitflags! {
 		const O_EXCL = 0o0200;
 		const O_TRUNC = 0o1000;
 		const O_APPEND = 0o2000;
+		const O_NONBLOCK = 0o4000;
 		const O_DIRECT = 0o40000;
+		const O_CLOEXEC = 0o2000000;
+	}
+}
+
+bitflags! {
+	/// Flags of a file descriptor
+	#[derive(Debug, Copy, Clone, Default)]
+	pub(crate) struct FdFlags: i32 {
+		const FD_CLOEXEC = 1;
+	}
+}
+
+#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
+bitflags! {
+	/// Flags of `send` and `recv` operations
+	#[derive(Debug, Copy, Clone, Default)]
+	pub(crate) struct MsgFlags: i32 {
+		const MSG_PEEK = 0x01;
+		const MSG_TRUNC = 0x20;
+		const MSG_DONTWAIT = 0x40;
+		const MSG_WAITALL = 0x100;
+	}
+}
+
+/// Result of a receive operation on a socket
+#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct RecvMeta {
+	/// Number of bytes, which are copied into the buffers
+	pub len: usize,
+	/// Length of the datagram, which is larger than `len` if it is truncated
+	pub datagram_len: usize,
+	/// Origin of the message
+	pub endpoint: Option<IpEndpoint>,
+	/// Destination address of the datagram, if `IP_PKTINFO` is enabled
+	pub local_addr: Option<IpAddress>,
+	/// Index of the network device, which received the datagram, if
+	/// `IP_PKTINFO` is enabled. Devices are numbered like by `sys_getifstats`.
+	pub device: Option<usize>,
+}
+
+#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
+impl RecvMeta {
+	/// Meta data of a stream, which has no message boundaries
+	pub fn stream(len: usize) -> Self {
+		Self {
+			len,
+			datagram_len: len,
+			endpoint: None,
+			local_addr: None,
+			device: None,
+		}
+	}
+}
+
+/// Entry of the per-task object map, which binds a file descriptor to an object
+#[derive(Debug, Clone)]
+pub(crate) struct ObjectEntry {
+	pub object: Arc<dyn ObjectInterface>,
+	/// File status flags like `O_NONBLOCK`, which are shared by duplicated descriptors
+	pub status: Arc<AtomicI32>,
+	/// Flags of the file descriptor like `FD_CLOEXEC`
+	pub flags: FdFlags,
+	/// Number of calls, which returned `EAGAIN`. A change resets the
+	/// edge-triggered state of the descriptor in epoll instances.
+	pub would_block: Arc<AtomicUsize>,
+}
+
+impl ObjectEntry {
+	pub fn new(object: Arc<dyn ObjectInterface>) -> Self {
+		Self::with_flags(object, OpenOption::empty(), FdFlags::empty())
+	}
+
+	pub fn with_flags(
+		object: Arc<dyn ObjectInterface>,
+		status: OpenOption,
+		flags: FdFlags,
+	) -> Self {
+		Self {
+			object,
+			status: Arc::new(AtomicI32::new(status.bits())),
+			flags,
+			would_block: Arc::new(AtomicUsize::new(0)),
+		}
+	}
+
+	pub fn status(&self) -> OpenOption {
+		OpenOption::from_bits_retain(self.status.load(Ordering::Acquire))
+	}
+
+	pub fn set_status(&self, status: OpenOption) {
+		self.status.store(status.bits(), Ordering::Release);
+	}
+
+	/// Returns `true`, if `O_NONBLOCK` is set or the object itself doesn't block
+	pub fn is_nonblocking(&self) -> bool {
+		self.status().contains(OpenOption::O_NONBLOCK) || self.object.is_nonblocking()
+	}
+
+	/// Converts the timeout of a non-blocking or timed call into `EAGAIN`
+	/// and counts the calls, which return `EAGAIN`
+	pub fn map_timeout(&self, err: IoError) -> IoError {
+		let err = if err == IoError::ETIME {
+			IoError::EAGAIN
+		} else {
+			err
+		};
+		if err == IoError::EAGAIN {
+			self.would_block.fetch_add(1, Ordering::AcqRel);
+		}
+
+		err
 	}
 }
 
@@ -162,6 +325,48 @@ pub(crate) trait ObjectInterface: Sync + Send + core::fmt::Debug + DynClone {
 		Err(IoError::ENOSYS)
 	}
 
+	/// `async_pread` reads from the object at `offset` without changing
+	/// the file offset
+	async fn async_pread(&self, _buf: &mut [u8], _offset: usize) -> Result<usize, IoError> {
+		Err(IoError::ESPIPE)
+	}
+
+	/// `async_pwrite` writes to the object at `offset` without changing
+	/// the file offset
+	async fn async_pwrite(&self, _buf: &[u8], _offset: usize) -> Result<usize, IoError> {
+		Err(IoError::ESPIPE)
+	}
+
+	/// `async_readv` scatters the read data into `bufs`. By default, only
+	/// the first non-empty buffer is filled.
+	async fn async_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
+		match bufs.iter_mut().find(|buf| !buf.is_empty()) {
+			Some(buf) => self.async_read(buf).await,
+			None => Ok(0),
+		}
+	}
+
+	/// `async_writev` gathers the data to write from `bufs`. By default,
+	/// only the first non-empty buffer is written.
+	async fn async_writev(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		match bufs.iter().find(|buf| !buf.is_empty()) {
+			Some(buf) => self.async_write(buf).await,
+			None => Ok(0),
+		}
+	}
+
+	/// `async_append` writes `buf` at the end of the object and moves the
+	/// file offset behind it in a single step. Objects without a file
+	/// offset write `buf` like `async_write`.
+	async fn async_append(&self, buf: &[u8]) -> Result<usize, IoError> {
+		self.async_write(buf).await
+	}
+
+	/// `async_appendv` gathers the data to append from `bufs`
+	async fn async_appendv(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		self.async_writev(bufs).await
+	}
+
 	/// `is_nonblocking` returns `true`, if `read`, `write`, `recv` and send operations
 	/// don't block.
 	fn is_nonblocking(&self) -> bool {
@@ -203,9 +408,45 @@ pub(crate) trait ObjectInterface: Sync + Send + core::fmt::Debug + DynClone {
 		Err(IoError::EINVAL)
 	}
 
-	/// `accept` a connection on a socket
+	/// `getlk` returns a lock of another open file description, which
+	/// prevents placing `lock`. If there is none, a lock of type
+	/// `LockKind::Unlock` is returned.
+	async fn getlk(&self, _lock: FileLock) -> Result<FileLock, IoError> {
+		Err(IoError::EINVAL)
+	}
+
+	/// `setlk` places or removes an advisory lock of type `ty`. If `wait`
+	/// is set, the task is blocked until conflicting locks are released,
+	/// otherwise `EAGAIN` is returned.
+	async fn setlk(&self, _lock: FileLock, _ty: LockType, _wait: bool) -> Result<(), IoError> {
+		Err(IoError::EINVAL)
+	}
+
+	/// `epoll_ctl` adds, modifies or removes the interest of an epoll
+	/// instance in `fd`
+	async fn epoll_ctl(
+		&self,
+		_op: EpollCtl,
+		_fd: FileDescriptor,
+		_event: EpollEvent,
+	) -> Result<(), IoError> {
+		Err(IoError::EINVAL)
+	}
+
+	/// `epoll_wait` waits for events on the file descriptors of an epoll
+	/// instance and returns the number of reported events
+	async fn epoll_wait(&self, _events: &mut [EpollEvent]) -> Result<usize, IoError> {
+		Err(IoError::EINVAL)
+	}
+
+	/// `get_path` returns the absolute path of an opened directory
+	fn get_path(&self) -> Result<String, IoError> {
+		Err(IoError::ENOTDIR)
+	}
+
+	/// `accept` a connection on a socket and return the connected socket
 	#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
-	fn accept(&self) -> Result<IpEndpoint, IoError> {
+	fn accept(&self) -> Result<(Arc<dyn ObjectInterface>, IpEndpoint), IoError> {
 		Err(IoError::EINVAL)
 	}
 
@@ -229,14 +470,14 @@ pub(crate) trait ObjectInterface: Sync + Send + core::fmt::Debug + DynClone {
 
 	/// `setsockopt` sets options on sockets
 	#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
-	fn setsockopt(&self, _opt: SocketOption, _optval: bool) -> Result<(), IoError> {
-		Err(IoError::EINVAL)
+	fn setsockopt(&self, _opt: SocketOption, _optval: SocketOptionValue) -> Result<(), IoError> {
+		Err(IoError::ENOPROTOOPT)
 	}
 
 	/// `getsockopt` gets options on sockets
 	#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
-	fn getsockopt(&self, _opt: SocketOption) -> Result<bool, IoError> {
-		Err(IoError::EINVAL)
+	fn getsockopt(&self, _opt: SocketOption) -> Result<SocketOptionValue, IoError> {
+		Err(IoError::ENOPROTOOPT)
 	}
 
 	/// `getsockname` gets socket name
@@ -252,22 +493,38 @@ pub(crate) trait ObjectInterface: Sync + Send + core::fmt::Debug + DynClone {
 		None
 	}
 
-	/// receive a message from a socket
+	/// `async_recvmsg` receives a message and scatters it into `bufs`. By
+	/// default, the object is read like a stream and `MSG_PEEK` isn't supported.
+	/// A stream returns as soon as any data is received, `MSG_WAITALL` is
+	/// handled by [`recvmsg`].
 	#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
-	fn recvfrom(&self, _buffer: &mut [u8]) -> Result<(usize, IpEndpoint), IoError> {
-		Err(IoError::ENOSYS)
+	async fn async_recvmsg(
+		&self,
+		bufs: &mut [&mut [u8]],
+		flags: MsgFlags,
+	) -> Result<RecvMeta, IoError> {
+		if flags.contains(MsgFlags::MSG_PEEK) {
+			return Err(IoError::EINVAL);
+		}
+
+		self.async_readv(bufs).await.map(RecvMeta::stream)
 	}
 
-	/// send a message from a socket
-	///
-	/// The sendto() function shall send a message.
-	/// If the socket is a connectionless-mode socket, the message shall
-	/// If a peer address has been prespecified, either the message shall
-	/// be sent to the address specified by dest_addr (overriding the pre-specified peer
-	/// address).
+	/// `async_sendmsg` sends a message, which is gathered from `bufs`.
+	/// The `endpoint` overrides the peer of a connected socket and
+	/// `local_addr` selects the source address of the message.
 	#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
-	fn sendto(&self, _buffer: &[u8], _endpoint: IpEndpoint) -> Result<usize, IoError> {
-		Err(IoError::ENOSYS)
+	async fn async_sendmsg(
+		&self,
+		bufs: &[&[u8]],
+		endpoint: Option<IpEndpoint>,
+		_local_addr: Option<IpAddress>,
+	) -> Result<usize, IoError> {
+		if endpoint.is_some() {
+			return Err(IoError::EINVAL);
+		}
+
+		self.async_writev(bufs).await
 	}
 
 	/// shut down part of a full-duplex connection
@@ -281,6 +538,18 @@ pub(crate) trait ObjectInterface: Sync + Send + core::fmt::Debug + DynClone {
 	fn ioctl(&self, _cmd: IoCtl, _value: bool) -> Result<(), IoError> {
 		Err(IoError::ENOSYS)
 	}
+
+	/// `mmap` returns the memory, which backs a mapping of `len` bytes at
+	/// `offset`. If `writable` is set, writes through the mapping have to
+	/// reach the object.
+	fn mmap(
+		&self,
+		_offset: usize,
+		_len: usize,
+		_writable: bool,
+	) -> Result<Arc<dyn MappedObject>, IoError> {
+		Err(IoError::ENODEV)
+	}
 }
 
 pub(crate) fn open(
@@ -296,28 +565,48 @@ pub(crate) fn open(
 
 	let fs = fs::FILESYSTEM.get().unwrap();
 	if let Ok(file) = fs.open(name, flags, mode) {
-		let fd = insert_object(file)?;
+		let status = flags.intersection(
+			OpenOption::O_WRONLY
+				| OpenOption::O_RDWR
+				| OpenOption::O_APPEND
+				| OpenOption::O_NONBLOCK
+				| OpenOption::O_DIRECT,
+		);
+		let fd_flags = if flags.contains(OpenOption::O_CLOEXEC) {
+			FdFlags::FD_CLOEXEC
+		} else {
+			FdFlags::empty()
+		};
+		let fd = insert_entry(ObjectEntry::with_flags(file, status, fd_flags))?;
 		Ok(fd)
 	} else {
 		Err(IoError::EINVAL)
 	}
 }
 
+/// Returns the timeout of blocking operations on a socket, which is set
+/// by `SO_RCVTIMEO` or `SO_SNDTIMEO`
+fn socket_timeout(_obj: &Arc<dyn ObjectInterface>, _opt: SocketOption) -> Option<Duration> {
+	#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
+	if let Ok(SocketOptionValue::Duration(timeout)) = _obj.getsockopt(_opt) {
+		return timeout;
+	}
+
+	None
+}
+
 pub(crate) fn read(fd: FileDescriptor, buf: &mut [u8]) -> Result<usize, IoError> {
-	let obj = get_object(fd)?;
+	let entry = get_entry(fd)?;
+	let obj = entry.object.clone();
 
 	if buf.is_empty() {
 		return Ok(0);
 	}
 
-	if obj.is_nonblocking() {
-		poll_on(obj.async_read(buf), Some(Duration::ZERO)).map_err(|x| {
-			if x == IoError::ETIME {
-				IoError::EAGAIN
-			} else {
-				x
-			}
-		})
+	if entry.is_nonblocking() {
+		poll_on(obj.async_read(buf), Some(Duration::ZERO)).map_err(|x| entry.map_timeout(x))
+	} else if let Some(timeout) = socket_timeout(&obj, SocketOption::RecvTimeout) {
+		block_on(obj.async_read(buf), Some(timeout)).map_err(|x| entry.map_timeout(x))
 	} else {
 		match poll_on(obj.async_read(buf), Some(Duration::from_secs(2))) {
 			Err(IoError::ETIME) => block_on(obj.async_read(buf), None),
@@ -328,23 +617,231 @@ pub(crate) fn read(fd: FileDescriptor, buf: &mut [u8]) -> Result<usize, IoError>
 }
 
 pub(crate) fn write(fd: FileDescriptor, buf: &[u8]) -> Result<usize, IoError> {
-	let obj = get_object(fd)?;
+	let entry = get_entry(fd)?;
+	let obj = entry.object.clone();
 
 	if buf.is_empty() {
 		return Ok(0);
 	}
 
-	if obj.is_nonblocking() {
-		poll_on(obj.async_write(buf), Some(Duration::ZERO)).map_err(|x| {
-			if x == IoError::ETIME {
-				IoError::EAGAIN
+	// the object appends under its own lock, so that concurrent writes
+	// can't end up at the same offset
+	let append = entry.status().contains(OpenOption::O_APPEND);
+	let write = || {
+		if append {
+			obj.async_append(buf)
+		} else {
+			obj.async_write(buf)
+		}
+	};
+
+	if entry.is_nonblocking() {
+		poll_on(write(), Some(Duration::ZERO)).map_err(|x| entry.map_timeout(x))
+	} else if let Some(timeout) = socket_timeout(&obj, SocketOption::SendTimeout) {
+		block_on(write(), Some(timeout)).map_err(|x| entry.map_timeout(x))
+	} else {
+		match poll_on(write(), Some(Duration::from_secs(2))) {
+			Err(IoError::ETIME) => block_on(write(), None),
+			Err(x) => Err(x),
+			Ok(x) => Ok(x),
+		}
+	}
+}
+
+pub(crate) fn pread(fd: FileDescriptor, buf: &mut [u8], offset: usize) -> Result<usize, IoError> {
+	let entry = get_entry(fd)?;
+	let obj = entry.object.clone();
+
+	if buf.is_empty() {
+		return Ok(0);
+	}
+
+	if entry.is_nonblocking() {
+		poll_on(obj.async_pread(buf, offset), Some(Duration::ZERO))
+			.map_err(|x| entry.map_timeout(x))
+	} else if let Some(timeout) = socket_timeout(&obj, SocketOption::RecvTimeout) {
+		block_on(obj.async_pread(buf, offset), Some(timeout)).map_err(|x| entry.map_timeout(x))
+	} else {
+		match poll_on(obj.async_pread(buf, offset), Some(Duration::from_secs(2))) {
+			Err(IoError::ETIME) => block_on(obj.async_pread(buf, offset), None),
+			Err(x) => Err(x),
+			Ok(x) => Ok(x),
+		}
+	}
+}
+
+pub(crate) fn pwrite(fd: FileDescriptor, buf: &[u8], offset: usize) -> Result<usize, IoError> {
+	let entry = get_entry(fd)?;
+	let obj = entry.object.clone();
+
+	if buf.is_empty() {
+		return Ok(0);
+	}
+
+	if entry.is_nonblocking() {
+		poll_on(obj.async_pwrite(buf, offset), Some(Duration::ZERO))
+			.map_err(|x| entry.map_timeout(x))
+	} else if let Some(timeout) = socket_timeout(&obj, SocketOption::SendTimeout) {
+		block_on(obj.async_pwrite(buf, offset), Some(timeout)).map_err(|x| entry.map_timeout(x))
+	} else {
+		match poll_on(obj.async_pwrite(buf, offset), Some(Duration::from_secs(2))) {
+			Err(IoError::ETIME) => block_on(obj.async_pwrite(buf, offset), None),
+			Err(x) => Err(x),
+			Ok(x) => Ok(x),
+		}
+	}
+}
+
+pub(crate) fn readv(fd: FileDescriptor, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
+	let entry = get_entry(fd)?;
+	let obj = entry.object.clone();
+
+	if bufs.iter().all(|buf| buf.is_empty()) {
+		return Ok(0);
+	}
+
+	if entry.is_nonblocking() {
+		poll_on(obj.async_readv(bufs), Some(Duration::ZERO)).map_err(|x| entry.map_timeout(x))
+	} else if let Some(timeout) = socket_timeout(&obj, SocketOption::RecvTimeout) {
+		block_on(obj.async_readv(bufs), Some(timeout)).map_err(|x| entry.map_timeout(x))
+	} else {
+		match poll_on(obj.async_readv(bufs), Some(Duration::from_secs(2))) {
+			Err(IoError::ETIME) => block_on(obj.async_readv(bufs), None),
+			Err(x) => Err(x),
+			Ok(x) => Ok(x),
+		}
+	}
+}
+
+pub(crate) fn writev(fd: FileDescriptor, bufs: &[&[u8]]) -> Result<usize, IoError> {
+	let entry = get_entry(fd)?;
+	let obj = entry.object.clone();
+
+	if bufs.iter().all(|buf| buf.is_empty()) {
+		return Ok(0);
+	}
+
+	// the object appends under its own lock, so that concurrent writes
+	// can't end up at the same offset
+	let append = entry.status().contains(OpenOption::O_APPEND);
+	let writev = || {
+		if append {
+			obj.async_appendv(bufs)
+		} else {
+			obj.async_writev(bufs)
+		}
+	};
+
+	if entry.is_nonblocking() {
+		poll_on(writev(), Some(Duration::ZERO)).map_err(|x| entry.map_timeout(x))
+	} else if let Some(timeout) = socket_timeout(&obj, SocketOption::SendTimeout) {
+		block_on(writev(), Some(timeout)).map_err(|x| entry.map_timeout(x))
+	} else {
+		match poll_on(writev(), Some(Duration::from_secs(2))) {
+			Err(IoError::ETIME) => block_on(writev(), None),
+			Err(x) => Err(x),
+			Ok(x) => Ok(x),
+		}
+	}
+}
+
+/// Receives a message on a socket. `MSG_DONTWAIT` makes a single call
+/// non-blocking, otherwise the timeout of `SO_RCVTIMEO` applies.
+///
+/// With `MSG_WAITALL`, a stream is received until the buffers are full.
+/// If the stream ends, the call times out or fails after some data has been
+/// received, the received data is returned.
+#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
+pub(crate) fn recvmsg(
+	fd: FileDescriptor,
+	bufs: &mut [&mut [u8]],
+	flags: MsgFlags,
+) -> Result<RecvMeta, IoError> {
+	let entry = get_entry(fd)?;
+	let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+
+	if !flags.contains(MsgFlags::MSG_WAITALL) || flags.contains(MsgFlags::MSG_PEEK) || total == 0 {
+		return recvmsg_once(&entry, bufs, flags);
+	}
+
+	let mut pos = 0;
+	while pos < total {
+		// receive into the part of the buffers, which isn't yet filled
+		let mut skip = pos;
+		let mut remaining: Vec<&mut [u8]> = Vec::with_capacity(bufs.len());
+		for buf in bufs.iter_mut() {
+			if skip >= buf.len() {
+				skip -= buf.len();
 			} else {
-				x
+				remaining.push(&mut buf[skip..]);
+				skip = 0;
 			}
-		})
+		}
+
+		match recvmsg_once(&entry, &mut remaining, flags) {
+			// a datagram is always received as a whole
+			Ok(meta) if meta.endpoint.is_some() => return Ok(meta),
+			Ok(meta) if meta.len == 0 => break,
+			Ok(meta) => pos += meta.len,
+			Err(_) if pos > 0 => break,
+			Err(err) => return Err(err),
+		}
+	}
+
+	Ok(RecvMeta::stream(pos))
+}
+
+/// Receives a single message or the data of a stream, which is available
+#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
+fn recvmsg_once(
+	entry: &ObjectEntry,
+	bufs: &mut [&mut [u8]],
+	flags: MsgFlags,
+) -> Result<RecvMeta, IoError> {
+	let obj = entry.object.clone();
+
+	if entry.is_nonblocking() || flags.contains(MsgFlags::MSG_DONTWAIT) {
+		poll_on(obj.async_recvmsg(bufs, flags), Some(Duration::ZERO))
+			.map_err(|x| entry.map_timeout(x))
+	} else if let Some(timeout) = socket_timeout(&obj, SocketOption::RecvTimeout) {
+		block_on(obj.async_recvmsg(bufs, flags), Some(timeout)).map_err(|x| entry.map_timeout(x))
+	} else {
+		match poll_on(obj.async_recvmsg(bufs, flags), Some(Duration::from_secs(2))) {
+			Err(IoError::ETIME) => block_on(obj.async_recvmsg(bufs, flags), None),
+			Err(x) => Err(x),
+			Ok(x) => Ok(x),
+		}
+	}
+}
+
+/// Sends a message on a socket. `MSG_DONTWAIT` makes a single call
+/// non-blocking, otherwise the timeout of `SO_SNDTIMEO` applies.
+#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
+pub(crate) fn sendmsg(
+	fd: FileDescriptor,
+	bufs: &[&[u8]],
+	endpoint: Option<IpEndpoint>,
+	local_addr: Option<IpAddress>,
+	flags: MsgFlags,
+) -> Result<usize, IoError> {
+	let entry = get_entry(fd)?;
+	let obj = entry.object.clone();
+
+	if entry.is_nonblocking() || flags.contains(MsgFlags::MSG_DONTWAIT) {
+		poll_on(
+			obj.async_sendmsg(bufs, endpoint, local_addr),
+			Some(Duration::ZERO),
+		)
+		.map_err(|x| entry.map_timeout(x))
+	} else if let Some(timeout) = socket_timeout(&obj, SocketOption::SendTimeout) {
+		block_on(obj.async_sendmsg(bufs, endpoint, local_addr), Some(timeout))
+			.map_err(|x| entry.map_timeout(x))
 	} else {
-		match poll_on(obj.async_write(buf), Some(Duration::from_secs(2))) {
-			Err(IoError::ETIME) => block_on(obj.async_write(buf), None),
+		match poll_on(
+			obj.async_sendmsg(bufs, endpoint, local_addr),
+			Some(Duration::from_secs(2)),
+		) {
+			Err(IoError::ETIME) => block_on(obj.async_sendmsg(bufs, endpoint, local_addr), None),
 			Err(x) => Err(x),
 			Ok(x) => Ok(x),
 		}
@@ -421,6 +918,89 @@ pub fn eventfd(initval: u64, flags: EventFlags) -> Result<FileDescriptor, IoErro
 	Ok(fd)
 }
 
+/// `pipe` creates an unidirectional data channel and returns the file
+/// descriptors of the read and the write end.
+pub(crate) fn pipe(nonblocking: bool) -> Result<(FileDescriptor, FileDescriptor), IoError> {
+	let (rx, tx) = self::pipe::Pipe::new(nonblocking);
+	insert_pair(Arc::new(rx), Arc::new(tx))
+}
+
+/// `socketpair` creates a pair of connected, bidirectional byte streams
+pub(crate) fn socketpair(nonblocking: bool) -> Result<(FileDescriptor, FileDescriptor), IoError> {
+	let (a, b) = self::pipe::Pipe::pair(nonblocking);
+	insert_pair(Arc::new(a), Arc::new(b))
+}
+
+fn insert_pair(
+	a: Arc<dyn ObjectInterface>,
+	b: Arc<dyn ObjectInterface>,
+) -> Result<(FileDescriptor, FileDescriptor), IoError> {
+	let fd0 = insert_object(a)?;
+	let fd1 = insert_object(b).inspect_err(|_| {
+		let _ = remove_object(fd0);
+	})?;
+
+	Ok((fd0, fd1))
+}
+
+/// `epoll_create1` creates a new epoll instance and returns a file
+/// descriptor with the flags `flags` referring to it.
+pub(crate) fn epoll_create1(flags: FdFlags) -> Result<FileDescriptor, IoError> {
+	insert_entry(ObjectEntry::with_flags(
+		Arc::new(self::epoll::Epoll::new()),
+		OpenOption::empty(),
+		flags,
+	))
+}
+
+/// `epoll_ctl` adds, modifies or removes the entry of `fd` in the
+/// interest set of the epoll instance `epfd`.
+pub(crate) fn epoll_ctl(
+	epfd: FileDescriptor,
+	op: EpollCtl,
+	fd: FileDescriptor,
+	event: EpollEvent,
+) -> Result<(), IoError> {
+	if epfd == fd {
+		return Err(IoError::EINVAL);
+	}
+
+	let obj = get_object(epfd)?;
+	block_on(obj.epoll_ctl(op, fd, event), None)
+}
+
+/// `epoll_wait` waits for events on the epoll instance `epfd`. A return
+/// value of zero indicates that the call timed out.
+pub(crate) fn epoll_wait(
+	epfd: FileDescriptor,
+	events: &mut [EpollEvent],
+	timeout: Option<Duration>,
+) -> Result<usize, IoError> {
+	let obj = get_object(epfd)?;
+
+	match block_on(obj.epoll_wait(events), timeout) {
+		Err(IoError::ETIME) => Ok(0),
+		result => result,
+	}
+}
+
+/// Tests, if `lock` could be placed on the file referenced by `fd`
+pub(crate) fn getlk(fd: FileDescriptor, lock: FileLock) -> Result<FileLock, IoError> {
+	let obj = get_object(fd)?;
+	block_on(obj.getlk(lock), None)
+}
+
+/// Places or removes an advisory lock on the file referenced by `fd`
+pub(crate) fn setlk(
+	fd: FileDescriptor,
+	lock: FileLock,
+	ty: LockType,
+	wait: bool,
+) -> Result<(), IoError> {
+	let obj = get_object(fd)?;
+	block_on(obj.setlk(lock, ty, wait), None)
+}
+
 pub(crate) fn get_object(fd: FileDescriptor) -> Result<Arc<dyn ObjectInterface>, IoError> {
 	block_on(core_scheduler().get_object(fd), None)
 }
@@ -437,12 +1017,46 @@ pub(crate) fn replace_object(
 	block_on(core_scheduler().replace_object(fd, obj), None)
 }
 
+pub(crate) fn get_entry(fd: FileDescriptor) -> Result<ObjectEntry, IoError> {
+	block_on(core_scheduler().get_entry(fd), None)
+}
+
+pub(crate) fn insert_entry(entry: ObjectEntry) -> Result<FileDescriptor, IoError> {
+	block_on(core_scheduler().insert_entry(entry), None)
+}
+
+pub(crate) fn set_fd_flags(fd: FileDescriptor, flags: FdFlags) -> Result<(), IoError> {
+	block_on(core_scheduler().set_fd_flags(fd, flags), None)
+}
+
 // The dup system call allocates a new file descriptor that refers
 // to the same open file description as the descriptor oldfd. The new
 // file descriptor number is guaranteed to be the lowest-numbered
-// file descriptor that was unused in the calling process.
-pub(crate) fn dup_object(fd: FileDescriptor) -> Result<FileDescriptor, IoError> {
-	block_on(core_scheduler().dup_object(fd), None)
+// file descriptor, which is unused in the calling process and greater
+// than or equal to `lowest`.
+pub(crate) fn dup_object(
+	fd: FileDescriptor,
+	lowest: FileDescriptor,
+	flags: FdFlags,
+) -> Result<FileDescriptor, IoError> {
+	block_on(core_scheduler().dup_object(fd, lowest, flags), None)
+}
+
+/// `dup_object2` makes `fd2` refer to the same open file description as
+/// `fd1`. If `fd2` was already in use, the referenced object is closed.
+pub(crate) fn dup_object2(
+	fd1: FileDescriptor,
+	fd2: FileDescriptor,
+	flags: FdFlags,
+) -> Result<FileDescriptor, IoError> {
+	if fd2 < 0 {
+		return Err(IoError::EBADF);
+	}
+
+	// the previous object is released outside of the object map
+	let _previous = block_on(core_scheduler().dup_object2(fd1, fd2, flags), None)?;
+
+	Ok(fd2)
 }
 
 pub(crate) fn remove_object(fd: FileDescriptor) -> Result<Arc<dyn ObjectInterface>, IoError> {
@@ -0,0 +1,259 @@
+//! Bounded byte streams between tasks.
+//!
+//! A pipe consists of a read and a write end, which share a ring buffer.
+//! A socket pair is realized by two ring buffers, where each end reads
+//! from one buffer and writes into the other one.
+
+use alloc::boxed::Box;
+use alloc::collections::vec_deque::VecDeque;
+use alloc::sync::Arc;
+use core::future::{self, Future};
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{ready, Poll, Waker};
+
+use async_lock::Mutex;
+use async_trait::async_trait;
+
+use crate::fd::{block_on, IoCtl, IoError, ObjectInterface, PollEvent};
+
+/// Capacity of a ring buffer
+const PIPE_CAPACITY: usize = 65536;
+/// Writes up to this size are atomic
+const PIPE_BUF: usize = 4096;
+
+#[derive(Debug)]
+struct PipeState {
+	buffer: VecDeque<u8>,
+	/// Number of open read ends
+	readers: usize,
+	/// Number of open write ends
+	writers: usize,
+	read_queue: VecDeque<Waker>,
+	write_queue: VecDeque<Waker>,
+}
+
+impl PipeState {
+	fn new() -> Self {
+		Self {
+			buffer: VecDeque::new(),
+			readers: 0,
+			writers: 0,
+			read_queue: VecDeque::new(),
+			write_queue: VecDeque::new(),
+		}
+	}
+}
+
+fn register(queue: &mut VecDeque<Waker>, waker: &Waker) {
+	if !queue.iter().any(|w| w.will_wake(waker)) {
+		queue.push_back(waker.clone());
+	}
+}
+
+fn wakeup(queue: &mut VecDeque<Waker>) {
+	while let Some(waker) = queue.pop_front() {
+		waker.wake();
+	}
+}
+
+#[derive(Debug)]
+pub(crate) struct Pipe {
+	/// Buffer, from which the end reads
+	rx: Option<Arc<Mutex<PipeState>>>,
+	/// Buffer, into which the end writes
+	tx: Option<Arc<Mutex<PipeState>>>,
+	nonblocking: AtomicBool,
+}
+
+impl Pipe {
+	fn with(
+		rx: Option<Arc<Mutex<PipeState>>>,
+		tx: Option<Arc<Mutex<PipeState>>>,
+		nonblocking: bool,
+	) -> Self {
+		block_on(
+			async {
+				if let Some(rx) = &rx {
+					rx.lock().await.readers += 1;
+				}
+				if let Some(tx) = &tx {
+					tx.lock().await.writers += 1;
+				}
+				Ok(())
+			},
+			None,
+		)
+		.unwrap();
+
+		Self {
+			rx,
+			tx,
+			nonblocking: AtomicBool::new(nonblocking),
+		}
+	}
+
+	/// Creates the read and the write end of a pipe
+	pub fn new(nonblocking: bool) -> (Self, Self) {
+		let state = Arc::new(Mutex::new(PipeState::new()));
+
+		(
+			Self::with(Some(state.clone()), None, nonblocking),
+			Self::with(None, Some(state), nonblocking),
+		)
+	}
+
+	/// Creates two connected, bidirectional ends
+	pub fn pair(nonblocking: bool) -> (Self, Self) {
+		let a = Arc::new(Mutex::new(PipeState::new()));
+		let b = Arc::new(Mutex::new(PipeState::new()));
+
+		(
+			Self::with(Some(a.clone()), Some(b.clone()), nonblocking),
+			Self::with(Some(b), Some(a), nonblocking),
+		)
+	}
+}
+
+impl Clone for Pipe {
+	fn clone(&self) -> Self {
+		Self::with(
+			self.rx.clone(),
+			self.tx.clone(),
+			self.nonblocking.load(Ordering::Acquire),
+		)
+	}
+}
+
+impl Drop for Pipe {
+	fn drop(&mut self) {
+		let _ = block_on(
+			async {
+				// the other ends have to detect the end of the stream
+				if let Some(rx) = &self.rx {
+					let mut guard = rx.lock().await;
+					guard.readers -= 1;
+					wakeup(&mut guard.write_queue);
+				}
+				if let Some(tx) = &self.tx {
+					let mut guard = tx.lock().await;
+					guard.writers -= 1;
+					wakeup(&mut guard.read_queue);
+				}
+				Ok(())
+			},
+			None,
+		);
+	}
+}
+
+#[async_trait]
+impl ObjectInterface for Pipe {
+	async fn poll(&self, event: PollEvent) -> Result<PollEvent, IoError> {
+		let recv_events = PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND;
+		let send_events = PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND;
+
+		future::poll_fn(|cx| {
+			let mut ret = PollEvent::empty();
+
+			if let Some(rx) = &self.rx {
+				let mut pinned = core::pin::pin!(rx.lock());
+				let mut guard = ready!(pinned.as_mut().poll(cx));
+
+				if !guard.buffer.is_empty() {
+					ret.insert(event & recv_events);
+				}
+				if guard.writers == 0 {
+					ret.insert(PollEvent::POLLHUP);
+				} else if event.intersects(recv_events) && !ret.intersects(recv_events) {
+					register(&mut guard.read_queue, cx.waker());
+				}
+			}
+
+			if let Some(tx) = &self.tx {
+				let mut pinned = core::pin::pin!(tx.lock());
+				let mut guard = ready!(pinned.as_mut().poll(cx));
+
+				if guard.readers == 0 {
+					ret.insert(PollEvent::POLLERR);
+				} else if guard.buffer.len() + PIPE_BUF <= PIPE_CAPACITY {
+					ret.insert(event & send_events);
+				} else if event.intersects(send_events) {
+					register(&mut guard.write_queue, cx.waker());
+				}
+			}
+
+			if ret.is_empty() {
+				Poll::Pending
+			} else {
+				Poll::Ready(Ok(ret))
+			}
+		})
+		.await
+	}
+
+	async fn async_read(&self, buf: &mut [u8]) -> Result<usize, IoError> {
+		let rx = self.rx.as_ref().ok_or(IoError::EBADF)?;
+
+		future::poll_fn(|cx| {
+			let mut pinned = core::pin::pin!(rx.lock());
+			let mut guard = ready!(pinned.as_mut().poll(cx));
+
+			if !guard.buffer.is_empty() {
+				let len = core::cmp::min(buf.len(), guard.buffer.len());
+				for (dst, src) in buf.iter_mut().zip(guard.buffer.drain(..len)) {
+					*dst = src;
+				}
+				wakeup(&mut guard.write_queue);
+
+				Poll::Ready(Ok(len))
+			} else if guard.writers == 0 {
+				// end of stream
+				Poll::Ready(Ok(0))
+			} else {
+				register(&mut guard.read_queue, cx.waker());
+				Poll::Pending
+			}
+		})
+		.await
+	}
+
+	async fn async_write(&self, buf: &[u8]) -> Result<usize, IoError> {
+		let tx = self.tx.as_ref().ok_or(IoError::EBADF)?;
+
+		future::poll_fn(|cx| {
+			let mut pinned = core::pin::pin!(tx.lock());
+			let mut guard = ready!(pinned.as_mut().poll(cx));
+
+			if guard.readers == 0 {
+				return Poll::Ready(Err(IoError::EPIPE));
+			}
+
+			let free = PIPE_CAPACITY - guard.buffer.len();
+			// small writes are not interleaved with other writes
+			if free == 0 || (buf.len() <= PIPE_BUF && free < buf.len()) {
+				register(&mut guard.write_queue, cx.waker());
+				return Poll::Pending;
+			}
+
+			let len = core::cmp::min(buf.len(), free);
+			guard.buffer.extend(&buf[..len]);
+			wakeup(&mut guard.read_queue);
+
+			Poll::Ready(Ok(len))
+		})
+		.await
+	}
+
+	fn is_nonblocking(&self) -> bool {
+		self.nonblocking.load(Ordering::Acquire)
+	}
+
+	fn ioctl(&self, cmd: IoCtl, value: bool) -> Result<(), IoError> {
+		if cmd == IoCtl::NonBlocking {
+			self.nonblocking.store(value, Ordering::Release);
+			Ok(())
+		} else {
+			Err(IoError::EINVAL)
+		}
+	}
+}
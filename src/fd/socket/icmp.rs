@@ -0,0 +1,299 @@
+use alloc::boxed::Box;
+use core::future;
+use core::ops::DerefMut;
+use core::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use core::task::Poll;
+
+use async_trait::async_trait;
+use crossbeam_utils::atomic::AtomicCell;
+use smoltcp::socket::icmp;
+use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
+
+use crate::executor::network::{now, Handle, NetworkState, NIC};
+use crate::fd::socket::{scatter, WakerFanout};
+use crate::fd::{IoCtl, IoError, MsgFlags, ObjectInterface, PollEvent, RecvMeta};
+
+/// Type of an ICMPv4 echo request
+const ICMPV4_ECHO_REQUEST: u8 = 8;
+/// Type of an ICMPv6 echo request
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+/// Size of the ICMP header
+const ICMP_HEADER_LEN: usize = 8;
+
+fn get_ident() -> u16 {
+	static IDENT: AtomicU16 = AtomicU16::new(1);
+
+	IDENT.fetch_add(1, Ordering::SeqCst)
+}
+
+/// Ping socket, which sends complete ICMP messages. Like on Linux, the
+/// identifier of outgoing echo requests is replaced by the identifier of
+/// the socket and only the corresponding echo replies are received.
+#[derive(Debug)]
+pub struct Socket {
+	/// The handle changes, if the socket moves to another network device
+	handle: AtomicCell<Handle>,
+	/// Tasks, which wait for received data
+	recv_wakers: WakerFanout,
+	/// Tasks, which wait for space in the send buffer
+	send_wakers: WakerFanout,
+	nonblocking: AtomicBool,
+	ident: AtomicCell<Option<u16>>,
+	endpoint: AtomicCell<Option<IpEndpoint>>,
+}
+
+impl Socket {
+	pub fn new(handle: Handle) -> Self {
+		Self {
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
+			nonblocking: AtomicBool::new(false),
+			ident: AtomicCell::new(None),
+			endpoint: AtomicCell::new(None),
+		}
+	}
+
+	fn with<R>(&self, f: impl FnOnce(&mut icmp::Socket<'_>) -> R) -> R {
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+		let result = f(nic.get_mut_socket::<icmp::Socket<'_>>(self.handle.load()));
+		nic.poll_common(now());
+
+		result
+	}
+
+	/// Moves the socket to the device, which routes `dst`
+	fn route(&self, dst: IpAddress) {
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+		if let Some(iface) = nic.route(dst) {
+			self.handle
+				.store(nic.move_socket(self.handle.load(), iface));
+		}
+	}
+
+	/// Binds the socket to an identifier, if it isn't already bound
+	fn ident(&self) -> Result<u16, IoError> {
+		if let Some(ident) = self.ident.load() {
+			return Ok(ident);
+		}
+
+		let ident = get_ident();
+		self.with(|socket| socket.bind(icmp::Endpoint::Ident(ident)))
+			.map_err(|_| IoError::EADDRINUSE)?;
+		self.ident.store(Some(ident));
+
+		Ok(ident)
+	}
+
+	async fn async_sendto(&self, bufs: &[&[u8]], addr: IpAddress) -> Result<usize, IoError> {
+		let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+		if total < ICMP_HEADER_LEN {
+			return Err(IoError::EINVAL);
+		}
+
+		let ident = self.ident()?;
+		self.route(addr);
+		let echo_request = match addr {
+			IpAddress::Ipv4(_) => ICMPV4_ECHO_REQUEST,
+			IpAddress::Ipv6(_) => ICMPV6_ECHO_REQUEST,
+		};
+
+		future::poll_fn(|cx| {
+			self.with(|socket| {
+				if socket.can_send() {
+					let packet = socket.send(total, addr).map_err(|_| IoError::EIO)?;
+					let mut pos = 0;
+					for buf in bufs {
+						packet[pos..pos + buf.len()].copy_from_slice(buf);
+						pos += buf.len();
+					}
+					if packet[0] == echo_request {
+						packet[4..6].copy_from_slice(&ident.to_be_bytes());
+					}
+
+					Poll::Ready(Ok(total))
+				} else {
+					socket.register_send_waker(&self.send_wakers.register(cx.waker()));
+					Poll::Pending
+				}
+			})
+		})
+		.await
+	}
+
+	async fn async_recvfrom(&self, bufs: &mut [&mut [u8]]) -> Result<RecvMeta, IoError> {
+		// replies are only received by a bound socket
+		self.ident()?;
+
+		future::poll_fn(|cx| {
+			self.with(|socket| {
+				if socket.can_recv() {
+					match socket.recv() {
+						Ok((data, addr)) => Poll::Ready(Ok(RecvMeta {
+							len: scatter(bufs, 0, data),
+							datagram_len: data.len(),
+							endpoint: Some(IpEndpoint::new(addr, 0)),
+							local_addr: None,
+							device: None,
+						})),
+						Err(_) => Poll::Ready(Err(IoError::EIO)),
+					}
+				} else {
+					socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
+					Poll::Pending
+				}
+			})
+		})
+		.await
+	}
+}
+
+#[async_trait]
+impl ObjectInterface for Socket {
+	async fn poll(&self, event: PollEvent) -> Result<PollEvent, IoError> {
+		future::poll_fn(|cx| {
+			self.with(|socket| {
+				let mut avail = PollEvent::empty();
+
+				if socket.can_send() {
+					avail
+						.insert(PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND);
+				}
+
+				if socket.can_recv() {
+					avail.insert(PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND);
+				}
+
+				let ret = event & avail;
+
+				// register the waker for all requested, but unavailable events,
+				// which allows epoll to detect the next change
+				let recv_events = PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND;
+				if event.intersects(recv_events) && !ret.intersects(recv_events) {
+					socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
+				}
+
+				let send_events =
+					PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND;
+				if event.intersects(send_events) && !ret.intersects(send_events) {
+					socket.register_send_waker(&self.send_wakers.register(cx.waker()));
+				}
+
+				if ret.is_empty() {
+					Poll::Pending
+				} else {
+					Poll::Ready(Ok(ret))
+				}
+			})
+		})
+		.await
+	}
+
+	/// The port of the endpoint is used as identifier of the echo requests
+	fn bind(&self, endpoint: IpListenEndpoint) -> Result<(), IoError> {
+		if self.ident.load().is_some() {
+			return Err(IoError::EINVAL);
+		}
+		if endpoint.port == 0 {
+			return self.ident().map(|_| ());
+		}
+
+		self.with(|socket| socket.bind(icmp::Endpoint::Ident(endpoint.port)))
+			.map_err(|_| IoError::EADDRINUSE)?;
+		self.ident.store(Some(endpoint.port));
+
+		Ok(())
+	}
+
+	fn connect(&self, endpoint: IpEndpoint) -> Result<(), IoError> {
+		self.endpoint.store(Some(endpoint));
+		Ok(())
+	}
+
+	async fn async_read(&self, buffer: &mut [u8]) -> Result<usize, IoError> {
+		self.async_recvfrom(&mut [buffer])
+			.await
+			.map(|meta| meta.len)
+	}
+
+	async fn async_write(&self, buf: &[u8]) -> Result<usize, IoError> {
+		self.async_sendmsg(&[buf], None, None).await
+	}
+
+	async fn async_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
+		self.async_recvfrom(bufs).await.map(|meta| meta.len)
+	}
+
+	async fn async_writev(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		self.async_sendmsg(bufs, None, None).await
+	}
+
+	async fn async_recvmsg(
+		&self,
+		bufs: &mut [&mut [u8]],
+		flags: MsgFlags,
+	) -> Result<RecvMeta, IoError> {
+		if flags.contains(MsgFlags::MSG_PEEK) {
+			return Err(IoError::EINVAL);
+		}
+
+		self.async_recvfrom(bufs).await
+	}
+
+	async fn async_sendmsg(
+		&self,
+		bufs: &[&[u8]],
+		endpoint: Option<IpEndpoint>,
+		_local_addr: Option<IpAddress>,
+	) -> Result<usize, IoError> {
+		let endpoint = endpoint
+			.or_else(|| self.endpoint.load())
+			.ok_or(IoError::EINVAL)?;
+		self.async_sendto(bufs, endpoint.addr).await
+	}
+
+	fn is_nonblocking(&self) -> bool {
+		self.nonblocking.load(Ordering::Acquire)
+	}
+
+	fn ioctl(&self, cmd: IoCtl, value: bool) -> Result<(), IoError> {
+		if cmd == IoCtl::NonBlocking {
+			self.nonblocking.store(value, Ordering::Release);
+			Ok(())
+		} else {
+			Err(IoError::EINVAL)
+		}
+	}
+}
+
+impl Clone for Socket {
+	fn clone(&self) -> Self {
+		let mut guard = NIC.lock();
+
+		let handle = if let NetworkState::Initialized(nic) = guard.deref_mut() {
+			nic.create_icmp_handle().unwrap()
+		} else {
+			panic!("Unable to create handle");
+		};
+
+		Self {
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
+			nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::Acquire)),
+			ident: AtomicCell::new(None),
+			endpoint: AtomicCell::new(self.endpoint.load()),
+		}
+	}
+}
+
+impl Drop for Socket {
+	fn drop(&mut self) {
+		NIC.lock()
+			.as_nic_mut()
+			.unwrap()
+			.destroy_socket(self.handle.load());
+	}
+}
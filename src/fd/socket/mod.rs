@@ -1,4 +1,104 @@
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use core::mem;
+use core::task::Waker;
+
+use hermit_sync::InterruptTicketMutex;
+
+#[cfg(feature = "icmp")]
+pub(crate) mod icmp;
+#[cfg(feature = "raw")]
+pub(crate) mod raw;
 #[cfg(feature = "tcp")]
 pub(crate) mod tcp;
 #[cfg(feature = "udp")]
 pub(crate) mod udp;
+
+/// Hop limit of smoltcp, if no limit is set
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub(crate) const DEFAULT_HOP_LIMIT: u8 = 64;
+/// Lower bound of `SO_RCVBUF` and `SO_SNDBUF`
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub(crate) const MIN_BUFFER_SIZE: usize = 1024;
+/// Upper bound of `SO_RCVBUF` and `SO_SNDBUF`
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub(crate) const MAX_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// Maximum number of tasks, which wait for the same event of a socket
+const MAX_WAITERS: usize = 16;
+
+#[derive(Debug, Default)]
+struct Waiters(InterruptTicketMutex<Vec<Waker>>);
+
+impl Waiters {
+	fn wake_all(&self) {
+		let wakers = mem::take(&mut *self.0.lock());
+		for waker in wakers {
+			waker.wake();
+		}
+	}
+}
+
+impl Wake for Waiters {
+	fn wake(self: Arc<Self>) {
+		self.wake_all();
+	}
+
+	fn wake_by_ref(self: &Arc<Self>) {
+		self.wake_all();
+	}
+}
+
+/// Tasks, which wait for the same event of a socket. smoltcp stores a single
+/// waker per event and replaces it with every registration, which would
+/// cut off an epoll instance by a concurrent read. Hence, the fan-out is
+/// registered at smoltcp and wakes all waiting tasks.
+#[derive(Debug, Default)]
+pub(crate) struct WakerFanout(Arc<Waiters>);
+
+impl WakerFanout {
+	/// Adds `waker` to the waiting tasks and returns the waker, which is
+	/// registered at the smoltcp socket
+	pub fn register(&self, waker: &Waker) -> Waker {
+		let mut wakers = self.0 .0.lock();
+		if !wakers.iter().any(|w| w.will_wake(waker)) {
+			if wakers.len() >= MAX_WAITERS {
+				// tasks may be woken spuriously, which bounds the list
+				let previous = mem::take(&mut *wakers);
+				drop(wakers);
+				for w in previous {
+					w.wake();
+				}
+				wakers = self.0 .0.lock();
+			}
+			wakers.push(waker.clone());
+		}
+		drop(wakers);
+
+		Waker::from(self.0.clone())
+	}
+}
+
+/// Copies `data` into `bufs`, starting at the offset `skip` of the buffers,
+/// and returns the number of copied bytes
+pub(crate) fn scatter(bufs: &mut [&mut [u8]], mut skip: usize, data: &[u8]) -> usize {
+	let mut pos = 0;
+
+	for buf in bufs.iter_mut() {
+		if pos == data.len() {
+			break;
+		}
+		if skip >= buf.len() {
+			skip -= buf.len();
+			continue;
+		}
+
+		let n = core::cmp::min(buf.len() - skip, data.len() - pos);
+		buf[skip..skip + n].copy_from_slice(&data[pos..pos + n]);
+		pos += n;
+		skip = 0;
+	}
+
+	pos
+}
@@ -0,0 +1,271 @@
+use alloc::boxed::Box;
+use core::future;
+use core::ops::DerefMut;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Poll;
+
+use async_trait::async_trait;
+use crossbeam_utils::atomic::AtomicCell;
+use smoltcp::socket::raw;
+use smoltcp::wire::{IpAddress, IpEndpoint, IpProtocol, IpVersion, Ipv4Packet, Ipv6Packet};
+
+use crate::executor::network::{now, Handle, NetworkState, NIC};
+use crate::fd::socket::{scatter, WakerFanout};
+use crate::fd::{IoCtl, IoError, MsgFlags, ObjectInterface, PollEvent, RecvMeta};
+
+/// Size of an IPv4 header without options
+const IPV4_HEADER_LEN: usize = 20;
+/// Size of an IPv6 header
+const IPV6_HEADER_LEN: usize = 40;
+
+/// Raw socket for a single IP protocol. Sent and received packets include
+/// the IP header, which corresponds to `IP_HDRINCL` on Linux.
+#[derive(Debug)]
+pub struct Socket {
+	/// The handle changes, if the socket moves to another network device
+	handle: AtomicCell<Handle>,
+	/// Tasks, which wait for received data
+	recv_wakers: WakerFanout,
+	/// Tasks, which wait for space in the send buffer
+	send_wakers: WakerFanout,
+	version: IpVersion,
+	protocol: IpProtocol,
+	nonblocking: AtomicBool,
+}
+
+impl Socket {
+	pub fn new(handle: Handle, version: IpVersion, protocol: IpProtocol) -> Self {
+		Self {
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
+			version,
+			protocol,
+			nonblocking: AtomicBool::new(false),
+		}
+	}
+
+	fn with<R>(&self, f: impl FnOnce(&mut raw::Socket<'_>) -> R) -> R {
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+		let result = f(nic.get_mut_socket::<raw::Socket<'_>>(self.handle.load()));
+		nic.poll_common(now());
+
+		result
+	}
+
+	/// Returns the source address of a received packet
+	fn source(&self, packet: &[u8]) -> Option<IpAddress> {
+		match self.version {
+			IpVersion::Ipv4 => Ipv4Packet::new_checked(packet)
+				.ok()
+				.map(|p| IpAddress::Ipv4(p.src_addr())),
+			IpVersion::Ipv6 => Ipv6Packet::new_checked(packet)
+				.ok()
+				.map(|p| IpAddress::Ipv6(p.src_addr())),
+		}
+	}
+
+	/// Returns the destination address in the IP header of a packet, which
+	/// is sent. The payload may be part of other buffers.
+	fn destination(&self, header: &[u8]) -> Option<IpAddress> {
+		match self.version {
+			IpVersion::Ipv4 => (header.len() >= IPV4_HEADER_LEN)
+				.then(|| IpAddress::Ipv4(Ipv4Packet::new_unchecked(header).dst_addr())),
+			IpVersion::Ipv6 => (header.len() >= IPV6_HEADER_LEN)
+				.then(|| IpAddress::Ipv6(Ipv6Packet::new_unchecked(header).dst_addr())),
+		}
+	}
+
+	/// Moves the socket to the device, which routes the destination of `header`
+	fn route(&self, header: &[u8]) {
+		let Some(dst) = self.destination(header) else {
+			return;
+		};
+
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+		if let Some(iface) = nic.route(dst) {
+			self.handle
+				.store(nic.move_socket(self.handle.load(), iface));
+		}
+	}
+
+	async fn async_send(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+		// the IP header is part of the first buffer
+		if let Some(header) = bufs.first() {
+			self.route(header);
+		}
+
+		future::poll_fn(|cx| {
+			self.with(|socket| {
+				if socket.can_send() {
+					let packet = socket.send(total).map_err(|_| IoError::EIO)?;
+					let mut pos = 0;
+					for buf in bufs {
+						packet[pos..pos + buf.len()].copy_from_slice(buf);
+						pos += buf.len();
+					}
+
+					Poll::Ready(Ok(total))
+				} else {
+					socket.register_send_waker(&self.send_wakers.register(cx.waker()));
+					Poll::Pending
+				}
+			})
+		})
+		.await
+	}
+
+	async fn async_recvfrom(&self, bufs: &mut [&mut [u8]]) -> Result<RecvMeta, IoError> {
+		future::poll_fn(|cx| {
+			self.with(|socket| {
+				if socket.can_recv() {
+					match socket.recv() {
+						Ok(data) => {
+							let addr = self.source(data).ok_or(IoError::EIO)?;
+							Poll::Ready(Ok(RecvMeta {
+								len: scatter(bufs, 0, data),
+								datagram_len: data.len(),
+								endpoint: Some(IpEndpoint::new(addr, 0)),
+								local_addr: None,
+								device: None,
+							}))
+						}
+						Err(_) => Poll::Ready(Err(IoError::EIO)),
+					}
+				} else {
+					socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
+					Poll::Pending
+				}
+			})
+		})
+		.await
+	}
+}
+
+#[async_trait]
+impl ObjectInterface for Socket {
+	async fn poll(&self, event: PollEvent) -> Result<PollEvent, IoError> {
+		future::poll_fn(|cx| {
+			self.with(|socket| {
+				let mut avail = PollEvent::empty();
+
+				if socket.can_send() {
+					avail
+						.insert(PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND);
+				}
+
+				if socket.can_recv() {
+					avail.insert(PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND);
+				}
+
+				let ret = event & avail;
+
+				// register the waker for all requested, but unavailable events,
+				// which allows epoll to detect the next change
+				let recv_events = PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND;
+				if event.intersects(recv_events) && !ret.intersects(recv_events) {
+					socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
+				}
+
+				let send_events =
+					PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND;
+				if event.intersects(send_events) && !ret.intersects(send_events) {
+					socket.register_send_waker(&self.send_wakers.register(cx.waker()));
+				}
+
+				if ret.is_empty() {
+					Poll::Pending
+				} else {
+					Poll::Ready(Ok(ret))
+				}
+			})
+		})
+		.await
+	}
+
+	async fn async_read(&self, buffer: &mut [u8]) -> Result<usize, IoError> {
+		self.async_recvfrom(&mut [buffer])
+			.await
+			.map(|meta| meta.len)
+	}
+
+	async fn async_write(&self, buf: &[u8]) -> Result<usize, IoError> {
+		self.async_send(&[buf]).await
+	}
+
+	async fn async_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
+		self.async_recvfrom(bufs).await.map(|meta| meta.len)
+	}
+
+	async fn async_writev(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		self.async_send(bufs).await
+	}
+
+	async fn async_recvmsg(
+		&self,
+		bufs: &mut [&mut [u8]],
+		flags: MsgFlags,
+	) -> Result<RecvMeta, IoError> {
+		if flags.contains(MsgFlags::MSG_PEEK) {
+			return Err(IoError::EINVAL);
+		}
+
+		self.async_recvfrom(bufs).await
+	}
+
+	/// The destination is taken from the IP header of the packet
+	async fn async_sendmsg(
+		&self,
+		bufs: &[&[u8]],
+		_endpoint: Option<IpEndpoint>,
+		_local_addr: Option<IpAddress>,
+	) -> Result<usize, IoError> {
+		self.async_send(bufs).await
+	}
+
+	fn is_nonblocking(&self) -> bool {
+		self.nonblocking.load(Ordering::Acquire)
+	}
+
+	fn ioctl(&self, cmd: IoCtl, value: bool) -> Result<(), IoError> {
+		if cmd == IoCtl::NonBlocking {
+			self.nonblocking.store(value, Ordering::Release);
+			Ok(())
+		} else {
+			Err(IoError::EINVAL)
+		}
+	}
+}
+
+impl Clone for Socket {
+	fn clone(&self) -> Self {
+		let mut guard = NIC.lock();
+
+		let handle = if let NetworkState::Initialized(nic) = guard.deref_mut() {
+			nic.create_raw_handle(self.version, self.protocol).unwrap()
+		} else {
+			panic!("Unable to create handle");
+		};
+
+		Self {
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
+			version: self.version,
+			protocol: self.protocol,
+			nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::Acquire)),
+		}
+	}
+}
+
+impl Drop for Socket {
+	fn drop(&mut self) {
+		NIC.lock()
+			.as_nic_mut()
+			.unwrap()
+			.destroy_socket(self.handle.load());
+	}
+}
@@ -1,20 +1,34 @@
 use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::future;
 use core::ops::DerefMut;
 use core::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use core::task::Poll;
 
 use async_trait::async_trait;
+use crossbeam_utils::atomic::AtomicCell;
+use hermit_sync::InterruptTicketMutex;
 use smoltcp::iface;
 use smoltcp::socket::tcp;
 use smoltcp::time::Duration;
-use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
+use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
+use crate::errno::ECONNREFUSED;
 use crate::executor::block_on;
-use crate::executor::network::{now, Handle, NetworkState, NIC};
-use crate::fd::{IoCtl, IoError, ObjectInterface, PollEvent, SocketOption};
+use crate::executor::network::{now, Handle, NetworkInterface, NetworkState, NIC};
+use crate::fd::socket::{
+	scatter, WakerFanout, DEFAULT_HOP_LIMIT, MAX_BUFFER_SIZE, MIN_BUFFER_SIZE,
+};
+use crate::fd::{
+	IoCtl, IoError, MsgFlags, ObjectInterface, PollEvent, RecvMeta, SocketOption, SocketOptionValue,
+};
 use crate::DEFAULT_KEEP_ALIVE_INTERVAL;
 
+/// Upper bound of the listen backlog. Each pending connection holds a
+/// smoltcp socket with preallocated buffers.
+const MAX_BACKLOG: usize = 64;
+
 /// further receives will be disallowed
 pub const SHUT_RD: i32 = 0;
 /// further sends will be disallowed
@@ -28,56 +42,192 @@ fn get_ephemeral_port() -> u16 {
 	LOCAL_ENDPOINT.fetch_add(1, Ordering::SeqCst)
 }
 
+/// Creates a smoltcp socket with the given buffer sizes, which inherits
+/// the options of `template`
+fn socket_like(template: &tcp::Socket<'_>, rx_size: usize, tx_size: usize) -> tcp::Socket<'static> {
+	let mut socket = tcp::Socket::new(
+		tcp::SocketBuffer::new(vec![0; rx_size]),
+		tcp::SocketBuffer::new(vec![0; tx_size]),
+	);
+	socket.set_nagle_enabled(template.nagle_enabled());
+	socket.set_ack_delay(template.ack_delay());
+	socket.set_keep_alive(template.keep_alive());
+	socket.set_timeout(template.timeout());
+	socket.set_hop_limit(template.hop_limit());
+
+	socket
+}
+
+/// Adds a socket to the device `iface`, which listens on `endpoint` and is
+/// configured like `template`
+fn create_listener(
+	nic: &mut NetworkInterface<'_>,
+	template: Handle,
+	iface: usize,
+	endpoint: IpListenEndpoint,
+) -> Result<Handle, IoError> {
+	let template = nic.get_mut_socket::<tcp::Socket<'_>>(template);
+	let mut listener = socket_like(template, template.recv_capacity(), template.send_capacity());
+	listener.listen(endpoint).map_err(|_| IoError::EADDRINUSE)?;
+
+	Ok(nic.add_tcp_socket(iface, listener))
+}
+
 #[derive(Debug)]
 pub struct Socket {
-	handle: Handle,
+	/// The handle changes, if the socket moves to another network device
+	handle: AtomicCell<Handle>,
+	/// Tasks, which wait for received data
+	recv_wakers: WakerFanout,
+	/// Tasks, which wait for space in the send buffer
+	send_wakers: WakerFanout,
+	/// Local address of a bound socket, which determines the network device
+	addr: AtomicCell<Option<IpAddress>>,
 	port: AtomicU16,
 	nonblocking: AtomicBool,
 	listen: AtomicBool,
+	/// Set by `connect` until the connection attempt is finished,
+	/// which is required to report a refused connection by `SO_ERROR`
+	connecting: AtomicBool,
+	linger: AtomicCell<Option<core::time::Duration>>,
+	recv_timeout: AtomicCell<Option<core::time::Duration>>,
+	send_timeout: AtomicCell<Option<core::time::Duration>>,
+	/// Sockets of a listening socket, which wait for incoming connections.
+	/// The socket of `handle` only serves as template for them.
+	backlog: InterruptTicketMutex<Vec<Handle>>,
 }
 
 impl Socket {
 	pub fn new(handle: Handle) -> Self {
 		Self {
-			handle,
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
+			addr: AtomicCell::new(None),
 			port: AtomicU16::new(0),
 			nonblocking: AtomicBool::new(false),
 			listen: AtomicBool::new(false),
+			connecting: AtomicBool::new(false),
+			linger: AtomicCell::new(None),
+			recv_timeout: AtomicCell::new(None),
+			send_timeout: AtomicCell::new(None),
+			backlog: InterruptTicketMutex::new(Vec::new()),
 		}
 	}
 
 	fn with<R>(&self, f: impl FnOnce(&mut tcp::Socket<'_>) -> R) -> R {
 		let mut guard = NIC.lock();
 		let nic = guard.as_nic_mut().unwrap();
-		let result = f(nic.get_mut_socket::<tcp::Socket<'_>>(self.handle));
+		let result = f(nic.get_mut_socket::<tcp::Socket<'_>>(self.handle.load()));
 		nic.poll_common(now());
 
 		result
 	}
 
+	fn with_backlog<R>(
+		&self,
+		f: impl FnOnce(&mut NetworkInterface<'_>, &mut Vec<Handle>) -> R,
+	) -> R {
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+		let result = f(nic, &mut self.backlog.lock());
+		nic.poll_common(now());
+
+		result
+	}
+
+	fn is_listening(&self) -> bool {
+		!self.backlog.lock().is_empty()
+	}
+
 	fn with_context<R>(&self, f: impl FnOnce(&mut tcp::Socket<'_>, &mut iface::Context) -> R) -> R {
 		let mut guard = NIC.lock();
 		let nic = guard.as_nic_mut().unwrap();
-		let (s, cx) = nic.get_socket_and_context::<tcp::Socket<'_>>(self.handle);
+		let (s, cx) = nic.get_socket_and_context::<tcp::Socket<'_>>(self.handle.load());
 		let result = f(s, cx);
 		nic.poll_common(now());
 
 		result
 	}
 
+	/// Moves the socket to the device of the bound address or to the device,
+	/// which routes `dst`. Returns the local endpoint of a connection.
+	fn route(&self, dst: IpAddress) -> Result<IpListenEndpoint, IoError> {
+		let addr = self.addr.load();
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+
+		let iface = match addr {
+			Some(addr) => nic.device_of_address(addr),
+			None => nic.route(dst),
+		}
+		.ok_or(IoError::ENETUNREACH)?;
+		self.handle
+			.store(nic.move_socket(self.handle.load(), iface));
+
+		let port = match self.port.load(Ordering::Acquire) {
+			0 => get_ephemeral_port(),
+			port => port,
+		};
+
+		Ok(IpListenEndpoint { addr, port })
+	}
+
+	/// Replaces the smoltcp socket by one with other buffer sizes. This is
+	/// only possible, as long as the socket is closed.
+	fn resize_buffers(
+		&self,
+		rx_size: Option<usize>,
+		tx_size: Option<usize>,
+	) -> Result<(), IoError> {
+		self.with(|socket| {
+			if socket.state() != tcp::State::Closed {
+				return Err(IoError::EINVAL);
+			}
+
+			let rx_size = rx_size
+				.unwrap_or(socket.recv_capacity())
+				.clamp(MIN_BUFFER_SIZE, MAX_BUFFER_SIZE);
+			let tx_size = tx_size
+				.unwrap_or(socket.send_capacity())
+				.clamp(MIN_BUFFER_SIZE, MAX_BUFFER_SIZE);
+
+			*socket = socket_like(socket, rx_size, tx_size);
+
+			Ok(())
+		})
+	}
+
+	/// Returns the pending error of the socket and clears it
+	fn take_error(&self) -> i32 {
+		self.with(|socket| match socket.state() {
+			tcp::State::SynSent | tcp::State::SynReceived => 0,
+			tcp::State::Closed if self.connecting.swap(false, Ordering::AcqRel) => ECONNREFUSED,
+			_ => {
+				self.connecting.store(false, Ordering::Release);
+				0
+			}
+		})
+	}
+
 	async fn async_connect(&self, endpoint: IpEndpoint) -> Result<(), IoError> {
-		self.with_context(|socket, cx| socket.connect(cx, endpoint, get_ephemeral_port()))
+		let local_endpoint = self.route(endpoint.addr)?;
+		self.with_context(|socket, cx| socket.connect(cx, endpoint, local_endpoint))
 			.map_err(|_| IoError::EIO)?;
+		self.connecting.store(true, Ordering::Release);
 
 		future::poll_fn(|cx| {
 			self.with(|socket| match socket.state() {
 				tcp::State::Closed | tcp::State::TimeWait => Poll::Ready(Err(IoError::EFAULT)),
 				tcp::State::Listen => Poll::Ready(Err(IoError::EIO)),
 				tcp::State::SynSent | tcp::State::SynReceived => {
-					socket.register_send_waker(cx.waker());
+					socket.register_send_waker(&self.send_wakers.register(cx.waker()));
 					Poll::Pending
 				}
-				_ => Poll::Ready(Ok(())),
+				_ => {
+					self.connecting.store(false, Ordering::Release);
+					Poll::Ready(Ok(()))
+				}
 			})
 		})
 		.await
@@ -93,7 +243,7 @@ impl Socket {
 				| tcp::State::TimeWait => Poll::Ready(Err(IoError::EIO)),
 				_ => {
 					if socket.send_queue() > 0 {
-						socket.register_send_waker(cx.waker());
+						socket.register_send_waker(&self.send_wakers.register(cx.waker()));
 						Poll::Pending
 					} else {
 						socket.close();
@@ -112,7 +262,7 @@ impl Socket {
 				| tcp::State::Closing
 				| tcp::State::TimeWait => Poll::Ready(Ok(())),
 				_ => {
-					socket.register_send_waker(cx.waker());
+					socket.register_send_waker(&self.send_wakers.register(cx.waker()));
 					Poll::Pending
 				}
 			})
@@ -120,54 +270,94 @@ impl Socket {
 		.await
 	}
 
-	async fn async_accept(&self) -> Result<IpEndpoint, IoError> {
+	/// Waits for an established connection in the backlog and replaces
+	/// its socket by a new listener
+	async fn async_accept(&self) -> Result<(Handle, IpEndpoint), IoError> {
+		let local_endpoint = IpListenEndpoint {
+			addr: self.addr.load(),
+			port: self.port.load(Ordering::Acquire),
+		};
+
 		future::poll_fn(|cx| {
-			self.with(|socket| match socket.state() {
-				tcp::State::Closed => {
-					let _ = socket.listen(self.port.load(Ordering::Acquire));
-					Poll::Ready(())
+			self.with_backlog(|nic, backlog| {
+				if backlog.is_empty() {
+					return Poll::Ready(Err(IoError::EINVAL));
 				}
-				tcp::State::Listen | tcp::State::Established => Poll::Ready(()),
-				_ => {
-					socket.register_recv_waker(cx.waker());
-					Poll::Pending
+
+				for i in 0..backlog.len() {
+					let socket = nic.get_mut_socket::<tcp::Socket<'_>>(backlog[i]);
+					if socket.is_active() && socket.state() != tcp::State::SynReceived {
+						let handle = backlog[i];
+						let endpoint = socket.remote_endpoint().ok_or(IoError::EIO)?;
+						socket.set_keep_alive(Some(Duration::from_millis(
+							DEFAULT_KEEP_ALIVE_INTERVAL,
+						)));
+
+						match create_listener(nic, self.handle.load(), handle.iface, local_endpoint)
+						{
+							Ok(listener) => backlog[i] = listener,
+							Err(_) => {
+								backlog.swap_remove(i);
+							}
+						}
+
+						return Poll::Ready(Ok((handle, endpoint)));
+					}
+
+					// a listener, which was reset, has to listen again
+					if !socket.is_open() {
+						let _ = socket.listen(local_endpoint);
+					}
+					socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
 				}
+
+				Poll::Pending
 			})
 		})
-		.await;
+		.await
+	}
+
+	/// Polls the backlog of a listening socket, which is readable, if a
+	/// connection is established
+	async fn poll_backlog(&self, event: PollEvent) -> Result<PollEvent, IoError> {
+		let recv_events = PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND;
 
 		future::poll_fn(|cx| {
-			self.with(|socket| {
-				if socket.is_active() {
-					Poll::Ready(Ok(()))
-				} else {
-					match socket.state() {
-						tcp::State::Closed
-						| tcp::State::Closing
-						| tcp::State::FinWait1
-						| tcp::State::FinWait2 => Poll::Ready(Err(IoError::EIO)),
-						_ => {
-							socket.register_recv_waker(cx.waker());
-							Poll::Pending
-						}
+			self.with_backlog(|nic, backlog| {
+				let mut ready = false;
+				for handle in backlog.iter() {
+					let socket = nic.get_mut_socket::<tcp::Socket<'_>>(*handle);
+					if socket.is_active() && socket.state() != tcp::State::SynReceived {
+						ready = true;
+						break;
 					}
+					socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
 				}
-			})
-		})
-		.await?;
 
-		let mut guard = NIC.lock();
-		let nic = guard.as_nic_mut().map_err(|_| IoError::EIO)?;
-		let socket = nic.get_mut_socket::<tcp::Socket<'_>>(self.handle);
-		socket.set_keep_alive(Some(Duration::from_millis(DEFAULT_KEEP_ALIVE_INTERVAL)));
+				let ret = if ready {
+					event & recv_events
+				} else {
+					PollEvent::empty()
+				};
 
-		Ok(socket.remote_endpoint().unwrap())
+				if ret.is_empty() {
+					Poll::Pending
+				} else {
+					Poll::Ready(Ok(ret))
+				}
+			})
+		})
+		.await
 	}
 }
 
 #[async_trait]
 impl ObjectInterface for Socket {
 	async fn poll(&self, event: PollEvent) -> Result<PollEvent, IoError> {
+		if self.is_listening() {
+			return self.poll_backlog(event).await;
+		}
+
 		future::poll_fn(|cx| {
 			self.with(|socket| match socket.state() {
 				tcp::State::Closed | tcp::State::Closing | tcp::State::CloseWait => {
@@ -188,8 +378,8 @@ impl ObjectInterface for Socket {
 					Poll::Ready(Ok(PollEvent::POLLHUP))
 				}
 				tcp::State::Listen => {
-					socket.register_recv_waker(cx.waker());
-					socket.register_send_waker(cx.waker());
+					socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
+					socket.register_send_waker(&self.send_wakers.register(cx.waker()));
 					Poll::Pending
 				}
 				_ => {
@@ -212,19 +402,21 @@ impl ObjectInterface for Socket {
 
 					let ret = event & available;
 
-					if ret.is_empty() {
-						if event.intersects(
-							PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND,
-						) {
-							socket.register_recv_waker(cx.waker());
-						}
+					// register the waker for all requested, but unavailable events,
+					// which allows epoll to detect the next change
+					let recv_events =
+						PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND;
+					if event.intersects(recv_events) && !ret.intersects(recv_events) {
+						socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
+					}
 
-						if event.intersects(
-							PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND,
-						) {
-							socket.register_send_waker(cx.waker());
-						}
+					let send_events =
+						PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND;
+					if event.intersects(send_events) && !ret.intersects(send_events) {
+						socket.register_send_waker(&self.send_wakers.register(cx.waker()));
+					}
 
+					if ret.is_empty() {
 						Poll::Pending
 					} else {
 						Poll::Ready(Ok(ret))
@@ -260,7 +452,7 @@ impl ObjectInterface for Socket {
 								.map_err(|_| IoError::EIO),
 						)
 					} else {
-						socket.register_recv_waker(cx.waker());
+						socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
 						Poll::Pending
 					}
 				}
@@ -293,7 +485,7 @@ impl ObjectInterface for Socket {
 								// async write
 								Poll::Ready(Ok(0))
 							} else {
-								socket.register_send_waker(cx.waker());
+								socket.register_send_waker(&self.send_wakers.register(cx.waker()));
 								Poll::Pending
 							}
 						}
@@ -312,7 +504,146 @@ impl ObjectInterface for Socket {
 		Ok(pos)
 	}
 
+	async fn async_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
+		self.async_recvmsg(bufs, MsgFlags::empty())
+			.await
+			.map(|meta| meta.len)
+	}
+
+	async fn async_writev(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+		let mut pos: usize = 0;
+
+		while pos < total {
+			let n = future::poll_fn(|cx| {
+				self.with(|socket| {
+					match socket.state() {
+						tcp::State::Closed | tcp::State::Closing | tcp::State::CloseWait => {
+							Poll::Ready(Ok(0))
+						}
+						tcp::State::FinWait1
+						| tcp::State::FinWait2
+						| tcp::State::Listen
+						| tcp::State::TimeWait => Poll::Ready(Err(IoError::EIO)),
+						_ => {
+							if socket.can_send() {
+								// skip the data, which is already sent
+								let mut skip = pos;
+								let mut len = 0;
+								for buf in bufs {
+									if skip >= buf.len() {
+										skip -= buf.len();
+										continue;
+									}
+
+									let n = socket
+										.send_slice(&buf[skip..])
+										.map_err(|_| IoError::EIO)?;
+									len += n;
+									if n < buf.len() - skip {
+										break;
+									}
+									skip = 0;
+								}
+
+								Poll::Ready(Ok(len))
+							} else if pos > 0 {
+								// we already send some data => return 0 as signal to stop the
+								// async write
+								Poll::Ready(Ok(0))
+							} else {
+								socket.register_send_waker(&self.send_wakers.register(cx.waker()));
+								Poll::Pending
+							}
+						}
+					}
+				})
+			})
+			.await?;
+
+			if n == 0 {
+				break;
+			}
+
+			pos += n;
+		}
+
+		Ok(pos)
+	}
+
+	/// `MSG_TRUNC` has no effect on a stream. The data is consumed only, if
+	/// the call returns, which allows to restart a call after a timeout.
+	async fn async_recvmsg(
+		&self,
+		bufs: &mut [&mut [u8]],
+		flags: MsgFlags,
+	) -> Result<RecvMeta, IoError> {
+		let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+		let peek = flags.contains(MsgFlags::MSG_PEEK);
+
+		let len = future::poll_fn(|cx| {
+			self.with(|socket| match socket.state() {
+				tcp::State::Closed | tcp::State::Closing | tcp::State::CloseWait => {
+					Poll::Ready(Ok(0))
+				}
+				tcp::State::FinWait1
+				| tcp::State::FinWait2
+				| tcp::State::Listen
+				| tcp::State::TimeWait => Poll::Ready(Err(IoError::EIO)),
+				_ => {
+					if socket.can_recv() {
+						if peek {
+							let data = socket.peek(total).map_err(|_| IoError::EIO)?;
+							return Poll::Ready(Ok(scatter(bufs, 0, data)));
+						}
+
+						// the ring buffer can wrap around, which requires a second call
+						let mut len = 0;
+						while socket.can_recv() && len < total {
+							let offset = len;
+							len += socket
+								.recv(|data| {
+									let n = scatter(bufs, offset, data);
+									(n, n)
+								})
+								.map_err(|_| IoError::EIO)?;
+						}
+
+						Poll::Ready(Ok(len))
+					} else {
+						socket.register_recv_waker(&self.recv_wakers.register(cx.waker()));
+						Poll::Pending
+					}
+				}
+			})
+		})
+		.await?;
+
+		Ok(RecvMeta::stream(len))
+	}
+
+	/// The peer of a connected socket can't be overridden, the endpoint is ignored
+	async fn async_sendmsg(
+		&self,
+		bufs: &[&[u8]],
+		_endpoint: Option<IpEndpoint>,
+		_local_addr: Option<IpAddress>,
+	) -> Result<usize, IoError> {
+		self.async_writev(bufs).await
+	}
+
+	/// A socket, which is bound to an address, only uses the network
+	/// device of this address
 	fn bind(&self, endpoint: IpListenEndpoint) -> Result<(), IoError> {
+		if let Some(addr) = endpoint.addr {
+			let mut guard = NIC.lock();
+			let nic = guard.as_nic_mut().unwrap();
+			let iface = nic.device_of_address(addr).ok_or(IoError::EADDRNOTAVAIL)?;
+			self.handle
+				.store(nic.move_socket(self.handle.load(), iface));
+		}
+
+		self.addr.store(endpoint.addr);
 		self.port.store(endpoint.port, Ordering::Release);
 		Ok(())
 	}
@@ -331,18 +662,36 @@ impl ObjectInterface for Socket {
 		}
 	}
 
-	fn accept(&self) -> Result<IpEndpoint, IoError> {
-		if self.is_nonblocking() {
+	fn accept(&self) -> Result<(Arc<dyn ObjectInterface>, IpEndpoint), IoError> {
+		let (handle, endpoint) = if self.is_nonblocking() {
 			block_on(self.async_accept(), Some(Duration::ZERO.into())).map_err(|x| {
 				if x == IoError::ETIME {
 					IoError::EAGAIN
 				} else {
 					x
 				}
-			})
+			})?
 		} else {
-			block_on(self.async_accept(), None)
-		}
+			block_on(self.async_accept(), None)?
+		};
+
+		// the connection inherits the options of the listening socket
+		let socket = Self {
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
+			addr: AtomicCell::new(self.addr.load()),
+			port: AtomicU16::new(self.port.load(Ordering::Acquire)),
+			nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::Acquire)),
+			listen: AtomicBool::new(true),
+			connecting: AtomicBool::new(false),
+			linger: AtomicCell::new(self.linger.load()),
+			recv_timeout: AtomicCell::new(self.recv_timeout.load()),
+			send_timeout: AtomicCell::new(self.send_timeout.load()),
+			backlog: InterruptTicketMutex::new(Vec::new()),
+		};
+
+		Ok((Arc::new(socket), endpoint))
 	}
 
 	#[allow(dead_code)]
@@ -358,41 +707,126 @@ impl ObjectInterface for Socket {
 		self.nonblocking.load(Ordering::Acquire)
 	}
 
-	fn listen(&self, _backlog: i32) -> Result<(), IoError> {
-		self.with(|socket| {
-			if !socket.is_open() {
-				self.listen.store(true, Ordering::Relaxed);
-				socket
-					.listen(self.port.load(Ordering::Acquire))
-					.map(|_| ())
-					.map_err(|_| IoError::EIO)
+	/// The backlog is limited to `MAX_BACKLOG`. Calling `listen` again
+	/// can only increase the backlog. A socket, which isn't bound to an
+	/// address, listens on every network device.
+	fn listen(&self, backlog: i32) -> Result<(), IoError> {
+		let size = usize::try_from(backlog).unwrap_or(0).clamp(1, MAX_BACKLOG);
+
+		// an unbound socket listens on an ephemeral port
+		if self.port.load(Ordering::Acquire) == 0 {
+			self.port.store(get_ephemeral_port(), Ordering::Release);
+		}
+		let endpoint = IpListenEndpoint {
+			addr: self.addr.load(),
+			port: self.port.load(Ordering::Acquire),
+		};
+
+		self.with_backlog(|nic, backlog| {
+			let template = self.handle.load();
+			if nic.get_mut_socket::<tcp::Socket<'_>>(template).is_open() {
+				return Err(IoError::EINVAL);
+			}
+
+			let ifaces = if endpoint.addr.is_some() {
+				template.iface..template.iface + 1
 			} else {
-				Err(IoError::EIO)
+				0..nic.device_count()
+			};
+			for iface in ifaces {
+				let mut len = backlog
+					.iter()
+					.filter(|handle| handle.iface == iface)
+					.count();
+				while len < size {
+					backlog.push(create_listener(nic, template, iface, endpoint)?);
+					len += 1;
+				}
 			}
+
+			Ok(())
 		})
 	}
 
-	fn setsockopt(&self, opt: SocketOption, optval: bool) -> Result<(), IoError> {
-		if opt == SocketOption::TcpNoDelay {
-			self.with(|socket| {
-				socket.set_nagle_enabled(optval);
-				if optval {
-					socket.set_ack_delay(None);
-				} else {
-					socket.set_ack_delay(Some(Duration::from_millis(10)));
-				}
-			});
-			Ok(())
-		} else {
-			Err(IoError::EINVAL)
+	fn setsockopt(&self, opt: SocketOption, optval: SocketOptionValue) -> Result<(), IoError> {
+		match (opt, optval) {
+			(SocketOption::TcpNoDelay, SocketOptionValue::Bool(nodelay)) => {
+				self.with(|socket| {
+					socket.set_nagle_enabled(!nodelay);
+					if nodelay {
+						socket.set_ack_delay(None);
+					} else {
+						socket.set_ack_delay(Some(Duration::from_millis(10)));
+					}
+				});
+				Ok(())
+			}
+			(SocketOption::KeepAlive, SocketOptionValue::Bool(keep_alive)) => {
+				self.with(|socket| {
+					socket.set_keep_alive(
+						keep_alive.then(|| Duration::from_millis(DEFAULT_KEEP_ALIVE_INTERVAL)),
+					)
+				});
+				Ok(())
+			}
+			(SocketOption::Linger, SocketOptionValue::Duration(linger)) => {
+				self.linger.store(linger);
+				Ok(())
+			}
+			(SocketOption::RecvBufferSize, SocketOptionValue::Int(size)) => {
+				let size = usize::try_from(size).map_err(|_| IoError::EINVAL)?;
+				self.resize_buffers(Some(size), None)
+			}
+			(SocketOption::SendBufferSize, SocketOptionValue::Int(size)) => {
+				let size = usize::try_from(size).map_err(|_| IoError::EINVAL)?;
+				self.resize_buffers(None, Some(size))
+			}
+			(SocketOption::RecvTimeout, SocketOptionValue::Duration(timeout)) => {
+				self.recv_timeout.store(timeout);
+				Ok(())
+			}
+			(SocketOption::SendTimeout, SocketOptionValue::Duration(timeout)) => {
+				self.send_timeout.store(timeout);
+				Ok(())
+			}
+			(SocketOption::HopLimit, SocketOptionValue::Int(hop_limit)) => {
+				// -1 restores the default value
+				let hop_limit = match hop_limit {
+					-1 => None,
+					1..=255 => Some(hop_limit as u8),
+					_ => return Err(IoError::EINVAL),
+				};
+				self.with(|socket| socket.set_hop_limit(hop_limit));
+				Ok(())
+			}
+			_ => Err(IoError::ENOPROTOOPT),
 		}
 	}
 
-	fn getsockopt(&self, opt: SocketOption) -> Result<bool, IoError> {
-		if opt == SocketOption::TcpNoDelay {
-			self.with(|socket| Ok(socket.nagle_enabled()))
-		} else {
-			Err(IoError::EINVAL)
+	fn getsockopt(&self, opt: SocketOption) -> Result<SocketOptionValue, IoError> {
+		match opt {
+			SocketOption::TcpNoDelay => Ok(SocketOptionValue::Bool(
+				self.with(|socket| !socket.nagle_enabled()),
+			)),
+			SocketOption::KeepAlive => Ok(SocketOptionValue::Bool(
+				self.with(|socket| socket.keep_alive().is_some()),
+			)),
+			SocketOption::Linger => Ok(SocketOptionValue::Duration(self.linger.load())),
+			SocketOption::RecvBufferSize => Ok(SocketOptionValue::Int(
+				self.with(|socket| socket.recv_capacity() as i32),
+			)),
+			SocketOption::SendBufferSize => Ok(SocketOptionValue::Int(
+				self.with(|socket| socket.send_capacity() as i32),
+			)),
+			SocketOption::RecvTimeout => Ok(SocketOptionValue::Duration(self.recv_timeout.load())),
+			SocketOption::SendTimeout => Ok(SocketOptionValue::Duration(self.send_timeout.load())),
+			SocketOption::Error => Ok(SocketOptionValue::Int(self.take_error())),
+			SocketOption::HopLimit => Ok(SocketOptionValue::Int(self.with(|socket| {
+				socket
+					.hop_limit()
+					.map_or(i32::from(DEFAULT_HOP_LIMIT), i32::from)
+			}))),
+			SocketOption::RecvPacketInfo => Err(IoError::ENOPROTOOPT),
 		}
 	}
 
@@ -427,31 +861,57 @@ impl Clone for Socket {
 		let mut guard = NIC.lock();
 
 		let handle = if let NetworkState::Initialized(nic) = guard.deref_mut() {
-			nic.create_tcp_handle().unwrap()
+			let handle = nic.create_tcp_handle().unwrap();
+			match self
+				.addr
+				.load()
+				.and_then(|addr| nic.device_of_address(addr))
+			{
+				Some(iface) => nic.move_socket(handle, iface),
+				None => handle,
+			}
 		} else {
 			panic!("Unable to create handle");
 		};
 
-		drop(guard);
-		let port = self.port.load(Ordering::Acquire);
-		let obj = Self {
-			handle,
-			port: AtomicU16::new(port),
+		Self {
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
+			addr: AtomicCell::new(self.addr.load()),
+			port: AtomicU16::new(self.port.load(Ordering::Acquire)),
 			nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::Acquire)),
 			listen: AtomicBool::new(false),
-		};
-
-		if port > 0 {
-			let _ = obj.listen(1024);
+			connecting: AtomicBool::new(false),
+			linger: AtomicCell::new(self.linger.load()),
+			recv_timeout: AtomicCell::new(self.recv_timeout.load()),
+			send_timeout: AtomicCell::new(self.send_timeout.load()),
+			backlog: InterruptTicketMutex::new(Vec::new()),
 		}
-
-		obj
 	}
 }
 
 impl Drop for Socket {
 	fn drop(&mut self) {
-		let _ = block_on(self.async_close(), None);
-		NIC.lock().as_nic_mut().unwrap().destroy_socket(self.handle);
+		match self.linger.load() {
+			// a linger time of zero resets the connection
+			Some(linger) if linger.is_zero() => self.with(|socket| socket.abort()),
+			linger => {
+				let _ = block_on(self.async_close(), linger);
+			}
+		}
+
+		// pending connections, which aren't accepted, are reset
+		let backlog = core::mem::take(&mut *self.backlog.lock());
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+		for handle in backlog.iter() {
+			nic.get_mut_socket::<tcp::Socket<'_>>(*handle).abort();
+		}
+		nic.poll_common(now());
+		for handle in backlog {
+			nic.destroy_socket(handle);
+		}
+		nic.destroy_socket(self.handle.load());
 	}
 }
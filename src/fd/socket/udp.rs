@@ -1,19 +1,44 @@
 use alloc::boxed::Box;
-use core::future;
+use alloc::vec::Vec;
 use core::ops::DerefMut;
 use core::sync::atomic::{AtomicBool, Ordering};
 use core::task::Poll;
+use core::{future, iter};
 
 use async_trait::async_trait;
 use crossbeam_utils::atomic::AtomicCell;
+use hermit_sync::InterruptTicketMutex;
 use smoltcp::socket::udp;
-use smoltcp::socket::udp::UdpMetadata;
-use smoltcp::time::Duration;
-use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
+use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
-use crate::executor::network::{now, Handle, NetworkState, NIC};
-use crate::executor::{block_on, poll_on};
-use crate::fd::{IoCtl, IoError, ObjectInterface, PollEvent};
+use crate::executor::block_on;
+use crate::executor::network::{now, Handle, NetworkInterface, NetworkState, NIC};
+use crate::fd::socket::{
+	scatter, WakerFanout, DEFAULT_HOP_LIMIT, MAX_BUFFER_SIZE, MIN_BUFFER_SIZE,
+};
+use crate::fd::{
+	IoCtl, IoError, MsgFlags, ObjectInterface, PollEvent, RecvMeta, SocketOption, SocketOptionValue,
+};
+
+/// Assumed average size of a datagram, which determines the number of
+/// datagrams, which a buffer can hold
+const AVERAGE_DATAGRAM_SIZE: usize = 512;
+
+/// Creates a buffer of `size` bytes, which holds a datagram per
+/// [`AVERAGE_DATAGRAM_SIZE`] bytes
+pub(crate) fn packet_buffer(size: usize) -> udp::PacketBuffer<'static> {
+	let count = (size / AVERAGE_DATAGRAM_SIZE).max(1);
+	udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; count], vec![0; size])
+}
+
+/// Creates a smoltcp socket with the given buffer sizes, which inherits
+/// the options of `template`
+fn socket_like(template: &udp::Socket<'_>, rx_size: usize, tx_size: usize) -> udp::Socket<'static> {
+	let mut socket = udp::Socket::new(packet_buffer(rx_size), packet_buffer(tx_size));
+	socket.set_hop_limit(template.hop_limit());
+
+	socket
+}
 
 #[derive(Debug)]
 pub struct IPv4;
@@ -23,94 +48,148 @@ pub struct IPv6;
 
 #[derive(Debug)]
 pub struct Socket {
-	handle: Handle,
+	/// The handle changes, if the socket moves to another network device
+	handle: AtomicCell<Handle>,
+	/// Tasks, which wait for received data
+	recv_wakers: WakerFanout,
+	/// Tasks, which wait for space in the send buffer
+	send_wakers: WakerFanout,
 	nonblocking: AtomicBool,
 	endpoint: AtomicCell<Option<IpEndpoint>>,
+	recv_timeout: AtomicCell<Option<core::time::Duration>>,
+	send_timeout: AtomicCell<Option<core::time::Duration>>,
+	/// Report the destination address of received datagrams
+	pktinfo: AtomicBool,
+	/// Sockets on the other network devices of a socket, which is bound to
+	/// the wildcard address. They receive the datagrams of their device.
+	wildcard: InterruptTicketMutex<Vec<Handle>>,
 }
 
 impl Socket {
 	pub fn new(handle: Handle) -> Self {
 		Self {
-			handle,
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
 			nonblocking: AtomicBool::new(false),
 			endpoint: AtomicCell::new(None),
+			recv_timeout: AtomicCell::new(None),
+			send_timeout: AtomicCell::new(None),
+			pktinfo: AtomicBool::new(false),
+			wildcard: InterruptTicketMutex::new(Vec::new()),
 		}
 	}
 
 	fn with<R>(&self, f: impl FnOnce(&mut udp::Socket<'_>) -> R) -> R {
 		let mut guard = NIC.lock();
 		let nic = guard.as_nic_mut().unwrap();
-		let result = f(nic.get_mut_socket::<udp::Socket<'_>>(self.handle));
+		let result = f(nic.get_mut_socket::<udp::Socket<'_>>(self.handle.load()));
 		nic.poll_common(now());
 
 		result
 	}
 
-	async fn async_close(&self) -> Result<(), IoError> {
-		future::poll_fn(|_cx| {
-			self.with(|socket| {
-				socket.close();
-				Poll::Ready(Ok(()))
-			})
-		})
-		.await
+	fn with_nic<R>(&self, f: impl FnOnce(&mut NetworkInterface<'_>, Handle) -> R) -> R {
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+		let result = f(nic, self.handle.load());
+		nic.poll_common(now());
+
+		result
 	}
 
-	async fn async_recvfrom(&self, buffer: &mut [u8]) -> Result<(usize, IpEndpoint), IoError> {
-		future::poll_fn(|cx| {
-			self.with(|socket| {
-				if socket.is_open() {
-					if socket.can_recv() {
-						match socket.recv_slice(buffer) {
-							Ok((len, meta)) => match self.endpoint.load() {
-								Some(ep) => {
-									if meta.endpoint == ep {
-										Poll::Ready(Ok((len, meta.endpoint)))
-									} else {
-										buffer[..len].iter_mut().for_each(|x| *x = 0);
-										socket.register_recv_waker(cx.waker());
-										Poll::Pending
-									}
-								}
-								None => Poll::Ready(Ok((len, meta.endpoint))),
-							},
-							_ => Poll::Ready(Err(IoError::EIO)),
-						}
-					} else {
-						socket.register_recv_waker(cx.waker());
-						Poll::Pending
-					}
-				} else {
-					Poll::Ready(Err(IoError::EIO))
-				}
-			})
+	/// Calls `f` for the socket on every network device, which is used
+	fn for_each(
+		&self,
+		mut f: impl FnMut(&mut udp::Socket<'_>) -> Result<(), IoError>,
+	) -> Result<(), IoError> {
+		self.with_nic(|nic, handle| {
+			let wildcard = self.wildcard.lock();
+			for handle in iter::once(handle).chain(wildcard.iter().copied()) {
+				f(nic.get_mut_socket::<udp::Socket<'_>>(handle))?;
+			}
+
+			Ok(())
 		})
-		.await
 	}
 
-	async fn async_write_with_meta(
+	/// Returns the socket, which sends to `dst` by the device of `local_addr`
+	/// or by the device, which routes `dst`. A socket, which is bound to an
+	/// address, stays on the device of this address. A socket, which is bound
+	/// to the wildcard address, sends by its socket on the selected device.
+	/// An unbound socket moves to the selected device.
+	fn route(
 		&self,
-		buffer: &[u8],
-		meta: &UdpMetadata,
-	) -> Result<usize, IoError> {
-		future::poll_fn(|cx| {
-			self.with(|socket| {
-				if socket.is_open() {
-					if socket.can_send() {
-						Poll::Ready(
-							socket
-								.send_slice(buffer, *meta)
-								.map(|_| buffer.len())
-								.map_err(|_| IoError::EIO),
-						)
-					} else {
-						socket.register_recv_waker(cx.waker());
-						Poll::Pending
-					}
-				} else {
-					Poll::Ready(Err(IoError::EIO))
-				}
-			})
+		nic: &mut NetworkInterface<'_>,
+		dst: IpAddress,
+		local_addr: Option<IpAddress>,
+	) -> Handle {
+		let handle = self.handle.load();
+		if nic
+			.get_mut_socket::<udp::Socket<'_>>(handle)
+			.endpoint()
+			.addr
+			.is_some()
+		{
+			return handle;
+		}
+
+		let iface = match local_addr {
+			Some(addr) => nic.device_of_address(addr),
+			None => nic.route(dst),
+		};
+		let Some(iface) = iface else {
+			return handle;
+		};
+
+		let wildcard = self.wildcard.lock();
+		if wildcard.is_empty() {
+			let handle = nic.move_socket(handle, iface);
+			self.handle.store(handle);
+			handle
+		} else {
+			wildcard
+				.iter()
+				.copied()
+				.find(|handle| handle.iface == iface)
+				.unwrap_or(handle)
+		}
+	}
+
+	/// Replaces the smoltcp socket by one with other buffer sizes and binds
+	/// it to the same endpoint. Queued datagrams are dropped.
+	fn resize_buffers(
+		&self,
+		rx_size: Option<usize>,
+		tx_size: Option<usize>,
+	) -> Result<(), IoError> {
+		self.for_each(|socket| {
+			let rx_size = rx_size
+				.unwrap_or(socket.payload_recv_capacity())
+				.clamp(MIN_BUFFER_SIZE, MAX_BUFFER_SIZE);
+			let tx_size = tx_size
+				.unwrap_or(socket.payload_send_capacity())
+				.clamp(MIN_BUFFER_SIZE, MAX_BUFFER_SIZE);
+
+			let mut resized = socket_like(socket, rx_size, tx_size);
+			if socket.is_open() {
+				resized
+					.bind(socket.endpoint())
+					.map_err(|_| IoError::EADDRINUSE)?;
+			}
+			*socket = resized;
+
+			Ok(())
+		})
+	}
+
+	async fn async_close(&self) -> Result<(), IoError> {
+		future::poll_fn(|_cx| {
+			let result = self.for_each(|socket| {
+				socket.close();
+				Ok(())
+			});
+			Poll::Ready(result)
 		})
 		.await
 	}
@@ -120,7 +199,11 @@ impl Socket {
 impl ObjectInterface for Socket {
 	async fn poll(&self, event: PollEvent) -> Result<PollEvent, IoError> {
 		future::poll_fn(|cx| {
-			self.with(|socket| {
+			self.with_nic(|nic, handle| {
+				let wildcard = self.wildcard.lock();
+				let handles = || iter::once(handle).chain(wildcard.iter().copied());
+
+				let socket = nic.get_mut_socket::<udp::Socket<'_>>(handle);
 				let ret = if socket.is_open() {
 					let mut avail = PollEvent::empty();
 
@@ -130,7 +213,9 @@ impl ObjectInterface for Socket {
 						);
 					}
 
-					if socket.can_recv() {
+					// datagrams can arrive on every device of a wildcard socket
+					if handles().any(|handle| nic.get_socket::<udp::Socket<'_>>(handle).can_recv())
+					{
 						avail.insert(
 							PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND,
 						);
@@ -141,19 +226,24 @@ impl ObjectInterface for Socket {
 					PollEvent::POLLNVAL
 				};
 
-				if ret.is_empty() {
-					if event.intersects(
-						PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND,
-					) {
-						socket.register_recv_waker(cx.waker());
+				// register the waker for all requested, but unavailable events,
+				// which allows epoll to detect the next change
+				let recv_events = PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND;
+				if event.intersects(recv_events) && !ret.intersects(recv_events) {
+					for handle in handles() {
+						nic.get_mut_socket::<udp::Socket<'_>>(handle)
+							.register_recv_waker(&self.recv_wakers.register(cx.waker()));
 					}
+				}
 
-					if event.intersects(
-						PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND,
-					) {
-						socket.register_send_waker(cx.waker());
-					}
+				let send_events =
+					PollEvent::POLLOUT | PollEvent::POLLWRNORM | PollEvent::POLLWRBAND;
+				if event.intersects(send_events) && !ret.intersects(send_events) {
+					nic.get_mut_socket::<udp::Socket<'_>>(handle)
+						.register_send_waker(&self.send_wakers.register(cx.waker()));
+				}
 
+				if ret.is_empty() {
 					Poll::Pending
 				} else {
 					Poll::Ready(Ok(ret))
@@ -163,71 +253,164 @@ impl ObjectInterface for Socket {
 		.await
 	}
 
+	/// A socket, which is bound to an address, only uses the network
+	/// device of this address. A socket, which is bound to the wildcard
+	/// address, gets a socket on every network device.
 	fn bind(&self, endpoint: IpListenEndpoint) -> Result<(), IoError> {
-		self.with(|socket| socket.bind(endpoint).map_err(|_| IoError::EADDRINUSE))
+		if let Some(addr) = endpoint.addr {
+			let mut guard = NIC.lock();
+			let nic = guard.as_nic_mut().unwrap();
+			let iface = nic.device_of_address(addr).ok_or(IoError::EADDRNOTAVAIL)?;
+			self.handle
+				.store(nic.move_socket(self.handle.load(), iface));
+		}
+
+		self.with_nic(|nic, handle| {
+			let socket = nic.get_mut_socket::<udp::Socket<'_>>(handle);
+			socket.bind(endpoint).map_err(|_| IoError::EADDRINUSE)?;
+			if endpoint.addr.is_some() {
+				return Ok(());
+			}
+
+			let mut wildcard = self.wildcard.lock();
+			for iface in (0..nic.device_count()).filter(|iface| *iface != handle.iface) {
+				let template = nic.get_mut_socket::<udp::Socket<'_>>(handle);
+				let mut socket = socket_like(
+					template,
+					template.payload_recv_capacity(),
+					template.payload_send_capacity(),
+				);
+				socket.bind(endpoint).map_err(|_| IoError::EADDRINUSE)?;
+				wildcard.push(nic.add_udp_socket(iface, socket));
+			}
+
+			Ok(())
+		})
 	}
 
 	fn connect(&self, endpoint: IpEndpoint) -> Result<(), IoError> {
+		self.with_nic(|nic, _| self.route(nic, endpoint.addr, None));
 		self.endpoint.store(Some(endpoint));
 		Ok(())
 	}
 
-	fn sendto(&self, buf: &[u8], endpoint: IpEndpoint) -> Result<usize, IoError> {
-		let meta = UdpMetadata::from(endpoint);
+	async fn async_read(&self, buffer: &mut [u8]) -> Result<usize, IoError> {
+		self.async_recvmsg(&mut [buffer], MsgFlags::empty())
+			.await
+			.map(|meta| meta.len)
+	}
 
-		if self.nonblocking.load(Ordering::Acquire) {
-			poll_on(
-				self.async_write_with_meta(buf, &meta),
-				Some(Duration::ZERO.into()),
-			)
-		} else {
-			poll_on(self.async_write_with_meta(buf, &meta), None)
-		}
+	async fn async_write(&self, buf: &[u8]) -> Result<usize, IoError> {
+		self.async_sendmsg(&[buf], None, None).await
 	}
 
-	fn recvfrom(&self, buf: &mut [u8]) -> Result<(usize, IpEndpoint), IoError> {
-		if self.nonblocking.load(Ordering::Acquire) {
-			poll_on(self.async_recvfrom(buf), Some(Duration::ZERO.into())).map_err(|x| {
-				if x == IoError::ETIME {
-					IoError::EAGAIN
-				} else {
-					x
+	async fn async_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
+		self.async_recvmsg(bufs, MsgFlags::empty())
+			.await
+			.map(|meta| meta.len)
+	}
+
+	async fn async_writev(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		self.async_sendmsg(bufs, None, None).await
+	}
+
+	async fn async_recvmsg(
+		&self,
+		bufs: &mut [&mut [u8]],
+		flags: MsgFlags,
+	) -> Result<RecvMeta, IoError> {
+		future::poll_fn(|cx| {
+			self.with_nic(|nic, handle| {
+				if !nic.get_mut_socket::<udp::Socket<'_>>(handle).is_open() {
+					return Poll::Ready(Err(IoError::EIO));
+				}
+
+				// a wildcard socket receives the datagrams of every device
+				let wildcard = self.wildcard.lock();
+				let Some(handle) = iter::once(handle)
+					.chain(wildcard.iter().copied())
+					.find(|handle| nic.get_socket::<udp::Socket<'_>>(*handle).can_recv())
+				else {
+					for handle in iter::once(handle).chain(wildcard.iter().copied()) {
+						nic.get_mut_socket::<udp::Socket<'_>>(handle)
+							.register_recv_waker(&self.recv_wakers.register(cx.waker()));
+					}
+					return Poll::Pending;
+				};
+
+				let socket = nic.get_mut_socket::<udp::Socket<'_>>(handle);
+				let (data, meta) = socket.peek().map_err(|_| IoError::EIO)?;
+				let meta = *meta;
+				if self.endpoint.load().is_some_and(|ep| meta.endpoint != ep) {
+					// drop datagrams of other peers and check the next one
+					let _ = socket.recv();
+					cx.waker().wake_by_ref();
+					return Poll::Pending;
 				}
+
+				// the remainder of the datagram is discarded
+				let len = scatter(bufs, 0, data);
+				let datagram_len = data.len();
+				if !flags.contains(MsgFlags::MSG_PEEK) {
+					let _ = socket.recv();
+				}
+
+				// smoltcp doesn't report the destination address of a datagram
+				// => use the bound address or an address of the receiving device
+				let (local_addr, device) = if self.pktinfo.load(Ordering::Acquire) {
+					let bound_addr = socket.endpoint().addr;
+					(
+						bound_addr
+							.or_else(|| nic.address_of_device(handle.iface, meta.endpoint.addr)),
+						Some(handle.iface),
+					)
+				} else {
+					(None, None)
+				};
+
+				Poll::Ready(Ok(RecvMeta {
+					len,
+					datagram_len,
+					endpoint: Some(meta.endpoint),
+					local_addr,
+					device,
+				}))
 			})
-		} else {
-			match poll_on(
-				self.async_recvfrom(buf),
-				Some(Duration::from_secs(2).into()),
-			) {
-				Err(IoError::ETIME) => block_on(self.async_recvfrom(buf), None),
-				Err(x) => Err(x),
-				Ok(x) => Ok(x),
-			}
-		}
+		})
+		.await
 	}
 
-	async fn async_read(&self, buffer: &mut [u8]) -> Result<usize, IoError> {
+	async fn async_sendmsg(
+		&self,
+		bufs: &[&[u8]],
+		endpoint: Option<IpEndpoint>,
+		local_addr: Option<IpAddress>,
+	) -> Result<usize, IoError> {
+		let endpoint = endpoint
+			.or_else(|| self.endpoint.load())
+			.ok_or(IoError::EINVAL)?;
+		let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+
 		future::poll_fn(|cx| {
-			self.with(|socket| {
+			self.with_nic(|nic, _| {
+				// another task may have moved the socket in the meantime
+				// => route on every poll under the lock of the interface.
+				// smoltcp selects the source address => `local_addr` only selects the device
+				let handle = self.route(nic, endpoint.addr, local_addr);
+				let socket = nic.get_mut_socket::<udp::Socket<'_>>(handle);
 				if socket.is_open() {
-					if socket.can_recv() {
-						match socket.recv_slice(buffer) {
-							Ok((len, meta)) => match self.endpoint.load() {
-								Some(ep) => {
-									if meta.endpoint == ep {
-										Poll::Ready(Ok(len))
-									} else {
-										buffer[..len].iter_mut().for_each(|x| *x = 0);
-										socket.register_recv_waker(cx.waker());
-										Poll::Pending
-									}
-								}
-								None => Poll::Ready(Ok(len)),
-							},
-							_ => Poll::Ready(Err(IoError::EIO)),
+					if socket.can_send() {
+						// gather all buffers directly into a single datagram
+						let payload = socket.send(total, endpoint).map_err(|_| IoError::EIO)?;
+						let mut pos = 0;
+						for buf in bufs {
+							payload[pos..pos + buf.len()].copy_from_slice(buf);
+							pos += buf.len();
 						}
+
+						Poll::Ready(Ok(total))
 					} else {
-						socket.register_recv_waker(cx.waker());
+						socket.register_send_waker(&self.send_wakers.register(cx.waker()));
 						Poll::Pending
 					}
 				} else {
@@ -238,15 +421,72 @@ impl ObjectInterface for Socket {
 		.await
 	}
 
-	async fn async_write(&self, buf: &[u8]) -> Result<usize, IoError> {
-		if let Some(endpoint) = self.endpoint.load() {
-			let meta = UdpMetadata::from(endpoint);
-			self.async_write_with_meta(buf, &meta).await
-		} else {
-			Err(IoError::EINVAL)
+	fn setsockopt(&self, opt: SocketOption, optval: SocketOptionValue) -> Result<(), IoError> {
+		match (opt, optval) {
+			(SocketOption::RecvBufferSize, SocketOptionValue::Int(size)) => {
+				let size = usize::try_from(size).map_err(|_| IoError::EINVAL)?;
+				self.resize_buffers(Some(size), None)
+			}
+			(SocketOption::SendBufferSize, SocketOptionValue::Int(size)) => {
+				let size = usize::try_from(size).map_err(|_| IoError::EINVAL)?;
+				self.resize_buffers(None, Some(size))
+			}
+			(SocketOption::RecvTimeout, SocketOptionValue::Duration(timeout)) => {
+				self.recv_timeout.store(timeout);
+				Ok(())
+			}
+			(SocketOption::SendTimeout, SocketOptionValue::Duration(timeout)) => {
+				self.send_timeout.store(timeout);
+				Ok(())
+			}
+			(SocketOption::HopLimit, SocketOptionValue::Int(hop_limit)) => {
+				// -1 restores the default value
+				let hop_limit = match hop_limit {
+					-1 => None,
+					1..=255 => Some(hop_limit as u8),
+					_ => return Err(IoError::EINVAL),
+				};
+				self.for_each(|socket| {
+					socket.set_hop_limit(hop_limit);
+					Ok(())
+				})
+			}
+			(SocketOption::RecvPacketInfo, SocketOptionValue::Bool(pktinfo)) => {
+				self.pktinfo.store(pktinfo, Ordering::Release);
+				Ok(())
+			}
+			_ => Err(IoError::ENOPROTOOPT),
+		}
+	}
+
+	fn getsockopt(&self, opt: SocketOption) -> Result<SocketOptionValue, IoError> {
+		match opt {
+			SocketOption::RecvBufferSize => Ok(SocketOptionValue::Int(
+				self.with(|socket| socket.payload_recv_capacity() as i32),
+			)),
+			SocketOption::SendBufferSize => Ok(SocketOptionValue::Int(
+				self.with(|socket| socket.payload_send_capacity() as i32),
+			)),
+			SocketOption::RecvTimeout => Ok(SocketOptionValue::Duration(self.recv_timeout.load())),
+			SocketOption::SendTimeout => Ok(SocketOptionValue::Duration(self.send_timeout.load())),
+			// errors of datagram sockets are reported immediately
+			SocketOption::Error => Ok(SocketOptionValue::Int(0)),
+			SocketOption::HopLimit => Ok(SocketOptionValue::Int(self.with(|socket| {
+				socket
+					.hop_limit()
+					.map_or(i32::from(DEFAULT_HOP_LIMIT), i32::from)
+			}))),
+			SocketOption::RecvPacketInfo => Ok(SocketOptionValue::Bool(
+				self.pktinfo.load(Ordering::Acquire),
+			)),
+			_ => Err(IoError::ENOPROTOOPT),
 		}
 	}
 
+	fn is_nonblocking(&self) -> bool {
+		self.nonblocking.load(Ordering::Acquire)
+	}
+
 	fn ioctl(&self, cmd: IoCtl, value: bool) -> Result<(), IoError> {
 		if cmd == IoCtl::NonBlocking {
 			if value {
@@ -275,9 +515,15 @@ impl Clone for Socket {
 		};
 
 		Self {
-			handle,
+			handle: AtomicCell::new(handle),
+			recv_wakers: WakerFanout::default(),
+			send_wakers: WakerFanout::default(),
 			nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::Acquire)),
 			endpoint: AtomicCell::new(self.endpoint.load()),
+			recv_timeout: AtomicCell::new(self.recv_timeout.load()),
+			send_timeout: AtomicCell::new(self.send_timeout.load()),
+			pktinfo: AtomicBool::new(self.pktinfo.load(Ordering::Acquire)),
+			wildcard: InterruptTicketMutex::new(Vec::new()),
 		}
 	}
 }
@@ -285,5 +531,12 @@ impl Clone for Socket {
 impl Drop for Socket {
 	fn drop(&mut self) {
 		let _ = block_on(self.async_close(), None);
+
+		let wildcard = core::mem::take(&mut *self.wildcard.lock());
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().unwrap();
+		for handle in wildcard {
+			nic.destroy_socket(handle);
+		}
 	}
 }
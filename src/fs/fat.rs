@@ -0,0 +1,654 @@
+//! Implements a read-only FAT32 file system on top of a block device.
+//!
+//! The file system is either expected at the beginning of the device
+//! or within the first FAT32 partition of a MBR partition table.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use async_lock::Mutex;
+use async_trait::async_trait;
+
+use crate::drivers::block::{BlockDevice, SECTOR_SIZE};
+#[cfg(not(feature = "pci"))]
+use crate::drivers::mmio::get_block_driver;
+#[cfg(feature = "pci")]
+use crate::drivers::pci::get_block_driver;
+use crate::executor::block_on;
+use crate::fd::{AccessPermission, IoError, ObjectInterface, OpenOption, PollEvent};
+use crate::fs::{self, DirectoryEntry, FileAttr, NodeKind, SeekWhence, VfsNode};
+
+const ATTR_READ_ONLY: u8 = 0x01;
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_LONG_NAME: u8 = 0x0F;
+
+const DIR_ENTRY_SIZE: usize = 32;
+const END_OF_CHAIN: u32 = 0x0FFF_FFF8;
+const CLUSTER_MASK: u32 = 0x0FFF_FFFF;
+/// Maximum number of data clusters of FAT32
+const MAX_CLUSTER_COUNT: u32 = 0x0FFF_FFF5;
+
+#[inline]
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+	u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+#[inline]
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+	u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+/// Converts a FAT timestamp to seconds since the epoch.
+///
+/// FAT does not store a time zone, the timestamp is therefore interpreted as UTC.
+fn fat_time(date: u16, time_of_day: u16) -> u64 {
+	let year = 1980 + i32::from(date >> 9);
+	let Ok(month) = time::Month::try_from(((date >> 5) & 0xF) as u8) else {
+		return 0;
+	};
+	let day = (date & 0x1F) as u8;
+	let hour = (time_of_day >> 11) as u8;
+	let minute = ((time_of_day >> 5) & 0x3F) as u8;
+	let second = ((time_of_day & 0x1F) * 2) as u8;
+
+	time::Date::from_calendar_date(year, month, day)
+		.and_then(|date| date.with_hms(hour, minute, second))
+		.map(|datetime| datetime.assume_utc().unix_timestamp() as u64)
+		.unwrap_or(0)
+}
+
+/// Layout of the file system, as described by the boot sector.
+#[derive(Debug)]
+struct FatFs {
+	/// First device sector of the file system
+	start: u64,
+	bytes_per_sector: usize,
+	sectors_per_cluster: usize,
+	/// First sector of the allocation table
+	fat_start: u64,
+	/// First sector of the data region (cluster 2)
+	data_start: u64,
+	root_cluster: u32,
+	cluster_count: u32,
+}
+
+impl FatFs {
+	/// Parses the boot sector at device sector `start`. Returns `None`,
+	/// if the sector does not describe a FAT32 file system.
+	fn parse(start: u64, boot: &[u8]) -> Option<Self> {
+		if boot[510] != 0x55 || boot[511] != 0xAA {
+			return None;
+		}
+
+		let bytes_per_sector = usize::from(read_u16(boot, 0x0B));
+		let sectors_per_cluster = usize::from(boot[0x0D]);
+		let reserved_sectors = u64::from(read_u16(boot, 0x0E));
+		let num_fats = u64::from(boot[0x10]);
+		let root_entry_count = read_u16(boot, 0x11);
+		let total_sectors_16 = read_u16(boot, 0x13);
+		let fat_size_16 = read_u16(boot, 0x16);
+		let total_sectors_32 = read_u32(boot, 0x20);
+		let fat_size_32 = u64::from(read_u32(boot, 0x24));
+		let root_cluster = read_u32(boot, 0x2C);
+
+		if !bytes_per_sector.is_power_of_two()
+			|| !(SECTOR_SIZE..=4096).contains(&bytes_per_sector)
+			|| !sectors_per_cluster.is_power_of_two()
+			|| num_fats == 0
+		{
+			return None;
+		}
+
+		// FAT12 and FAT16 use a fixed root directory and a 16-bit FAT size
+		if root_entry_count != 0 || fat_size_16 != 0 || total_sectors_16 != 0 {
+			return None;
+		}
+
+		let data_start = reserved_sectors + num_fats * fat_size_32;
+		let data_sectors = u64::from(total_sectors_32).checked_sub(data_start)?;
+		// a corrupt boot sector may describe more clusters than FAT32 can address
+		let cluster_count = u32::try_from(data_sectors / sectors_per_cluster as u64)
+			.ok()
+			.filter(|count| *count < MAX_CLUSTER_COUNT)?;
+
+		let fs = Self {
+			start,
+			bytes_per_sector,
+			sectors_per_cluster,
+			fat_start: reserved_sectors,
+			data_start,
+			root_cluster,
+			cluster_count,
+		};
+		fs.is_cluster(root_cluster).then_some(fs)
+	}
+
+	/// Searches the device for a FAT32 file system.
+	fn probe() -> Result<Self, IoError> {
+		let mut sector = vec![0u8; SECTOR_SIZE];
+		read_device(0, &mut sector)?;
+
+		if let Some(fs) = Self::parse(0, &sector) {
+			return Ok(fs);
+		}
+
+		// Check the primary partitions of a MBR partition table
+		if sector[510] == 0x55 && sector[511] == 0xAA {
+			let partitions: Vec<u64> = (0..4)
+				.map(|i| 0x1BE + 16 * i)
+				.filter(|entry| matches!(sector[entry + 4], 0x0B | 0x0C))
+				.map(|entry| u64::from(read_u32(&sector, entry + 8)))
+				.collect();
+
+			for start in partitions {
+				read_device(start, &mut sector)?;
+				if let Some(fs) = Self::parse(start, &sector) {
+					return Ok(fs);
+				}
+			}
+		}
+
+		Err(IoError::EINVAL)
+	}
+
+	/// Returns true, if `cluster` is a cluster of the data region.
+	fn is_cluster(&self, cluster: u32) -> bool {
+		cluster >= 2 && cluster - 2 < self.cluster_count
+	}
+
+	fn cluster_size(&self) -> usize {
+		self.bytes_per_sector * self.sectors_per_cluster
+	}
+
+	/// Reads file system sectors starting at `sector` into `buf`.
+	fn read_sectors(&self, sector: u64, buf: &mut [u8]) -> Result<(), IoError> {
+		let factor = (self.bytes_per_sector / SECTOR_SIZE) as u64;
+		read_device(self.start + sector * factor, buf)
+	}
+
+	fn read_cluster(&self, cluster: u32, buf: &mut [u8]) -> Result<(), IoError> {
+		if !self.is_cluster(cluster) {
+			return Err(IoError::EIO);
+		}
+
+		let sector = self.data_start + u64::from(cluster - 2) * self.sectors_per_cluster as u64;
+		self.read_sectors(sector, buf)
+	}
+
+	/// Follows the allocation table and returns all clusters of a chain.
+	fn cluster_chain(&self, first: u32) -> Result<Vec<u32>, IoError> {
+		let mut chain = Vec::new();
+		let mut buf = vec![0u8; self.bytes_per_sector];
+		let mut cached_sector = None;
+		let mut cluster = first;
+
+		// empty files have no clusters
+		while cluster != 0 && cluster < END_OF_CHAIN {
+			if !self.is_cluster(cluster) || chain.len() >= self.cluster_count as usize {
+				return Err(IoError::EIO);
+			}
+			chain.push(cluster);
+
+			let offset = cluster as usize * 4;
+			let sector = self.fat_start + (offset / self.bytes_per_sector) as u64;
+			if cached_sector != Some(sector) {
+				self.read_sectors(sector, &mut buf)?;
+				cached_sector = Some(sector);
+			}
+			cluster = read_u32(&buf, offset % self.bytes_per_sector) & CLUSTER_MASK;
+		}
+
+		Ok(chain)
+	}
+
+	/// Returns all entries of the directory starting at `cluster`.
+	fn read_dir(&self, cluster: u32) -> Result<Vec<FatEntry>, IoError> {
+		let mut entries = Vec::new();
+		let mut lfn: Vec<u16> = Vec::new();
+		let mut buf = vec![0u8; self.cluster_size()];
+
+		for cluster in self.cluster_chain(cluster)? {
+			self.read_cluster(cluster, &mut buf)?;
+
+			for raw in buf.chunks_exact(DIR_ENTRY_SIZE) {
+				match raw[0] {
+					// end of directory
+					0x00 => return Ok(entries),
+					// deleted entry
+					0xE5 => {
+						lfn.clear();
+						continue;
+					}
+					_ => {}
+				}
+
+				let attr = raw[11];
+				if attr & ATTR_LONG_NAME == ATTR_LONG_NAME {
+					// long file names are stored in reverse order in front of the short entry
+					if raw[0] & 0x40 != 0 {
+						lfn.clear();
+					}
+					let mut part: Vec<u16> = [1..11, 14..26, 28..32]
+						.into_iter()
+						.flat_map(|range| raw[range].chunks_exact(2))
+						.map(|c| u16::from_le_bytes([c[0], c[1]]))
+						.take_while(|c| *c != 0x0000)
+						.filter(|c| *c != 0xFFFF)
+						.collect();
+					part.append(&mut lfn);
+					lfn = part;
+					continue;
+				}
+
+				if attr & ATTR_VOLUME_ID != 0 {
+					lfn.clear();
+					continue;
+				}
+
+				let name = if lfn.is_empty() {
+					short_name(raw)
+				} else {
+					char::decode_utf16(lfn.iter().copied())
+						.map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+						.collect()
+				};
+				lfn.clear();
+
+				if name == "." || name == ".." {
+					continue;
+				}
+
+				entries.push(FatEntry {
+					name,
+					attr,
+					cluster: (u32::from(read_u16(raw, 20)) << 16) | u32::from(read_u16(raw, 26)),
+					size: read_u32(raw, 28),
+					ctime: fat_time(read_u16(raw, 16), read_u16(raw, 14)),
+					atime: fat_time(read_u16(raw, 18), 0),
+					mtime: fat_time(read_u16(raw, 24), read_u16(raw, 22)),
+				});
+			}
+		}
+
+		Ok(entries)
+	}
+
+	/// Returns the entry of the root directory.
+	fn root(&self) -> FatEntry {
+		FatEntry {
+			name: String::new(),
+			attr: ATTR_DIRECTORY,
+			cluster: self.root_cluster,
+			size: 0,
+			ctime: 0,
+			atime: 0,
+			mtime: 0,
+		}
+	}
+
+	/// Walks along the path given by `components` and returns the found entry.
+	fn lookup(&self, components: &mut Vec<&str>) -> Result<FatEntry, IoError> {
+		let mut entry = self.root();
+
+		while let Some(component) = components.pop() {
+			if component.is_empty() || component == "." {
+				continue;
+			}
+
+			if !entry.is_dir() {
+				return Err(IoError::ENOTDIR);
+			}
+
+			// FAT is case-insensitive
+			entry = self
+				.read_dir(entry.cluster)?
+				.into_iter()
+				.find(|e| e.name.eq_ignore_ascii_case(component))
+				.ok_or(IoError::ENOENT)?;
+		}
+
+		Ok(entry)
+	}
+}
+
+/// Reads device sectors starting at `sector` into `buf`.
+fn read_device(sector: u64, buf: &mut [u8]) -> Result<(), IoError> {
+	get_block_driver()
+		.ok_or(IoError::ENOENT)?
+		.lock()
+		.read(sector, buf)
+}
+
+/// Builds the 8.3 name of a directory entry.
+fn short_name(raw: &[u8]) -> String {
+	// Windows NT stores the case of the base name and the extension in the reserved byte
+	let lower_base = raw[12] & 0x08 != 0;
+	let lower_ext = raw[12] & 0x10 != 0;
+
+	let convert = |bytes: &[u8], lower: bool| -> String {
+		bytes
+			.iter()
+			.map(|b| if lower { b.to_ascii_lowercase() } else { *b })
+			.map(char::from)
+			.collect::<String>()
+			.trim_end()
+			.into()
+	};
+
+	let mut base = raw[0..8].to_vec();
+	// 0x05 is used as replacement for a leading 0xE5
+	if base[0] == 0x05 {
+		base[0] = 0xE5;
+	}
+
+	let mut name = convert(&base, lower_base);
+	let ext = convert(&raw[8..11], lower_ext);
+	if !ext.is_empty() {
+		name.push('.');
+		name.push_str(&ext);
+	}
+
+	name
+}
+
+#[derive(Debug, Clone)]
+struct FatEntry {
+	name: String,
+	attr: u8,
+	cluster: u32,
+	size: u32,
+	ctime: u64,
+	atime: u64,
+	mtime: u64,
+}
+
+impl FatEntry {
+	fn is_dir(&self) -> bool {
+		self.attr & ATTR_DIRECTORY != 0
+	}
+
+	fn attributes(&self, fs: &FatFs) -> FileAttr {
+		let mode = if self.is_dir() {
+			AccessPermission::S_IFDIR | AccessPermission::from_bits(0o555).unwrap()
+		} else if self.attr & ATTR_READ_ONLY != 0 {
+			AccessPermission::S_IFREG | AccessPermission::from_bits(0o444).unwrap()
+		} else {
+			AccessPermission::S_IFREG | AccessPermission::from_bits(0o644).unwrap()
+		};
+
+		FileAttr {
+			st_ino: self.cluster.into(),
+			st_nlink: 1,
+			st_mode: mode,
+			st_size: self.size.into(),
+			st_blksize: fs.cluster_size().try_into().unwrap(),
+			st_blocks: u64::from(self.size)
+				.div_ceil(SECTOR_SIZE as u64)
+				.try_into()
+				.unwrap(),
+			st_atime: self.atime,
+			st_mtime: self.mtime,
+			st_ctime: self.ctime,
+			..Default::default()
+		}
+	}
+}
+
+#[derive(Debug)]
+struct FatFileInner {
+	fs: Arc<FatFs>,
+	clusters: Vec<u32>,
+	attr: FileAttr,
+}
+
+impl FatFileInner {
+	/// Reads the file content at offset `pos` into `buf`.
+	fn read_at(&self, pos: usize, buf: &mut [u8]) -> Result<usize, IoError> {
+		let size = self.attr.st_size as usize;
+		if pos >= size {
+			return Ok(0);
+		}
+
+		let cluster_size = self.fs.cluster_size();
+		let len = buf.len().min(size - pos);
+		let mut tmp = Vec::new();
+		let mut done = 0;
+
+		while done < len {
+			let offset = (pos + done) % cluster_size;
+			let cluster = *self
+				.clusters
+				.get((pos + done) / cluster_size)
+				.ok_or(IoError::EIO)?;
+			let n = (cluster_size - offset).min(len - done);
+
+			if n == cluster_size {
+				self.fs
+					.read_cluster(cluster, &mut buf[done..done + cluster_size])?;
+			} else {
+				tmp.resize(cluster_size, 0);
+				self.fs.read_cluster(cluster, &mut tmp)?;
+				buf[done..done + n].copy_from_slice(&tmp[offset..offset + n]);
+			}
+
+			done += n;
+		}
+
+		Ok(len)
+	}
+}
+
+#[derive(Debug, Clone)]
+struct FatFileInterface {
+	/// Position within the file
+	pos: Arc<Mutex<usize>>,
+	inner: Arc<FatFileInner>,
+}
+
+impl FatFileInterface {
+	pub fn new(inner: Arc<FatFileInner>) -> Self {
+		Self {
+			pos: Arc::new(Mutex::new(0)),
+			inner,
+		}
+	}
+}
+
+#[async_trait]
+impl ObjectInterface for FatFileInterface {
+	async fn poll(&self, event: PollEvent) -> Result<PollEvent, IoError> {
+		let len = self.inner.attr.st_size as usize;
+		let pos = *self.pos.lock().await;
+
+		let ret = if pos < len {
+			event.intersection(PollEvent::POLLIN | PollEvent::POLLRDNORM | PollEvent::POLLRDBAND)
+		} else {
+			PollEvent::empty()
+		};
+
+		Ok(ret)
+	}
+
+	async fn async_read(&self, buf: &mut [u8]) -> Result<usize, IoError> {
+		let mut pos_guard = self.pos.lock().await;
+		let len = self.inner.read_at(*pos_guard, buf)?;
+		*pos_guard += len;
+
+		Ok(len)
+	}
+
+	async fn async_write(&self, _buf: &[u8]) -> Result<usize, IoError> {
+		Err(IoError::EBADF)
+	}
+
+	fn lseek(&self, offset: isize, whence: SeekWhence) -> Result<isize, IoError> {
+		block_on(
+			async {
+				let mut pos_guard = self.pos.lock().await;
+				let base = match whence {
+					SeekWhence::Set => 0,
+					SeekWhence::Cur => *pos_guard as isize,
+					SeekWhence::End => self.inner.attr.st_size as isize,
+					_ => return Err(IoError::EINVAL),
+				};
+
+				let pos = base.checked_add(offset).ok_or(IoError::EOVERFLOW)?;
+				if pos < 0 {
+					return Err(IoError::EINVAL);
+				}
+				*pos_guard = pos as usize;
+
+				Ok(pos)
+			},
+			None,
+		)
+	}
+
+	fn fstat(&self, stat: &mut FileAttr) -> Result<(), IoError> {
+		*stat = self.inner.attr;
+		Ok(())
+	}
+}
+
+/// Root directory of a mounted FAT32 file system.
+#[derive(Debug)]
+pub(crate) struct FatDirectory {
+	fs: Arc<FatFs>,
+}
+
+impl FatDirectory {
+	/// Probes the block device for a FAT32 file system.
+	pub fn new() -> Result<Self, IoError> {
+		Ok(Self {
+			fs: Arc::new(FatFs::probe()?),
+		})
+	}
+}
+
+impl VfsNode for FatDirectory {
+	fn get_kind(&self) -> NodeKind {
+		NodeKind::Directory
+	}
+
+	fn get_file_attributes(&self) -> Result<FileAttr, IoError> {
+		Ok(self.fs.root().attributes(&self.fs))
+	}
+
+	fn traverse_mkdir(
+		&self,
+		_components: &mut Vec<&str>,
+		_mode: AccessPermission,
+	) -> Result<(), IoError> {
+		Err(IoError::EROFS)
+	}
+
+	fn traverse_rmdir(&self, _components: &mut Vec<&str>) -> Result<(), IoError> {
+		Err(IoError::EROFS)
+	}
+
+	fn traverse_unlink(&self, _components: &mut Vec<&str>) -> Result<(), IoError> {
+		Err(IoError::EROFS)
+	}
+
+	fn traverse_rename(&self, _from: &mut Vec<&str>, _to: &mut Vec<&str>) -> Result<(), IoError> {
+		Err(IoError::EROFS)
+	}
+
+	fn traverse_link(&self, _from: &mut Vec<&str>, _to: &mut Vec<&str>) -> Result<(), IoError> {
+		Err(IoError::EROFS)
+	}
+
+	fn traverse_symlink(&self, _target: &str, _components: &mut Vec<&str>) -> Result<(), IoError> {
+		Err(IoError::EROFS)
+	}
+
+	fn traverse_readlink(&self, components: &mut Vec<&str>) -> Result<String, IoError> {
+		// FAT does not support symbolic links
+		self.fs.lookup(components)?;
+		Err(IoError::EINVAL)
+	}
+
+	fn traverse_readdir(&self, components: &mut Vec<&str>) -> Result<Vec<DirectoryEntry>, IoError> {
+		let entry = self.fs.lookup(components)?;
+		if !entry.is_dir() {
+			return Err(IoError::ENOTDIR);
+		}
+
+		Ok(self
+			.fs
+			.read_dir(entry.cluster)?
+			.into_iter()
+			.map(|e| DirectoryEntry::new(e.name))
+			.collect())
+	}
+
+	fn traverse_lstat(&self, components: &mut Vec<&str>) -> Result<FileAttr, IoError> {
+		// FAT does not support symbolic links
+		self.traverse_stat(components)
+	}
+
+	fn traverse_stat(&self, components: &mut Vec<&str>) -> Result<FileAttr, IoError> {
+		Ok(self.fs.lookup(components)?.attributes(&self.fs))
+	}
+
+	fn traverse_open(
+		&self,
+		components: &mut Vec<&str>,
+		opt: OpenOption,
+		_mode: AccessPermission,
+	) -> Result<Arc<dyn ObjectInterface>, IoError> {
+		if opt.intersects(
+			OpenOption::O_WRONLY
+				| OpenOption::O_RDWR
+				| OpenOption::O_CREAT
+				| OpenOption::O_TRUNC
+				| OpenOption::O_APPEND,
+		) {
+			return Err(IoError::EROFS);
+		}
+
+		let entry = self.fs.lookup(components)?;
+		if entry.is_dir() {
+			return Err(IoError::EISDIR);
+		}
+
+		let inner = FatFileInner {
+			clusters: self.fs.cluster_chain(entry.cluster)?,
+			attr: entry.attributes(&self.fs),
+			fs: self.fs.clone(),
+		};
+
+		Ok(Arc::new(FatFileInterface::new(Arc::new(inner))))
+	}
+}
+
+/// Mounts the FAT32 file system of the block device at `mount_point`
+fn mount(mount_point: &str) -> Result<(), IoError> {
+	let dir = FatDirectory::new().inspect_err(|_| {
+		warn!("Block device does not contain a FAT32 file system");
+	})?;
+
+	info!("Mounting FAT32 file system at {}", mount_point);
+	fs::FILESYSTEM
+		.get()
+		.unwrap()
+		.mount(mount_point, Box::new(dir))
+}
+
+pub(crate) fn init() {
+	if get_block_driver().is_none() {
+		return;
+	}
+
+	info!("Try to mount FAT32 file system from block device");
+	let mount_point = hermit_var_or!("HERMIT_BLK_MOUNT", "/data").to_string();
+	if let Err(err) = mount(&mount_point) {
+		error!(
+			"Unable to mount FAT32 file system at {}: {:?}",
+			mount_point, err
+		);
+	}
+}
@@ -1,31 +1,41 @@
 use alloc::alloc::{alloc, Layout};
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::ffi::CStr;
-use core::future;
 use core::mem::MaybeUninit;
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use core::task::Poll;
+use core::time::Duration;
+use core::{future, ptr};
 
+use align_address::Align;
 use async_lock::Mutex;
 use async_trait::async_trait;
+use hermit_sync::InterruptTicketMutex;
 
 use crate::alloc::string::ToString;
 #[cfg(not(feature = "pci"))]
 use crate::arch::kernel::mmio::get_filesystem_driver;
+use crate::arch::kernel::systemtime::now_micros;
+use crate::arch::mm::paging::{BasePageSize, PageSize};
+use crate::arch::mm::PhysAddr;
+use crate::drivers::fs::virtio_fs::DaxWindow;
 #[cfg(feature = "pci")]
 use crate::drivers::pci::get_filesystem_driver;
 use crate::drivers::virtio::virtqueue::error::VirtqError;
 use crate::drivers::virtio::virtqueue::AsSliceU8;
-use crate::executor::block_on;
+use crate::executor::{self, block_on};
+use crate::fd::lock::{FileLock, LockKind, LockTable, LockType};
 use crate::fd::{IoError, PollEvent};
 use crate::fs::{
 	self, fuse_abi, AccessPermission, DirectoryEntry, FileAttr, NodeKind, ObjectInterface,
-	OpenOption, SeekWhence, VfsNode,
+	OpenOption, Resolution, SeekWhence, VfsNode, Walk,
 };
+use crate::mm::mmap::{MappedObject, MappedPage};
 
 // response out layout eg @ https://github.com/zargony/fuse-rs/blob/bf6d1cf03f3277e35b580f3c7b9999255d72ecf3/src/ll/request.rs#L44
 // op in/out sizes/layout: https://github.com/hanwen/go-fuse/blob/204b45dba899dfa147235c255908236d5fde2d32/fuse/opcode.go#L439
@@ -34,6 +44,13 @@ use crate::fs::{
 const MAX_READ_LEN: usize = 1024 * 64;
 const MAX_WRITE_LEN: usize = 1024 * 64;
 
+/// Delays in microseconds, after which a conflicting lock is requested again
+const MIN_LOCK_DELAY: u64 = 10_000;
+const MAX_LOCK_DELAY: u64 = 500_000;
+
+/// Maximum number of lookups, which are cached for the resolution of links
+const MAX_LINK_CACHE_ENTRIES: usize = 1024;
+
 const U64_SIZE: usize = ::core::mem::size_of::<u64>();
 
 const S_IFLNK: u32 = 40960;
@@ -47,10 +64,14 @@ pub(crate) trait FuseInterface {
 	) -> Result<(), VirtqError>;
 
 	fn get_mount_point(&self) -> String;
+
+	/// Returns the window, in which the host maps files, if the device offers one
+	fn get_dax_window(&mut self) -> Option<&mut DaxWindow>;
 }
 
 pub(crate) mod ops {
 	use alloc::boxed::Box;
+	use alloc::vec::Vec;
 	use core::ffi::CStr;
 	use core::mem::MaybeUninit;
 
@@ -86,7 +107,7 @@ pub(crate) mod ops {
 					major: 7,
 					minor: 31,
 					max_readahead: 0,
-					flags: 0,
+					flags: fuse_abi::INIT_MAP_ALIGNMENT,
 				},
 			);
 			let rsp = unsafe { Box::new_uninit().assume_init() };
@@ -339,6 +360,137 @@ pub(crate) mod ops {
 		}
 	}
 
+	#[derive(Debug)]
+	pub(crate) struct Getlk;
+
+	impl Op for Getlk {
+		const OP_CODE: fuse_abi::Opcode = fuse_abi::Opcode::Getlk;
+		type InStruct = fuse_abi::LkIn;
+		type InPayload = ();
+		type OutStruct = fuse_abi::LkOut;
+		type OutPayload = ();
+	}
+
+	impl Getlk {
+		pub(crate) fn create(
+			nid: u64,
+			fh: u64,
+			owner: u64,
+			lk: fuse_abi::FileLock,
+		) -> (Box<Cmd<Self>>, Box<Rsp<Self>>) {
+			let cmd = Cmd::<Self>::new(
+				nid,
+				fuse_abi::LkIn {
+					fh,
+					owner,
+					lk,
+					..Default::default()
+				},
+			);
+			let rsp = unsafe { Box::new_uninit().assume_init() };
+
+			(cmd, rsp)
+		}
+	}
+
+	#[derive(Debug)]
+	pub(crate) struct Setlk;
+
+	impl Op for Setlk {
+		const OP_CODE: fuse_abi::Opcode = fuse_abi::Opcode::Setlk;
+		type InStruct = fuse_abi::LkIn;
+		type InPayload = ();
+		type OutStruct = fuse_abi::SetlkOut;
+		type OutPayload = ();
+	}
+
+	impl Setlk {
+		pub(crate) fn create(
+			nid: u64,
+			fh: u64,
+			owner: u64,
+			lk: fuse_abi::FileLock,
+			lk_flags: u32,
+		) -> (Box<Cmd<Self>>, Box<Rsp<Self>>) {
+			let cmd = Cmd::<Self>::new(
+				nid,
+				fuse_abi::LkIn {
+					fh,
+					owner,
+					lk,
+					lk_flags,
+					..Default::default()
+				},
+			);
+			let rsp = unsafe { Box::new_uninit().assume_init() };
+
+			(cmd, rsp)
+		}
+	}
+
+	#[derive(Debug)]
+	pub(crate) struct SetupMapping;
+
+	impl Op for SetupMapping {
+		const OP_CODE: fuse_abi::Opcode = fuse_abi::Opcode::SetupMapping;
+		type InStruct = fuse_abi::SetupMappingIn;
+		type InPayload = ();
+		type OutStruct = fuse_abi::SetupMappingOut;
+		type OutPayload = ();
+	}
+
+	impl SetupMapping {
+		pub(crate) fn create(
+			nid: u64,
+			fh: u64,
+			foffset: u64,
+			len: u64,
+			flags: u64,
+			moffset: u64,
+		) -> (Box<Cmd<Self>>, Box<Rsp<Self>>) {
+			let cmd = Cmd::<Self>::new(
+				nid,
+				fuse_abi::SetupMappingIn {
+					fh,
+					foffset,
+					len,
+					flags,
+					moffset,
+				},
+			);
+			let rsp = unsafe { Box::new_uninit().assume_init() };
+
+			(cmd, rsp)
+		}
+	}
+
+	#[derive(Debug)]
+	pub(crate) struct RemoveMapping;
+
+	impl Op for RemoveMapping {
+		const OP_CODE: fuse_abi::Opcode = fuse_abi::Opcode::RemoveMapping;
+		type InStruct = fuse_abi::RemoveMappingIn;
+		type InPayload = ();
+		type OutStruct = fuse_abi::RemoveMappingOut;
+		type OutPayload = ();
+	}
+
+	impl RemoveMapping {
+		pub(crate) fn create(nid: u64, moffset: u64, len: u64) -> (Box<Cmd<Self>>, Box<Rsp<Self>>) {
+			let cmd = Cmd::<Self>::new(
+				nid,
+				fuse_abi::RemoveMappingIn {
+					count: 1,
+					moffset,
+					len,
+				},
+			);
+			let rsp = unsafe { Box::new_uninit().assume_init() };
+
+			(cmd, rsp)
+		}
+	}
+
 	#[derive(Debug)]
 	pub(crate) struct Mkdir;
 
@@ -406,6 +558,87 @@ pub(crate) mod ops {
 		}
 	}
 
+	#[derive(Debug)]
+	pub(crate) struct Rename;
+
+	impl Op for Rename {
+		const OP_CODE: fuse_abi::Opcode = fuse_abi::Opcode::Rename;
+		type InStruct = fuse_abi::RenameIn;
+		type InPayload = [u8];
+		type OutStruct = fuse_abi::RenameOut;
+		type OutPayload = ();
+	}
+
+	impl Rename {
+		pub(crate) fn create(from: &str, to: &str) -> (Box<Cmd<Self>>, Box<Rsp<Self>>) {
+			// the payload consists of both NUL-terminated names
+			let mut names = Vec::with_capacity(from.len() + to.len() + 2);
+			names.extend_from_slice(from.as_bytes());
+			names.push(b'\0');
+			names.extend_from_slice(to.as_bytes());
+			names.push(b'\0');
+
+			let cmd = Cmd::<Self>::from_array(
+				fuse_abi::ROOT_ID,
+				fuse_abi::RenameIn {
+					newdir: fuse_abi::ROOT_ID,
+				},
+				&names,
+			);
+			let rsp = unsafe { Box::new_uninit().assume_init() };
+
+			(cmd, rsp)
+		}
+	}
+
+	#[derive(Debug)]
+	pub(crate) struct Link;
+
+	impl Op for Link {
+		const OP_CODE: fuse_abi::Opcode = fuse_abi::Opcode::Link;
+		type InStruct = fuse_abi::LinkIn;
+		type InPayload = CStr;
+		type OutStruct = fuse_abi::EntryOut;
+		type OutPayload = ();
+	}
+
+	impl Link {
+		pub(crate) fn create(nid: u64, name: &str) -> (Box<Cmd<Self>>, Box<Rsp<Self>>) {
+			let cmd =
+				Cmd::<Self>::from_str(fuse_abi::ROOT_ID, fuse_abi::LinkIn { oldnodeid: nid }, name);
+			let rsp = unsafe { Box::new_uninit().assume_init() };
+
+			(cmd, rsp)
+		}
+	}
+
+	#[derive(Debug)]
+	pub(crate) struct Symlink;
+
+	impl Op for Symlink {
+		const OP_CODE: fuse_abi::Opcode = fuse_abi::Opcode::Symlink;
+		type InStruct = fuse_abi::SymlinkIn;
+		type InPayload = [u8];
+		type OutStruct = fuse_abi::EntryOut;
+		type OutPayload = ();
+	}
+
+	impl Symlink {
+		pub(crate) fn create(name: &str, target: &str) -> (Box<Cmd<Self>>, Box<Rsp<Self>>) {
+			// the payload consists of the NUL-terminated name and target
+			let mut names = Vec::with_capacity(name.len() + target.len() + 2);
+			names.extend_from_slice(name.as_bytes());
+			names.push(b'\0');
+			names.extend_from_slice(target.as_bytes());
+			names.push(b'\0');
+
+			let cmd = Cmd::<Self>::from_array(fuse_abi::ROOT_ID, fuse_abi::SymlinkIn {}, &names);
+			let rsp = unsafe { Box::new_uninit().assume_init() };
+
+			(cmd, rsp)
+		}
+	}
+
 	#[derive(Debug)]
 	pub(crate) struct Lookup;
 
@@ -647,11 +880,125 @@ fn readlink(nid: u64) -> Result<String, IoError> {
 	.unwrap())
 }
 
+/// Alignment of the file offsets and the window offsets of DAX mappings,
+/// which the host announces on initialization
+static MAP_ALIGNMENT: AtomicUsize = AtomicUsize::new(BasePageSize::SIZE as usize);
+
+/// Locks, which the handles of this kernel hold on the host, by the node
+/// and the type of the lock. Tasks wait here for the release of a lock,
+/// which is held by another handle of this kernel.
+static LOCAL_LOCKS: InterruptTicketMutex<BTreeMap<(u64, LockType), LockTable>> =
+	InterruptTicketMutex::new(BTreeMap::new());
+
+/// Lookups of the path resolution by host path: the target of a symbolic
+/// link or `None`, if the path isn't a link, and the time in microseconds,
+/// until which the host allows to cache the lookup.
+static LINK_CACHE: InterruptTicketMutex<BTreeMap<String, (Option<String>, u64)>> =
+	InterruptTicketMutex::new(BTreeMap::new());
+
+/// Returns the cached lookup of `path`, if it is still valid
+fn cached_link(path: &str) -> Option<Option<String>> {
+	let now = now_micros();
+	LINK_CACHE
+		.lock()
+		.get(path)
+		.filter(|(_, valid_until)| *valid_until > now)
+		.map(|(target, _)| target.clone())
+}
+
+/// Caches the lookup `entry` of `path`, as long as the host allows it
+fn cache_link(path: String, target: Option<String>, entry: &fuse_abi::EntryOut) {
+	let valid = entry
+		.entry_valid
+		.saturating_mul(1_000_000)
+		.saturating_add(u64::from(entry.entry_valid_nsec) / 1000);
+	if valid == 0 {
+		return;
+	}
+
+	let now = now_micros();
+	let mut cache = LINK_CACHE.lock();
+	if cache.len() >= MAX_LINK_CACHE_ENTRIES {
+		cache.retain(|_, (_, valid_until)| *valid_until > now);
+		if cache.len() >= MAX_LINK_CACHE_ENTRIES {
+			cache.clear();
+		}
+	}
+	cache.insert(path, (target, now.saturating_add(valid)));
+}
+
+/// Forgets the cached lookups, after paths have been removed or replaced
+fn invalidate_links() {
+	LINK_CACHE.lock().clear();
+}
+
+/// Converts a lock into the representation of the host
+fn lock_to_fuse(lock: FileLock) -> fuse_abi::FileLock {
+	fuse_abi::FileLock {
+		start: lock.start,
+		end: lock.end.min(fuse_abi::OFFSET_MAX),
+		typ: num::ToPrimitive::to_u32(&lock.kind).unwrap(),
+		pid: 0,
+	}
+}
+
+fn lock_from_fuse(lk: fuse_abi::FileLock) -> Result<FileLock, IoError> {
+	Ok(FileLock {
+		kind: num::FromPrimitive::from_u32(lk.typ).ok_or(IoError::EIO)?,
+		start: lk.start,
+		end: if lk.end >= fuse_abi::OFFSET_MAX {
+			u64::MAX
+		} else {
+			lk.end
+		},
+	})
+}
+
+/// Part of a file, which the host has mapped into the DAX window
+#[derive(Debug)]
+struct DaxMapping {
+	nid: u64,
+	/// Offset within the DAX window
+	moffset: usize,
+	len: usize,
+	/// Physical address of the mapping
+	addr: PhysAddr,
+}
+
+impl MappedObject for DaxMapping {
+	fn page(&self, offset: usize) -> Option<MappedPage<'_>> {
+		(offset < self.len)
+			.then(|| MappedPage::Frame(PhysAddr(self.addr.0 + u64::try_from(offset).unwrap())))
+	}
+}
+
+impl Drop for DaxMapping {
+	fn drop(&mut self) {
+		let (cmd, mut rsp) = ops::RemoveMapping::create(
+			self.nid,
+			self.moffset.try_into().unwrap(),
+			self.len.try_into().unwrap(),
+		);
+		let mut driver = get_filesystem_driver().unwrap().lock();
+		if driver.send_command(cmd.as_ref(), rsp.as_mut()).is_err()
+			|| unsafe { rsp.out_header.assume_init_ref().error } != 0
+		{
+			warn!("Unable to remove the DAX mapping at {:#x}", self.moffset);
+		}
+		driver.get_dax_window().unwrap().deallocate(
+			self.moffset,
+			self.len.align_up(MAP_ALIGNMENT.load(Ordering::Relaxed)),
+		);
+	}
+}
+
 #[derive(Debug)]
 struct FuseFileHandleInner {
 	fuse_nid: Option<u64>,
 	fuse_fh: Option<u64>,
 	offset: usize,
+	/// Is set, if a lock has been placed on the host
+	locked: bool,
 }
 
 impl FuseFileHandleInner {
@@ -660,6 +1007,58 @@ impl FuseFileHandleInner {
 			fuse_nid: None,
 			fuse_fh: None,
 			offset: 0,
+			locked: false,
+		}
+	}
+
+	/// Identifies the open file description as owner of locks
+	fn lock_owner(&self) -> u64 {
+		ptr::from_ref(self).addr().try_into().unwrap()
+	}
+
+	fn getlk(&self, lock: FileLock) -> Result<FileLock, IoError> {
+		if let (Some(nid), Some(fh)) = (self.fuse_nid, self.fuse_fh) {
+			let (cmd, mut rsp) = ops::Getlk::create(nid, fh, self.lock_owner(), lock_to_fuse(lock));
+			get_filesystem_driver()
+				.ok_or(IoError::ENOSYS)?
+				.lock()
+				.send_command(cmd.as_ref(), rsp.as_mut())?;
+			check_error(unsafe { rsp.out_header.assume_init_ref().error })?;
+
+			lock_from_fuse(unsafe { rsp.op_header.assume_init_ref().lk })
+		} else {
+			Err(IoError::EBADF)
+		}
+	}
+
+	fn setlk(&mut self, lock: FileLock, ty: LockType) -> Result<(), IoError> {
+		if let (Some(nid), Some(fh)) = (self.fuse_nid, self.fuse_fh) {
+			let lk_flags = match ty {
+				LockType::Posix => 0,
+				LockType::Flock => fuse_abi::LK_FLOCK,
+			};
+			let (cmd, mut rsp) =
+				ops::Setlk::create(nid, fh, self.lock_owner(), lock_to_fuse(lock), lk_flags);
+			get_filesystem_driver()
+				.ok_or(IoError::ENOSYS)?
+				.lock()
+				.send_command(cmd.as_ref(), rsp.as_mut())?;
+			check_error(unsafe { rsp.out_header.assume_init_ref().error })?;
+
+			// the host accepted the lock, so it can't conflict locally
+			let owner = self.lock_owner().try_into().unwrap();
+			let _ = LOCAL_LOCKS
+				.lock()
+				.entry((nid, ty))
+				.or_default()
+				.set(owner, lock);
+
+			if lock.kind != LockKind::Unlock {
+				self.locked = true;
+			}
+			Ok(())
+		} else {
+			Err(IoError::EBADF)
 		}
 	}
 
@@ -702,14 +1101,20 @@ impl FuseFileHandleInner {
 	}
 
 	fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+		let len = self.read_at(buf, self.offset)?;
+		self.offset += len;
+
+		Ok(len)
+	}
+
+	fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<usize, IoError> {
 		let mut len = buf.len();
 		if len > MAX_READ_LEN {
 			debug!("Reading longer than max_read_len: {}", len);
 			len = MAX_READ_LEN;
 		}
 		if let (Some(nid), Some(fh)) = (self.fuse_nid, self.fuse_fh) {
-			let (cmd, mut rsp) =
-				ops::Read::create(nid, fh, len.try_into().unwrap(), self.offset as u64);
+			let (cmd, mut rsp) = ops::Read::create(nid, fh, len.try_into().unwrap(), offset as u64);
 			get_filesystem_driver()
 				.ok_or(IoError::ENOSYS)?
 				.lock()
@@ -725,7 +1130,6 @@ impl FuseFileHandleInner {
 					- ::core::mem::size_of::<fuse_abi::OutHeader>()
 					- ::core::mem::size_of::<fuse_abi::ReadOut>()
 			};
-			self.offset += len;
 
 			buf[..len].copy_from_slice(unsafe {
 				MaybeUninit::slice_assume_init_ref(&rsp.payload[..len])
@@ -738,7 +1142,40 @@ impl FuseFileHandleInner {
 		}
 	}
 
+	/// Reads into all buffers with a single request to the host
+	fn readv_at(&self, bufs: &mut [&mut [u8]], offset: usize) -> Result<usize, IoError> {
+		let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+		let mut data = vec![0; core::cmp::min(total, MAX_READ_LEN)];
+		let len = self.read_at(&mut data, offset)?;
+
+		let mut pos = 0;
+		for buf in bufs.iter_mut() {
+			let n = core::cmp::min(buf.len(), len - pos);
+			buf[..n].copy_from_slice(&data[pos..pos + n]);
+			pos += n;
+			if pos == len {
+				break;
+			}
+		}
+
+		Ok(len)
+	}
+
 	fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+		let len = self.write_at(buf, self.offset)?;
+		self.offset += len;
+
+		Ok(len)
+	}
+
+	/// Writes `buf` at the end of the file. The caller holds the lock of
+	/// the handle, so that other writes through it can't interleave.
+	fn append(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+		self.offset = self.lseek(0, SeekWhence::End)?.try_into().unwrap();
+		self.write(buf)
+	}
+
+	fn write_at(&self, buf: &[u8], offset: usize) -> Result<usize, IoError> {
 		debug!("FUSE write!");
 		let mut len = buf.len();
 		if len > MAX_WRITE_LEN {
@@ -750,7 +1187,7 @@ impl FuseFileHandleInner {
 			len = MAX_WRITE_LEN;
 		}
 		if let (Some(nid), Some(fh)) = (self.fuse_nid, self.fuse_fh) {
-			let (cmd, mut rsp) = ops::Write::create(nid, fh, &buf[..len], self.offset as u64);
+			let (cmd, mut rsp) = ops::Write::create(nid, fh, &buf[..len], offset as u64);
 			get_filesystem_driver()
 				.ok_or(IoError::ENOSYS)?
 				.lock()
@@ -766,7 +1203,6 @@ impl FuseFileHandleInner {
 			} else {
 				rsp_size.try_into().unwrap()
 			};
-			self.offset += len;
 			Ok(len)
 		} else {
 			warn!("File not open, cannot read!");
@@ -795,10 +1231,93 @@ impl FuseFileHandleInner {
 			Err(IoError::EIO)
 		}
 	}
+
+	/// Asks the host to map `len` bytes of the file at `offset` into the DAX window
+	fn mmap(
+		&mut self,
+		offset: usize,
+		len: usize,
+		writable: bool,
+	) -> Result<Arc<dyn MappedObject>, IoError> {
+		let (Some(nid), Some(fh)) = (self.fuse_nid, self.fuse_fh) else {
+			return Err(IoError::EBADF);
+		};
+
+		// the host only maps aligned ranges of the file into aligned ranges
+		// of the window
+		let alignment = MAP_ALIGNMENT.load(Ordering::Relaxed);
+		if offset % alignment != 0 {
+			return Err(IoError::EINVAL);
+		}
+
+		// the host cannot map pages beyond the end of the file
+		let size: usize = self.lseek(0, SeekWhence::End)?.try_into().unwrap();
+		let len = len
+			.min(size.saturating_sub(offset))
+			.align_up(BasePageSize::SIZE as usize);
+		if len == 0 {
+			return Err(IoError::EINVAL);
+		}
+
+		let mut driver = get_filesystem_driver().ok_or(IoError::ENOSYS)?.lock();
+		let window = driver.get_dax_window().ok_or(IoError::ENODEV)?;
+		let moffset = window
+			.allocate(len.align_up(alignment), alignment)
+			.ok_or(IoError::ENOMEM)?;
+		let addr = PhysAddr(window.addr().0 + u64::try_from(moffset).unwrap());
+
+		let mut flags = fuse_abi::SETUPMAPPING_FLAG_READ;
+		if writable {
+			flags |= fuse_abi::SETUPMAPPING_FLAG_WRITE;
+		}
+		let (cmd, mut rsp) = ops::SetupMapping::create(
+			nid,
+			fh,
+			offset.try_into().unwrap(),
+			len.try_into().unwrap(),
+			flags,
+			moffset.try_into().unwrap(),
+		);
+		let result = driver
+			.send_command(cmd.as_ref(), rsp.as_mut())
+			.map_err(IoError::from)
+			.and_then(|()| check_error(unsafe { rsp.out_header.assume_init_ref().error }));
+		if let Err(err) = result {
+			driver
+				.get_dax_window()
+				.unwrap()
+				.deallocate(moffset, len.align_up(alignment));
+			return Err(err);
+		}
+
+		Ok(Arc::new(DaxMapping {
+			nid,
+			moffset,
+			len,
+			addr,
+		}))
+	}
 }
 
 impl Drop for FuseFileHandleInner {
 	fn drop(&mut self) {
+		if self.locked {
+			let _ = self.setlk(FileLock::whole_file(LockKind::Unlock), LockType::Posix);
+			let _ = self.setlk(FileLock::whole_file(LockKind::Unlock), LockType::Flock);
+
+			let owner = self.lock_owner().try_into().unwrap();
+			let nid = self.fuse_nid.unwrap();
+			let mut locks = LOCAL_LOCKS.lock();
+			for ty in [LockType::Posix, LockType::Flock] {
+				if let Some(table) = locks.get_mut(&(nid, ty)) {
+					table.release(owner);
+					if table.is_unused() {
+						locks.remove(&(nid, ty));
+					}
+				}
+			}
+		}
+
 		if self.fuse_nid.is_some() && self.fuse_fh.is_some() {
 			let (cmd, mut rsp) =
 				ops::Release::create(self.fuse_nid.unwrap(), self.fuse_fh.unwrap());
@@ -834,9 +1353,96 @@ impl ObjectInterface for FuseFileHandle {
 		self.0.lock().await.write(buf)
 	}
 
+	async fn async_pread(&self, buf: &mut [u8], offset: usize) -> Result<usize, IoError> {
+		self.0.lock().await.read_at(buf, offset)
+	}
+
+	async fn async_pwrite(&self, buf: &[u8], offset: usize) -> Result<usize, IoError> {
+		self.0.lock().await.write_at(buf, offset)
+	}
+
+	async fn async_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
+		let mut guard = self.0.lock().await;
+		let len = guard.readv_at(bufs, guard.offset)?;
+		guard.offset += len;
+
+		Ok(len)
+	}
+
+	async fn async_writev(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		// gather the buffers to send them with a single request
+		self.0.lock().await.write(&bufs.concat())
+	}
+
+	async fn async_append(&self, buf: &[u8]) -> Result<usize, IoError> {
+		self.0.lock().await.append(buf)
+	}
+
+	async fn async_appendv(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		self.0.lock().await.append(&bufs.concat())
+	}
+
 	fn lseek(&self, offset: isize, whence: SeekWhence) -> Result<isize, IoError> {
 		block_on(async { self.0.lock().await.lseek(offset, whence) }, None)
 	}
+
+	fn mmap(
+		&self,
+		offset: usize,
+		len: usize,
+		writable: bool,
+	) -> Result<Arc<dyn MappedObject>, IoError> {
+		block_on(
+			async { self.0.lock().await.mmap(offset, len, writable) },
+			None,
+		)
+	}
+
+	async fn getlk(&self, lock: FileLock) -> Result<FileLock, IoError> {
+		self.0.lock().await.getlk(lock)
+	}
+
+	async fn setlk(&self, lock: FileLock, ty: LockType, wait: bool) -> Result<(), IoError> {
+		// `Setlkw` isn't used, because the host would block the request queue
+		// until the lock is available. If a handle of this kernel holds the
+		// conflicting lock, the task waits for its release. Otherwise, another
+		// process on the host holds it and the lock is requested again after
+		// a delay, which doubles up to `MAX_LOCK_DELAY`.
+		let mut delay = MIN_LOCK_DELAY;
+		loop {
+			let (nid, owner) = {
+				let mut guard = self.0.lock().await;
+				match guard.setlk(lock, ty) {
+					Err(IoError::EAGAIN) if wait => {}
+					result => return result,
+				}
+				let owner: usize = guard.lock_owner().try_into().unwrap();
+				(guard.fuse_nid.unwrap(), owner)
+			};
+
+			let local = LOCAL_LOCKS
+				.lock()
+				.get(&(nid, ty))
+				.is_some_and(|table| table.conflict(owner, &lock).is_some());
+			if local {
+				future::poll_fn(|cx| {
+					let mut locks = LOCAL_LOCKS.lock();
+					let table = locks.entry((nid, ty)).or_default();
+					if table.conflict(owner, &lock).is_some() {
+						table.wait(cx.waker());
+						Poll::Pending
+					} else {
+						Poll::Ready(())
+					}
+				})
+				.await;
+				delay = MIN_LOCK_DELAY;
+			} else {
+				executor::sleep(Duration::from_micros(delay)).await;
+				delay = (2 * delay).min(MAX_LOCK_DELAY);
+			}
+		}
+	}
 }
 
 impl Clone for FuseFileHandle {
@@ -855,6 +1461,30 @@ impl FuseDirectory {
 	pub const fn new(prefix: Option<String>) -> Self {
 		FuseDirectory { prefix }
 	}
+
+	/// Converts the remaining components into a path on the host
+	fn host_path(&self, components: &[&str]) -> String {
+		let path: String = components
+			.iter()
+			.rev()
+			.map(|v| "/".to_owned() + v)
+			.collect();
+
+		match &self.prefix {
+			Some(prefix) => "/".to_owned() + prefix + &path,
+			None if path.is_empty() => "/".to_string(),
+			None => path,
+		}
+	}
+}
+
+/// Converts the error code of a FUSE response into a result
+fn check_error(error: i32) -> Result<(), IoError> {
+	if error == 0 {
+		Ok(())
+	} else {
+		Err(num::FromPrimitive::from_i32(-error).unwrap_or(IoError::EIO))
+	}
 }
 
 impl VfsNode for FuseDirectory {
@@ -1035,6 +1665,7 @@ impl VfsNode for FuseDirectory {
 			.unwrap()
 			.lock()
 			.send_command(cmd.as_ref(), rsp.as_mut())?;
+		check_error(unsafe { rsp.out_header.assume_init_ref().error })?;
 
 		let attr = unsafe { rsp.op_header.assume_init().attr };
 		Ok(FileAttr::from(attr))
@@ -1132,6 +1763,7 @@ impl VfsNode for FuseDirectory {
 			}
 		};
 
+		invalidate_links();
 		let (cmd, mut rsp) = ops::Unlink::create(&path);
 		get_filesystem_driver()
 			.ok_or(IoError::ENOSYS)?
@@ -1163,6 +1795,7 @@ impl VfsNode for FuseDirectory {
 			}
 		};
 
+		invalidate_links();
 		let (cmd, mut rsp) = ops::Rmdir::create(&path);
 		get_filesystem_driver()
 			.ok_or(IoError::ENOSYS)?
@@ -1212,6 +1845,113 @@ impl VfsNode for FuseDirectory {
 			)
 		}
 	}
+
+	fn traverse_rename(&self, from: &mut Vec<&str>, to: &mut Vec<&str>) -> Result<(), IoError> {
+		let from = self.host_path(from);
+		let to = self.host_path(to);
+
+		debug!("FUSE rename: {} -> {}", from, to);
+
+		invalidate_links();
+		let (cmd, mut rsp) = ops::Rename::create(&from, &to);
+		get_filesystem_driver()
+			.ok_or(IoError::ENOSYS)?
+			.lock()
+			.send_command(cmd.as_ref(), rsp.as_mut())?;
+
+		check_error(unsafe { rsp.out_header.assume_init_ref().error })
+	}
+
+	fn traverse_link(&self, from: &mut Vec<&str>, to: &mut Vec<&str>) -> Result<(), IoError> {
+		let from = self.host_path(from);
+		let to = self.host_path(to);
+
+		debug!("FUSE link: {} -> {}", to, from);
+
+		let fuse_nid = lookup(&from).ok_or(IoError::ENOENT)?;
+		let (cmd, mut rsp) = ops::Link::create(fuse_nid, &to);
+		get_filesystem_driver()
+			.ok_or(IoError::ENOSYS)?
+			.lock()
+			.send_command(cmd.as_ref(), rsp.as_mut())?;
+
+		check_error(unsafe { rsp.out_header.assume_init_ref().error })
+	}
+
+	fn traverse_symlink(&self, target: &str, components: &mut Vec<&str>) -> Result<(), IoError> {
+		let path = self.host_path(components);
+
+		debug!("FUSE symlink: {} -> {}", path, target);
+
+		invalidate_links();
+		let (cmd, mut rsp) = ops::Symlink::create(&path, target);
+		get_filesystem_driver()
+			.ok_or(IoError::ENOSYS)?
+			.lock()
+			.send_command(cmd.as_ref(), rsp.as_mut())?;
+
+		check_error(unsafe { rsp.out_header.assume_init_ref().error })
+	}
+
+	fn traverse_readlink(&self, components: &mut Vec<&str>) -> Result<String, IoError> {
+		let path = self.host_path(components);
+
+		debug!("FUSE readlink: {}", path);
+
+		// the path resolution reads every component => use the cache
+		if let Some(target) = cached_link(&path) {
+			return target.ok_or(IoError::EINVAL);
+		}
+
+		let (cmd, mut rsp) = ops::Lookup::create(&path);
+		get_filesystem_driver()
+			.ok_or(IoError::ENOSYS)?
+			.lock()
+			.send_command(cmd.as_ref(), rsp.as_mut())?;
+		check_error(unsafe { rsp.out_header.assume_init_ref().error })?;
+
+		let rsp = unsafe { rsp.op_header.assume_init() };
+		let target = if rsp.attr.mode & S_IFMT == S_IFLNK {
+			Some(readlink(rsp.nodeid)?)
+		} else {
+			None
+		};
+		cache_link(path, target.clone(), &rsp);
+
+		target.ok_or(IoError::EINVAL)
+	}
+
+	fn traverse_resolve(&self, resolution: &mut Resolution) -> Result<Walk, IoError> {
+		// resolved components below the mount point
+		let mut walked: Vec<String> = Vec::new();
+
+		loop {
+			let Some(component) = resolution.next_component() else {
+				let walk = resolution.stop();
+				if walk == Walk::Done || walked.pop().is_none() {
+					return Ok(walk);
+				}
+				resolution.leave();
+				continue;
+			};
+
+			if resolution.follows() {
+				// the lookup of every prefix is cached by traverse_readlink
+				let mut components: Vec<&str> = core::iter::once(component.as_str())
+					.chain(walked.iter().rev().map(String::as_str))
+					.collect();
+				if let Ok(target) = self.traverse_readlink(&mut components) {
+					if resolution.follow_link(&target)? {
+						return Ok(Walk::Root);
+					}
+					continue;
+				}
+			}
+
+			walked.push(component.clone());
+			resolution.push(component);
+		}
+	}
 }
 
 pub(crate) fn init() {
@@ -1225,6 +1965,15 @@ pub(crate) fn init() {
 			.unwrap();
 		trace!("fuse init answer: {:?}", rsp);
 
+		let init = unsafe { rsp.op_header.assume_init_ref() };
+		if init.flags & fuse_abi::INIT_MAP_ALIGNMENT != 0 {
+			let alignment = 1usize
+				.checked_shl(init.map_alignment.into())
+				.unwrap_or(BasePageSize::SIZE as usize)
+				.max(BasePageSize::SIZE as usize);
+			MAP_ALIGNMENT.store(alignment, Ordering::Relaxed);
+		}
+
 		let mount_point = driver.lock().get_mount_point().to_string();
 		if mount_point == "/" {
 			let fuse_nid = lookup("/").unwrap();
@@ -57,9 +57,15 @@ pub(crate) struct InitOut {
 	pub congestion_threshold: u16,
 	pub max_write: u32,
 	pub time_gran: u32,
-	pub unused: [u32; 9],
+	pub max_pages: u16,
+	/// Alignment of DAX mappings as power of two, if `INIT_MAP_ALIGNMENT` is set
+	pub map_alignment: u16,
+	pub unused: [u32; 8],
 }
 
+/// Flag of `InitIn` and `InitOut`, which announces the alignment of DAX mappings
+pub(crate) const INIT_MAP_ALIGNMENT: u32 = 1 << 26;
+
 #[repr(C)]
 #[derive(Default, Debug)]
 pub(crate) struct ReadIn {
@@ -228,6 +234,26 @@ pub(crate) struct UnlinkIn {}
 #[derive(Default, Debug)]
 pub(crate) struct UnlinkOut {}
 
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct RenameIn {
+	pub newdir: u64,
+}
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct RenameOut {}
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct LinkIn {
+	pub oldnodeid: u64,
+}
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct SymlinkIn {}
+
 #[repr(C)]
 #[derive(Default, Debug)]
 pub(crate) struct LseekIn {
@@ -259,6 +285,76 @@ pub(crate) struct PollOut {
 	padding: u32,
 }
 
+/// Flag of `LkIn`, which marks a lock of `flock`
+pub(crate) const LK_FLOCK: u32 = 1 << 0;
+
+/// Largest offset of a lock range, which corresponds to `OFFSET_MAX` of the host
+pub(crate) const OFFSET_MAX: u64 = 0x7fff_ffff_ffff_ffff;
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone)]
+pub(crate) struct FileLock {
+	pub start: u64,
+	pub end: u64,
+	pub typ: u32,
+	pub pid: u32,
+}
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct LkIn {
+	pub fh: u64,
+	pub owner: u64,
+	pub lk: FileLock,
+	pub lk_flags: u32,
+	pub padding: u32,
+}
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct LkOut {
+	pub lk: FileLock,
+}
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct SetlkOut {}
+
+/// The mapping of the DAX window permits writing
+pub(crate) const SETUPMAPPING_FLAG_WRITE: u64 = 1 << 0;
+/// The mapping of the DAX window permits reading
+pub(crate) const SETUPMAPPING_FLAG_READ: u64 = 1 << 1;
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct SetupMappingIn {
+	pub fh: u64,
+	/// Offset within the file
+	pub foffset: u64,
+	pub len: u64,
+	pub flags: u64,
+	/// Offset within the DAX window
+	pub moffset: u64,
+}
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct SetupMappingOut {}
+
+/// Removes a single mapping of the DAX window. The list of removed
+/// mappings directly follows the count without padding.
+#[repr(C, packed)]
+#[derive(Default, Debug)]
+pub(crate) struct RemoveMappingIn {
+	pub count: u32,
+	pub moffset: u64,
+	pub len: u64,
+}
+
+#[repr(C)]
+#[derive(Default, Debug)]
+pub(crate) struct RemoveMappingOut {}
+
 #[repr(u32)]
 #[derive(Debug, Copy, Clone)]
 #[allow(dead_code)]
@@ -307,6 +403,9 @@ pub(crate) enum Opcode {
 	Readdirplus = 44,
 	Rename2 = 45,
 	Lseek = 46,
+	CopyFileRange = 47,
+	SetupMapping = 48,
+	RemoveMapping = 49,
 
 	Setvolname = 61,
 	Getxtimes = 62,
@@ -0,0 +1,362 @@
+//! Unpacks an initial ramdisk into the in-memory file system.
+//!
+//! The archive is embedded into the kernel at build time and can either be
+//! a newc cpio archive or a ustar archive. Regular files are not copied,
+//! they directly refer to the embedded data.
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str;
+
+use crate::fd::{AccessPermission, IoError};
+use crate::fs::mem::{MemDirectory, RomFile};
+use crate::fs::{FileAttr, FILESYSTEM};
+
+static INITRAMFS: &[u8] = include_bytes!(env!("HERMIT_INITRAMFS"));
+
+const CPIO_NEWC_MAGIC: &[u8] = b"070701";
+const CPIO_CRC_MAGIC: &[u8] = b"070702";
+const CPIO_HEADER_LEN: usize = 110;
+const CPIO_TRAILER: &str = "TRAILER!!!";
+const TAR_BLOCK_LEN: usize = 512;
+
+/// Type of an archive entry
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum EntryKind {
+	File,
+	Directory,
+	Symlink,
+	Other,
+}
+
+#[derive(Debug)]
+struct Entry<'a> {
+	path: String,
+	kind: EntryKind,
+	/// access permissions without the file type
+	mode: u32,
+	mtime: u64,
+	/// file content or target of a symbolic link
+	data: &'a [u8],
+}
+
+fn kind_from_mode(mode: u32) -> EntryKind {
+	match mode & AccessPermission::S_IFMT.bits() {
+		0o100000 => EntryKind::File,
+		0o040000 => EntryKind::Directory,
+		0o120000 => EntryKind::Symlink,
+		_ => EntryKind::Other,
+	}
+}
+
+/// Removes leading `./` and `/` from archive paths.
+fn normalize(path: &str) -> String {
+	path.split('/')
+		.filter(|c| !c.is_empty() && *c != ".")
+		.collect::<Vec<_>>()
+		.join("/")
+}
+
+fn parse_hex(field: &[u8]) -> Result<u64, IoError> {
+	let s = str::from_utf8(field).map_err(|_| IoError::EINVAL)?;
+	u64::from_str_radix(s, 16).map_err(|_| IoError::EINVAL)
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64, IoError> {
+	let s = str::from_utf8(field).map_err(|_| IoError::EINVAL)?;
+	let s = s.trim_matches(|c: char| c == '\0' || c == ' ');
+	if s.is_empty() {
+		return Ok(0);
+	}
+	u64::from_str_radix(s, 8).map_err(|_| IoError::EINVAL)
+}
+
+/// Parses a newc cpio archive.
+fn parse_cpio(archive: &[u8]) -> Result<Vec<Entry<'_>>, IoError> {
+	let mut entries = Vec::new();
+	let mut offset = 0;
+
+	loop {
+		let header = archive
+			.get(offset..offset + CPIO_HEADER_LEN)
+			.ok_or(IoError::EINVAL)?;
+		if &header[..6] != CPIO_NEWC_MAGIC && &header[..6] != CPIO_CRC_MAGIC {
+			return Err(IoError::EINVAL);
+		}
+
+		let field = |i: usize| parse_hex(&header[6 + 8 * i..14 + 8 * i]);
+		let mode = field(1)? as u32;
+		let mtime = field(5)?;
+		let filesize = field(6)? as usize;
+		let namesize = field(11)? as usize;
+
+		let name_start = offset + CPIO_HEADER_LEN;
+		let name = archive
+			.get(name_start..name_start + namesize.saturating_sub(1))
+			.ok_or(IoError::EINVAL)?;
+		let name = str::from_utf8(name).map_err(|_| IoError::EINVAL)?;
+		if name == CPIO_TRAILER {
+			return Ok(entries);
+		}
+
+		// header and name as well as the file data are padded to 4 bytes
+		let data_start = (name_start + namesize).next_multiple_of(4);
+		let data = archive
+			.get(data_start..data_start + filesize)
+			.ok_or(IoError::EINVAL)?;
+		offset = (data_start + filesize).next_multiple_of(4);
+
+		entries.push(Entry {
+			path: normalize(name),
+			kind: kind_from_mode(mode),
+			mode: mode & 0o777,
+			mtime,
+			data,
+		});
+	}
+}
+
+/// Parses a ustar archive.
+fn parse_tar(archive: &[u8]) -> Result<Vec<Entry<'_>>, IoError> {
+	let mut entries = Vec::new();
+	let mut offset = 0;
+
+	while let Some(header) = archive.get(offset..offset + TAR_BLOCK_LEN) {
+		// the archive ends with two zero blocks
+		if header.iter().all(|b| *b == 0) {
+			break;
+		}
+
+		let cstr = |field: &'_ [u8]| -> Result<String, IoError> {
+			let len = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+			str::from_utf8(&field[..len])
+				.map(|s| s.to_owned())
+				.map_err(|_| IoError::EINVAL)
+		};
+
+		let name = cstr(&header[0..100])?;
+		let mode = parse_octal(&header[100..108])? as u32;
+		let size = parse_octal(&header[124..136])? as usize;
+		let mtime = parse_octal(&header[136..148])?;
+		let typeflag = header[156];
+		let prefix = cstr(&header[345..500])?;
+
+		let path = if prefix.is_empty() {
+			name
+		} else {
+			prefix + "/" + &name
+		};
+
+		let data_start = offset + TAR_BLOCK_LEN;
+		offset = data_start + size.next_multiple_of(TAR_BLOCK_LEN);
+
+		let (kind, data) = match typeflag {
+			b'0' | b'\0' => (
+				EntryKind::File,
+				archive
+					.get(data_start..data_start + size)
+					.ok_or(IoError::EINVAL)?,
+			),
+			b'5' => (EntryKind::Directory, &[][..]),
+			b'2' => {
+				let target = &header[157..257];
+				let len = target.iter().position(|b| *b == 0).unwrap_or(target.len());
+				(EntryKind::Symlink, &target[..len])
+			}
+			_ => (EntryKind::Other, &[][..]),
+		};
+
+		entries.push(Entry {
+			path: normalize(&path),
+			kind,
+			mode: mode & 0o777,
+			mtime,
+			data,
+		});
+	}
+
+	Ok(entries)
+}
+
+fn parse(archive: &[u8]) -> Result<Vec<Entry<'_>>, IoError> {
+	if archive.starts_with(CPIO_NEWC_MAGIC) || archive.starts_with(CPIO_CRC_MAGIC) {
+		parse_cpio(archive)
+	} else if archive.get(257..262) == Some(&b"ustar"[..]) {
+		parse_tar(archive)
+	} else {
+		Err(IoError::EINVAL)
+	}
+}
+
+/// Creates all missing parent directories of `path`.
+fn create_parents(path: &str) -> Result<(), IoError> {
+	let fs = FILESYSTEM.get().unwrap();
+	let components: Vec<&str> = path.split('/').collect();
+	let mut parent = String::new();
+
+	for component in &components[..components.len() - 1] {
+		parent.push('/');
+		parent.push_str(component);
+
+		if fs.stat(&parent).is_err() {
+			fs.mkdir(&parent, AccessPermission::from_bits(0o755).unwrap())?;
+		}
+	}
+
+	Ok(())
+}
+
+fn unpack(entry: &Entry<'static>) -> Result<(), IoError> {
+	let fs = FILESYSTEM.get().unwrap();
+	let path = "/".to_owned() + &entry.path;
+	let attr = FileAttr {
+		st_size: entry.data.len().try_into().unwrap(),
+		st_mode: AccessPermission::from_bits_truncate(entry.mode),
+		st_atime: entry.mtime,
+		st_mtime: entry.mtime,
+		st_ctime: entry.mtime,
+		..Default::default()
+	};
+
+	create_parents(&entry.path)?;
+
+	match entry.kind {
+		EntryKind::Directory => {
+			// directories may already exist, e.g. /tmp
+			if fs.stat(&path).is_err() {
+				let attr = FileAttr {
+					st_size: 0,
+					st_mode: attr.st_mode | AccessPermission::S_IFDIR,
+					..attr
+				};
+				fs.mount(&path, Box::new(MemDirectory::with_attr(attr)))?;
+			}
+		}
+		EntryKind::File => {
+			let attr = FileAttr {
+				st_mode: attr.st_mode | AccessPermission::S_IFREG,
+				..attr
+			};
+			let file = unsafe { RomFile::with_attr(entry.data.as_ptr(), entry.data.len(), attr) };
+			fs.mount(&path, Box::new(file))?;
+		}
+		EntryKind::Symlink => {
+			let target = str::from_utf8(entry.data).map_err(|_| IoError::EINVAL)?;
+			fs.symlink(target, &path)?;
+		}
+		EntryKind::Other => {
+			warn!("Initramfs: {} has an unsupported file type", path);
+		}
+	}
+
+	Ok(())
+}
+
+pub(crate) fn init() {
+	info!("Unpacking initramfs ({} bytes)", INITRAMFS.len());
+
+	let entries = match parse(INITRAMFS) {
+		Ok(entries) => entries,
+		Err(_) => {
+			error!("Initramfs is neither a newc cpio nor a ustar archive");
+			return;
+		}
+	};
+
+	for entry in entries.iter().filter(|e| !e.path.is_empty()) {
+		if let Err(err) = unpack(entry) {
+			error!("Unable to unpack {} from initramfs: {:?}", entry.path, err);
+		}
+	}
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	fn cpio_entry(archive: &mut Vec<u8>, name: &str, mode: u32, data: &[u8]) {
+		let fields = [
+			0,
+			mode,
+			0,
+			0,
+			1,
+			1_700_000_000,
+			data.len() as u32,
+			0,
+			0,
+			0,
+			0,
+			name.len() as u32 + 1,
+			0,
+		];
+		archive.extend_from_slice(CPIO_NEWC_MAGIC);
+		for field in fields {
+			archive.extend_from_slice(format!("{field:08X}").as_bytes());
+		}
+		archive.extend_from_slice(name.as_bytes());
+		archive.push(0);
+		archive.resize(archive.len().next_multiple_of(4), 0);
+		archive.extend_from_slice(data);
+		archive.resize(archive.len().next_multiple_of(4), 0);
+	}
+
+	#[test]
+	fn cpio() {
+		let mut archive = Vec::new();
+		cpio_entry(&mut archive, ".", 0o040755, &[]);
+		cpio_entry(&mut archive, "./etc", 0o040700, &[]);
+		cpio_entry(
+			&mut archive,
+			"./etc/hosts",
+			0o100644,
+			b"127.0.0.1 localhost\n",
+		);
+		cpio_entry(&mut archive, CPIO_TRAILER, 0, &[]);
+
+		let entries = parse(&archive).unwrap();
+		assert_eq!(entries.len(), 3);
+		assert_eq!(entries[0].path, "");
+		assert_eq!(entries[1].path, "etc");
+		assert_eq!(entries[1].kind, EntryKind::Directory);
+		assert_eq!(entries[1].mode, 0o700);
+		assert_eq!(entries[2].path, "etc/hosts");
+		assert_eq!(entries[2].kind, EntryKind::File);
+		assert_eq!(entries[2].mode, 0o644);
+		assert_eq!(entries[2].mtime, 1_700_000_000);
+		assert_eq!(entries[2].data, b"127.0.0.1 localhost\n");
+	}
+
+	#[test]
+	fn tar() {
+		let data = b"hello";
+		let mut header = [0u8; TAR_BLOCK_LEN];
+		header[..9].copy_from_slice(b"hello.txt");
+		header[100..107].copy_from_slice(b"0000644");
+		header[124..135].copy_from_slice(b"00000000005");
+		header[136..147].copy_from_slice(b"14524770400");
+		header[156] = b'0';
+		header[257..262].copy_from_slice(b"ustar");
+		header[345..348].copy_from_slice(b"www");
+
+		let mut archive = header.to_vec();
+		archive.extend_from_slice(data);
+		archive.resize(archive.len().next_multiple_of(TAR_BLOCK_LEN), 0);
+		archive.resize(archive.len() + 2 * TAR_BLOCK_LEN, 0);
+
+		let entries = parse(&archive).unwrap();
+		assert_eq!(entries.len(), 1);
+		assert_eq!(entries[0].path, "www/hello.txt");
+		assert_eq!(entries[0].kind, EntryKind::File);
+		assert_eq!(entries[0].mode, 0o644);
+		assert_eq!(entries[0].mtime, 0o14524770400);
+		assert_eq!(entries[0].data, data);
+	}
+
+	#[test]
+	fn unknown_format() {
+		assert!(parse(&[0u8; 1024]).is_err());
+	}
+}
@@ -14,15 +14,25 @@ use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::slice;
+use core::ops::Range;
+use core::task::Poll;
+use core::{fmt, future, mem, slice};
 
+use align_address::Align;
 use async_lock::{Mutex, RwLock};
 use async_trait::async_trait;
 
 use crate::arch;
+use crate::arch::mm::paging::{BasePageSize, PageSize};
+use crate::arch::mm::{PhysAddr, VirtAddr};
 use crate::executor::block_on;
+use crate::fd::lock::{FileLock, LockKind, LockTable, LockType};
 use crate::fd::{AccessPermission, IoError, ObjectInterface, OpenOption, PollEvent};
-use crate::fs::{DirectoryEntry, FileAttr, NodeKind, VfsNode};
+use crate::fs::{DirectoryEntry, FileAttr, NodeKind, Resolution, SeekWhence, VfsNode, Walk};
+use crate::mm::mmap::{MappedObject, MappedPage};
+
+/// Size of the pages, in which a `RamFile` stores its content
+const PAGE_SIZE: usize = BasePageSize::SIZE as usize;
 
 #[derive(Debug)]
 pub(crate) struct RomFileInner {
@@ -37,6 +47,78 @@ impl RomFileInner {
 			attr,
 		}
 	}
+
+	/// Updates the access time
+	fn touch(&mut self) {
+		let microseconds = arch::kernel::systemtime::now_micros();
+		self.attr.st_atime = microseconds / 1_000_000;
+		self.attr.st_atime_nsec = (microseconds % 1_000_000) * 1000;
+	}
+}
+
+/// Copies `data` starting at `offset` into `buf` and returns the number of copied bytes
+fn read_at(data: &[u8], buf: &mut [u8], offset: usize) -> usize {
+	if offset >= data.len() {
+		return 0;
+	}
+
+	let len = core::cmp::min(data.len() - offset, buf.len());
+	buf[..len].copy_from_slice(&data[offset..offset + len]);
+
+	len
+}
+
+/// Scatters the data starting at `offset` into `bufs` and returns the number of copied bytes
+fn readv_with(
+	bufs: &mut [&mut [u8]],
+	offset: usize,
+	read_at: impl Fn(&mut [u8], usize) -> usize,
+) -> usize {
+	let mut pos = offset;
+
+	for buf in bufs.iter_mut() {
+		let len = read_at(buf, pos);
+		pos += len;
+		if len < buf.len() {
+			break;
+		}
+	}
+
+	pos - offset
+}
+
+/// Scatters `data` starting at `offset` into `bufs` and returns the number of copied bytes
+fn readv_at(data: &[u8], bufs: &mut [&mut [u8]], offset: usize) -> usize {
+	readv_with(bufs, offset, |buf, pos| read_at(data, buf, pos))
+}
+
+/// Returns the frame of the page at `addr` in kernel memory
+fn frame_of(addr: *const u8) -> Option<PhysAddr> {
+	arch::mm::paging::virtual_to_physical(VirtAddr(addr.addr().try_into().unwrap()))
+}
+
+/// Part of a `RomFile`, which is mapped by `mmap`
+#[derive(Debug)]
+struct RomFileMapping {
+	data: &'static [u8],
+}
+
+impl MappedObject for RomFileMapping {
+	fn page(&self, offset: usize) -> Option<MappedPage<'_>> {
+		if offset >= self.data.len() {
+			return None;
+		}
+
+		// complete and aligned pages are shared, while the rest is copied
+		let page = &self.data[offset..self.data.len().min(offset + PAGE_SIZE)];
+		if page.len() == PAGE_SIZE && page.as_ptr().addr() % PAGE_SIZE == 0 {
+			if let Some(frame) = frame_of(page.as_ptr()) {
+				return Some(MappedPage::Frame(frame));
+			}
+		}
+
+		Some(MappedPage::Data(page))
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -63,32 +145,50 @@ impl ObjectInterface for RomFileInterface {
 	}
 
 	async fn async_read(&self, buf: &mut [u8]) -> Result<usize, IoError> {
-		{
-			let microseconds = arch::kernel::systemtime::now_micros();
-			let mut guard = self.inner.write().await;
-			guard.attr.st_atime = microseconds / 1_000_000;
-			guard.attr.st_atime_nsec = (microseconds % 1_000_000) * 1000;
-		}
+		let mut guard = self.inner.write().await;
+		guard.touch();
 
-		let vec = self.inner.read().await.data;
 		let mut pos_guard = self.pos.lock().await;
-		let pos = *pos_guard;
+		let len = read_at(guard.data, buf, *pos_guard);
+		*pos_guard += len;
 
-		if pos >= vec.len() {
-			return Ok(0);
-		}
+		Ok(len)
+	}
 
-		let len = if vec.len() - pos < buf.len() {
-			vec.len() - pos
-		} else {
-			buf.len()
-		};
+	async fn async_pread(&self, buf: &mut [u8], offset: usize) -> Result<usize, IoError> {
+		let mut guard = self.inner.write().await;
+		guard.touch();
 
-		buf[0..len].clone_from_slice(&vec[pos..pos + len]);
-		*pos_guard = pos + len;
+		Ok(read_at(guard.data, buf, offset))
+	}
+
+	async fn async_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
+		let mut guard = self.inner.write().await;
+		guard.touch();
+
+		let mut pos_guard = self.pos.lock().await;
+		let len = readv_at(guard.data, bufs, *pos_guard);
+		*pos_guard += len;
 
 		Ok(len)
 	}
+
+	fn mmap(
+		&self,
+		offset: usize,
+		len: usize,
+		writable: bool,
+	) -> Result<Arc<dyn MappedObject>, IoError> {
+		if writable {
+			return Err(IoError::EACCES);
+		}
+
+		let data = block_on(async { Ok(self.inner.read().await.data) }, None)?;
+		let data = &data[offset.min(data.len())..];
+		Ok(Arc::new(RomFileMapping {
+			data: &data[..len.min(data.len())],
+		}))
+	}
 }
 
 impl RomFileInterface {
@@ -104,19 +204,148 @@ impl RomFileInterface {
 	}
 }
 
+#[repr(C, align(4096))]
+struct Page([u8; PAGE_SIZE]);
+
+const _: () = assert!(mem::align_of::<Page>() == PAGE_SIZE);
+
+/// Content of a `RamFile`, which is stored in separate pages. The pages
+/// don't move, if the file grows, so that they can be shared with mappings.
+#[derive(Default)]
+pub(crate) struct PageBuffer {
+	pages: Vec<Box<Page>>,
+	len: usize,
+}
+
+impl fmt::Debug for PageBuffer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PageBuffer")
+			.field("len", &self.len)
+			.finish_non_exhaustive()
+	}
+}
+
+impl PageBuffer {
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Copies the content starting at `offset` into `buf` and returns the number of copied bytes
+	fn read_at(&self, buf: &mut [u8], offset: usize) -> usize {
+		if offset >= self.len {
+			return 0;
+		}
+
+		let len = core::cmp::min(self.len - offset, buf.len());
+		let mut pos = 0;
+		while pos < len {
+			let (index, start) = ((offset + pos) / PAGE_SIZE, (offset + pos) % PAGE_SIZE);
+			let count = core::cmp::min(PAGE_SIZE - start, len - pos);
+			buf[pos..pos + count].copy_from_slice(&self.pages[index].0[start..start + count]);
+			pos += count;
+		}
+
+		len
+	}
+
+	/// Writes `buf` at `offset` and allocates zeroed pages, if the buffer grows
+	fn write_at(&mut self, buf: &[u8], offset: usize) -> usize {
+		let end = offset + buf.len();
+		while self.pages.len() * PAGE_SIZE < end {
+			self.pages.push(Box::new(Page([0; PAGE_SIZE])));
+		}
+		self.len = self.len.max(end);
+
+		let mut pos = 0;
+		while pos < buf.len() {
+			let (index, start) = ((offset + pos) / PAGE_SIZE, (offset + pos) % PAGE_SIZE);
+			let count = core::cmp::min(PAGE_SIZE - start, buf.len() - pos);
+			self.pages[index].0[start..start + count].copy_from_slice(&buf[pos..pos + count]);
+			pos += count;
+		}
+
+		buf.len()
+	}
+
+	/// Returns the indices of the pages, which store the content in `range`
+	fn pages_of(&self, range: Range<usize>) -> Range<usize> {
+		let end = range.end.min(self.len).align_up(PAGE_SIZE);
+		range.start / PAGE_SIZE..end / PAGE_SIZE
+	}
+
+	/// Returns the frames of the pages, which store the content in `range`
+	fn frames(&self, range: Range<usize>) -> Vec<PhysAddr> {
+		self.pages_of(range)
+			.map(|index| frame_of(self.pages[index].0.as_ptr()).unwrap())
+			.collect()
+	}
+}
+
+/// Pages of a `RamFile`, which are shared with a mapping
+#[derive(Debug)]
+struct RamFileMapping {
+	frames: Vec<PhysAddr>,
+	/// Keeps the pages alive
+	_file: Arc<RwLock<RamFileInner>>,
+}
+
+impl MappedObject for RamFileMapping {
+	fn page(&self, offset: usize) -> Option<MappedPage<'_>> {
+		self.frames
+			.get(offset / PAGE_SIZE)
+			.map(|frame| MappedPage::Frame(*frame))
+	}
+}
+
 #[derive(Debug)]
 pub(crate) struct RamFileInner {
-	pub data: Vec<u8>,
+	pub data: PageBuffer,
 	pub attr: FileAttr,
+	/// Record locks of all open file descriptions
+	pub locks: LockTable,
+	/// Locks of `flock` of all open file descriptions
+	pub flocks: LockTable,
 }
 
 impl RamFileInner {
 	pub fn new(attr: FileAttr) -> Self {
 		Self {
-			data: Vec::new(),
+			data: PageBuffer::default(),
 			attr,
+			locks: LockTable::new(),
+			flocks: LockTable::new(),
+		}
+	}
+
+	/// Returns the lock table of the locks of type `ty`
+	fn table(&mut self, ty: LockType) -> &mut LockTable {
+		match ty {
+			LockType::Posix => &mut self.locks,
+			LockType::Flock => &mut self.flocks,
 		}
 	}
+
+	/// Updates the access time and, if the content is `modified`, the
+	/// modification and change time
+	fn touch(&mut self, modified: bool) {
+		let microseconds = arch::kernel::systemtime::now_micros();
+		self.attr.st_atime = microseconds / 1_000_000;
+		self.attr.st_atime_nsec = (microseconds % 1_000_000) * 1000;
+		if modified {
+			self.attr.st_mtime = self.attr.st_atime;
+			self.attr.st_mtime_nsec = self.attr.st_atime_nsec;
+			self.attr.st_ctime = self.attr.st_atime;
+			self.attr.st_ctime_nsec = self.attr.st_atime_nsec;
+		}
+	}
+
+	/// Writes `buf` at `offset` and extends the file, if required
+	fn write_at(&mut self, buf: &[u8], offset: usize) -> usize {
+		let len = self.data.write_at(buf, offset);
+		self.attr.st_size = self.data.len().try_into().unwrap();
+
+		len
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -143,54 +372,167 @@ impl ObjectInterface for RamFileInterface {
 	}
 
 	async fn async_read(&self, buf: &mut [u8]) -> Result<usize, IoError> {
-		{
-			let microseconds = arch::kernel::systemtime::now_micros();
-			let mut guard = self.inner.write().await;
-			guard.attr.st_atime = microseconds / 1_000_000;
-			guard.attr.st_atime_nsec = (microseconds % 1_000_000) * 1000;
-		}
+		let mut guard = self.inner.write().await;
+		guard.touch(false);
 
-		let guard = self.inner.read().await;
 		let mut pos_guard = self.pos.lock().await;
-		let pos = *pos_guard;
+		let len = guard.data.read_at(buf, *pos_guard);
+		*pos_guard += len;
 
-		if pos >= guard.data.len() {
-			return Ok(0);
-		}
+		Ok(len)
+	}
 
-		let len = if guard.data.len() - pos < buf.len() {
-			guard.data.len() - pos
-		} else {
-			buf.len()
-		};
+	async fn async_write(&self, buf: &[u8]) -> Result<usize, IoError> {
+		let mut guard = self.inner.write().await;
+		guard.touch(true);
+
+		let mut pos_guard = self.pos.lock().await;
+		let len = guard.write_at(buf, *pos_guard);
+		*pos_guard += len;
+
+		Ok(len)
+	}
 
-		buf[0..len].clone_from_slice(&guard.data[pos..pos + len]);
+	async fn async_append(&self, buf: &[u8]) -> Result<usize, IoError> {
+		self.async_appendv(&[buf]).await
+	}
+
+	async fn async_appendv(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		let mut guard = self.inner.write().await;
+		guard.touch(true);
+
+		// the end of the file can't change, while the content is locked
+		let mut pos_guard = self.pos.lock().await;
+		let pos = guard.data.len();
+		let mut len = 0;
+		for buf in bufs {
+			len += guard.write_at(buf, pos + len);
+		}
 		*pos_guard = pos + len;
 
 		Ok(len)
 	}
 
-	async fn async_write(&self, buf: &[u8]) -> Result<usize, IoError> {
-		let microseconds = arch::kernel::systemtime::now_micros();
+	async fn async_pread(&self, buf: &mut [u8], offset: usize) -> Result<usize, IoError> {
+		let mut guard = self.inner.write().await;
+		guard.touch(false);
+
+		Ok(guard.data.read_at(buf, offset))
+	}
+
+	async fn async_pwrite(&self, buf: &[u8], offset: usize) -> Result<usize, IoError> {
+		let mut guard = self.inner.write().await;
+		guard.touch(true);
+
+		Ok(guard.write_at(buf, offset))
+	}
+
+	async fn async_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize, IoError> {
 		let mut guard = self.inner.write().await;
+		guard.touch(false);
+
+		let mut pos_guard = self.pos.lock().await;
+		let len = readv_with(bufs, *pos_guard, |buf, pos| guard.data.read_at(buf, pos));
+		*pos_guard += len;
+
+		Ok(len)
+	}
+
+	async fn async_writev(&self, bufs: &[&[u8]]) -> Result<usize, IoError> {
+		let mut guard = self.inner.write().await;
+		guard.touch(true);
+
+		// all buffers are written at once and can't be interleaved with other writes
 		let mut pos_guard = self.pos.lock().await;
 		let pos = *pos_guard;
+		let mut len = 0;
+		for buf in bufs {
+			len += guard.write_at(buf, pos + len);
+		}
+		*pos_guard = pos + len;
 
-		if pos + buf.len() > guard.data.len() {
-			guard.data.resize(pos + buf.len(), 0);
-			guard.attr.st_size = guard.data.len().try_into().unwrap();
+		Ok(len)
+	}
+
+	fn lseek(&self, offset: isize, whence: SeekWhence) -> Result<isize, IoError> {
+		block_on(
+			async {
+				let len: isize = self.inner.read().await.data.len().try_into().unwrap();
+				let mut pos_guard = self.pos.lock().await;
+				let pos: isize = (*pos_guard).try_into().unwrap();
+
+				let new_pos = match whence {
+					SeekWhence::Set => offset,
+					SeekWhence::Cur => pos + offset,
+					SeekWhence::End => len + offset,
+					_ => return Err(IoError::EINVAL),
+				};
+				if new_pos < 0 {
+					return Err(IoError::EINVAL);
+				}
+
+				*pos_guard = new_pos.try_into().unwrap();
+				Ok(new_pos)
+			},
+			None,
+		)
+	}
+
+	fn fstat(&self, stat: &mut FileAttr) -> Result<(), IoError> {
+		*stat = block_on(async { Ok(self.inner.read().await.attr) }, None)?;
+		Ok(())
+	}
+
+	async fn getlk(&self, lock: FileLock) -> Result<FileLock, IoError> {
+		let guard = self.inner.read().await;
+
+		Ok(guard
+			.locks
+			.conflict(self.lock_owner(), &lock)
+			.unwrap_or(FileLock {
+				kind: LockKind::Unlock,
+				..lock
+			}))
+	}
+
+	async fn setlk(&self, lock: FileLock, ty: LockType, wait: bool) -> Result<(), IoError> {
+		let owner = self.lock_owner();
+
+		loop {
+			let mut guard = self.inner.write().await;
+			match guard.table(ty).set(owner, lock) {
+				Err(IoError::EAGAIN) if wait => {
+					// register as waiter and release the file, before the task is suspended
+					let mut guard = Some(guard);
+					future::poll_fn(|cx| match guard.take() {
+						Some(mut guard) => {
+							guard.table(ty).wait(cx.waker());
+							Poll::Pending
+						}
+						None => Poll::Ready(()),
+					})
+					.await;
+				}
+				result => return result,
+			}
 		}
-		guard.attr.st_atime = microseconds / 1_000_000;
-		guard.attr.st_atime_nsec = (microseconds % 1_000_000) * 1000;
-		guard.attr.st_mtime = guard.attr.st_atime;
-		guard.attr.st_mtime_nsec = guard.attr.st_atime_nsec;
-		guard.attr.st_ctime = guard.attr.st_atime;
-		guard.attr.st_ctime_nsec = guard.attr.st_atime_nsec;
+	}
 
-		guard.data[pos..pos + buf.len()].clone_from_slice(buf);
-		*pos_guard = pos + buf.len();
+	fn mmap(
+		&self,
+		offset: usize,
+		len: usize,
+		_writable: bool,
+	) -> Result<Arc<dyn MappedObject>, IoError> {
+		let frames = block_on(
+			async { Ok(self.inner.read().await.data.frames(offset..offset + len)) },
+			None,
+		)?;
 
-		Ok(buf.len())
+		Ok(Arc::new(RamFileMapping {
+			frames,
+			_file: self.inner.clone(),
+		}))
 	}
 }
 
@@ -202,12 +544,35 @@ impl RamFileInterface {
 		}
 	}
 
+	/// Identifies the open file description, which owns a lock
+	fn lock_owner(&self) -> usize {
+		Arc::as_ptr(&self.pos).addr()
+	}
+
 	pub fn len(&self) -> usize {
 		block_on(async { Ok(self.inner.read().await.data.len()) }, None).unwrap()
 	}
 }
 
-#[derive(Debug)]
+impl Drop for RamFileInterface {
+	fn drop(&mut self) {
+		// all clones share the position and belong to the same open file description
+		if Arc::strong_count(&self.pos) == 1 {
+			let owner = self.lock_owner();
+			let _ = block_on(
+				async {
+					let mut guard = self.inner.write().await;
+					guard.locks.release(owner);
+					guard.flocks.release(owner);
+					Ok(())
+				},
+				None,
+			);
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct RomFile {
 	data: Arc<RwLock<RomFileInner>>,
 }
@@ -217,6 +582,12 @@ impl VfsNode for RomFile {
 		NodeKind::File
 	}
 
+	fn hard_link(
+		&self,
+	) -> Result<Box<dyn VfsNode + core::marker::Send + core::marker::Sync>, IoError> {
+		Ok(Box::new(self.clone()))
+	}
+
 	fn get_object(&self) -> Result<Arc<dyn ObjectInterface>, IoError> {
 		Ok(Arc::new(RomFileInterface::new(self.data.clone())))
 	}
@@ -261,6 +632,13 @@ impl RomFile {
 			data: unsafe { Arc::new(RwLock::new(RomFileInner::new(ptr, length, attr))) },
 		}
 	}
+
+	/// Creates a read-only file with the given attributes, e.g. taken from an archive.
+	pub unsafe fn with_attr(ptr: *const u8, length: usize, attr: FileAttr) -> Self {
+		Self {
+			data: unsafe { Arc::new(RwLock::new(RomFileInner::new(ptr, length, attr))) },
+		}
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -273,6 +651,12 @@ impl VfsNode for RamFile {
 		NodeKind::File
 	}
 
+	fn hard_link(
+		&self,
+	) -> Result<Box<dyn VfsNode + core::marker::Send + core::marker::Sync>, IoError> {
+		Ok(Box::new(self.clone()))
+	}
+
 	fn get_object(&self) -> Result<Arc<dyn ObjectInterface>, IoError> {
 		Ok(Arc::new(RamFileInterface::new(self.data.clone())))
 	}
@@ -318,10 +702,74 @@ impl RamFile {
 	}
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct Symlink {
+	/// Path, to which the link refers
+	target: String,
+	attr: FileAttr,
+}
+
+impl VfsNode for Symlink {
+	fn get_kind(&self) -> NodeKind {
+		NodeKind::Symlink
+	}
+
+	fn get_file_attributes(&self) -> Result<FileAttr, IoError> {
+		Ok(self.attr)
+	}
+
+	fn hard_link(
+		&self,
+	) -> Result<Box<dyn VfsNode + core::marker::Send + core::marker::Sync>, IoError> {
+		Ok(Box::new(self.clone()))
+	}
+
+	fn traverse_lstat(&self, components: &mut Vec<&str>) -> Result<FileAttr, IoError> {
+		if components.is_empty() {
+			self.get_file_attributes()
+		} else {
+			Err(IoError::ENOTDIR)
+		}
+	}
+
+	fn traverse_readlink(&self, components: &mut Vec<&str>) -> Result<String, IoError> {
+		if components.is_empty() {
+			Ok(self.target.clone())
+		} else {
+			Err(IoError::ENOTDIR)
+		}
+	}
+}
+
+impl Symlink {
+	pub fn new(target: &str) -> Self {
+		let microseconds = arch::kernel::systemtime::now_micros();
+		let attr = FileAttr {
+			st_size: target.len().try_into().unwrap(),
+			st_mode: AccessPermission::from_bits(0o777).unwrap() | AccessPermission::S_IFLNK,
+			st_atime: microseconds / 1_000_000,
+			st_atime_nsec: (microseconds % 1_000_000) * 1000,
+			st_mtime: microseconds / 1_000_000,
+			st_mtime_nsec: (microseconds % 1_000_000) * 1000,
+			st_ctime: microseconds / 1_000_000,
+			st_ctime_nsec: (microseconds % 1_000_000) * 1000,
+			..Default::default()
+		};
+
+		Self {
+			target: target.to_string(),
+			attr,
+		}
+	}
+}
+
+/// Content of an in-memory directory
+pub(crate) type DirectoryMap =
+	Arc<RwLock<BTreeMap<String, Box<dyn VfsNode + core::marker::Send + core::marker::Sync>>>>;
+
 #[derive(Debug)]
 pub(crate) struct MemDirectory {
-	inner:
-		Arc<RwLock<BTreeMap<String, Box<dyn VfsNode + core::marker::Send + core::marker::Sync>>>>,
+	inner: DirectoryMap,
 	attr: FileAttr,
 }
 
@@ -344,6 +792,14 @@ impl MemDirectory {
 		}
 	}
 
+	/// Creates an empty directory with the given attributes, e.g. taken from an archive.
+	pub fn with_attr(attr: FileAttr) -> Self {
+		Self {
+			inner: Arc::new(RwLock::new(BTreeMap::new())),
+			attr,
+		}
+	}
+
 	async fn async_traverse_open(
 		&self,
 		components: &mut Vec<&str>,
@@ -458,7 +914,7 @@ impl VfsNode for MemDirectory {
 						let mut guard = self.inner.write().await;
 
 						let obj = guard.remove(&node_name).ok_or(IoError::ENOENT)?;
-						if obj.get_kind() == NodeKind::File {
+						if obj.get_kind() != NodeKind::Directory {
 							return Ok(());
 						} else {
 							guard.insert(node_name, obj);
@@ -517,7 +973,7 @@ impl VfsNode for MemDirectory {
 						Err(IoError::EBADF)
 					}
 				} else {
-					Err(IoError::ENOSYS)
+					Ok(self.attr)
 				}
 			},
 			None,
@@ -542,7 +998,7 @@ impl VfsNode for MemDirectory {
 						Err(IoError::EBADF)
 					}
 				} else {
-					Err(IoError::ENOSYS)
+					Ok(self.attr)
 				}
 			},
 			None,
@@ -615,4 +1071,312 @@ impl VfsNode for MemDirectory {
 			None,
 		)
 	}
+	fn traverse_rename(&self, from: &mut Vec<&str>, to: &mut Vec<&str>) -> Result<(), IoError> {
+		block_on(
+			async {
+				// both paths are located in the same subdirectory
+				if from.len() > 1 && to.len() > 1 && from.last() == to.last() {
+					let node_name = String::from(from.pop().unwrap());
+					to.pop();
+
+					if let Some(directory) = self.inner.read().await.get(&node_name) {
+						return directory.traverse_rename(from, to);
+					}
+
+					return Err(IoError::ENOENT);
+				}
+
+				if from.is_empty() || to.is_empty() {
+					return Err(IoError::EINVAL);
+				}
+
+				// a directory cannot become a subdirectory of itself
+				if to.len() > from.len() && to.ends_with(from.as_slice()) {
+					return Err(IoError::EINVAL);
+				}
+
+				let (src_dir, src_name) = self.traverse_parent(from)?;
+				let (dst_dir, dst_name) = self.traverse_parent(to)?;
+
+				if Arc::ptr_eq(&src_dir, &dst_dir) {
+					let mut guard = src_dir.write().await;
+					let node = guard.get(&src_name).ok_or(IoError::ENOENT)?;
+					check_replace(node.as_ref(), guard.get(&dst_name).map(|n| n.as_ref()))?;
+
+					if src_name != dst_name {
+						let node = guard.remove(&src_name).unwrap();
+						guard.insert(dst_name, node);
+					}
+				} else {
+					// always lock the directories in the same order to avoid deadlocks
+					let (mut src_guard, mut dst_guard) =
+						if Arc::as_ptr(&src_dir) < Arc::as_ptr(&dst_dir) {
+							let src_guard = src_dir.write().await;
+							(src_guard, dst_dir.write().await)
+						} else {
+							let dst_guard = dst_dir.write().await;
+							(src_dir.write().await, dst_guard)
+						};
+
+					let node = src_guard.get(&src_name).ok_or(IoError::ENOENT)?;
+					check_replace(node.as_ref(), dst_guard.get(&dst_name).map(|n| n.as_ref()))?;
+
+					let node = src_guard.remove(&src_name).unwrap();
+					dst_guard.insert(dst_name, node);
+				}
+
+				Ok(())
+			},
+			None,
+		)
+	}
+
+	fn traverse_link(&self, from: &mut Vec<&str>, to: &mut Vec<&str>) -> Result<(), IoError> {
+		block_on(
+			async {
+				// both paths are located in the same subdirectory
+				if from.len() > 1 && to.len() > 1 && from.last() == to.last() {
+					let node_name = String::from(from.pop().unwrap());
+					to.pop();
+
+					if let Some(directory) = self.inner.read().await.get(&node_name) {
+						return directory.traverse_link(from, to);
+					}
+
+					return Err(IoError::ENOENT);
+				}
+
+				let (src_dir, src_name) = self.traverse_parent(from)?;
+				let (dst_dir, dst_name) = self.traverse_parent(to)?;
+
+				let node = src_dir
+					.read()
+					.await
+					.get(&src_name)
+					.ok_or(IoError::ENOENT)?
+					.hard_link()?;
+
+				let mut guard = dst_dir.write().await;
+				if guard.contains_key(&dst_name) {
+					return Err(IoError::EEXIST);
+				}
+				guard.insert(dst_name, node);
+
+				Ok(())
+			},
+			None,
+		)
+	}
+
+	fn traverse_symlink(&self, target: &str, components: &mut Vec<&str>) -> Result<(), IoError> {
+		block_on(
+			async {
+				if let Some(component) = components.pop() {
+					let node_name = String::from(component);
+
+					if components.is_empty() {
+						let mut guard = self.inner.write().await;
+						if guard.contains_key(&node_name) {
+							return Err(IoError::EEXIST);
+						}
+						guard.insert(node_name, Box::new(Symlink::new(target)));
+						return Ok(());
+					}
+
+					if let Some(directory) = self.inner.read().await.get(&node_name) {
+						return directory.traverse_symlink(target, components);
+					}
+				}
+
+				Err(IoError::ENOENT)
+			},
+			None,
+		)
+	}
+
+	fn traverse_readlink(&self, components: &mut Vec<&str>) -> Result<String, IoError> {
+		block_on(
+			async {
+				if let Some(component) = components.pop() {
+					let node_name = String::from(component);
+
+					if let Some(node) = self.inner.read().await.get(&node_name) {
+						if components.is_empty() && node.get_kind() != NodeKind::Symlink {
+							return Err(IoError::EINVAL);
+						}
+
+						return node.traverse_readlink(components);
+					}
+
+					Err(IoError::ENOENT)
+				} else {
+					Err(IoError::EINVAL)
+				}
+			},
+			None,
+		)
+	}
+
+	fn traverse_resolve(&self, resolution: &mut Resolution) -> Result<Walk, IoError> {
+		block_on(
+			async {
+				loop {
+					let Some(component) = resolution.next_component() else {
+						return Ok(resolution.stop());
+					};
+
+					let guard = self.inner.read().await;
+					let walk = match guard.get(&component) {
+						Some(node)
+							if node.get_kind() == NodeKind::Symlink && resolution.follows() =>
+						{
+							// the link is replaced by its target, which is
+							// relative to this directory
+							let target = node.traverse_readlink(&mut Vec::new())?;
+							if resolution.follow_link(&target)? {
+								return Ok(Walk::Root);
+							}
+							continue;
+						}
+						Some(node) if node.get_kind() == NodeKind::Directory => {
+							resolution.push(component);
+							node.traverse_resolve(resolution)?
+						}
+						// files and missing nodes don't contain links
+						_ => {
+							resolution.push(component);
+							resolution.walk_lexically()
+						}
+					};
+
+					match walk {
+						Walk::Parent => resolution.leave(),
+						walk => return Ok(walk),
+					}
+				}
+			},
+			None,
+		)
+	}
+
+	fn traverse_parent(
+		&self,
+		components: &mut Vec<&str>,
+	) -> Result<(DirectoryMap, String), IoError> {
+		block_on(
+			async {
+				if let Some(component) = components.pop() {
+					let node_name = String::from(component);
+
+					if components.is_empty() {
+						return Ok((self.inner.clone(), node_name));
+					}
+
+					if let Some(node) = self.inner.read().await.get(&node_name) {
+						if node.get_kind() != NodeKind::Directory {
+							return Err(IoError::ENOTDIR);
+						}
+
+						return node.traverse_parent(components);
+					}
+
+					Err(IoError::ENOENT)
+				} else {
+					Err(IoError::EINVAL)
+				}
+			},
+			None,
+		)
+	}
+}
+
+/// Checks if `node` is allowed to replace `target` by renaming it.
+fn check_replace(
+	node: &(dyn VfsNode + core::marker::Send + core::marker::Sync),
+	target: Option<&(dyn VfsNode + core::marker::Send + core::marker::Sync)>,
+) -> Result<(), IoError> {
+	let Some(target) = target else {
+		return Ok(());
+	};
+
+	match (node.get_kind(), target.get_kind()) {
+		(NodeKind::Directory, NodeKind::Directory) => {
+			if target.traverse_readdir(&mut Vec::new())?.is_empty() {
+				Ok(())
+			} else {
+				Err(IoError::ENOTEMPTY)
+			}
+		}
+		(NodeKind::Directory, _) => Err(IoError::ENOTDIR),
+		(_, NodeKind::Directory) => Err(IoError::EISDIR),
+		_ => Ok(()),
+	}
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn write_across_pages() {
+		let mut buffer = PageBuffer::default();
+		let data: Vec<u8> = (0..2 * PAGE_SIZE).map(|i| i as u8).collect();
+		assert_eq!(buffer.write_at(&data, PAGE_SIZE - 10), data.len());
+		assert_eq!(buffer.len(), 3 * PAGE_SIZE - 10);
+		assert_eq!(buffer.pages.len(), 3);
+
+		let mut buf = vec![0xff; data.len()];
+		assert_eq!(buffer.read_at(&mut buf, PAGE_SIZE - 10), data.len());
+		assert_eq!(buf, data);
+
+		// the content in front of the written data is zeroed
+		let mut buf = [0xff; 16];
+		assert_eq!(buffer.read_at(&mut buf, PAGE_SIZE - 16), 16);
+		assert_eq!(buf[..6], [0; 6]);
+		assert_eq!(buf[6..], data[..10]);
+	}
+
+	#[test]
+	fn read_past_end() {
+		let mut buffer = PageBuffer::default();
+		buffer.write_at(b"hello", PAGE_SIZE - 2);
+
+		let mut buf = [0; 8];
+		assert_eq!(buffer.read_at(&mut buf, PAGE_SIZE - 1), 4);
+		assert_eq!(&buf[..4], b"ello");
+		assert_eq!(buffer.read_at(&mut buf, PAGE_SIZE + 3), 0);
+		assert_eq!(buffer.read_at(&mut buf, 10 * PAGE_SIZE), 0);
+	}
+
+	#[test]
+	fn grow() {
+		let mut buffer = PageBuffer::default();
+		assert_eq!(buffer.len(), 0);
+
+		buffer.write_at(b"abc", 0);
+		assert_eq!((buffer.len(), buffer.pages.len()), (3, 1));
+		buffer.write_at(b"x", 1);
+		assert_eq!(buffer.len(), 3);
+
+		// the pages don't move, if the buffer grows
+		let first = buffer.pages[0].0.as_ptr();
+		buffer.write_at(b"end", 4 * PAGE_SIZE);
+		assert_eq!((buffer.len(), buffer.pages.len()), (4 * PAGE_SIZE + 3, 5));
+		assert_eq!(buffer.pages[0].0.as_ptr(), first);
+
+		let mut buf = [0xff; 4];
+		assert_eq!(buffer.read_at(&mut buf, 0), 4);
+		assert_eq!(&buf, b"axc\0");
+	}
+
+	#[test]
+	fn pages_of() {
+		let mut buffer = PageBuffer::default();
+		buffer.write_at(&[1; 10], 2 * PAGE_SIZE);
+
+		assert_eq!(buffer.pages_of(0..PAGE_SIZE), 0..1);
+		assert_eq!(buffer.pages_of(PAGE_SIZE..2 * PAGE_SIZE + 1), 1..3);
+		assert_eq!(buffer.pages_of(0..10 * PAGE_SIZE), 0..3);
+		assert!(buffer.pages_of(4 * PAGE_SIZE..5 * PAGE_SIZE).is_empty());
+	}
 }
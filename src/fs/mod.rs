@@ -1,6 +1,10 @@
+#[cfg(feature = "blk")]
+mod fat;
 #[cfg(all(feature = "fuse", feature = "pci"))]
 pub(crate) mod fuse;
 mod fuse_abi;
+#[cfg(feature = "initramfs")]
+mod initramfs;
 mod mem;
 mod uhyve;
 
@@ -10,8 +14,9 @@ use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 use hermit_sync::OnceCell;
-use mem::MemDirectory;
+use mem::{DirectoryMap, MemDirectory};
 
+use crate::arch::kernel::core_local::core_scheduler;
 use crate::fd::{
 	insert_object, remove_object, AccessPermission, IoError, ObjectInterface, OpenOption,
 };
@@ -38,6 +43,8 @@ pub(crate) enum NodeKind {
 	File,
 	/// Node represent a directory
 	Directory,
+	/// Node represent a symbolic link
+	Symlink,
 }
 
 /// VfsNode represents an internal node of the ramdisk.
@@ -121,20 +128,194 @@ pub(crate) trait VfsNode: core::fmt::Debug {
 	) -> Result<(), IoError> {
 		Err(IoError::ENOSYS)
 	}
+
+	/// Helper function to rename a node, an existing destination will be replaced
+	fn traverse_rename(&self, _from: &mut Vec<&str>, _to: &mut Vec<&str>) -> Result<(), IoError> {
+		Err(IoError::ENOSYS)
+	}
+
+	/// Helper function to create a hard link
+	fn traverse_link(&self, _from: &mut Vec<&str>, _to: &mut Vec<&str>) -> Result<(), IoError> {
+		Err(IoError::ENOSYS)
+	}
+
+	/// Helper function to create a symbolic link
+	fn traverse_symlink(&self, _target: &str, _components: &mut Vec<&str>) -> Result<(), IoError> {
+		Err(IoError::ENOSYS)
+	}
+
+	/// Helper function to read the target of a symbolic link
+	fn traverse_readlink(&self, _components: &mut Vec<&str>) -> Result<String, IoError> {
+		Err(IoError::ENOSYS)
+	}
+
+	/// Helper function to resolve the remaining components of `resolution`
+	/// below the node. By default, the node doesn't contain symbolic links
+	/// and the components are appended as they are.
+	fn traverse_resolve(&self, resolution: &mut Resolution) -> Result<Walk, IoError> {
+		Ok(resolution.walk_lexically())
+	}
+
+	/// Helper function to determine the in-memory directory, which contains
+	/// the node. Returns the directory and the name of the node.
+	fn traverse_parent(
+		&self,
+		_components: &mut Vec<&str>,
+	) -> Result<(DirectoryMap, String), IoError> {
+		Err(IoError::EXDEV)
+	}
+
+	/// Creates a new node, which shares the content with the current node
+	fn hard_link(
+		&self,
+	) -> Result<Box<dyn VfsNode + core::marker::Send + core::marker::Sync>, IoError> {
+		Err(IoError::EPERM)
+	}
 }
 
 #[derive(Debug, Clone)]
-struct DirectoryReader(Vec<DirectoryEntry>);
+struct DirectoryReader {
+	/// Absolute path of the directory
+	path: String,
+	entries: Vec<DirectoryEntry>,
+}
 
 impl DirectoryReader {
-	pub fn new(data: Vec<DirectoryEntry>) -> Self {
-		Self(data)
+	pub fn new(path: String, entries: Vec<DirectoryEntry>) -> Self {
+		Self { path, entries }
 	}
 }
 
 impl ObjectInterface for DirectoryReader {
 	fn readdir(&self) -> Result<Vec<DirectoryEntry>, IoError> {
-		Ok(self.0.clone())
+		Ok(self.entries.clone())
+	}
+
+	fn get_path(&self) -> Result<String, IoError> {
+		Ok(self.path.clone())
+	}
+}
+
+/// Maximum number of symbolic links, which are followed while resolving a path
+const MAX_SYMLINKS: usize = 40;
+
+/// Splits an absolute path into its components in reverse order, as
+/// expected by the `traverse_*` functions of a [`VfsNode`].
+fn components(path: &str) -> Vec<&str> {
+	path.split('/').filter(|c| !c.is_empty()).rev().collect()
+}
+
+/// Position, at which [`VfsNode::traverse_resolve`] returns to its caller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Walk {
+	/// All components are resolved
+	Done,
+	/// The next component is `..`, which leaves the node
+	Parent,
+	/// A symbolic link with an absolute target restarts the walk at the root
+	Root,
+}
+
+/// State of a path, which is resolved node by node
+#[derive(Debug)]
+pub(crate) struct Resolution {
+	/// Components, which aren't resolved yet, in reverse order
+	remaining: Vec<String>,
+	/// Components, which are resolved
+	resolved: Vec<String>,
+	/// The last component is followed, if it is a symbolic link
+	follow: bool,
+	/// Number of followed symbolic links
+	links: usize,
+}
+
+impl Resolution {
+	fn new(path: &str, follow: bool) -> Self {
+		Self {
+			remaining: components(path).into_iter().map(String::from).collect(),
+			resolved: Vec::new(),
+			follow,
+			links: 0,
+		}
+	}
+
+	/// Takes the next component. `.` is skipped, while `..` is left for the
+	/// caller.
+	pub fn next_component(&mut self) -> Option<String> {
+		loop {
+			let component = self.remaining.pop()?;
+			match component.as_str() {
+				"." => {}
+				".." => {
+					self.remaining.push(component);
+					return None;
+				}
+				_ => return Some(component),
+			}
+		}
+	}
+
+	/// Returns the walk, which has stopped in front of the next component
+	pub fn stop(&self) -> Walk {
+		if self.remaining.is_empty() {
+			Walk::Done
+		} else {
+			Walk::Parent
+		}
+	}
+
+	/// Leaves the last resolved component, after the walk below it has
+	/// returned [`Walk::Parent`]
+	pub fn leave(&mut self) {
+		self.remaining.pop();
+		self.resolved.pop();
+	}
+
+	/// Returns true, if a symbolic link in front of the remaining components is followed
+	pub fn follows(&self) -> bool {
+		self.follow || !self.remaining.is_empty()
+	}
+
+	/// Adds a resolved component
+	pub fn push(&mut self, component: String) {
+		self.resolved.push(component);
+	}
+
+	/// Replaces a symbolic link by its target. Returns true, if the target
+	/// is absolute and the walk restarts at the root.
+	pub fn follow_link(&mut self, target: &str) -> Result<bool, IoError> {
+		self.links += 1;
+		if self.links > MAX_SYMLINKS {
+			return Err(IoError::ELOOP);
+		}
+
+		self.remaining
+			.extend(components(target).into_iter().map(String::from));
+		if target.starts_with('/') {
+			self.resolved.clear();
+			Ok(true)
+		} else {
+			Ok(false)
+		}
+	}
+
+	/// Appends the remaining components without following symbolic links,
+	/// e.g., below a component, which doesn't exist. Returns [`Walk::Parent`]
+	/// at a `..`, which leaves the node.
+	pub fn walk_lexically(&mut self) -> Walk {
+		let mut depth = 0;
+		loop {
+			while let Some(component) = self.next_component() {
+				depth += 1;
+				self.resolved.push(component);
+			}
+			if depth == 0 || self.remaining.is_empty() {
+				return self.stop();
+			}
+
+			depth -= 1;
+			self.leave();
+		}
 	}
 }
 
@@ -150,6 +331,41 @@ impl Filesystem {
 		}
 	}
 
+	/// Converts `path` into an absolute path without `.` and `..` components.
+	///
+	/// Relative paths are resolved against the current working directory of
+	/// the running task. Symbolic links are followed, the last component only
+	/// if `follow` is set.
+	pub fn resolve(&self, path: &str, follow: bool) -> Result<String, IoError> {
+		if path.is_empty() {
+			return Err(IoError::ENOENT);
+		}
+
+		let mut resolution = Resolution::new(path, follow);
+		if !path.starts_with('/') {
+			// the walk starts at the root => prepend the working directory
+			let cwd = core_scheduler().get_current_task_cwd();
+			resolution
+				.remaining
+				.extend(components(&cwd).into_iter().map(String::from));
+		}
+
+		// every node walks its own components, so that a symbolic link is
+		// looked up in its directory and not from the root
+		loop {
+			match self.root.traverse_resolve(&mut resolution)? {
+				Walk::Done => break,
+				// the root is its own parent
+				Walk::Parent => {
+					resolution.remaining.pop();
+				}
+				Walk::Root => {}
+			}
+		}
+
+		Ok("/".to_string() + &resolution.resolved.join("/"))
+	}
+
 	/// Tries to open file at given path.
 	pub fn open(
 		&self,
@@ -158,87 +374,64 @@ impl Filesystem {
 		mode: AccessPermission,
 	) -> Result<Arc<dyn ObjectInterface>, IoError> {
 		debug!("Open file {} with {:?}", path, opt);
-		let mut components: Vec<&str> = path.split('/').collect();
+		let path = self.resolve(path, true)?;
 
-		components.reverse();
-		components.pop();
-
-		self.root.traverse_open(&mut components, opt, mode)
+		self.root.traverse_open(&mut components(&path), opt, mode)
 	}
 
 	/// Unlinks a file given by path
 	pub fn unlink(&self, path: &str) -> Result<(), IoError> {
 		debug!("Unlinking file {}", path);
-		let mut components: Vec<&str> = path.split('/').collect();
-
-		components.reverse();
-		components.pop();
+		let path = self.resolve(path, false)?;
 
-		self.root.traverse_unlink(&mut components)
+		self.root.traverse_unlink(&mut components(&path))
 	}
 
 	/// Remove directory given by path
 	pub fn rmdir(&self, path: &str) -> Result<(), IoError> {
 		debug!("Removing directory {}", path);
-		let mut components: Vec<&str> = path.split('/').collect();
+		let path = self.resolve(path, false)?;
 
-		components.reverse();
-		components.pop();
-
-		self.root.traverse_rmdir(&mut components)
+		self.root.traverse_rmdir(&mut components(&path))
 	}
 
 	/// Create directory given by path
 	pub fn mkdir(&self, path: &str, mode: AccessPermission) -> Result<(), IoError> {
 		debug!("Create directory {}", path);
-		let mut components: Vec<&str> = path.split('/').collect();
-
-		components.reverse();
-		components.pop();
+		let path = self.resolve(path, false)?;
 
-		self.root.traverse_mkdir(&mut components, mode)
+		self.root.traverse_mkdir(&mut components(&path), mode)
 	}
 
 	pub fn opendir(&self, path: &str) -> Result<Arc<dyn ObjectInterface>, IoError> {
 		debug!("Open directory {}", path);
-		Ok(Arc::new(DirectoryReader::new(self.readdir(path)?)))
+		let path = self.resolve(path, true)?;
+		let entries = self.root.traverse_readdir(&mut components(&path))?;
+
+		Ok(Arc::new(DirectoryReader::new(path, entries)))
 	}
 
 	/// List given directory
 	pub fn readdir(&self, path: &str) -> Result<Vec<DirectoryEntry>, IoError> {
-		if path.trim() == "/" {
-			let mut components: Vec<&str> = Vec::new();
-			self.root.traverse_readdir(&mut components)
-		} else {
-			let mut components: Vec<&str> = path.split('/').collect();
-
-			components.reverse();
-			components.pop();
+		let path = self.resolve(path, true)?;
 
-			self.root.traverse_readdir(&mut components)
-		}
+		self.root.traverse_readdir(&mut components(&path))
 	}
 
 	/// stat
 	pub fn stat(&self, path: &str) -> Result<FileAttr, IoError> {
 		debug!("Getting stats {}", path);
+		let path = self.resolve(path, true)?;
 
-		let mut components: Vec<&str> = path.split('/').collect();
-		components.reverse();
-		components.pop();
-
-		self.root.traverse_stat(&mut components)
+		self.root.traverse_stat(&mut components(&path))
 	}
 
 	/// lstat
 	pub fn lstat(&self, path: &str) -> Result<FileAttr, IoError> {
 		debug!("Getting lstats {}", path);
+		let path = self.resolve(path, false)?;
 
-		let mut components: Vec<&str> = path.split('/').collect();
-		components.reverse();
-		components.pop();
-
-		self.root.traverse_lstat(&mut components)
+		self.root.traverse_lstat(&mut components(&path))
 	}
 
 	/// Create new backing-fs at mountpoint mntpath
@@ -248,13 +441,45 @@ impl Filesystem {
 		obj: Box<dyn VfsNode + core::marker::Send + core::marker::Sync>,
 	) -> Result<(), IoError> {
 		debug!("Mounting {}", path);
+		let path = self.resolve(path, false)?;
+
+		self.root.traverse_mount(&mut components(&path), obj)
+	}
+
+	/// Renames a file or directory, an existing destination will be replaced atomically
+	pub fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+		debug!("Rename {} to {}", from, to);
+		let from = self.resolve(from, false)?;
+		let to = self.resolve(to, false)?;
 
-		let mut components: Vec<&str> = path.split('/').collect();
+		self.root
+			.traverse_rename(&mut components(&from), &mut components(&to))
+	}
+
+	/// Creates a new hard link `to` for the file `from`
+	pub fn link(&self, from: &str, to: &str) -> Result<(), IoError> {
+		debug!("Link {} to {}", to, from);
+		let from = self.resolve(from, false)?;
+		let to = self.resolve(to, false)?;
+
+		self.root
+			.traverse_link(&mut components(&from), &mut components(&to))
+	}
+
+	/// Creates a symbolic link at path, which points to target
+	pub fn symlink(&self, target: &str, path: &str) -> Result<(), IoError> {
+		debug!("Create symbolic link {} -> {}", path, target);
+		let path = self.resolve(path, false)?;
 
-		components.reverse();
-		components.pop();
+		self.root.traverse_symlink(target, &mut components(&path))
+	}
+
+	/// Returns the target of the symbolic link given by path
+	pub fn readlink(&self, path: &str) -> Result<String, IoError> {
+		debug!("Read symbolic link {}", path);
+		let path = self.resolve(path, false)?;
 
-		self.root.traverse_mount(&mut components, obj)
+		self.root.traverse_readlink(&mut components(&path))
 	}
 
 	/// Create read-only file
@@ -266,14 +491,10 @@ impl Filesystem {
 		mode: AccessPermission,
 	) -> Result<(), IoError> {
 		debug!("Create read-only file {}", path);
-
-		let mut components: Vec<&str> = path.split('/').collect();
-
-		components.reverse();
-		components.pop();
+		let path = self.resolve(path, false)?;
 
 		self.root
-			.traverse_create_file(&mut components, ptr, length, mode)
+			.traverse_create_file(&mut components(&path), ptr, length, mode)
 	}
 }
 
@@ -354,8 +575,13 @@ pub(crate) fn init() {
 		error!("Unable to create /proc/version");
 	}
 
+	#[cfg(feature = "initramfs")]
+	initramfs::init();
+
 	#[cfg(all(feature = "fuse", feature = "pci"))]
 	fuse::init();
+	#[cfg(feature = "blk")]
+	fat::init();
 	uhyve::init();
 }
 
@@ -386,6 +612,32 @@ pub(crate) fn opendir(name: &str) -> Result<FileDescriptor, IoError> {
 	insert_object(obj)
 }
 
+/// Returns the current working directory of the running task
+pub(crate) fn getcwd() -> String {
+	core_scheduler().get_current_task_cwd()
+}
+
+/// Changes the current working directory of the running task
+pub(crate) fn chdir(path: &str) -> Result<(), IoError> {
+	let fs = FILESYSTEM.get().unwrap();
+	let path = fs.resolve(path, true)?;
+
+	let attr = fs.stat(&path)?;
+	if attr.st_mode.intersection(AccessPermission::S_IFMT) != AccessPermission::S_IFDIR {
+		return Err(IoError::ENOTDIR);
+	}
+
+	core_scheduler().set_current_task_cwd(path);
+	Ok(())
+}
+
+/// Changes the current working directory to the directory referenced by `fd`
+pub(crate) fn fchdir(fd: FileDescriptor) -> Result<(), IoError> {
+	let path = fd::get_object(fd)?.get_path()?;
+	core_scheduler().set_current_task_cwd(path);
+	Ok(())
+}
+
 use crate::fd::{self, FileDescriptor};
 
 pub fn file_attributes(path: &str) -> Result<FileAttr, IoError> {
@@ -404,12 +656,17 @@ impl Metadata {
 
 	/// Returns true if this metadata is for a file.
 	pub fn is_file(&self) -> bool {
-		self.0.st_mode.contains(AccessPermission::S_IFREG)
+		self.0.st_mode.intersection(AccessPermission::S_IFMT) == AccessPermission::S_IFREG
 	}
 
 	/// Returns true if this metadata is for a directory.
 	pub fn is_dir(&self) -> bool {
-		self.0.st_mode.contains(AccessPermission::S_IFDIR)
+		self.0.st_mode.intersection(AccessPermission::S_IFMT) == AccessPermission::S_IFDIR
+	}
+
+	/// Returns true if this metadata is for a symbolic link.
+	pub fn is_symlink(&self) -> bool {
+		self.0.st_mode.intersection(AccessPermission::S_IFMT) == AccessPermission::S_IFLNK
 	}
 
 	/// Returns the last modification time listed in this metadata.
@@ -493,3 +750,42 @@ impl Drop for File {
 		let _ = remove_object(self.fd);
 	}
 }
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn walk_lexically() {
+		let mut resolution = Resolution::new("a/./b/../c", false);
+		assert_eq!(resolution.walk_lexically(), Walk::Done);
+		assert_eq!(resolution.resolved, ["a", "c"]);
+
+		// the second `..` leaves the node
+		let mut resolution = Resolution::new("a/../../b", false);
+		assert_eq!(resolution.walk_lexically(), Walk::Parent);
+		assert!(resolution.resolved.is_empty());
+		assert_eq!(resolution.remaining, ["b", ".."]);
+	}
+
+	#[test]
+	fn follow_link() {
+		let mut resolution = Resolution::new("/x/link/c", true);
+		let component = resolution.next_component().unwrap();
+		resolution.push(component);
+		assert_eq!(resolution.next_component().unwrap(), "link");
+		assert!(!resolution.follow_link("a/b").unwrap());
+		assert_eq!(resolution.walk_lexically(), Walk::Done);
+		assert_eq!(resolution.resolved, ["x", "a", "b", "c"]);
+
+		assert!(resolution.follow_link("/d").unwrap());
+		assert!(resolution.resolved.is_empty());
+		assert_eq!(resolution.walk_lexically(), Walk::Done);
+		assert_eq!(resolution.resolved, ["d"]);
+
+		for _ in 1..MAX_SYMLINKS {
+			resolution.follow_link("e").unwrap();
+		}
+		assert!(resolution.follow_link("e").is_err());
+	}
+}
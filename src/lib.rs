@@ -152,6 +152,8 @@ extern "C" fn initd(_arg: usize) {
 	// Initialize Drivers
 	arch::init_drivers();
 	crate::executor::init();
+	#[cfg(feature = "balloon")]
+	crate::drivers::balloon::init();
 
 	// Initialize MMIO Drivers if on riscv64
 	#[cfg(target_arch = "riscv64")]
@@ -83,6 +83,47 @@ impl FreeList {
 		Err(AllocError)
 	}
 
+	/// Allocates `size` bytes, which are aligned to `alignment`, at the lowest
+	/// free address, which isn't below `min_address`.
+	pub fn allocate_above(
+		&mut self,
+		min_address: usize,
+		size: usize,
+		alignment: usize,
+	) -> Result<usize, AllocError> {
+		trace!(
+			"Allocating {} bytes above {:#X} from Free List {self:p}",
+			size,
+			min_address
+		);
+
+		for (i, node) in self.list.iter_mut().enumerate() {
+			let addr = node.start.max(min_address).align_up(alignment);
+			let end = addr + size;
+			if end > node.end {
+				continue;
+			}
+
+			match (addr == node.start, end == node.end) {
+				(true, true) => {
+					self.list.remove(i);
+				}
+				(true, false) => node.start = end,
+				(false, true) => node.end = addr,
+				(false, false) => {
+					// Split the region around the allocated memory.
+					let new_entry = FreeListEntry::new(node.start, addr);
+					node.start = end;
+					self.list.insert(i, new_entry);
+				}
+			}
+
+			return Ok(addr);
+		}
+
+		Err(AllocError)
+	}
+
 	#[cfg(all(target_arch = "x86_64", not(feature = "pci")))]
 	pub fn reserve(&mut self, address: usize, size: usize) -> Result<(), AllocError> {
 		trace!(
@@ -176,6 +217,11 @@ impl FreeList {
 		self.push(new_element);
 	}
 
+	/// Returns the number of free bytes
+	pub fn free_space(&self) -> usize {
+		self.list.iter().map(|node| node.end - node.start).sum()
+	}
+
 	pub fn print_information(&self, header: &str) {
 		infoheader!(header);
 
@@ -212,6 +258,41 @@ mod tests {
 		assert_eq!(iter.next().unwrap().start, 0x13000);
 	}
 
+	#[test]
+	fn free_space() {
+		let mut freelist = FreeList::new();
+		freelist.push(FreeListEntry::new(0x10000, 0x20000));
+		freelist.push(FreeListEntry::new(0x30000, 0x100000));
+		assert_eq!(freelist.free_space(), 0xe0000);
+
+		freelist.allocate(0x2000, None).unwrap();
+		assert_eq!(freelist.free_space(), 0xde000);
+		freelist.deallocate(0x10000, 0x2000);
+		assert_eq!(freelist.free_space(), 0xe0000);
+	}
+
+	#[test]
+	fn allocate_above() {
+		let mut freelist = FreeList::new();
+		freelist.push(FreeListEntry::new(0x10000, 0x20000));
+		freelist.push(FreeListEntry::new(0x30000, 0x100000));
+
+		let addr = freelist.allocate_above(0x12000, 0x2000, 0x4000);
+		assert_eq!(addr.unwrap(), 0x14000);
+		let addr = freelist.allocate_above(0x1f000, 0x2000, 0x1000);
+		assert_eq!(addr.unwrap(), 0x30000);
+		assert!(freelist.allocate_above(0x100000, 0x1000, 0x1000).is_err());
+
+		let mut iter = freelist.list.iter();
+		let node = iter.next().unwrap();
+		assert_eq!((node.start, node.end), (0x10000, 0x14000));
+		let node = iter.next().unwrap();
+		assert_eq!((node.start, node.end), (0x16000, 0x20000));
+		let node = iter.next().unwrap();
+		assert_eq!((node.start, node.end), (0x32000, 0x100000));
+		assert!(iter.next().is_none());
+	}
+
 	#[test]
 	fn deallocate() {
 		let mut freelist = FreeList::new();
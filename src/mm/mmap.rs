@@ -0,0 +1,741 @@
+//! Memory mappings of the application.
+//!
+//! Mappings are placed in a region of the virtual address space, which is
+//! reserved by the first mapping. Physical memory is allocated on the first
+//! access: if a page fault hits a mapping, whose protection permits the
+//! access, the page fault handler maps a zeroed frame. The frames of the
+//! mappings are tracked, so their content survives a change of the protection
+//! to `PROT_NONE`, which only removes the pages from the page tables.
+//!
+//! Mappings of files are backed by a [`MappedObject`]. Its frames are shared
+//! with the mapping, if possible. Private mappings map shared frames read-only
+//! and copy them on the first write.
+//!
+//! Anonymous mappings with huge pages (`MAP_HUGETLB`) are placed in a second
+//! region, which is only mapped with 2 MiB pages. Their ranges are aligned
+//! to 2 MiB and every page fault maps a whole 2 MiB frame.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::{fmt, mem, slice};
+
+use align_address::Align;
+use hermit_sync::InterruptTicketMutex;
+
+use crate::arch;
+#[cfg(target_arch = "x86_64")]
+use crate::arch::mm::paging::PageTableEntryFlagsExt;
+use crate::arch::mm::paging::{BasePageSize, LargePageSize, PageSize, PageTableEntryFlags};
+use crate::arch::mm::physicalmem::total_memory_size;
+use crate::arch::mm::{PhysAddr, VirtAddr};
+use crate::fd::IoError;
+
+/// Size of a page of a mapping
+const PAGE_SIZE: usize = BasePageSize::SIZE as usize;
+/// Size of a page of a mapping with huge pages
+const HUGE_PAGE_SIZE: usize = LargePageSize::SIZE as usize;
+
+bitflags! {
+	/// Permitted accesses to a mapping
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub(crate) struct Protection: i32 {
+		const READ = 1 << 0;
+		const WRITE = 1 << 1;
+		const EXEC = 1 << 2;
+	}
+}
+
+/// Kind of the memory access, which raised a page fault
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Access {
+	Read,
+	Write,
+	Execute,
+}
+
+impl Protection {
+	/// Returns true, if the protection permits the access
+	fn permits(self, access: Access) -> bool {
+		match access {
+			// the page tables cannot prevent reading an accessible page
+			Access::Read => !self.is_empty(),
+			Access::Write => self.contains(Self::WRITE),
+			Access::Execute => self.contains(Self::EXEC),
+		}
+	}
+
+	/// Returns the flags of a page table entry with this protection
+	fn page_flags(self) -> PageTableEntryFlags {
+		let mut flags = PageTableEntryFlags::empty();
+		flags.normal();
+		if self.contains(Self::WRITE) {
+			flags.writable();
+		} else {
+			flags.read_only();
+		}
+		if !self.contains(Self::EXEC) {
+			flags.execute_disable();
+		}
+		flags
+	}
+}
+
+/// Content of a page of a [`MappedObject`]
+pub(crate) enum MappedPage<'a> {
+	/// Frame, which is shared with the object
+	Frame(PhysAddr),
+	/// Content, which is copied into a private frame and padded with zeros
+	Data(&'a [u8]),
+}
+
+/// Memory of an object, e.g., a file, which backs a mapping
+pub(crate) trait MappedObject: Send + Sync + fmt::Debug {
+	/// Returns the page at `offset` relative to the start of the mapping or
+	/// `None`, if the page lies beyond the end of the object
+	fn page(&self, offset: usize) -> Option<MappedPage<'_>>;
+}
+
+/// Object, which backs a mapping
+#[derive(Debug, Clone)]
+pub(crate) struct Backing {
+	pub object: Arc<dyn MappedObject>,
+	/// Writes are visible to the object and to other mappings (`MAP_SHARED`)
+	pub shared: bool,
+	/// The object accepts writes through a shared mapping
+	pub writable: bool,
+}
+
+#[derive(Debug, Clone)]
+struct FileArea {
+	/// Address of the offset 0 of the object
+	base: usize,
+	backing: Backing,
+}
+
+impl FileArea {
+	/// Returns true, if both areas belong to the same mapping of an object
+	fn is_same(&self, other: &Self) -> bool {
+		self.base == other.base
+			&& self.backing.shared == other.backing.shared
+			&& self.backing.writable == other.backing.writable
+			&& Arc::ptr_eq(&self.backing.object, &other.backing.object)
+	}
+}
+
+#[derive(Debug, Clone)]
+struct Area {
+	end: usize,
+	prot: Protection,
+	file: Option<FileArea>,
+}
+
+impl Area {
+	/// Returns true, if writes are shared with the backing object
+	fn is_shared(&self) -> bool {
+		self.file.as_ref().is_some_and(|file| file.backing.shared)
+	}
+
+	/// Returns true, if `next` directly follows this area and both can be joined
+	fn is_continued_by(&self, start: usize, next: &Area) -> bool {
+		self.end == start
+			&& self.prot == next.prot
+			&& match (&self.file, &next.file) {
+				(None, None) => true,
+				(Some(file), Some(next)) => file.is_same(next),
+				_ => false,
+			}
+	}
+}
+
+/// Physical frame of an accessed page
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+	addr: PhysAddr,
+	/// The frame belongs to the mapping and is released by unmapping it.
+	/// Otherwise, the frame is shared with the backing object.
+	owned: bool,
+}
+
+/// Mappings, which are sorted by their start address
+#[derive(Debug)]
+struct Areas(BTreeMap<usize, Area>);
+
+impl Areas {
+	const fn new() -> Self {
+		Self(BTreeMap::new())
+	}
+
+	/// Returns the mapping, which contains `addr`
+	fn get(&self, addr: usize) -> Option<&Area> {
+		self.0
+			.range(..=addr)
+			.next_back()
+			.filter(|(_, area)| addr < area.end)
+			.map(|(_, area)| area)
+	}
+
+	/// Returns the protection of the mapping, which contains `addr`
+	fn protection(&self, addr: usize) -> Option<Protection> {
+		self.get(addr).map(|area| area.prot)
+	}
+
+	/// Splits the mapping, which contains `addr`, into two mappings at `addr`
+	fn split(&mut self, addr: usize) {
+		if let Some((_, area)) = self.0.range_mut(..addr).next_back() {
+			if addr < area.end {
+				let tail = Area {
+					end: mem::replace(&mut area.end, addr),
+					prot: area.prot,
+					file: area.file.clone(),
+				};
+				self.0.insert(addr, tail);
+			}
+		}
+	}
+
+	/// Joins adjacent mappings with the same protection, which start in `range`
+	fn merge(&mut self, range: Range<usize>) {
+		let starts: Vec<usize> = self.0.range(range).map(|(start, _)| *start).collect();
+		for start in starts {
+			let Some(area) = self.0.get(&start).cloned() else {
+				continue;
+			};
+			if let Some((_, prev)) = self.0.range_mut(..start).next_back() {
+				if prev.is_continued_by(start, &area) {
+					prev.end = area.end;
+					self.0.remove(&start);
+				}
+			}
+		}
+	}
+
+	/// Returns true, if no mapping overlaps `range`
+	fn is_free(&self, range: Range<usize>) -> bool {
+		self.protection(range.start).is_none() && self.0.range(range).next().is_none()
+	}
+
+	/// Returns true, if every address of `range` is mapped
+	fn is_mapped(&self, range: Range<usize>) -> bool {
+		let mut addr = range.start;
+		while addr < range.end {
+			match self.0.range(..=addr).next_back() {
+				Some((_, area)) if addr < area.end => addr = area.end,
+				_ => return false,
+			}
+		}
+
+		true
+	}
+
+	/// Returns the lowest address in `region`, which is followed by at least
+	/// `len` unmapped bytes
+	fn find_free(&self, region: Range<usize>, len: usize) -> Option<usize> {
+		let mut start = region.start;
+		for (area_start, area) in self.0.range(region.clone()) {
+			if area_start - start >= len {
+				return Some(start);
+			}
+			start = area.end;
+		}
+
+		(region.end - start >= len).then_some(start)
+	}
+
+	fn insert(&mut self, range: Range<usize>, prot: Protection, backing: Option<Backing>) {
+		self.0.insert(
+			range.start,
+			Area {
+				end: range.end,
+				prot,
+				file: backing.map(|backing| FileArea {
+					base: range.start,
+					backing,
+				}),
+			},
+		);
+		self.merge(range.start..range.end + 1);
+	}
+
+	/// Removes the mappings in `range` and returns them, so that their
+	/// backing objects can be released outside of the lock
+	fn remove(&mut self, range: Range<usize>) -> Vec<Area> {
+		self.split(range.start);
+		self.split(range.end);
+		let starts: Vec<usize> = self.0.range(range).map(|(start, _)| *start).collect();
+		starts
+			.into_iter()
+			.filter_map(|start| self.0.remove(&start))
+			.collect()
+	}
+
+	/// Returns true, if `prot` can be applied to `range`. Shared mappings of
+	/// objects only become writable, if they have been created writable.
+	fn can_protect(&self, range: Range<usize>, prot: Protection) -> bool {
+		!prot.contains(Protection::WRITE)
+			|| self
+				.0
+				.range(..range.end)
+				.filter(|(_, area)| area.end > range.start)
+				.all(|(_, area)| {
+					area.file
+						.as_ref()
+						.map_or(true, |file| !file.backing.shared || file.backing.writable)
+				})
+	}
+
+	/// Changes the protection of `range`, which has to be mapped completely
+	fn protect(&mut self, range: Range<usize>, prot: Protection) {
+		self.split(range.start);
+		self.split(range.end);
+		for (_, area) in self.0.range_mut(range.clone()) {
+			area.prot = prot;
+		}
+		self.merge(range.start..range.end + 1);
+	}
+}
+
+struct AddressSpace {
+	/// Virtual address range, which is reserved for the mappings
+	region: Option<Range<usize>>,
+	/// Virtual address range, which is reserved for the mappings with huge pages
+	huge_region: Option<Range<usize>>,
+	areas: Areas,
+	/// Physical frames of the pages, which have been accessed
+	frames: BTreeMap<usize, Frame>,
+}
+
+static ADDRESS_SPACE: InterruptTicketMutex<AddressSpace> =
+	InterruptTicketMutex::new(AddressSpace {
+		region: None,
+		huge_region: None,
+		areas: Areas::new(),
+		frames: BTreeMap::new(),
+	});
+
+/// Checks the alignment of `addr` and returns the range of `len` bytes,
+/// which is rounded up to pages of `page_size` bytes
+fn page_range(addr: usize, len: usize, page_size: usize) -> Result<Range<usize>, IoError> {
+	if len == 0 || addr % page_size != 0 {
+		return Err(IoError::EINVAL);
+	}
+	let end = addr
+		.checked_add(len.align_up(page_size))
+		.ok_or(IoError::ENOMEM)?;
+
+	Ok(addr..end)
+}
+
+/// Maps the page of `page_size` bytes at `page` to the frame `addr`
+fn map_page(page: usize, page_size: usize, addr: PhysAddr, flags: PageTableEntryFlags) {
+	let virtual_address = VirtAddr(page as u64);
+	if page_size == HUGE_PAGE_SIZE {
+		arch::mm::paging::map::<LargePageSize>(virtual_address, addr, 1, flags);
+	} else {
+		arch::mm::paging::map::<BasePageSize>(virtual_address, addr, 1, flags);
+	}
+}
+
+/// Removes the page of `page_size` bytes at `page` from the page tables
+fn unmap_page(page: usize, page_size: usize) {
+	let virtual_address = VirtAddr(page as u64);
+	if page_size == HUGE_PAGE_SIZE {
+		arch::mm::paging::unmap::<LargePageSize>(virtual_address, 1);
+	} else {
+		arch::mm::paging::unmap::<BasePageSize>(virtual_address, 1);
+	}
+}
+
+impl AddressSpace {
+	/// Returns the region of the mappings with huge pages, if `huge` is set,
+	/// or with base pages otherwise. A region is reserved on the first call.
+	fn region(&mut self, huge: bool) -> Result<Range<usize>, IoError> {
+		let region = if huge {
+			&mut self.huge_region
+		} else {
+			&mut self.region
+		};
+		if let Some(region) = region.clone() {
+			return Ok(region);
+		}
+
+		let size = total_memory_size().align_up(HUGE_PAGE_SIZE);
+		let start = arch::mm::virtualmem::allocate_aligned(size, HUGE_PAGE_SIZE)
+			.map_err(|_| IoError::ENOMEM)?
+			.as_usize();
+		let new_region = start..start + size;
+		if huge {
+			info!(
+				"Reserve {:#x?} for memory mappings with huge pages",
+				new_region
+			);
+		} else {
+			info!("Reserve {:#x?} for memory mappings", new_region);
+		}
+		*region = Some(new_region.clone());
+
+		Ok(new_region)
+	}
+
+	/// Returns the size of the page at `addr`
+	fn page_size(&self, addr: usize) -> usize {
+		if self
+			.huge_region
+			.as_ref()
+			.is_some_and(|region| region.contains(&addr))
+		{
+			HUGE_PAGE_SIZE
+		} else {
+			PAGE_SIZE
+		}
+	}
+
+	/// Rounds `range` up to huge pages, if it hits the region of the mappings
+	/// with huge pages. Their start has to be aligned to a huge page.
+	fn align_range(&self, range: Range<usize>) -> Result<Range<usize>, IoError> {
+		let Some(huge_region) = &self.huge_region else {
+			return Ok(range);
+		};
+		if range.start >= huge_region.end || range.end <= huge_region.start {
+			return Ok(range);
+		}
+
+		page_range(range.start, range.len(), HUGE_PAGE_SIZE)
+	}
+
+	/// Applies `prot` to accessed pages, which are given with their old
+	/// protection. Shared frames of private mappings stay read-only, so that
+	/// they are copied on the first write.
+	fn remap(&self, pages: &[(usize, Protection)], prot: Protection) {
+		for (page, old) in pages {
+			let page_size = self.page_size(*page);
+			if !prot.is_empty() {
+				let frame = self.frames[page];
+				let prot = if frame.owned || self.areas.get(*page).is_some_and(Area::is_shared) {
+					prot
+				} else {
+					prot.difference(Protection::WRITE)
+				};
+				map_page(*page, page_size, frame.addr, prot.page_flags());
+			} else if !old.is_empty() {
+				// pages without access rights aren't part of the page tables
+				unmap_page(*page, page_size);
+			}
+		}
+	}
+
+	/// Returns the accessed pages in `range` with their current protection
+	fn accessed_pages(&self, range: Range<usize>) -> Vec<(usize, Protection)> {
+		self.frames
+			.range(range)
+			.map(|(page, _)| (*page, self.areas.protection(*page).unwrap()))
+			.collect()
+	}
+
+	/// Removes the mappings in `range`. The removed mappings and their
+	/// frames are returned to release them after unlocking the address space.
+	fn unmap(&mut self, range: Range<usize>) -> Removed {
+		let old = self.accessed_pages(range.clone());
+		self.remap(&old, Protection::empty());
+		let mut frames = Vec::new();
+		for (page, _) in old {
+			let frame = self.frames.remove(&page).unwrap();
+			if frame.owned {
+				frames.push((frame.addr, self.page_size(page)));
+			}
+		}
+
+		Removed {
+			_areas: self.areas.remove(range),
+			frames,
+		}
+	}
+
+	/// Maps a private frame at `page`, which is zeroed and filled by `fill`,
+	/// before `prot` is applied
+	fn map_private(&mut self, page: usize, prot: Protection, fill: impl FnOnce(&mut [u8])) -> bool {
+		let page_size = self.page_size(page);
+		let Ok(addr) = arch::mm::physicalmem::allocate_aligned(page_size, page_size) else {
+			error!("Unable to allocate a frame for {:#x}", page);
+			return false;
+		};
+
+		// fill the frame through a writable mapping, before the protection is applied
+		let mut flags = PageTableEntryFlags::empty();
+		flags.normal().writable().execute_disable();
+		map_page(page, page_size, addr, flags);
+		let content = unsafe { slice::from_raw_parts_mut(page as *mut u8, page_size) };
+		content.fill(0);
+		fill(content);
+		if prot != Protection::READ | Protection::WRITE {
+			map_page(page, page_size, addr, prot.page_flags());
+		}
+		self.frames.insert(page, Frame { addr, owned: true });
+		super::count_mapped(page_size, 1);
+
+		true
+	}
+
+	/// Replaces the shared frame of a private mapping by a copy
+	fn copy_on_write(&mut self, page: usize, prot: Protection) -> bool {
+		let content = unsafe { slice::from_raw_parts(page as *const u8, PAGE_SIZE) }.to_vec();
+		self.map_private(page, prot, |frame| frame.copy_from_slice(&content))
+	}
+}
+
+/// Mappings, which have been removed from the address space. Other cores
+/// may still access their frames through stale TLB entries, so the frames
+/// are only released after the TLBs have been flushed on drop. It has to be
+/// dropped after unlocking the address space.
+#[must_use]
+struct Removed {
+	_areas: Vec<Area>,
+	/// Owned frames with their size
+	frames: Vec<(PhysAddr, usize)>,
+}
+
+impl Drop for Removed {
+	fn drop(&mut self) {
+		if self.frames.is_empty() {
+			return;
+		}
+
+		flush_remote_tlbs();
+		for (addr, page_size) in self.frames.drain(..) {
+			arch::mm::physicalmem::deallocate(addr, page_size);
+			super::count_unmapped(page_size, 1);
+		}
+	}
+}
+
+/// Flushes the TLBs of the other cores and waits for them, so that pages,
+/// which have been removed from the page tables, are no longer accessible.
+/// On aarch64, `tlbi` already broadcasts the invalidation to all cores,
+/// while riscv64 doesn't support flushing remote TLBs yet.
+fn flush_remote_tlbs() {
+	#[cfg(all(target_arch = "x86_64", feature = "smp"))]
+	arch::x86_64::kernel::apic::ipi_tlb_shootdown();
+}
+
+/// Creates a mapping of `len` bytes. Pages of anonymous mappings are zeroed
+/// on the first access, while mappings with a `backing` object map its pages.
+/// Without `fixed`, `addr` is only a hint and the mapping is placed at a free
+/// address. With `fixed`, the mapping replaces the mappings at `addr`, which
+/// has to be part of the region of the mappings. Anonymous mappings with
+/// `huge` pages are placed in the region of the mappings with huge pages.
+pub(crate) fn map(
+	addr: usize,
+	len: usize,
+	prot: Protection,
+	fixed: bool,
+	huge: bool,
+	backing: Option<Backing>,
+) -> Result<usize, IoError> {
+	if let Some(backing) = &backing {
+		if huge {
+			return Err(IoError::EINVAL);
+		}
+		if backing.shared && !backing.writable && prot.contains(Protection::WRITE) {
+			return Err(IoError::EACCES);
+		}
+	}
+	if huge && !arch::processor::supports_2mib_pages() {
+		return Err(IoError::EINVAL);
+	}
+
+	let page_size = if huge { HUGE_PAGE_SIZE } else { PAGE_SIZE };
+	let mut space = ADDRESS_SPACE.lock();
+	let region = space.region(huge)?;
+
+	let mut replaced = None;
+	let range = if fixed {
+		let range = page_range(addr, len, page_size)?;
+		if range.start < region.start || range.end > region.end {
+			return Err(IoError::ENOMEM);
+		}
+		replaced = Some(space.unmap(range.clone()));
+		range
+	} else {
+		let len = len.align_up(page_size);
+		if len == 0 {
+			return Err(IoError::EINVAL);
+		}
+		match page_range(addr.align_down(page_size), len, page_size) {
+			Ok(hint)
+				if hint.start >= region.start
+					&& hint.end <= region.end
+					&& space.areas.is_free(hint.clone()) =>
+			{
+				hint
+			}
+			_ => {
+				let start = space.areas.find_free(region, len).ok_or(IoError::ENOMEM)?;
+				start..start + len
+			}
+		}
+	};
+
+	trace!("Map {:#x?} with {:?}", range, prot);
+	space.areas.insert(range.clone(), prot, backing);
+	drop(space);
+	drop(replaced);
+
+	Ok(range.start)
+}
+
+/// Removes the mappings in the range of `len` bytes at `addr`
+pub(crate) fn unmap(addr: usize, len: usize) -> Result<(), IoError> {
+	let range = page_range(addr, len, PAGE_SIZE)?;
+	let mut space = ADDRESS_SPACE.lock();
+	let range = space.align_range(range)?;
+
+	trace!("Unmap {:#x?}", range);
+	let removed = space.unmap(range);
+	drop(space);
+	drop(removed);
+
+	Ok(())
+}
+
+/// Changes the protection of the range of `len` bytes at `addr`, which
+/// has to be mapped completely
+pub(crate) fn protect(addr: usize, len: usize, prot: Protection) -> Result<(), IoError> {
+	let range = page_range(addr, len, PAGE_SIZE)?;
+	let mut space = ADDRESS_SPACE.lock();
+	let range = space.align_range(range)?;
+	if !space.areas.is_mapped(range.clone()) {
+		return Err(IoError::ENOMEM);
+	}
+	if !space.areas.can_protect(range.clone(), prot) {
+		return Err(IoError::EACCES);
+	}
+
+	trace!("Protect {:#x?} with {:?}", range, prot);
+	let old = space.accessed_pages(range.clone());
+	space.areas.protect(range.clone(), prot);
+	space.remap(&old, prot);
+	drop(space);
+	if !old.is_empty() {
+		// other cores mustn't keep access rights, which have been revoked
+		flush_remote_tlbs();
+	}
+
+	Ok(())
+}
+
+/// Maps a frame, if the page fault at `addr` hits a mapping, whose page
+/// hasn't been accessed yet and whose protection permits the access. Writes
+/// to shared frames of private mappings are resolved by copying the frame.
+/// Returns false, if the page fault is an error.
+pub(crate) fn handle_page_fault(addr: usize, access: Access) -> bool {
+	let mut space = ADDRESS_SPACE.lock();
+	let page = addr.align_down(space.page_size(addr));
+
+	let Some(area) = space.areas.get(page).cloned() else {
+		return false;
+	};
+	if !area.prot.permits(access) {
+		error!(
+			"Access {:?} to {:#x} violates protection {:?}",
+			access, addr, area.prot
+		);
+		return false;
+	}
+
+	if let Some(frame) = space.frames.get(&page).copied() {
+		if access == Access::Write && !frame.owned && !area.is_shared() {
+			return space.copy_on_write(page, area.prot);
+		}
+
+		// another core has mapped the page in the meantime
+		space.remap(&[(page, area.prot)], area.prot);
+		return true;
+	}
+
+	let Some(file) = &area.file else {
+		return space.map_private(page, area.prot, |_| {});
+	};
+
+	match file.backing.object.page(page - file.base) {
+		Some(MappedPage::Frame(addr)) => {
+			space.frames.insert(page, Frame { addr, owned: false });
+			space.remap(&[(page, Protection::empty())], area.prot);
+			if access == Access::Write && !area.is_shared() {
+				space.copy_on_write(page, area.prot)
+			} else {
+				true
+			}
+		}
+		Some(MappedPage::Data(data)) => space.map_private(page, area.prot, |frame| {
+			frame[..data.len()].copy_from_slice(data);
+		}),
+		None => {
+			error!("Access to {:#x} beyond the end of the mapped object", addr);
+			false
+		}
+	}
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	const RW: Protection = Protection::READ.union(Protection::WRITE);
+
+	fn areas(areas: &Areas) -> Vec<(usize, usize, Protection)> {
+		areas
+			.0
+			.iter()
+			.map(|(start, area)| (*start, area.end, area.prot))
+			.collect()
+	}
+
+	#[test]
+	fn find_free() {
+		let mut list = Areas::new();
+		assert_eq!(list.find_free(0x10000..0x20000, 0x1000), Some(0x10000));
+
+		list.insert(0x10000..0x12000, RW, None);
+		list.insert(0x13000..0x14000, RW, None);
+		assert_eq!(list.find_free(0x10000..0x20000, 0x1000), Some(0x12000));
+		assert_eq!(list.find_free(0x10000..0x20000, 0x2000), Some(0x14000));
+		assert_eq!(list.find_free(0x10000..0x20000, 0x10000), None);
+		assert!(list.is_free(0x12000..0x13000));
+		assert!(!list.is_free(0x11000..0x13000));
+	}
+
+	#[test]
+	fn protect() {
+		let mut list = Areas::new();
+		list.insert(0x10000..0x14000, RW, None);
+
+		list.protect(0x11000..0x12000, Protection::empty());
+		assert_eq!(
+			areas(&list),
+			[
+				(0x10000, 0x11000, RW),
+				(0x11000, 0x12000, Protection::empty()),
+				(0x12000, 0x14000, RW)
+			]
+		);
+		assert_eq!(list.protection(0x11fff), Some(Protection::empty()));
+		assert!(list.is_mapped(0x10000..0x14000));
+		assert!(!list.is_mapped(0x10000..0x15000));
+
+		list.protect(0x11000..0x12000, RW);
+		assert_eq!(areas(&list), [(0x10000, 0x14000, RW)]);
+	}
+
+	#[test]
+	fn remove() {
+		let mut list = Areas::new();
+		list.insert(0x10000..0x14000, RW, None);
+		list.remove(0x11000..0x13000);
+		assert_eq!(
+			areas(&list),
+			[(0x10000, 0x11000, RW), (0x13000, 0x14000, RW)]
+		);
+		assert_eq!(list.protection(0x12000), None);
+	}
+}
@@ -1,9 +1,16 @@
 pub mod allocator;
 pub mod device_alloc;
 pub mod freelist;
+pub mod mmap;
+// riscv64 handles traps on the stack of the interrupted task, so it cannot
+// resolve a page fault on that stack and maps the stacks completely
+#[cfg(not(target_arch = "riscv64"))]
+pub(crate) mod stack;
 
+use alloc::vec::Vec;
 use core::mem;
 use core::ops::Range;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use align_address::Align;
 use hermit_sync::Lazy;
@@ -11,17 +18,15 @@ use hermit_sync::Lazy;
 use hermit_sync::OnceCell;
 
 use self::allocator::LockedAllocator;
-#[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
-use crate::arch::mm::paging::HugePageSize;
 #[cfg(target_arch = "x86_64")]
 use crate::arch::mm::paging::PageTableEntryFlagsExt;
-use crate::arch::mm::paging::{BasePageSize, LargePageSize, PageSize, PageTableEntryFlags};
+use crate::arch::mm::paging::{
+	BasePageSize, HugePageSize, LargePageSize, PageSize, PageTableEntryFlags,
+};
 use crate::arch::mm::physicalmem::total_memory_size;
 #[cfg(feature = "newlib")]
 use crate::arch::mm::virtualmem::kernel_heap_end;
-#[cfg(feature = "pci")]
-use crate::arch::mm::PhysAddr;
-use crate::arch::mm::VirtAddr;
+use crate::arch::mm::{PhysAddr, VirtAddr};
 use crate::{arch, env};
 
 #[cfg(target_os = "none")]
@@ -43,6 +48,181 @@ static KERNEL_ADDR_RANGE: Lazy<Range<VirtAddr>> = Lazy::new(|| {
 /// User heap address range.
 static HEAP_ADDR_RANGE: OnceCell<Range<VirtAddr>> = OnceCell::new();
 
+/// Number of mapped 2 MiB pages
+static LARGE_PAGES: AtomicUsize = AtomicUsize::new(0);
+/// Number of mapped 1 GiB pages
+static HUGE_PAGES: AtomicUsize = AtomicUsize::new(0);
+
+/// Adds `count` mapped pages of `page_size` bytes to the counter of their size
+fn count_mapped(page_size: usize, count: usize) {
+	if page_size == LargePageSize::SIZE as usize {
+		LARGE_PAGES.fetch_add(count, Ordering::Relaxed);
+	} else if page_size == HugePageSize::SIZE as usize {
+		HUGE_PAGES.fetch_add(count, Ordering::Relaxed);
+	}
+}
+
+/// Subtracts `count` unmapped pages of `page_size` bytes from the counter of their size
+fn count_unmapped(page_size: usize, count: usize) {
+	if page_size == LargePageSize::SIZE as usize {
+		LARGE_PAGES.fetch_sub(count, Ordering::Relaxed);
+	} else if page_size == HugePageSize::SIZE as usize {
+		HUGE_PAGES.fetch_sub(count, Ordering::Relaxed);
+	}
+}
+
+/// Returns how many of the `count` pages of `page_size` bytes at
+/// `virtual_address` are really mapped with pages of this size. Mapping a
+/// page falls back to 4 KiB pages, if a page table of 4 KiB pages covers it.
+#[cfg(not(target_arch = "riscv64"))]
+fn pages_of_size(virtual_address: VirtAddr, page_size: usize, count: usize) -> usize {
+	if page_size == BasePageSize::SIZE as usize {
+		return count;
+	}
+
+	// a 4 KiB page is only present, if the mapping fell back to them
+	(0..count)
+		.filter(|i| {
+			arch::mm::paging::get_physical_address::<BasePageSize>(virtual_address + i * page_size)
+				.is_none()
+		})
+		.count()
+}
+
+/// riscv64 doesn't fall back to 4 KiB pages
+#[cfg(target_arch = "riscv64")]
+fn pages_of_size(_virtual_address: VirtAddr, _page_size: usize, count: usize) -> usize {
+	count
+}
+
+/// Splits the range of `size` bytes at `virt`, which is mapped to `phys`,
+/// into runs of pages with the same size. A run uses the largest pages,
+/// which are permitted by `large` and `huge` and the alignment of both
+/// addresses. Returns the page size and the number of pages of every run.
+fn page_runs(
+	virt: usize,
+	phys: usize,
+	size: usize,
+	large: bool,
+	huge: bool,
+) -> Vec<(usize, usize)> {
+	let base_size = BasePageSize::SIZE as usize;
+	let large_size = LargePageSize::SIZE as usize;
+	let huge_size = HugePageSize::SIZE as usize;
+	// a page maps an aligned virtual address to an aligned physical address
+	let large = large && (virt ^ phys) % large_size == 0;
+	let huge = large && huge && (virt ^ phys) % huge_size == 0;
+
+	let mut runs = Vec::new();
+	let mut addr = virt;
+	let end = virt + size;
+	while addr < end {
+		let remaining = end - addr;
+		let (page_size, limit) = if huge && addr % huge_size == 0 && remaining >= huge_size {
+			(huge_size, remaining)
+		} else if large && addr % large_size == 0 && remaining >= large_size {
+			let limit = if huge {
+				huge_size - addr % huge_size
+			} else {
+				remaining
+			};
+			(large_size, limit)
+		} else {
+			let limit = if large {
+				large_size - addr % large_size
+			} else {
+				remaining
+			};
+			(base_size, limit)
+		};
+
+		let count = remaining.min(limit) / page_size;
+		runs.push((page_size, count));
+		addr += count * page_size;
+	}
+
+	runs
+}
+
+/// Maps `size` bytes at `virtual_address` to `physical_address`. 2 MiB and
+/// 1 GiB pages are used, if the processor and the alignment permit them.
+fn map_pages(
+	virtual_address: VirtAddr,
+	physical_address: PhysAddr,
+	size: usize,
+	flags: PageTableEntryFlags,
+) {
+	let runs = page_runs(
+		virtual_address.as_usize(),
+		physical_address.as_u64() as usize,
+		size,
+		arch::processor::supports_2mib_pages(),
+		arch::processor::supports_1gib_pages(),
+	);
+
+	let mut offset = 0;
+	for (page_size, count) in runs {
+		let virtual_address = virtual_address + offset;
+		let physical_address = physical_address + offset;
+		if page_size == HugePageSize::SIZE as usize {
+			arch::mm::paging::map::<HugePageSize>(virtual_address, physical_address, count, flags);
+		} else if page_size == LargePageSize::SIZE as usize {
+			arch::mm::paging::map::<LargePageSize>(virtual_address, physical_address, count, flags);
+		} else {
+			arch::mm::paging::map::<BasePageSize>(virtual_address, physical_address, count, flags);
+		}
+		count_mapped(page_size, pages_of_size(virtual_address, page_size, count));
+		offset += page_size * count;
+	}
+}
+
+/// Unmaps `size` bytes at `virtual_address`, which have been mapped to
+/// `physical_address` by `map_pages`
+fn unmap_pages(virtual_address: VirtAddr, physical_address: PhysAddr, size: usize) {
+	let runs = page_runs(
+		virtual_address.as_usize(),
+		physical_address.as_u64() as usize,
+		size,
+		arch::processor::supports_2mib_pages(),
+		arch::processor::supports_1gib_pages(),
+	);
+
+	let mut offset = 0;
+	for (page_size, count) in runs {
+		let virtual_address = virtual_address + offset;
+		count_unmapped(page_size, pages_of_size(virtual_address, page_size, count));
+		if page_size == HugePageSize::SIZE as usize {
+			arch::mm::paging::unmap::<HugePageSize>(virtual_address, count);
+		} else if page_size == LargePageSize::SIZE as usize {
+			arch::mm::paging::unmap::<LargePageSize>(virtual_address, count);
+		} else {
+			arch::mm::paging::unmap::<BasePageSize>(virtual_address, count);
+		}
+		offset += page_size * count;
+	}
+}
+
+/// Returns the alignment of a virtual address, which permits mapping the
+/// `size` bytes at `physical_address` with the largest pages
+fn page_alignment(physical_address: PhysAddr, size: usize) -> usize {
+	let physical_address = physical_address.as_u64() as usize;
+	[
+		(
+			HugePageSize::SIZE as usize,
+			arch::processor::supports_1gib_pages(),
+		),
+		(
+			LargePageSize::SIZE as usize,
+			arch::processor::supports_2mib_pages(),
+		),
+	]
+	.into_iter()
+	.find(|(page_size, supported)| {
+		*supported && size >= *page_size && physical_address % page_size == 0
+	})
+	.map_or(BasePageSize::SIZE as usize, |(page_size, _)| page_size)
+}
+
 pub(crate) fn kernel_start_address() -> VirtAddr {
 	KERNEL_ADDR_RANGE.start
 }
@@ -122,18 +302,19 @@ pub(crate) fn init() {
 
 		unsafe {
 			let start = {
-				let physical_address = arch::mm::physicalmem::allocate(kernel_heap_size).unwrap();
-				let virtual_address = arch::mm::virtualmem::allocate(kernel_heap_size).unwrap();
+				let alignment = if has_2mib_pages {
+					LargePageSize::SIZE as usize
+				} else {
+					BasePageSize::SIZE as usize
+				};
+				let physical_address =
+					arch::mm::physicalmem::allocate_aligned(kernel_heap_size, alignment).unwrap();
+				let virtual_address =
+					arch::mm::virtualmem::allocate_aligned(kernel_heap_size, alignment).unwrap();
 
-				let count = kernel_heap_size / BasePageSize::SIZE as usize;
 				let mut flags = PageTableEntryFlags::empty();
 				flags.normal().writable().execute_disable();
-				arch::mm::paging::map::<BasePageSize>(
-					virtual_address,
-					physical_address,
-					count,
-					flags,
-				);
+				map_pages(virtual_address, physical_address, kernel_heap_size, flags);
 
 				virtual_address
 			};
@@ -180,9 +361,17 @@ pub(crate) fn init() {
 			let npages = (virt_addr.align_up_to_huge_page().as_usize() - virt_addr.as_usize())
 				/ LargePageSize::SIZE as usize;
 			if let Err(n) = paging::map_heap::<LargePageSize>(virt_addr, npages) {
+				count_mapped(
+					LargePageSize::SIZE as usize,
+					pages_of_size(virt_addr, LargePageSize::SIZE as usize, n),
+				);
 				map_addr = virt_addr + n * LargePageSize::SIZE as usize;
 				map_size = virt_size - (map_addr - virt_addr).as_usize();
 			} else {
+				count_mapped(
+					LargePageSize::SIZE as usize,
+					pages_of_size(virt_addr, LargePageSize::SIZE as usize, npages),
+				);
 				map_addr = virt_addr.align_up_to_huge_page();
 				map_size = virt_size - (map_addr - virt_addr).as_usize();
 			}
@@ -228,9 +417,17 @@ pub(crate) fn init() {
 			let npages = (virt_addr.align_up_to_huge_page().as_usize() - virt_addr.as_usize())
 				/ LargePageSize::SIZE as usize;
 			if let Err(n) = paging::map_heap::<LargePageSize>(virt_addr, npages) {
+				count_mapped(
+					LargePageSize::SIZE as usize,
+					pages_of_size(virt_addr, LargePageSize::SIZE as usize, n),
+				);
 				map_addr = virt_addr + n * LargePageSize::SIZE as usize;
 				map_size = virt_size - (map_addr - virt_addr).as_usize();
 			} else {
+				count_mapped(
+					LargePageSize::SIZE as usize,
+					pages_of_size(virt_addr, LargePageSize::SIZE as usize, npages),
+				);
 				map_addr = virt_addr.align_up_to_huge_page();
 				map_size = virt_size - (map_addr - virt_addr).as_usize();
 			}
@@ -255,9 +452,21 @@ pub(crate) fn init() {
 		if let Err(num_pages) =
 			paging::map_heap::<HugePageSize>(map_addr, size / HugePageSize::SIZE as usize)
 		{
+			count_mapped(
+				HugePageSize::SIZE as usize,
+				pages_of_size(map_addr, HugePageSize::SIZE as usize, num_pages),
+			);
 			map_size -= num_pages * HugePageSize::SIZE as usize;
 			map_addr += num_pages * HugePageSize::SIZE as usize;
 		} else {
+			count_mapped(
+				HugePageSize::SIZE as usize,
+				pages_of_size(
+					map_addr,
+					HugePageSize::SIZE as usize,
+					size / HugePageSize::SIZE as usize,
+				),
+			);
 			map_size -= size;
 			map_addr += size;
 		}
@@ -271,9 +480,21 @@ pub(crate) fn init() {
 		if let Err(num_pages) =
 			paging::map_heap::<LargePageSize>(map_addr, size / LargePageSize::SIZE as usize)
 		{
+			count_mapped(
+				LargePageSize::SIZE as usize,
+				pages_of_size(map_addr, LargePageSize::SIZE as usize, num_pages),
+			);
 			map_size -= num_pages * LargePageSize::SIZE as usize;
 			map_addr += num_pages * LargePageSize::SIZE as usize;
 		} else {
+			count_mapped(
+				LargePageSize::SIZE as usize,
+				pages_of_size(
+					map_addr,
+					LargePageSize::SIZE as usize,
+					size / LargePageSize::SIZE as usize,
+				),
+			);
 			map_size -= size;
 			map_addr += size;
 		}
@@ -304,28 +525,40 @@ pub(crate) fn init() {
 
 	let heap_addr_range = heap_start_addr..heap_end_addr;
 	info!("Heap is located at {heap_addr_range:#x?} ({map_size} Bytes unmapped)");
+	print_huge_pages();
 	#[cfg(feature = "newlib")]
 	HEAP_ADDR_RANGE.set(heap_addr_range).unwrap();
 }
 
+/// Prints the number of mapped 2 MiB and 1 GiB pages
+fn print_huge_pages() {
+	info!(
+		"Huge pages: {} of 2 MiB, {} of 1 GiB",
+		LARGE_PAGES.load(Ordering::Relaxed),
+		HUGE_PAGES.load(Ordering::Relaxed)
+	);
+}
+
 pub(crate) fn print_information() {
 	arch::mm::physicalmem::print_information();
 	arch::mm::virtualmem::print_information();
+	print_huge_pages();
 }
 
 /// Soft-deprecated in favor of `DeviceAlloc`
 pub(crate) fn allocate(sz: usize, no_execution: bool) -> VirtAddr {
 	let size = sz.align_up(BasePageSize::SIZE as usize);
 	let physical_address = arch::mm::physicalmem::allocate(size).unwrap();
-	let virtual_address = arch::mm::virtualmem::allocate(size).unwrap();
+	let virtual_address =
+		arch::mm::virtualmem::allocate_aligned(size, page_alignment(physical_address, size))
+			.unwrap();
 
-	let count = size / BasePageSize::SIZE as usize;
 	let mut flags = PageTableEntryFlags::empty();
 	flags.normal().writable();
 	if no_execution {
 		flags.execute_disable();
 	}
-	arch::mm::paging::map::<BasePageSize>(virtual_address, physical_address, count, flags);
+	map_pages(virtual_address, physical_address, size, flags);
 
 	virtual_address
 }
@@ -335,10 +568,7 @@ pub(crate) fn deallocate(virtual_address: VirtAddr, sz: usize) {
 	let size = sz.align_up(BasePageSize::SIZE as usize);
 
 	if let Some(phys_addr) = arch::mm::paging::virtual_to_physical(virtual_address) {
-		arch::mm::paging::unmap::<BasePageSize>(
-			virtual_address,
-			size / BasePageSize::SIZE as usize,
-		);
+		unmap_pages(virtual_address, phys_addr, size);
 		arch::mm::virtualmem::deallocate(virtual_address, size);
 		arch::mm::physicalmem::deallocate(phys_addr, size);
 	} else {
@@ -350,6 +580,7 @@ pub(crate) fn deallocate(virtual_address: VirtAddr, sz: usize) {
 }
 
 /// Maps a given physical address and size in virtual space and returns address.
+/// Device memory, which is aligned to 2 MiB or 1 GiB, is mapped with huge pages.
 #[cfg(feature = "pci")]
 pub(crate) fn map(
 	physical_address: PhysAddr,
@@ -359,7 +590,6 @@ pub(crate) fn map(
 	no_cache: bool,
 ) -> VirtAddr {
 	let size = sz.align_up(BasePageSize::SIZE as usize);
-	let count = size / BasePageSize::SIZE as usize;
 
 	let mut flags = PageTableEntryFlags::empty();
 	flags.normal();
@@ -373,8 +603,10 @@ pub(crate) fn map(
 		flags.device();
 	}
 
-	let virtual_address = arch::mm::virtualmem::allocate(size).unwrap();
-	arch::mm::paging::map::<BasePageSize>(virtual_address, physical_address, count, flags);
+	let virtual_address =
+		arch::mm::virtualmem::allocate_aligned(size, page_alignment(physical_address, size))
+			.unwrap();
+	map_pages(virtual_address, physical_address, size, flags);
 
 	virtual_address
 }
@@ -384,11 +616,8 @@ pub(crate) fn map(
 pub(crate) fn unmap(virtual_address: VirtAddr, sz: usize) {
 	let size = sz.align_up(BasePageSize::SIZE as usize);
 
-	if arch::mm::paging::virtual_to_physical(virtual_address).is_some() {
-		arch::mm::paging::unmap::<BasePageSize>(
-			virtual_address,
-			size / BasePageSize::SIZE as usize,
-		);
+	if let Some(phys_addr) = arch::mm::paging::virtual_to_physical(virtual_address) {
+		unmap_pages(virtual_address, phys_addr, size);
 		arch::mm::virtualmem::deallocate(virtual_address, size);
 	} else {
 		panic!(
@@ -397,3 +626,57 @@ pub(crate) fn unmap(virtual_address: VirtAddr, sz: usize) {
 		);
 	}
 }
+
+/// Resolves the page fault at `addr` by mapping a page of a task stack or of
+/// a memory mapping. Returns false, if the page fault is an error.
+pub(crate) fn handle_page_fault(addr: usize, access: mmap::Access) -> bool {
+	#[cfg(not(target_arch = "riscv64"))]
+	if stack::handle_page_fault(addr, access) {
+		return true;
+	}
+
+	mmap::handle_page_fault(addr, access)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod tests {
+	use super::*;
+
+	const KIB: usize = 1024;
+	const MIB: usize = 1024 * KIB;
+	const GIB: usize = 1024 * MIB;
+
+	#[test]
+	fn page_runs_use_largest_pages() {
+		assert_eq!(
+			page_runs(
+				GIB - 4 * KIB,
+				GIB - 4 * KIB,
+				GIB + 2 * MIB + 8 * KIB,
+				true,
+				true
+			),
+			[(4 * KIB, 1), (GIB, 1), (2 * MIB, 1), (4 * KIB, 1)]
+		);
+		// a run of 2 MiB pages ends at the next boundary of 1 GiB
+		assert_eq!(
+			page_runs(GIB - 2 * MIB, GIB - 2 * MIB, GIB, true, true),
+			[(2 * MIB, 1), (2 * MIB, 511)]
+		);
+	}
+
+	#[test]
+	fn page_runs_fall_back_to_smaller_pages() {
+		// both addresses have to be aligned in the same way
+		assert_eq!(
+			page_runs(4 * MIB, 4 * MIB + 4 * KIB, 4 * MIB, true, true),
+			[(4 * KIB, 1024)]
+		);
+		assert_eq!(
+			page_runs(GIB, 2 * GIB + 2 * MIB, GIB, true, true),
+			[(2 * MIB, 512)]
+		);
+		assert_eq!(page_runs(GIB, GIB, GIB, true, false), [(2 * MIB, 512)]);
+		assert_eq!(page_runs(GIB, GIB, 8 * KIB, false, true), [(4 * KIB, 2)]);
+	}
+}
@@ -0,0 +1,262 @@
+//! Task stacks, which are backed by physical memory on demand.
+//!
+//! The stack of a task is reserved as a range of virtual memory below an
+//! unmapped guard page. Only the top of the stack is mapped, when the task is
+//! spawned. The remaining pages are mapped by the page fault handler, when the
+//! stack grows into them, and are released together with the stack. A page
+//! fault in the guard page is a stack overflow of the current task.
+//!
+//! The kernel runs on the stack of the task, so a page fault on a stack may
+//! interrupt code, which holds any lock of the kernel. Therefore, the page
+//! fault handler of the stacks neither waits for a lock nor allocates heap
+//! memory: the stacks are registered in slots, which are read without a lock,
+//! their page tables are created in advance and the frames are taken from the
+//! free list only if it isn't locked, or from a reserve otherwise.
+
+use alloc::boxed::Box;
+use core::ops::Range;
+use core::slice;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use align_address::Align;
+use hermit_sync::OnceCell;
+
+#[cfg(target_arch = "x86_64")]
+use crate::arch::mm::paging::PageTableEntryFlagsExt;
+use crate::arch::mm::paging::{BasePageSize, LargePageSize, PageSize, PageTableEntryFlags};
+use crate::arch::mm::{PhysAddr, VirtAddr};
+use crate::config::KERNEL_STACK_SIZE;
+use crate::mm::mmap::Access;
+use crate::{arch, core_scheduler};
+
+/// Size of a page of a stack
+const PAGE_SIZE: usize = BasePageSize::SIZE as usize;
+
+/// Size of the memory, which is covered by a page table of base pages
+const TABLE_SIZE: usize = LargePageSize::SIZE as usize;
+
+/// Byte, which fills the pages of a new stack
+const STACK_PATTERN: u8 = 0xAC;
+
+/// Number of bits of a slot, which hold the number of pages of the stack.
+/// The remaining bits hold the page number of the guard page.
+const PAGES_BITS: u32 = 28;
+
+/// Number of frames, which are kept in reserve for page faults on stacks,
+/// while the free list is locked
+const RESERVED_FRAMES: usize = 16;
+
+/// Value of an empty slot of the reserve
+const NO_FRAME: u64 = u64::MAX;
+
+/// Stacks, which are backed on demand, by the page number of their guard
+/// page and their number of pages. Empty slots are 0. Every stack maps its
+/// top immediately, so the physical memory limits the number of stacks. The
+/// slots are allocated with the first stack, so that the page fault handler
+/// reads them without allocating.
+static STACKS: OnceCell<Box<[AtomicU64]>> = OnceCell::new();
+
+/// Number of slots, which have ever been used. The page fault handler
+/// doesn't search the slots behind them.
+static USED_SLOTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the slots of the stacks and allocates them on the first call
+fn slots() -> &'static [AtomicU64] {
+	STACKS.get_or_init(|| {
+		let count = arch::mm::physicalmem::total_memory_size() / KERNEL_STACK_SIZE;
+		(0..count.max(1)).map(|_| AtomicU64::new(0)).collect()
+	})
+}
+
+/// Frames, which are kept in reserve for page faults on stacks
+static RESERVE: [AtomicU64; RESERVED_FRAMES] = {
+	#[allow(clippy::declare_interior_mutable_const)]
+	const EMPTY: AtomicU64 = AtomicU64::new(NO_FRAME);
+	[EMPTY; RESERVED_FRAMES]
+};
+
+/// Returns the slot value of the stack of `pages` pages above the guard page
+/// at `guard`, if it can be represented
+fn encode(guard: usize, pages: usize) -> Option<u64> {
+	let page_number = u64::try_from(guard / PAGE_SIZE).ok()?;
+	let pages = u64::try_from(pages).ok()?;
+	if page_number >> (u64::BITS - PAGES_BITS) != 0 || pages >> PAGES_BITS != 0 {
+		return None;
+	}
+
+	Some(page_number << PAGES_BITS | pages)
+}
+
+/// Returns the address of the guard page and the address range of the
+/// stack, which is stored in `slot`
+fn decode(slot: u64) -> (usize, Range<usize>) {
+	let guard = (slot >> PAGES_BITS) as usize * PAGE_SIZE;
+	let pages = (slot & ((1 << PAGES_BITS) - 1)) as usize;
+	let start = guard + PAGE_SIZE;
+
+	(guard, start..start + pages * PAGE_SIZE)
+}
+
+/// Fills the reserve of frames for page faults on stacks
+fn refill_reserve() {
+	for slot in RESERVE.iter() {
+		if slot.load(Ordering::Relaxed) != NO_FRAME {
+			continue;
+		}
+		let Ok(addr) = arch::mm::physicalmem::allocate(PAGE_SIZE) else {
+			return;
+		};
+		if slot
+			.compare_exchange(NO_FRAME, addr.as_u64(), Ordering::AcqRel, Ordering::Relaxed)
+			.is_err()
+		{
+			arch::mm::physicalmem::deallocate(addr, PAGE_SIZE);
+		}
+	}
+}
+
+/// Returns a frame for a page fault on a stack without waiting for a lock
+fn take_frame() -> Option<PhysAddr> {
+	if let Ok(addr) = arch::mm::physicalmem::try_allocate(PAGE_SIZE) {
+		return Some(addr);
+	}
+
+	RESERVE.iter().find_map(|slot| {
+		let addr = slot.swap(NO_FRAME, Ordering::AcqRel);
+		(addr != NO_FRAME).then_some(PhysAddr(addr))
+	})
+}
+
+/// Maps the frame `addr` at `page` and fills it with the stack pattern
+fn map_page(page: usize, addr: PhysAddr) {
+	let virtual_address = VirtAddr(page as u64);
+	let mut flags = PageTableEntryFlags::empty();
+	flags.normal().writable().execute_disable();
+	arch::mm::paging::map::<BasePageSize>(virtual_address, addr, 1, flags);
+	unsafe { slice::from_raw_parts_mut(virtual_address.as_mut_ptr::<u8>(), PAGE_SIZE) }
+		.fill(STACK_PATTERN);
+}
+
+/// Maps the pages of `range` to new frames
+fn map_pages(range: Range<usize>) {
+	for page in range.step_by(PAGE_SIZE) {
+		let addr = arch::mm::physicalmem::allocate(PAGE_SIZE)
+			.expect("Failed to allocate Physical Memory for the stack");
+		map_page(page, addr);
+	}
+}
+
+/// Reserves the stack of `size` bytes above the guard page at `guard`. The
+/// top of the stack is mapped immediately, the other pages on the first access.
+pub(crate) fn reserve(guard: VirtAddr, size: usize) {
+	let start = guard.as_usize() + PAGE_SIZE;
+	let range = start..start + size.align_up(PAGE_SIZE);
+
+	// every task uses the top of its stack => map it immediately
+	let top = range.end - KERNEL_STACK_SIZE.min(range.len());
+	map_pages(top..range.end);
+
+	let registered = encode(guard.as_usize(), range.len() / PAGE_SIZE).is_some_and(|value| {
+		slots().iter().enumerate().any(|(i, slot)| {
+			let free = slot
+				.compare_exchange(0, value, Ordering::AcqRel, Ordering::Relaxed)
+				.is_ok();
+			if free {
+				USED_SLOTS.fetch_max(i + 1, Ordering::AcqRel);
+			}
+			free
+		})
+	});
+	if !registered {
+		warn!(
+			"No free slot for the stack at {:#x?}, map it completely",
+			range
+		);
+		map_pages(range.start..top);
+		return;
+	}
+
+	// create the page tables by mapping a page temporarily, so that mapping
+	// a page on a fault doesn't allocate frames for them
+	let frame =
+		arch::mm::paging::get_physical_address::<BasePageSize>(VirtAddr(top as u64)).unwrap();
+	let mut flags = PageTableEntryFlags::empty();
+	flags.normal().execute_disable();
+	for table in (range.start.align_down(TABLE_SIZE)..top).step_by(TABLE_SIZE) {
+		let page = VirtAddr(table.max(range.start) as u64);
+		arch::mm::paging::map::<BasePageSize>(page, frame, 1, flags);
+		arch::mm::paging::unmap::<BasePageSize>(page, 1);
+	}
+
+	refill_reserve();
+}
+
+/// Unmaps the stack above the guard page at `guard` and releases its frames
+pub(crate) fn release(guard: VirtAddr, size: usize) {
+	let start = guard.as_usize() + PAGE_SIZE;
+	let range = start..start + size.align_up(PAGE_SIZE);
+
+	if let Some(value) = encode(guard.as_usize(), range.len() / PAGE_SIZE) {
+		for slot in slots().iter() {
+			if slot
+				.compare_exchange(value, 0, Ordering::AcqRel, Ordering::Relaxed)
+				.is_ok()
+			{
+				break;
+			}
+		}
+	}
+
+	for page in range.step_by(PAGE_SIZE) {
+		let virtual_address = VirtAddr(page as u64);
+		if let Some(addr) = arch::mm::paging::get_physical_address::<BasePageSize>(virtual_address)
+		{
+			arch::mm::paging::unmap::<BasePageSize>(virtual_address, 1);
+			arch::mm::physicalmem::deallocate(addr, PAGE_SIZE);
+		}
+	}
+
+	refill_reserve();
+}
+
+/// Maps a frame, if the page fault at `addr` hits a page of a stack, which
+/// hasn't been accessed yet. Panics, if the fault hits the guard page of a
+/// stack. Returns false, if the page fault doesn't belong to a stack.
+pub(crate) fn handle_page_fault(addr: usize, access: Access) -> bool {
+	let page = addr.align_down(PAGE_SIZE);
+
+	let Some(stacks) = STACKS.get() else {
+		return false;
+	};
+	let Some((guard, _)) = stacks[..USED_SLOTS.load(Ordering::Acquire)]
+		.iter()
+		.map(|slot| slot.load(Ordering::Acquire))
+		.filter(|value| *value != 0)
+		.map(decode)
+		.find(|(guard, range)| (*guard..range.end).contains(&page))
+	else {
+		return false;
+	};
+	if page == guard {
+		panic!(
+			"stack overflow in task {} at {:#x}",
+			core_scheduler().get_current_task_id(),
+			addr
+		);
+	}
+	if access == Access::Execute {
+		return false;
+	}
+
+	if arch::mm::paging::get_physical_address::<BasePageSize>(VirtAddr(page as u64)).is_some() {
+		// the page has been mapped in the meantime
+		return true;
+	}
+
+	let Some(frame) = take_frame() else {
+		return false;
+	};
+	map_page(page, frame);
+
+	true
+}
@@ -3,6 +3,7 @@
 use alloc::boxed::Box;
 use alloc::collections::{BTreeMap, VecDeque};
 use alloc::rc::Rc;
+use alloc::string::String;
 use alloc::sync::Arc;
 #[cfg(feature = "smp")]
 use alloc::vec::Vec;
@@ -27,7 +28,7 @@ use crate::arch::switch::switch_to_task;
 #[cfg(target_arch = "x86_64")]
 use crate::arch::switch::{switch_to_fpu_owner, switch_to_task};
 use crate::arch::{get_processor_count, interrupts};
-use crate::fd::{FileDescriptor, IoError, ObjectInterface};
+use crate::fd::{FdFlags, FileDescriptor, IoError, ObjectEntry, ObjectInterface};
 use crate::kernel::scheduler::TaskStacks;
 use crate::scheduler::task::*;
 
@@ -208,8 +209,8 @@ struct NewTask {
 	prio: Priority,
 	core_id: CoreId,
 	stacks: TaskStacks,
-	object_map:
-		Arc<async_lock::RwLock<HashMap<FileDescriptor, Arc<dyn ObjectInterface>, RandomState>>>,
+	object_map: Arc<async_lock::RwLock<HashMap<FileDescriptor, ObjectEntry, RandomState>>>,
+	cwd: String,
 }
 
 impl From<NewTask> for Task {
@@ -222,8 +223,17 @@ impl From<NewTask> for Task {
 			core_id,
 			stacks,
 			object_map,
+			cwd,
 		} = value;
-		let mut task = Self::new(tid, core_id, TaskStatus::Ready, prio, stacks, object_map);
+		let mut task = Self::new(
+			tid,
+			core_id,
+			TaskStatus::Ready,
+			prio,
+			stacks,
+			object_map,
+			cwd,
+		);
 		task.create_stack_frame(func, arg);
 		task
 	}
@@ -249,6 +259,7 @@ impl PerCoreScheduler {
 			core_id,
 			stacks,
 			object_map: core_scheduler().get_current_task_object_map(),
+			cwd: core_scheduler().get_current_task_cwd(),
 		};
 
 		// Add it to the task lists.
@@ -329,6 +340,7 @@ impl PerCoreScheduler {
 			core_id,
 			stacks: TaskStacks::new(current_task_borrowed.stacks.get_user_stack_size()),
 			object_map: current_task_borrowed.object_map.clone(),
+			cwd: current_task_borrowed.cwd.clone(),
 		};
 
 		// Add it to the task lists.
@@ -462,10 +474,20 @@ impl PerCoreScheduler {
 	#[inline]
 	pub fn get_current_task_object_map(
 		&self,
-	) -> Arc<async_lock::RwLock<HashMap<FileDescriptor, Arc<dyn ObjectInterface>, RandomState>>> {
+	) -> Arc<async_lock::RwLock<HashMap<FileDescriptor, ObjectEntry, RandomState>>> {
 		without_interrupts(|| self.current_task.borrow().object_map.clone())
 	}
 
+	#[inline]
+	pub fn get_current_task_cwd(&self) -> String {
+		without_interrupts(|| self.current_task.borrow().cwd.clone())
+	}
+
+	#[inline]
+	pub fn set_current_task_cwd(&self, cwd: String) {
+		without_interrupts(|| self.current_task.borrow_mut().cwd = cwd);
+	}
+
 	/// Map a file descriptor to their IO interface and returns
 	/// the shared reference
 	#[inline]
@@ -479,7 +501,42 @@ impl PerCoreScheduler {
 				let mut pinned_obj = core::pin::pin!(borrowed.object_map.read());
 
 				let guard = ready!(pinned_obj.as_mut().poll(cx));
-				Ready(guard.get(&fd).cloned().ok_or(IoError::EINVAL))
+				Ready(
+					guard
+						.get(&fd)
+						.map(|entry| entry.object.clone())
+						.ok_or(IoError::EINVAL),
+				)
+			})
+		})
+		.await
+	}
+
+	/// Returns the entry of a file descriptor including its flags
+	pub async fn get_entry(&self, fd: FileDescriptor) -> Result<ObjectEntry, IoError> {
+		future::poll_fn(|cx| {
+			without_interrupts(|| {
+				let borrowed = self.current_task.borrow();
+				let mut pinned_obj = core::pin::pin!(borrowed.object_map.read());
+
+				let guard = ready!(pinned_obj.as_mut().poll(cx));
+				Ready(guard.get(&fd).cloned().ok_or(IoError::EBADF))
+			})
+		})
+		.await
+	}
+
+	/// Sets the flags of the file descriptor `fd`
+	pub async fn set_fd_flags(&self, fd: FileDescriptor, flags: FdFlags) -> Result<(), IoError> {
+		future::poll_fn(|cx| {
+			without_interrupts(|| {
+				let borrowed = self.current_task.borrow();
+				let mut pinned_obj = core::pin::pin!(borrowed.object_map.write());
+
+				let mut guard = ready!(pinned_obj.as_mut().poll(cx));
+				let entry = guard.get_mut(&fd).ok_or(IoError::EBADF)?;
+				entry.flags = flags;
+				Ready(Ok(()))
 			})
 		})
 		.await
@@ -489,7 +546,7 @@ impl PerCoreScheduler {
 	/// clone the standard descriptors.
 	#[allow(dead_code)]
 	pub async fn recreate_objmap(&self) -> Result<(), IoError> {
-		let mut map = HashMap::<FileDescriptor, Arc<dyn ObjectInterface>, RandomState>::with_hasher(
+		let mut map = HashMap::<FileDescriptor, ObjectEntry, RandomState>::with_hasher(
 			RandomState::with_seeds(0, 0, 0, 0),
 		);
 
@@ -524,6 +581,12 @@ impl PerCoreScheduler {
 		&self,
 		obj: Arc<dyn ObjectInterface>,
 	) -> Result<FileDescriptor, IoError> {
+		self.insert_entry(ObjectEntry::new(obj)).await
+	}
+
+	/// Insert a new entry including its flags and returns the lowest
+	/// available file descriptor
+	pub async fn insert_entry(&self, entry: ObjectEntry) -> Result<FileDescriptor, IoError> {
 		future::poll_fn(|cx| {
 			without_interrupts(|| {
 				let borrowed = self.current_task.borrow();
@@ -544,7 +607,7 @@ impl PerCoreScheduler {
 				};
 
 				let fd = new_fd()?;
-				let _ = guard.insert(fd, obj.clone());
+				let _ = guard.insert(fd, entry.clone());
 				Ready(Ok(fd))
 			})
 		})
@@ -564,7 +627,13 @@ impl PerCoreScheduler {
 				let mut pinned_obj = core::pin::pin!(borrowed.object_map.write());
 
 				let mut guard = ready!(pinned_obj.as_mut().poll(cx));
-				guard.insert(fd, obj.clone());
+				// the flags of the file descriptor are kept
+				match guard.get_mut(&fd) {
+					Some(entry) => entry.object = obj.clone(),
+					None => {
+						guard.insert(fd, ObjectEntry::new(obj.clone()));
+					}
+				}
 				Ready(Ok(()))
 			})
 		})
@@ -572,18 +641,27 @@ impl PerCoreScheduler {
 	}
 
 	/// Duplicate a IO interface and returns a new file descriptor as
-	/// identifier to the new copy
-	pub async fn dup_object(&self, fd: FileDescriptor) -> Result<FileDescriptor, IoError> {
+	/// identifier to the new copy. The new file descriptor is the lowest
+	/// available one, which is greater than or equal to `lowest`.
+	pub async fn dup_object(
+		&self,
+		fd: FileDescriptor,
+		lowest: FileDescriptor,
+		flags: FdFlags,
+	) -> Result<FileDescriptor, IoError> {
 		future::poll_fn(|cx| {
 			without_interrupts(|| {
 				let borrowed = self.current_task.borrow();
 				let mut pinned_obj = core::pin::pin!(borrowed.object_map.write());
 
 				let mut guard = ready!(pinned_obj.as_mut().poll(cx));
-				let obj = (*(guard.get(&fd).ok_or(IoError::EINVAL)?)).clone();
+				let obj = ObjectEntry {
+					flags,
+					..guard.get(&fd).ok_or(IoError::EBADF)?.clone()
+				};
 
 				let new_fd = || -> Result<FileDescriptor, IoError> {
-					let mut fd: FileDescriptor = 0;
+					let mut fd: FileDescriptor = lowest;
 					loop {
 						if !guard.contains_key(&fd) {
 							break Ok(fd);
@@ -606,6 +684,32 @@ impl PerCoreScheduler {
 		.await
 	}
 
+	/// Duplicate the IO interface of `fd1` to the file descriptor `fd2`.
+	/// An object, which was previously referenced by `fd2`, is returned
+	/// and has to be released by the caller.
+	pub async fn dup_object2(
+		&self,
+		fd1: FileDescriptor,
+		fd2: FileDescriptor,
+		flags: FdFlags,
+	) -> Result<Option<ObjectEntry>, IoError> {
+		future::poll_fn(|cx| {
+			without_interrupts(|| {
+				let borrowed = self.current_task.borrow();
+				let mut pinned_obj = core::pin::pin!(borrowed.object_map.write());
+
+				let mut guard = ready!(pinned_obj.as_mut().poll(cx));
+				let obj = ObjectEntry {
+					flags,
+					..guard.get(&fd1).ok_or(IoError::EBADF)?.clone()
+				};
+
+				Ready(Ok(guard.insert(fd2, obj)))
+			})
+		})
+		.await
+	}
+
 	/// Remove a IO interface, which is named by the file descriptor
 	pub async fn remove_object(
 		&self,
@@ -616,7 +720,12 @@ impl PerCoreScheduler {
 				let borrowed = self.current_task.borrow();
 				let mut pinned_obj = core::pin::pin!(borrowed.object_map.write());
 				let mut guard = ready!(pinned_obj.as_mut().poll(cx));
-				Ready(guard.remove(&fd).ok_or(IoError::EINVAL))
+				Ready(
+					guard
+						.remove(&fd)
+						.map(|entry| entry.object)
+						.ok_or(IoError::EINVAL),
+				)
 			})
 		})
 		.await
@@ -4,6 +4,7 @@
 use alloc::boxed::Box;
 use alloc::collections::{LinkedList, VecDeque};
 use alloc::rc::Rc;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::cell::RefCell;
@@ -23,9 +24,7 @@ use crate::arch::scheduler::TaskStacks;
 use crate::arch::scheduler::TaskTLS;
 use crate::executor::poll_on;
 use crate::fd::stdio::*;
-use crate::fd::{
-	FileDescriptor, IoError, ObjectInterface, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO,
-};
+use crate::fd::{FileDescriptor, IoError, ObjectEntry, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
 use crate::scheduler::CoreId;
 use crate::{arch, env};
 
@@ -391,8 +390,9 @@ pub(crate) struct Task {
 	/// Stack of the task
 	pub stacks: TaskStacks,
 	/// Mapping between file descriptor and the referenced IO interface
-	pub object_map:
-		Arc<async_lock::RwLock<HashMap<FileDescriptor, Arc<dyn ObjectInterface>, RandomState>>>,
+	pub object_map: Arc<async_lock::RwLock<HashMap<FileDescriptor, ObjectEntry, RandomState>>>,
+	/// Current working directory, which is used to resolve relative paths
+	pub cwd: String,
 	/// Task Thread-Local-Storage (TLS)
 	#[cfg(not(feature = "common-os"))]
 	pub tls: Option<Box<TaskTLS>>,
@@ -416,9 +416,8 @@ impl Task {
 		task_status: TaskStatus,
 		task_prio: Priority,
 		stacks: TaskStacks,
-		object_map: Arc<
-			async_lock::RwLock<HashMap<FileDescriptor, Arc<dyn ObjectInterface>, RandomState>>,
-		>,
+		object_map: Arc<async_lock::RwLock<HashMap<FileDescriptor, ObjectEntry, RandomState>>>,
+		cwd: String,
 	) -> Task {
 		debug!("Creating new task {} on core {}", tid, core_id);
 
@@ -433,6 +432,7 @@ impl Task {
 			core_id,
 			stacks,
 			object_map,
+			cwd,
 			#[cfg(not(feature = "common-os"))]
 			tls: None,
 			#[cfg(all(target_arch = "x86_64", feature = "common-os"))]
@@ -447,14 +447,14 @@ impl Task {
 
 		/// All cores use the same mapping between file descriptor and the referenced object
 		static OBJECT_MAP: OnceCell<
-			Arc<async_lock::RwLock<HashMap<FileDescriptor, Arc<dyn ObjectInterface>, RandomState>>>,
+			Arc<async_lock::RwLock<HashMap<FileDescriptor, ObjectEntry, RandomState>>>,
 		> = OnceCell::new();
 
 		if core_id == 0 {
 			OBJECT_MAP
 				.set(Arc::new(async_lock::RwLock::new(HashMap::<
 					FileDescriptor,
-					Arc<dyn ObjectInterface>,
+					ObjectEntry,
 					RandomState,
 				>::with_hasher(
 					RandomState::with_seeds(0, 0, 0, 0),
@@ -466,23 +466,38 @@ impl Task {
 					let mut guard = objmap.write().await;
 					if env::is_uhyve() {
 						guard
-							.try_insert(STDIN_FILENO, Arc::new(UhyveStdin::new()))
+							.try_insert(STDIN_FILENO, ObjectEntry::new(Arc::new(UhyveStdin::new())))
 							.map_err(|_| IoError::EIO)?;
 						guard
-							.try_insert(STDOUT_FILENO, Arc::new(UhyveStdout::new()))
+							.try_insert(
+								STDOUT_FILENO,
+								ObjectEntry::new(Arc::new(UhyveStdout::new())),
+							)
 							.map_err(|_| IoError::EIO)?;
 						guard
-							.try_insert(STDERR_FILENO, Arc::new(UhyveStderr::new()))
+							.try_insert(
+								STDERR_FILENO,
+								ObjectEntry::new(Arc::new(UhyveStderr::new())),
+							)
 							.map_err(|_| IoError::EIO)?;
 					} else {
 						guard
-							.try_insert(STDIN_FILENO, Arc::new(GenericStdin::new()))
+							.try_insert(
+								STDIN_FILENO,
+								ObjectEntry::new(Arc::new(GenericStdin::new())),
+							)
 							.map_err(|_| IoError::EIO)?;
 						guard
-							.try_insert(STDOUT_FILENO, Arc::new(GenericStdout::new()))
+							.try_insert(
+								STDOUT_FILENO,
+								ObjectEntry::new(Arc::new(GenericStdout::new())),
+							)
 							.map_err(|_| IoError::EIO)?;
 						guard
-							.try_insert(STDERR_FILENO, Arc::new(GenericStderr::new()))
+							.try_insert(
+								STDERR_FILENO,
+								ObjectEntry::new(Arc::new(GenericStderr::new())),
+							)
 							.map_err(|_| IoError::EIO)?;
 					}
 
@@ -503,6 +518,7 @@ impl Task {
 			core_id,
 			stacks: TaskStacks::from_boot_stacks(),
 			object_map: OBJECT_MAP.get().unwrap().clone(),
+			cwd: "/".to_string(),
 			#[cfg(not(feature = "common-os"))]
 			tls: None,
 			#[cfg(all(target_arch = "x86_64", feature = "common-os"))]
@@ -34,6 +34,18 @@ static mut SHELL: Lazy<Shell<'_>> = Lazy::new(|| {
 			aliases: &["i"],
 		},
 	);
+	#[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
+	shell.commands.insert(
+		"netstat",
+		ShellCommand {
+			help: "Shows the network interfaces, their counters and the open sockets",
+			func: |_, shell| {
+				print!("{}", crate::executor::stats::report());
+				Ok(())
+			},
+			aliases: &["n"],
+		},
+	);
 	shell.commands.insert(
 		"shutdown",
 		ShellCommand {
@@ -0,0 +1,111 @@
+use core::ffi::c_void;
+
+use crate::arch::mm::paging::{BasePageSize, PageSize};
+use crate::errno::*;
+use crate::fd::{get_object, IoError};
+use crate::mm::mmap::{self, Backing, Protection};
+
+pub const PROT_NONE: i32 = 0;
+pub const PROT_READ: i32 = 1 << 0;
+pub const PROT_WRITE: i32 = 1 << 1;
+pub const PROT_EXEC: i32 = 1 << 2;
+pub const MAP_SHARED: i32 = 0x01;
+pub const MAP_PRIVATE: i32 = 0x02;
+pub const MAP_FIXED: i32 = 0x10;
+pub const MAP_ANONYMOUS: i32 = 0x20;
+pub const MAP_HUGETLB: i32 = 0x40000;
+
+/// Returns the memory of the file `fd`, which backs a mapping of `len` bytes at `offset`
+fn file_backing(
+	fd: i32,
+	offset: i64,
+	len: usize,
+	prot: Protection,
+	shared: bool,
+) -> Result<Backing, IoError> {
+	let offset = usize::try_from(offset).map_err(|_| IoError::EINVAL)?;
+	if offset % BasePageSize::SIZE as usize != 0 || offset.checked_add(len).is_none() {
+		return Err(IoError::EINVAL);
+	}
+
+	let writable = shared && prot.contains(Protection::WRITE);
+	let object = get_object(fd)?.mmap(offset, len, writable)?;
+
+	Ok(Backing {
+		object,
+		shared,
+		writable,
+	})
+}
+
+/// Creates a mapping of `len` bytes with the protection `prot` and returns
+/// its address or a negative error number. Pages of anonymous mappings are
+/// zeroed on the first access. Files in memory and on virtio-fs with a DAX
+/// window share their pages with the mapping, if possible. Private mappings
+/// copy a page on the first write. With `MAP_FIXED`, the mapping replaces
+/// the mappings at `addr`, which has to be part of the region of the memory
+/// mappings. Otherwise, `addr` is only a hint. The region is reserved apart
+/// from the task heap, because the heap is mapped at boot and owned by the
+/// heap allocator. Therefore, a `MAP_FIXED` address in the task heap fails
+/// with `ENOMEM`. Anonymous mappings with
+/// `MAP_HUGETLB` are mapped with 2 MiB pages and their length is rounded up
+/// to 2 MiB.
+#[hermit_macro::system]
+pub extern "C" fn sys_mmap(
+	addr: *mut c_void,
+	len: usize,
+	prot: i32,
+	flags: i32,
+	fd: i32,
+	offset: i64,
+) -> isize {
+	let Some(prot) = Protection::from_bits(prot) else {
+		return (-EINVAL).try_into().unwrap();
+	};
+	let known_flags = MAP_SHARED | MAP_PRIVATE | MAP_FIXED | MAP_ANONYMOUS | MAP_HUGETLB;
+	if flags & !known_flags != 0 || (flags & MAP_SHARED != 0) == (flags & MAP_PRIVATE != 0) {
+		return (-EINVAL).try_into().unwrap();
+	}
+
+	let backing = if flags & MAP_ANONYMOUS == 0 {
+		match file_backing(fd, offset, len, prot, flags & MAP_SHARED != 0) {
+			Ok(backing) => Some(backing),
+			Err(e) => return -num::ToPrimitive::to_isize(&e).unwrap(),
+		}
+	} else {
+		None
+	};
+
+	mmap::map(
+		addr.addr(),
+		len,
+		prot,
+		flags & MAP_FIXED != 0,
+		flags & MAP_HUGETLB != 0,
+		backing,
+	)
+	.map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|addr| addr.try_into().unwrap(),
+	)
+}
+
+/// Removes the mappings of the `len` bytes at `addr`
+#[hermit_macro::system]
+pub extern "C" fn sys_munmap(addr: *mut c_void, len: usize) -> i32 {
+	mmap::unmap(addr.addr(), len).map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
+/// Changes the protection of the `len` bytes at `addr`, which have to be
+/// mapped by `sys_mmap`. `PROT_NONE` turns the pages into guard pages and a
+/// JIT compiler can switch its code between writable and executable. Shared
+/// mappings of files only become writable, if they have been created writable.
+#[hermit_macro::system]
+pub extern "C" fn sys_mprotect(addr: *mut c_void, len: usize, prot: i32) -> i32 {
+	let Some(prot) = Protection::from_bits(prot) else {
+		return -EINVAL;
+	};
+
+	mmap::protect(addr.addr(), len, prot)
+		.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
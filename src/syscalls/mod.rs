@@ -1,5 +1,6 @@
 #![allow(clippy::result_unit_err)]
 
+use alloc::vec::Vec;
 #[cfg(all(target_os = "none", not(feature = "common-os")))]
 use core::alloc::{GlobalAlloc, Layout};
 use core::ffi::CStr;
@@ -12,6 +13,7 @@ use hermit_sync::Lazy;
 pub use self::condvar::*;
 pub use self::entropy::*;
 pub use self::futex::*;
+pub use self::mmap::*;
 pub use self::processor::*;
 #[cfg(feature = "newlib")]
 pub use self::recmutex::*;
@@ -21,11 +23,13 @@ pub use self::system::*;
 pub use self::tasks::*;
 pub use self::timer::*;
 use crate::env;
+use crate::fd::epoll::{EpollCtl, EpollEvent};
+use crate::fd::lock::{FileLock, LockKind, LockType};
 use crate::fd::{
-	dup_object, get_object, remove_object, AccessPermission, EventFlags, FileDescriptor, IoCtl,
-	IoError, OpenOption, PollFd,
+	dup_object, dup_object2, get_entry, get_object, remove_object, set_fd_flags, AccessPermission,
+	EventFlags, FdFlags, FileDescriptor, IoCtl, IoError, OpenOption, PollFd,
 };
-use crate::fs::{self, FileAttr};
+use crate::fs::{self, FileAttr, SeekWhence};
 #[cfg(all(target_os = "none", not(feature = "common-os")))]
 use crate::mm::ALLOCATOR;
 use crate::syscalls::interfaces::SyscallInterface;
@@ -36,6 +40,7 @@ mod futex;
 mod interfaces;
 #[cfg(feature = "newlib")]
 mod lwip;
+mod mmap;
 mod processor;
 #[cfg(feature = "newlib")]
 mod recmutex;
@@ -52,6 +57,18 @@ mod timer;
 #[cfg(feature = "newlib")]
 const LWIP_FD_BIT: i32 = 1 << 30;
 
+pub const AF_INET: i32 = 0;
+pub const AF_INET6: i32 = 1;
+pub const AF_UNSPEC: i32 = 2;
+pub const AF_UNIX: i32 = 41;
+
+// The standard library passes the address families of hermit-abi 0.3.9,
+// which it depends on for the pinned toolchain. Changing them breaks every
+// socket of applications, which have been built against it.
+const _: () = assert!(AF_INET == 0 && AF_INET6 == 1);
+const _: () = assert!(AF_UNSPEC != AF_INET && AF_UNSPEC != AF_INET6);
+const _: () = assert!(AF_UNIX != AF_INET && AF_UNIX != AF_INET6 && AF_UNIX != AF_UNSPEC);
+
 #[cfg(feature = "newlib")]
 pub(crate) static LWIP_LOCK: InterruptTicketMutex<()> = InterruptTicketMutex::new(());
 
@@ -248,6 +265,59 @@ pub unsafe extern "C" fn sys_rmdir(name: *const u8) -> i32 {
 		.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
 }
 
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_rename(oldpath: *const u8, newpath: *const u8) -> i32 {
+	let oldpath = unsafe { CStr::from_ptr(oldpath as _) }.to_str().unwrap();
+	let newpath = unsafe { CStr::from_ptr(newpath as _) }.to_str().unwrap();
+
+	fs::FILESYSTEM
+		.get()
+		.unwrap()
+		.rename(oldpath, newpath)
+		.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_link(oldpath: *const u8, newpath: *const u8) -> i32 {
+	let oldpath = unsafe { CStr::from_ptr(oldpath as _) }.to_str().unwrap();
+	let newpath = unsafe { CStr::from_ptr(newpath as _) }.to_str().unwrap();
+
+	fs::FILESYSTEM
+		.get()
+		.unwrap()
+		.link(oldpath, newpath)
+		.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_symlink(target: *const u8, linkpath: *const u8) -> i32 {
+	let target = unsafe { CStr::from_ptr(target as _) }.to_str().unwrap();
+	let linkpath = unsafe { CStr::from_ptr(linkpath as _) }.to_str().unwrap();
+
+	fs::FILESYSTEM
+		.get()
+		.unwrap()
+		.symlink(target, linkpath)
+		.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
+/// Places the target of the symbolic link `name` in `buf`. The target is not
+/// NUL-terminated and is truncated, if the buffer is too small.
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_readlink(name: *const u8, buf: *mut u8, len: usize) -> isize {
+	let name = unsafe { CStr::from_ptr(name as _) }.to_str().unwrap();
+
+	match fs::FILESYSTEM.get().unwrap().readlink(name) {
+		Ok(target) => {
+			let len = core::cmp::min(len, target.len());
+			let slice = unsafe { core::slice::from_raw_parts_mut(buf, len) };
+			slice.copy_from_slice(&target.as_bytes()[..len]);
+			len.try_into().unwrap()
+		}
+		Err(e) => -num::ToPrimitive::to_isize(&e).unwrap(),
+	}
+}
+
 #[hermit_macro::system]
 pub unsafe extern "C" fn sys_stat(name: *const u8, stat: *mut FileAttr) -> i32 {
 	let name = unsafe { CStr::from_ptr(name as _) }.to_str().unwrap();
@@ -274,6 +344,34 @@ pub unsafe extern "C" fn sys_lstat(name: *const u8, stat: *mut FileAttr) -> i32
 	}
 }
 
+/// Copies the NUL-terminated absolute path of the current working
+/// directory into `buf`.
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_getcwd(buf: *mut u8, size: usize) -> i32 {
+	let cwd = fs::getcwd();
+	if cwd.len() + 1 > size {
+		return -crate::errno::ERANGE;
+	}
+
+	let slice = unsafe { core::slice::from_raw_parts_mut(buf, cwd.len() + 1) };
+	slice[..cwd.len()].copy_from_slice(cwd.as_bytes());
+	slice[cwd.len()] = b'\0';
+
+	0
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_chdir(path: *const u8) -> i32 {
+	let path = unsafe { CStr::from_ptr(path as _) }.to_str().unwrap();
+
+	fs::chdir(path).map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
+#[hermit_macro::system]
+pub extern "C" fn sys_fchdir(fd: FileDescriptor) -> i32 {
+	fs::fchdir(fd).map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
 #[hermit_macro::system]
 pub unsafe extern "C" fn sys_fstat(fd: FileDescriptor, stat: *mut FileAttr) -> i32 {
 	let stat = unsafe { &mut *stat };
@@ -345,6 +443,95 @@ pub unsafe extern "C" fn sys_write(fd: FileDescriptor, buf: *const u8, len: usiz
 	unsafe { write(fd, buf, len) }
 }
 
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_pread(
+	fd: FileDescriptor,
+	buf: *mut u8,
+	len: usize,
+	offset: isize,
+) -> isize {
+	let Ok(offset) = usize::try_from(offset) else {
+		return -crate::errno::EINVAL as isize;
+	};
+	let slice = unsafe { core::slice::from_raw_parts_mut(buf, len) };
+	crate::fd::pread(fd, slice, offset).map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|v| v.try_into().unwrap(),
+	)
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_pwrite(
+	fd: FileDescriptor,
+	buf: *const u8,
+	len: usize,
+	offset: isize,
+) -> isize {
+	let Ok(offset) = usize::try_from(offset) else {
+		return -crate::errno::EINVAL as isize;
+	};
+	let slice = unsafe { core::slice::from_raw_parts(buf, len) };
+	crate::fd::pwrite(fd, slice, offset).map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|v| v.try_into().unwrap(),
+	)
+}
+
+/// Buffer of a vectored I/O operation
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IoVec {
+	pub iov_base: *mut u8,
+	pub iov_len: usize,
+}
+
+/// Maximum number of buffers of a vectored I/O operation
+const IOV_MAX: i32 = 1024;
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_readv(fd: FileDescriptor, iov: *const IoVec, iovcnt: i32) -> isize {
+	if !(0..=IOV_MAX).contains(&iovcnt) {
+		return -crate::errno::EINVAL as isize;
+	}
+
+	if iovcnt == 0 {
+		return 0;
+	}
+
+	let iov = unsafe { core::slice::from_raw_parts(iov, iovcnt.try_into().unwrap()) };
+	let mut bufs: Vec<&mut [u8]> = iov
+		.iter()
+		.filter(|v| v.iov_len > 0)
+		.map(|v| unsafe { core::slice::from_raw_parts_mut(v.iov_base, v.iov_len) })
+		.collect();
+	crate::fd::readv(fd, &mut bufs).map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|v| v.try_into().unwrap(),
+	)
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_writev(fd: FileDescriptor, iov: *const IoVec, iovcnt: i32) -> isize {
+	if !(0..=IOV_MAX).contains(&iovcnt) {
+		return -crate::errno::EINVAL as isize;
+	}
+
+	if iovcnt == 0 {
+		return 0;
+	}
+
+	let iov = unsafe { core::slice::from_raw_parts(iov, iovcnt.try_into().unwrap()) };
+	let bufs: Vec<&[u8]> = iov
+		.iter()
+		.filter(|v| v.iov_len > 0)
+		.map(|v| unsafe { core::slice::from_raw_parts(v.iov_base, v.iov_len) })
+		.collect();
+	crate::fd::writev(fd, &bufs).map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|v| v.try_into().unwrap(),
+	)
+}
+
 #[hermit_macro::system]
 pub unsafe extern "C" fn sys_ioctl(
 	fd: FileDescriptor,
@@ -369,30 +556,198 @@ pub unsafe extern "C" fn sys_ioctl(
 	}
 }
 
+/// Description of a record lock, which is used by `fcntl`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Flock {
+	pub l_type: i16,
+	pub l_whence: i16,
+	pub l_start: i64,
+	pub l_len: i64,
+	pub l_pid: i32,
+}
+
+/// Converts the range of `flock`, which is relative to `l_whence`, into an absolute lock
+fn flock_to_lock(fd: FileDescriptor, flock: &Flock) -> Result<FileLock, IoError> {
+	let kind = num::FromPrimitive::from_i16(flock.l_type).ok_or(IoError::EINVAL)?;
+	let base: i64 = match num::FromPrimitive::from_i16(flock.l_whence) {
+		Some(SeekWhence::Set) => 0,
+		Some(SeekWhence::Cur) => get_object(fd)?
+			.lseek(0, SeekWhence::Cur)?
+			.try_into()
+			.unwrap(),
+		Some(SeekWhence::End) => {
+			let mut attr = FileAttr::default();
+			get_object(fd)?.fstat(&mut attr)?;
+			attr.st_size.try_into().map_err(|_| IoError::EOVERFLOW)?
+		}
+		_ => return Err(IoError::EINVAL),
+	};
+	let start = base.checked_add(flock.l_start).ok_or(IoError::EOVERFLOW)?;
+	if start < 0 {
+		return Err(IoError::EINVAL);
+	}
+
+	// a negative length locks the bytes in front of `start`
+	let (start, end) = match flock.l_len {
+		0 => (start, u64::MAX),
+		len if len > 0 => {
+			let end = start.checked_add(len - 1).ok_or(IoError::EOVERFLOW)?;
+			(start, end as u64)
+		}
+		len => {
+			let first = start.checked_add(len).ok_or(IoError::EINVAL)?;
+			if first < 0 {
+				return Err(IoError::EINVAL);
+			}
+			(first, (start - 1) as u64)
+		}
+	};
+
+	Ok(FileLock {
+		kind,
+		start: start as u64,
+		end,
+	})
+}
+
 /// manipulate file descriptor
+///
+/// `arg` is a pointer to a `Flock` for the lock commands and an integer
+/// otherwise. Callers, which pass a 32-bit integer, only define the lower
+/// half of the argument, so integer arguments are truncated.
 #[hermit_macro::system]
-pub extern "C" fn sys_fcntl(fd: i32, cmd: i32, arg: i32) -> i32 {
+pub unsafe extern "C" fn sys_fcntl(fd: i32, cmd: i32, arg: usize) -> i32 {
+	const F_DUPFD: i32 = 0;
+	const F_GETFD: i32 = 1;
 	const F_SETFD: i32 = 2;
+	const F_GETFL: i32 = 3;
 	const F_SETFL: i32 = 4;
-	const FD_CLOEXEC: i32 = 1;
-	const O_NONBLOCK: i32 = 0o4000;
+	const F_GETLK: i32 = 5;
+	const F_SETLK: i32 = 6;
+	const F_SETLKW: i32 = 7;
+	const F_OFD_GETLK: i32 = 36;
+	const F_OFD_SETLK: i32 = 37;
+	const F_OFD_SETLKW: i32 = 38;
+	const F_DUPFD_CLOEXEC: i32 = 1030;
 
-	if cmd == F_SETFD && arg == FD_CLOEXEC {
-		0
-	} else if cmd == F_SETFL && arg == O_NONBLOCK {
-		let obj = get_object(fd);
-		obj.map_or_else(
+	if matches!(
+		cmd,
+		F_GETLK | F_SETLK | F_SETLKW | F_OFD_GETLK | F_OFD_SETLK | F_OFD_SETLKW
+	) {
+		return unsafe { fcntl_lock(fd, cmd, core::ptr::with_exposed_provenance_mut(arg)) };
+	}
+
+	let arg = arg as i32;
+	match cmd {
+		F_DUPFD | F_DUPFD_CLOEXEC => {
+			if arg < 0 {
+				return -crate::errno::EINVAL;
+			}
+			let flags = if cmd == F_DUPFD_CLOEXEC {
+				FdFlags::FD_CLOEXEC
+			} else {
+				FdFlags::empty()
+			};
+
+			dup_object(fd, arg, flags).unwrap_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap())
+		}
+		F_GETFD => get_entry(fd).map_or_else(
 			|e| -num::ToPrimitive::to_i32(&e).unwrap(),
-			|v| {
-				(*v).ioctl(IoCtl::NonBlocking, true)
-					.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+			|entry| entry.flags.bits(),
+		),
+		F_SETFD => set_fd_flags(fd, FdFlags::from_bits_truncate(arg))
+			.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0),
+		F_GETFL => get_entry(fd).map_or_else(
+			|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+			|entry| {
+				let mut status = entry.status();
+				if entry.object.is_nonblocking() {
+					status.insert(OpenOption::O_NONBLOCK);
+				}
+				status.bits()
 			},
-		)
-	} else {
-		-crate::errno::EINVAL
+		),
+		F_SETFL => get_entry(fd).map_or_else(
+			|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+			|entry| {
+				// only `O_APPEND` and `O_NONBLOCK` can be changed, other flags are ignored
+				let changeable = OpenOption::O_APPEND | OpenOption::O_NONBLOCK;
+				let new = OpenOption::from_bits_truncate(arg) & changeable;
+				entry.set_status(entry.status().difference(changeable) | new);
+
+				// sockets and pipes keep track of their own mode, files don't support it
+				let _ = entry
+					.object
+					.ioctl(IoCtl::NonBlocking, new.contains(OpenOption::O_NONBLOCK));
+				0
+			},
+		),
+		_ => -crate::errno::EINVAL,
 	}
 }
 
+/// Tests, places or removes the record lock `flock` with the lock commands
+/// of `fcntl`
+unsafe fn fcntl_lock(fd: i32, cmd: i32, flock: *mut Flock) -> i32 {
+	const F_GETLK: i32 = 5;
+	const F_SETLKW: i32 = 7;
+	const F_OFD_GETLK: i32 = 36;
+	const F_OFD_SETLKW: i32 = 38;
+
+	let Some(flock) = (unsafe { flock.as_mut() }) else {
+		return -crate::errno::EFAULT;
+	};
+
+	match cmd {
+		// locks are always owned by the open file description
+		F_GETLK | F_OFD_GETLK => flock_to_lock(fd, flock)
+			.and_then(|lock| crate::fd::getlk(fd, lock))
+			.map_or_else(
+				|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+				|lock| {
+					flock.l_type = num::ToPrimitive::to_i16(&lock.kind).unwrap();
+					if lock.kind != LockKind::Unlock {
+						flock.l_whence = 0;
+						flock.l_start = lock.start.try_into().unwrap_or(i64::MAX);
+						flock.l_len = if lock.end == u64::MAX {
+							0
+						} else {
+							(lock.end - lock.start + 1).try_into().unwrap_or(i64::MAX)
+						};
+						flock.l_pid = -1;
+					}
+					0
+				},
+			),
+		_ => {
+			let wait = cmd == F_SETLKW || cmd == F_OFD_SETLKW;
+			flock_to_lock(fd, flock)
+				.and_then(|lock| crate::fd::setlk(fd, lock, LockType::Posix, wait))
+				.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+		}
+	}
+}
+
+#[hermit_macro::system]
+pub extern "C" fn sys_flock(fd: FileDescriptor, operation: i32) -> i32 {
+	const LOCK_SH: i32 = 1;
+	const LOCK_EX: i32 = 2;
+	const LOCK_NB: i32 = 4;
+	const LOCK_UN: i32 = 8;
+
+	let kind = match operation & !LOCK_NB {
+		LOCK_SH => LockKind::Read,
+		LOCK_EX => LockKind::Write,
+		LOCK_UN => LockKind::Unlock,
+		_ => return -crate::errno::EINVAL,
+	};
+	let wait = operation & LOCK_NB == 0;
+
+	crate::fd::setlk(fd, FileLock::whole_file(kind), LockType::Flock, wait)
+		.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
 #[hermit_macro::system]
 pub extern "C" fn sys_lseek(fd: FileDescriptor, offset: isize, whence: i32) -> isize {
 	let obj = get_object(fd);
@@ -475,7 +830,36 @@ pub unsafe extern "C" fn sys_getdents64(
 
 #[hermit_macro::system]
 pub extern "C" fn sys_dup(fd: i32) -> i32 {
-	dup_object(fd).unwrap_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap())
+	dup_object(fd, 0, FdFlags::empty()).unwrap_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap())
+}
+
+#[hermit_macro::system]
+pub extern "C" fn sys_dup2(oldfd: i32, newfd: i32) -> i32 {
+	if oldfd == newfd {
+		// only the validity of `oldfd` is checked
+		return get_entry(oldfd).map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| newfd);
+	}
+
+	dup_object2(oldfd, newfd, FdFlags::empty())
+		.unwrap_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap())
+}
+
+#[hermit_macro::system]
+pub extern "C" fn sys_dup3(oldfd: i32, newfd: i32, flags: i32) -> i32 {
+	let Some(flags) = OpenOption::from_bits(flags) else {
+		return -crate::errno::EINVAL;
+	};
+	if oldfd == newfd || !OpenOption::O_CLOEXEC.contains(flags) {
+		return -crate::errno::EINVAL;
+	}
+
+	let fd_flags = if flags.contains(OpenOption::O_CLOEXEC) {
+		FdFlags::FD_CLOEXEC
+	} else {
+		FdFlags::empty()
+	};
+
+	dup_object2(oldfd, newfd, fd_flags).unwrap_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap())
 }
 
 #[hermit_macro::system]
@@ -501,6 +885,138 @@ pub unsafe extern "C" fn sys_poll(fds: *mut PollFd, nfds: usize, timeout: i32) -
 	)
 }
 
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_pipe2(fds: *mut FileDescriptor, flags: i32) -> i32 {
+	const O_NONBLOCK: i32 = 0o4000;
+	const O_CLOEXEC: i32 = 0o2000000;
+
+	if flags & !(O_NONBLOCK | O_CLOEXEC) != 0 {
+		return -crate::errno::EINVAL;
+	}
+
+	crate::fd::pipe(flags & O_NONBLOCK != 0).map_or_else(
+		|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+		|(rx, tx)| {
+			if flags & O_CLOEXEC != 0 {
+				let _ = set_fd_flags(rx, FdFlags::FD_CLOEXEC);
+				let _ = set_fd_flags(tx, FdFlags::FD_CLOEXEC);
+			}
+
+			let fds = unsafe { core::slice::from_raw_parts_mut(fds, 2) };
+			fds[0] = rx;
+			fds[1] = tx;
+			0
+		},
+	)
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_socketpair(
+	domain: i32,
+	type_: i32,
+	protocol: i32,
+	sv: *mut FileDescriptor,
+) -> i32 {
+	const SOCK_STREAM: i32 = 1;
+	const SOCK_NONBLOCK: i32 = 0o4000;
+	const SOCK_CLOEXEC: i32 = 0o40000;
+
+	if domain == AF_INET || domain == AF_INET6 {
+		return -crate::errno::EOPNOTSUPP;
+	}
+	if domain != AF_UNIX {
+		return -crate::errno::EAFNOSUPPORT;
+	}
+	if protocol != 0 {
+		return -crate::errno::EPROTONOSUPPORT;
+	}
+	if type_ & !(SOCK_NONBLOCK | SOCK_CLOEXEC) != SOCK_STREAM {
+		return -crate::errno::EINVAL;
+	}
+
+	crate::fd::socketpair(type_ & SOCK_NONBLOCK != 0).map_or_else(
+		|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+		|(a, b)| {
+			if type_ & SOCK_CLOEXEC != 0 {
+				let _ = set_fd_flags(a, FdFlags::FD_CLOEXEC);
+				let _ = set_fd_flags(b, FdFlags::FD_CLOEXEC);
+			}
+
+			let sv = unsafe { core::slice::from_raw_parts_mut(sv, 2) };
+			sv[0] = a;
+			sv[1] = b;
+			0
+		},
+	)
+}
+
+#[hermit_macro::system]
+pub extern "C" fn sys_epoll_create1(flags: i32) -> i32 {
+	const EPOLL_CLOEXEC: i32 = 0o2000000;
+
+	if flags & !EPOLL_CLOEXEC != 0 {
+		return -crate::errno::EINVAL;
+	}
+
+	let fd_flags = if flags & EPOLL_CLOEXEC != 0 {
+		FdFlags::FD_CLOEXEC
+	} else {
+		FdFlags::empty()
+	};
+
+	crate::fd::epoll_create1(fd_flags).unwrap_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap())
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_epoll_ctl(
+	epfd: FileDescriptor,
+	op: i32,
+	fd: FileDescriptor,
+	event: *mut EpollEvent,
+) -> i32 {
+	let Some(op) = num::FromPrimitive::from_i32(op) else {
+		return -crate::errno::EINVAL;
+	};
+
+	// the event is ignored by `EPOLL_CTL_DEL`
+	let event = if op == EpollCtl::Del {
+		EpollEvent::default()
+	} else if event.is_null() {
+		return -crate::errno::EFAULT;
+	} else {
+		unsafe { event.read_unaligned() }
+	};
+
+	crate::fd::epoll_ctl(epfd, op, fd, event)
+		.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_epoll_wait(
+	epfd: FileDescriptor,
+	events: *mut EpollEvent,
+	maxevents: i32,
+	timeout: i32,
+) -> i32 {
+	if maxevents <= 0 {
+		return -crate::errno::EINVAL;
+	}
+
+	let slice = unsafe { core::slice::from_raw_parts_mut(events, maxevents.try_into().unwrap()) };
+	let timeout = if timeout >= 0 {
+		Some(core::time::Duration::from_millis(
+			timeout.try_into().unwrap(),
+		))
+	} else {
+		None
+	};
+
+	crate::fd::epoll_wait(epfd, slice, timeout).map_or_else(
+		|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+		|v| v.try_into().unwrap(),
+	)
+}
+
 #[hermit_macro::system]
 pub extern "C" fn sys_eventfd(initval: u64, flags: i16) -> i32 {
 	if let Some(flags) = EventFlags::from_bits(flags) {
@@ -1,34 +1,55 @@
 #![allow(dead_code)]
 #![allow(nonstandard_style)]
+use alloc::boxed::Box;
 use alloc::sync::Arc;
-use core::ffi::c_void;
+use alloc::vec::Vec;
+use core::ffi::{c_void, CStr};
 use core::mem::size_of;
 use core::ops::DerefMut;
+use core::ptr;
+use core::str::FromStr;
+use core::time::Duration;
 
 #[cfg(all(any(feature = "tcp", feature = "udp"), not(feature = "newlib")))]
 use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
 use crate::errno::*;
+#[cfg(feature = "dns")]
+use crate::executor::dns;
 use crate::executor::network::{NetworkState, NIC};
+use crate::executor::{hosts, stats};
+#[cfg(feature = "icmp")]
+use crate::fd::socket::icmp;
+#[cfg(feature = "raw")]
+use crate::fd::socket::raw;
 #[cfg(feature = "tcp")]
 use crate::fd::socket::tcp;
 #[cfg(feature = "udp")]
 use crate::fd::socket::udp;
-use crate::fd::{get_object, insert_object, replace_object, ObjectInterface, SocketOption};
-use crate::syscalls::IoCtl;
+use crate::fd::{
+	get_entry, get_object, insert_entry, insert_object, FdFlags, MsgFlags, ObjectEntry,
+	ObjectInterface, OpenOption, RecvMeta, SocketOption, SocketOptionValue,
+};
+use crate::syscalls::{IoCtl, IoVec, IOV_MAX};
+pub use crate::syscalls::{AF_INET, AF_INET6, AF_UNIX, AF_UNSPEC};
+use crate::time::timeval;
 
-pub const AF_INET: i32 = 0;
-pub const AF_INET6: i32 = 1;
 pub const IPPROTO_IP: i32 = 0;
+pub const IPPROTO_ICMP: i32 = 1;
+pub const IPPROTO_ICMPV6: i32 = 58;
 pub const IPPROTO_IPV6: i32 = 41;
 pub const IPPROTO_TCP: i32 = 6;
 pub const IPPROTO_UDP: i32 = 17;
 pub const IPV6_ADD_MEMBERSHIP: i32 = 12;
 pub const IPV6_DROP_MEMBERSHIP: i32 = 13;
 pub const IPV6_MULTICAST_LOOP: i32 = 19;
+pub const IPV6_UNICAST_HOPS: i32 = 16;
+pub const IPV6_RECVPKTINFO: i32 = 49;
+pub const IPV6_PKTINFO: i32 = 50;
 pub const IPV6_V6ONLY: i32 = 27;
 pub const IP_TOS: i32 = 1;
 pub const IP_TTL: i32 = 2;
+pub const IP_PKTINFO: i32 = 8;
 pub const IP_MULTICAST_TTL: i32 = 5;
 pub const IP_MULTICAST_LOOP: i32 = 7;
 pub const IP_ADD_MEMBERSHIP: i32 = 3;
@@ -44,7 +65,15 @@ pub const SO_SNDTIMEO: i32 = 0x1005;
 pub const SO_RCVTIMEO: i32 = 0x1006;
 pub const SO_ERROR: i32 = 0x1007;
 pub const TCP_NODELAY: i32 = 1;
-pub const MSG_PEEK: i32 = 1;
+pub const MSG_PEEK: i32 = 0x01;
+pub const MSG_CTRUNC: i32 = 0x08;
+pub const MSG_TRUNC: i32 = 0x20;
+pub const MSG_DONTWAIT: i32 = 0x40;
+pub const MSG_WAITALL: i32 = 0x100;
+pub const AI_PASSIVE: i32 = 0x01;
+pub const AI_CANONNAME: i32 = 0x02;
+pub const AI_NUMERICHOST: i32 = 0x04;
+pub const AI_NUMERICSERV: i32 = 0x08;
 pub const EAI_NONAME: i32 = -2200;
 pub const EAI_SERVICE: i32 = -2201;
 pub const EAI_FAIL: i32 = -2202;
@@ -62,6 +91,7 @@ bitflags! {
 	pub struct SockType: i32 {
 		const SOCK_DGRAM = 2;
 		const SOCK_STREAM = 1;
+		const SOCK_RAW = 3;
 		const SOCK_NONBLOCK = 0o4000;
 		const SOCK_CLOEXEC = 0o40000;
 	}
@@ -249,6 +279,22 @@ pub struct addrinfo {
 	pub ai_next: *mut addrinfo,
 }
 
+/// Element of the list returned by `sys_getaddrinfo`, which also holds
+/// the socket address. `info` has to be the first field to cast between
+/// both types.
+#[repr(C)]
+struct AddrInfoNode {
+	info: addrinfo,
+	addr: AddrInfoSockAddr,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+union AddrInfoSockAddr {
+	v4: sockaddr_in,
+	v6: sockaddr_in6,
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct linger {
@@ -256,6 +302,76 @@ pub struct linger {
 	pub l_linger: i32,
 }
 
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct msghdr {
+	pub msg_name: *mut c_void,
+	pub msg_namelen: socklen_t,
+	pub msg_iov: *mut IoVec,
+	pub msg_iovlen: usize,
+	pub msg_control: *mut c_void,
+	pub msg_controllen: usize,
+	pub msg_flags: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct cmsghdr {
+	pub cmsg_len: usize,
+	pub cmsg_level: i32,
+	pub cmsg_type: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct in_pktinfo {
+	pub ipi_ifindex: i32,
+	pub ipi_spec_dst: in_addr,
+	pub ipi_addr: in_addr,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct in6_pktinfo {
+	pub ipi6_addr: in6_addr,
+	pub ipi6_ifindex: u32,
+}
+
+/// Maximum length of an interface name including the terminating zero
+pub const IFNAMSIZ: usize = 16;
+
+/// Name, MTU and counters of a network interface
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct if_stats {
+	pub ifs_name: [u8; IFNAMSIZ],
+	pub ifs_mtu: u32,
+	pub ifs_rx_packets: u64,
+	pub ifs_rx_bytes: u64,
+	pub ifs_rx_errors: u64,
+	pub ifs_rx_dropped: u64,
+	pub ifs_tx_packets: u64,
+	pub ifs_tx_bytes: u64,
+	pub ifs_tx_errors: u64,
+	pub ifs_tx_dropped: u64,
+}
+
+/// Inserts the new `socket` with the flags `SOCK_NONBLOCK` and
+/// `SOCK_CLOEXEC` of `type_` and returns its file descriptor
+fn insert_socket(socket: Arc<dyn ObjectInterface>, type_: SockType) -> i32 {
+	if type_.contains(SockType::SOCK_NONBLOCK) {
+		socket.ioctl(IoCtl::NonBlocking, true).unwrap();
+	}
+	let flags = if type_.contains(SockType::SOCK_CLOEXEC) {
+		FdFlags::FD_CLOEXEC
+	} else {
+		FdFlags::empty()
+	};
+
+	insert_entry(ObjectEntry::with_flags(socket, OpenOption::empty(), flags))
+		.expect("FD is already used")
+}
+
 #[hermit_macro::system]
 pub extern "C" fn sys_socket(domain: i32, type_: SockType, protocol: i32) -> i32 {
 	debug!(
@@ -263,43 +379,73 @@ pub extern "C" fn sys_socket(domain: i32, type_: SockType, protocol: i32) -> i32
 		domain, type_, protocol
 	);
 
-	if (domain != AF_INET && domain != AF_INET6)
-		|| !type_.intersects(SockType::SOCK_STREAM | SockType::SOCK_DGRAM)
-		|| protocol != 0
-	{
+	// `SOCK_RAW` consists of the bits of `SOCK_STREAM` and `SOCK_DGRAM`
+	let socktype = type_
+		.difference(SockType::SOCK_NONBLOCK | SockType::SOCK_CLOEXEC)
+		.bits();
+
+	if domain != AF_INET && domain != AF_INET6 {
 		-EINVAL
 	} else {
 		let mut guard = NIC.lock();
 
 		if let NetworkState::Initialized(nic) = guard.deref_mut() {
-			#[cfg(feature = "udp")]
-			if type_.contains(SockType::SOCK_DGRAM) {
-				let handle = nic.create_udp_handle().unwrap();
+			#[cfg(feature = "icmp")]
+			if socktype == SockType::SOCK_DGRAM.bits()
+				&& ((domain == AF_INET && protocol == IPPROTO_ICMP)
+					|| (domain == AF_INET6 && protocol == IPPROTO_ICMPV6))
+			{
+				let handle = nic.create_icmp_handle().unwrap();
 				drop(guard);
-				let socket = udp::Socket::new(handle);
+				let socket = icmp::Socket::new(handle);
+
+				return insert_socket(Arc::new(socket), type_);
+			}
+
+			#[cfg(feature = "raw")]
+			if socktype == SockType::SOCK_RAW.bits() {
+				use smoltcp::wire::{IpProtocol, IpVersion};
 
-				if type_.contains(SockType::SOCK_NONBLOCK) {
-					socket.ioctl(IoCtl::NonBlocking, true).unwrap();
+				let Ok(protocol) = u8::try_from(protocol) else {
+					return -EINVAL;
+				};
+				let version = if domain == AF_INET6 {
+					IpVersion::Ipv6
+				} else {
+					IpVersion::Ipv4
+				};
+				let protocol = IpProtocol::from(protocol);
+				let handle = nic.create_raw_handle(version, protocol).unwrap();
+				drop(guard);
+				let socket = raw::Socket::new(handle, version, protocol);
+
+				return insert_socket(Arc::new(socket), type_);
+			}
+
+			#[cfg(feature = "udp")]
+			if socktype == SockType::SOCK_DGRAM.bits() {
+				if protocol != 0 && protocol != IPPROTO_UDP {
+					return -EPROTONOSUPPORT;
 				}
 
-				let fd = insert_object(Arc::new(socket)).expect("FD is already used");
+				let handle = nic.create_udp_handle().unwrap();
+				drop(guard);
+				let socket = udp::Socket::new(handle);
 
-				return fd;
+				return insert_socket(Arc::new(socket), type_);
 			}
 
 			#[cfg(feature = "tcp")]
-			if type_.contains(SockType::SOCK_STREAM) {
+			if socktype == SockType::SOCK_STREAM.bits() {
+				if protocol != 0 && protocol != IPPROTO_TCP {
+					return -EPROTONOSUPPORT;
+				}
+
 				let handle = nic.create_tcp_handle().unwrap();
 				drop(guard);
 				let socket = tcp::Socket::new(handle);
 
-				if type_.contains(SockType::SOCK_NONBLOCK) {
-					socket.ioctl(IoCtl::NonBlocking, true).unwrap();
-				}
-
-				let fd = insert_object(Arc::new(socket)).expect("FD is already used");
-
-				return fd;
+				return insert_socket(Arc::new(socket), type_);
 			}
 
 			-EINVAL
@@ -311,16 +457,17 @@ pub extern "C" fn sys_socket(domain: i32, type_: SockType, protocol: i32) -> i32
 
 #[hermit_macro::system]
 pub unsafe extern "C" fn sys_accept(fd: i32, addr: *mut sockaddr, addrlen: *mut socklen_t) -> i32 {
-	let obj = get_object(fd);
-	obj.map_or_else(
+	let entry = get_entry(fd);
+	entry.map_or_else(
 		|e| -num::ToPrimitive::to_i32(&e).unwrap(),
 		|v| {
-			(*v).accept().map_or_else(
+			v.object.accept().map_err(|e| v.map_timeout(e)).map_or_else(
 				|e| -num::ToPrimitive::to_i32(&e).unwrap(),
-				|endpoint| {
-					let new_obj = dyn_clone::clone_box(&*v);
-					replace_object(fd, Arc::from(new_obj)).unwrap();
-					let new_fd = insert_object(v).unwrap();
+				|(new_obj, endpoint)| {
+					let new_fd = match insert_object(new_obj) {
+						Ok(new_fd) => new_fd,
+						Err(e) => return -num::ToPrimitive::to_i32(&e).unwrap(),
+					};
 
 					if !addr.is_null() && !addrlen.is_null() {
 						let addrlen = unsafe { &mut *addrlen };
@@ -446,6 +593,107 @@ pub unsafe extern "C" fn sys_getsockname(
 	)
 }
 
+/// Returns the socket option, which is selected by `level` and `optname`
+fn socket_option(level: i32, optname: i32) -> Option<SocketOption> {
+	match (level, optname) {
+		(IPPROTO_TCP, TCP_NODELAY) => Some(SocketOption::TcpNoDelay),
+		(SOL_SOCKET, SO_KEEPALIVE) => Some(SocketOption::KeepAlive),
+		(SOL_SOCKET, SO_LINGER) => Some(SocketOption::Linger),
+		(SOL_SOCKET, SO_RCVBUF) => Some(SocketOption::RecvBufferSize),
+		(SOL_SOCKET, SO_SNDBUF) => Some(SocketOption::SendBufferSize),
+		(SOL_SOCKET, SO_RCVTIMEO) => Some(SocketOption::RecvTimeout),
+		(SOL_SOCKET, SO_SNDTIMEO) => Some(SocketOption::SendTimeout),
+		(SOL_SOCKET, SO_ERROR) => Some(SocketOption::Error),
+		(IPPROTO_IP, IP_TTL) | (IPPROTO_IPV6, IPV6_UNICAST_HOPS) => Some(SocketOption::HopLimit),
+		(IPPROTO_IP, IP_PKTINFO) | (IPPROTO_IPV6, IPV6_RECVPKTINFO) => {
+			Some(SocketOption::RecvPacketInfo)
+		}
+		_ => None,
+	}
+}
+
+/// Decodes the value of `setsockopt`, whose layout depends on the option
+unsafe fn read_option_value(
+	opt: SocketOption,
+	optval: *const c_void,
+	optlen: socklen_t,
+) -> Result<SocketOptionValue, i32> {
+	let optlen = optlen as usize;
+
+	match opt {
+		SocketOption::Linger => {
+			if optlen < size_of::<linger>() {
+				return Err(EINVAL);
+			}
+
+			let linger = unsafe { ptr::read_unaligned(optval as *const linger) };
+			Ok(SocketOptionValue::Duration((linger.l_onoff != 0).then(
+				|| Duration::from_secs(linger.l_linger.max(0).try_into().unwrap()),
+			)))
+		}
+		SocketOption::RecvTimeout | SocketOption::SendTimeout => {
+			if optlen < size_of::<timeval>() {
+				return Err(EINVAL);
+			}
+
+			let timeout = unsafe { ptr::read_unaligned(optval as *const timeval) };
+			let usec = timeout.into_usec().ok_or(EDOM)?;
+			// a timeout of zero blocks forever
+			Ok(SocketOptionValue::Duration(
+				(usec > 0).then(|| Duration::from_micros(usec)),
+			))
+		}
+		_ => {
+			if optlen < size_of::<i32>() {
+				return Err(EINVAL);
+			}
+
+			let value = unsafe { ptr::read_unaligned(optval as *const i32) };
+			match opt {
+				SocketOption::TcpNoDelay
+				| SocketOption::KeepAlive
+				| SocketOption::RecvPacketInfo => Ok(SocketOptionValue::Bool(value != 0)),
+				_ => Ok(SocketOptionValue::Int(value)),
+			}
+		}
+	}
+}
+
+/// Copies the value of `getsockopt` to the user and updates its length
+unsafe fn write_option_value<T>(value: T, optval: *mut c_void, optlen: *mut socklen_t) -> i32 {
+	let len = unsafe { *optlen } as usize;
+	if len < size_of::<T>() {
+		return -EINVAL;
+	}
+
+	unsafe {
+		ptr::write_unaligned(optval as *mut T, value);
+		*optlen = size_of::<T>().try_into().unwrap();
+	}
+
+	0
+}
+
+/// Joins or leaves a multicast group. The membership belongs to the
+/// interface and isn't dropped, if the socket is closed.
+#[cfg(feature = "udp")]
+fn set_membership(fd: i32, addr: IpAddress, join: bool) -> i32 {
+	use crate::fd::IoError;
+
+	let result = get_object(fd).and_then(|_| {
+		let mut guard = NIC.lock();
+		let nic = guard.as_nic_mut().map_err(|_| IoError::EIO)?;
+
+		if join {
+			nic.join_multicast_group(addr)
+		} else {
+			nic.leave_multicast_group(addr)
+		}
+	});
+
+	result.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+}
+
 #[hermit_macro::system]
 pub unsafe extern "C" fn sys_setsockopt(
 	fd: i32,
@@ -459,28 +707,57 @@ pub unsafe extern "C" fn sys_setsockopt(
 		fd, level, optname
 	);
 
-	if level == IPPROTO_TCP
-		&& optname == TCP_NODELAY
-		&& optlen == size_of::<i32>().try_into().unwrap()
-	{
-		if optval.is_null() {
-			return -crate::errno::EINVAL;
+	if optval.is_null() {
+		return -EINVAL;
+	}
+
+	match (level, optname) {
+		#[cfg(feature = "udp")]
+		(IPPROTO_IP, IP_ADD_MEMBERSHIP | IP_DROP_MEMBERSHIP) => {
+			if (optlen as usize) < size_of::<ip_mreq>() {
+				return -EINVAL;
+			}
+
+			let mreq = unsafe { ptr::read_unaligned(optval as *const ip_mreq) };
+			let addr = smoltcp::wire::Ipv4Address::from_bytes(&mreq.imr_multiaddr.s_addr);
+			return set_membership(fd, addr.into(), optname == IP_ADD_MEMBERSHIP);
 		}
+		#[cfg(feature = "udp")]
+		(IPPROTO_IPV6, IPV6_ADD_MEMBERSHIP | IPV6_DROP_MEMBERSHIP) => {
+			if (optlen as usize) < size_of::<ipv6_mreq>() {
+				return -EINVAL;
+			}
 
-		let value = unsafe { *(optval as *const i32) };
-		let obj = get_object(fd);
-		obj.map_or_else(
-			|e| -num::ToPrimitive::to_i32(&e).unwrap(),
-			|v| {
-				(*v).setsockopt(SocketOption::TcpNoDelay, value != 0)
-					.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
-			},
-		)
-	} else if level == SOL_SOCKET && optname == SO_REUSEADDR {
-		0
-	} else {
-		-crate::errno::EINVAL
+			let mreq = unsafe { ptr::read_unaligned(optval as *const ipv6_mreq) };
+			let addr = smoltcp::wire::Ipv6Address::from_bytes(&mreq.ipv6mr_multiaddr.s6_addr);
+			return set_membership(fd, addr.into(), optname == IPV6_ADD_MEMBERSHIP);
+		}
+		// smoltcp neither restricts broadcasts nor the reuse of addresses and
+		// doesn't loop back multicast datagrams, these options have no effect
+		(SOL_SOCKET, SO_REUSEADDR | SO_BROADCAST)
+		| (IPPROTO_IP, IP_MULTICAST_TTL | IP_MULTICAST_LOOP)
+		| (IPPROTO_IPV6, IPV6_MULTICAST_LOOP) => {
+			return get_object(fd).map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0);
+		}
+		_ => {}
 	}
+
+	let Some(opt) = socket_option(level, optname) else {
+		return -ENOPROTOOPT;
+	};
+	let value = match unsafe { read_option_value(opt, optval, optlen) } {
+		Ok(value) => value,
+		Err(e) => return -e,
+	};
+
+	let obj = get_object(fd);
+	obj.map_or_else(
+		|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+		|v| {
+			(*v).setsockopt(opt, value)
+				.map_or_else(|e| -num::ToPrimitive::to_i32(&e).unwrap(), |_| 0)
+		},
+	)
 }
 
 #[hermit_macro::system]
@@ -496,35 +773,45 @@ pub unsafe extern "C" fn sys_getsockopt(
 		fd, level, optname
 	);
 
-	if level == IPPROTO_TCP && optname == TCP_NODELAY {
-		if optval.is_null() || optlen.is_null() {
-			return -crate::errno::EINVAL;
-		}
+	if optval.is_null() || optlen.is_null() {
+		return -EINVAL;
+	}
 
-		let optval = unsafe { &mut *(optval as *mut i32) };
-		let optlen = unsafe { &mut *(optlen as *mut socklen_t) };
-		let obj = get_object(fd);
-		obj.map_or_else(
-			|e| -num::ToPrimitive::to_i32(&e).unwrap(),
-			|v| {
-				(*v).getsockopt(SocketOption::TcpNoDelay).map_or_else(
-					|e| -num::ToPrimitive::to_i32(&e).unwrap(),
-					|value| {
-						if value {
-							*optval = 1;
-						} else {
-							*optval = 0;
-						}
-						*optlen = core::mem::size_of::<i32>().try_into().unwrap();
+	let Some(opt) = socket_option(level, optname) else {
+		return -ENOPROTOOPT;
+	};
 
-						0
+	let obj = get_object(fd);
+	obj.map_or_else(
+		|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+		|v| {
+			(*v).getsockopt(opt).map_or_else(
+				|e| -num::ToPrimitive::to_i32(&e).unwrap(),
+				|value| match value {
+					SocketOptionValue::Bool(value) => unsafe {
+						write_option_value(i32::from(value), optval, optlen)
 					},
-				)
-			},
-		)
-	} else {
-		-crate::errno::EINVAL
-	}
+					SocketOptionValue::Int(value) => unsafe {
+						write_option_value(value, optval, optlen)
+					},
+					SocketOptionValue::Duration(time) if opt == SocketOption::Linger => {
+						let value = linger {
+							l_onoff: time.is_some().into(),
+							l_linger: time
+								.map_or(0, |d| d.as_secs().try_into().unwrap_or(i32::MAX)),
+						};
+						unsafe { write_option_value(value, optval, optlen) }
+					}
+					SocketOptionValue::Duration(timeout) => {
+						let timeout = timeval::from_usec(
+							timeout.map_or(0, |d| d.as_micros().try_into().unwrap_or(u64::MAX)),
+						);
+						unsafe { write_option_value(timeout, optval, optlen) }
+					}
+				},
+			)
+		},
+	)
 }
 
 #[hermit_macro::system]
@@ -571,22 +858,206 @@ pub unsafe extern "C" fn sys_getpeername(
 	)
 }
 
+fn matches_family(addr: &IpAddress, family: i32) -> bool {
+	match addr {
+		IpAddress::Ipv4(_) => family != AF_INET6,
+		IpAddress::Ipv6(_) => family != AF_INET,
+	}
+}
+
+/// Returns the addresses of the host `name`, which is either a numeric
+/// address, an entry of the hosts table or resolved by the name servers.
+fn lookup_host(name: &str, flags: i32, family: i32) -> Result<Vec<IpAddress>, i32> {
+	if let Ok(addr) = IpAddress::from_str(name) {
+		return Ok(vec![addr]);
+	}
+	if flags & AI_NUMERICHOST != 0 {
+		return Err(EAI_NONAME);
+	}
+
+	let addrs: Vec<IpAddress> = hosts::lookup(name)
+		.into_iter()
+		.filter(|addr| matches_family(addr, family))
+		.collect();
+	if !addrs.is_empty() {
+		return Ok(addrs);
+	}
+
+	#[cfg(feature = "dns")]
+	{
+		use smoltcp::wire::DnsQueryType;
+
+		use crate::fd::IoError;
+
+		let query_types: &[DnsQueryType] = match family {
+			AF_INET => &[DnsQueryType::A],
+			AF_INET6 => &[DnsQueryType::Aaaa],
+			_ => &[DnsQueryType::A, DnsQueryType::Aaaa],
+		};
+
+		let mut addrs = Vec::new();
+		let mut error = IoError::ENOENT;
+		for query_type in query_types {
+			match crate::executor::block_on(dns::resolve(name, *query_type), None) {
+				Ok(mut v) => addrs.append(&mut v),
+				Err(e) => error = e,
+			}
+		}
+
+		if !addrs.is_empty() {
+			Ok(addrs)
+		} else if error == IoError::ENOENT {
+			Err(EAI_NONAME)
+		} else {
+			Err(EAI_FAIL)
+		}
+	}
+
+	#[cfg(not(feature = "dns"))]
+	{
+		let _ = family;
+		Err(EAI_NONAME)
+	}
+}
+
 #[hermit_macro::system]
-pub unsafe extern "C" fn sys_freeaddrinfo(_ai: *mut addrinfo) {}
+pub unsafe extern "C" fn sys_freeaddrinfo(ai: *mut addrinfo) {
+	let mut ai = ai;
+	while !ai.is_null() {
+		let node = unsafe { Box::from_raw(ai.cast::<AddrInfoNode>()) };
+		ai = node.info.ai_next;
+	}
+}
 
 #[hermit_macro::system]
 pub unsafe extern "C" fn sys_getaddrinfo(
-	_nodename: *const u8,
-	_servname: *const u8,
-	_hints: *const addrinfo,
-	_res: *mut *mut addrinfo,
+	nodename: *const u8,
+	servname: *const u8,
+	hints: *const addrinfo,
+	res: *mut *mut addrinfo,
 ) -> i32 {
-	-EINVAL
+	if res.is_null() || (nodename.is_null() && servname.is_null()) {
+		return EAI_NONAME;
+	}
+
+	let hints = unsafe { hints.as_ref() };
+	let flags = hints.map_or(0, |h| h.ai_flags);
+	let family = hints.map_or(AF_UNSPEC, |h| h.ai_family);
+	if family != AF_UNSPEC && family != AF_INET && family != AF_INET6 {
+		return EAI_FAMILY;
+	}
+
+	// a socket type of zero returns an entry for each supported type
+	let socktypes: &[(i32, i32)] = match hints.map_or(0, |h| h.ai_socktype) {
+		0 => &[
+			(SockType::SOCK_STREAM.bits(), IPPROTO_TCP),
+			(SockType::SOCK_DGRAM.bits(), IPPROTO_UDP),
+		],
+		t if t == SockType::SOCK_STREAM.bits() => &[(t, IPPROTO_TCP)],
+		t if t == SockType::SOCK_DGRAM.bits() => &[(t, IPPROTO_UDP)],
+		_ => return EAI_SERVICE,
+	};
+
+	// services are only supported as port numbers
+	let port = if servname.is_null() {
+		0
+	} else {
+		let service = unsafe { CStr::from_ptr(servname.cast()) };
+		match service.to_str().ok().and_then(|s| s.parse::<u16>().ok()) {
+			Some(port) => port,
+			None => return EAI_SERVICE,
+		}
+	};
+
+	let addrs = if nodename.is_null() {
+		if flags & AI_PASSIVE != 0 {
+			vec![
+				IpAddress::Ipv4(smoltcp::wire::Ipv4Address::UNSPECIFIED),
+				IpAddress::Ipv6(smoltcp::wire::Ipv6Address::UNSPECIFIED),
+			]
+		} else {
+			vec![
+				IpAddress::Ipv4(smoltcp::wire::Ipv4Address::new(127, 0, 0, 1)),
+				IpAddress::Ipv6(smoltcp::wire::Ipv6Address::LOOPBACK),
+			]
+		}
+	} else {
+		let Ok(name) = unsafe { CStr::from_ptr(nodename.cast()) }.to_str() else {
+			return EAI_NONAME;
+		};
+		match lookup_host(name, flags, family) {
+			Ok(addrs) => addrs,
+			Err(e) => return e,
+		}
+	};
+
+	let mut head: *mut addrinfo = ptr::null_mut();
+	let mut tail = &mut head;
+	for addr in addrs
+		.into_iter()
+		.filter(|addr| matches_family(addr, family))
+	{
+		let endpoint = IpEndpoint::new(addr, port);
+		let (ai_family, ai_addrlen, sockaddr) = match addr {
+			IpAddress::Ipv4(_) => (
+				AF_INET,
+				size_of::<sockaddr_in>(),
+				AddrInfoSockAddr {
+					v4: sockaddr_in::from(endpoint),
+				},
+			),
+			IpAddress::Ipv6(_) => (
+				AF_INET6,
+				size_of::<sockaddr_in6>(),
+				AddrInfoSockAddr {
+					v6: sockaddr_in6::from(endpoint),
+				},
+			),
+		};
+
+		for (socktype, protocol) in socktypes {
+			let node = Box::into_raw(Box::new(AddrInfoNode {
+				info: addrinfo {
+					ai_flags: flags,
+					ai_family,
+					ai_socktype: *socktype,
+					ai_protocol: *protocol,
+					ai_addrlen: ai_addrlen.try_into().unwrap(),
+					ai_addr: ptr::null_mut(),
+					// canonical names aren't supported
+					ai_canonname: ptr::null_mut(),
+					ai_next: ptr::null_mut(),
+				},
+				addr: sockaddr,
+			}));
+			unsafe {
+				(*node).info.ai_addr = ptr::addr_of_mut!((*node).addr).cast();
+			}
+
+			*tail = node.cast();
+			tail = unsafe { &mut (*node).info.ai_next };
+		}
+	}
+
+	if head.is_null() {
+		// no address of the requested family
+		return EAI_NONAME;
+	}
+
+	unsafe {
+		*res = head;
+	}
+
+	0
 }
 
 #[hermit_macro::system]
-pub unsafe extern "C" fn sys_send(s: i32, mem: *const c_void, len: usize, _flags: i32) -> isize {
-	unsafe { super::write(s, mem.cast(), len) }
+pub unsafe extern "C" fn sys_send(s: i32, mem: *const c_void, len: usize, flags: i32) -> isize {
+	let slice = unsafe { core::slice::from_raw_parts(mem.cast::<u8>(), len) };
+	crate::fd::sendmsg(s, &[slice], None, None, MsgFlags::from_bits_truncate(flags)).map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|v| v.try_into().unwrap(),
+	)
 }
 
 #[hermit_macro::system]
@@ -601,17 +1072,143 @@ pub extern "C" fn sys_shutdown_socket(fd: i32, how: i32) -> i32 {
 	)
 }
 
-#[hermit_macro::system]
-pub unsafe extern "C" fn sys_recv(fd: i32, buf: *mut u8, len: usize, flags: i32) -> isize {
-	if flags == 0 {
-		let slice = unsafe { core::slice::from_raw_parts_mut(buf, len) };
-		crate::fd::read(fd, slice).map_or_else(
-			|e| -num::ToPrimitive::to_isize(&e).unwrap(),
-			|v| v.try_into().unwrap(),
-		)
+/// Converts the socket address of the user into an endpoint
+unsafe fn read_sockaddr(addr: *const sockaddr, addrlen: socklen_t) -> Result<IpEndpoint, i32> {
+	if addrlen == size_of::<sockaddr_in>().try_into().unwrap() {
+		Ok(IpEndpoint::from(unsafe {
+			ptr::read_unaligned(addr as *const sockaddr_in)
+		}))
+	} else if addrlen == size_of::<sockaddr_in6>().try_into().unwrap() {
+		Ok(IpEndpoint::from(unsafe {
+			ptr::read_unaligned(addr as *const sockaddr_in6)
+		}))
 	} else {
-		(-crate::errno::EINVAL).try_into().unwrap()
+		Err(EINVAL)
+	}
+}
+
+/// Copies an endpoint into the socket address of the user
+unsafe fn write_sockaddr(
+	endpoint: IpEndpoint,
+	addr: *mut sockaddr,
+	addrlen: &mut socklen_t,
+) -> Result<(), i32> {
+	match endpoint.addr {
+		IpAddress::Ipv4(_) => {
+			if *addrlen < size_of::<sockaddr_in>().try_into().unwrap() {
+				return Err(EINVAL);
+			}
+
+			unsafe {
+				ptr::write_unaligned(addr as *mut sockaddr_in, sockaddr_in::from(endpoint));
+			}
+			*addrlen = size_of::<sockaddr_in>().try_into().unwrap();
+		}
+		IpAddress::Ipv6(_) => {
+			if *addrlen < size_of::<sockaddr_in6>().try_into().unwrap() {
+				return Err(EINVAL);
+			}
+
+			unsafe {
+				ptr::write_unaligned(addr as *mut sockaddr_in6, sockaddr_in6::from(endpoint));
+			}
+			*addrlen = size_of::<sockaddr_in6>().try_into().unwrap();
+		}
 	}
+
+	Ok(())
+}
+
+/// Returns the result of a receive operation. With `MSG_TRUNC`, this is
+/// the real length of the datagram.
+fn received_len(meta: &RecvMeta, flags: MsgFlags) -> isize {
+	if flags.contains(MsgFlags::MSG_TRUNC) {
+		meta.datagram_len.try_into().unwrap()
+	} else {
+		meta.len.try_into().unwrap()
+	}
+}
+
+/// Rounds up the length of a control message to the alignment of `cmsghdr`
+const fn cmsg_align(len: usize) -> usize {
+	(len + size_of::<usize>() - 1) & !(size_of::<usize>() - 1)
+}
+
+/// Stores a single control message in the control buffer of `msg`.
+/// If the buffer is too small, the message is dropped and `MSG_CTRUNC` is set.
+unsafe fn put_cmsg<T>(msg: &mut msghdr, level: i32, type_: i32, value: T) {
+	let header_len = cmsg_align(size_of::<cmsghdr>());
+	let space = header_len + cmsg_align(size_of::<T>());
+
+	if msg.msg_control.is_null() || msg.msg_controllen < space {
+		msg.msg_flags |= MSG_CTRUNC;
+		msg.msg_controllen = 0;
+		return;
+	}
+
+	let header = cmsghdr {
+		cmsg_len: header_len + size_of::<T>(),
+		cmsg_level: level,
+		cmsg_type: type_,
+	};
+	unsafe {
+		ptr::write_unaligned(msg.msg_control as *mut cmsghdr, header);
+		ptr::write_unaligned(
+			msg.msg_control.cast::<u8>().add(header_len).cast::<T>(),
+			value,
+		);
+	}
+	msg.msg_controllen = space;
+}
+
+/// Returns the source address, which is selected by `IP_PKTINFO` or
+/// `IPV6_PKTINFO` in the control messages of `msg`
+unsafe fn get_pktinfo(msg: &msghdr) -> Result<Option<IpAddress>, i32> {
+	if msg.msg_control.is_null() {
+		return Ok(None);
+	}
+
+	let header_len = cmsg_align(size_of::<cmsghdr>());
+	let control = msg.msg_control.cast::<u8>();
+	let mut local_addr = None;
+	let mut offset = 0;
+
+	while offset + size_of::<cmsghdr>() <= msg.msg_controllen {
+		let header = unsafe { ptr::read_unaligned(control.add(offset) as *const cmsghdr) };
+		if header.cmsg_len < size_of::<cmsghdr>() || offset + header.cmsg_len > msg.msg_controllen {
+			return Err(EINVAL);
+		}
+
+		let data = unsafe { control.add(offset + header_len) };
+		let data_len = header.cmsg_len.saturating_sub(header_len);
+		match (header.cmsg_level, header.cmsg_type) {
+			(IPPROTO_IP, IP_PKTINFO) if data_len >= size_of::<in_pktinfo>() => {
+				let info = unsafe { ptr::read_unaligned(data as *const in_pktinfo) };
+				let addr = smoltcp::wire::Ipv4Address::from_bytes(&info.ipi_spec_dst.s_addr);
+				local_addr = (!addr.is_unspecified()).then_some(IpAddress::Ipv4(addr));
+			}
+			(IPPROTO_IPV6, IPV6_PKTINFO) if data_len >= size_of::<in6_pktinfo>() => {
+				let info = unsafe { ptr::read_unaligned(data as *const in6_pktinfo) };
+				let addr = smoltcp::wire::Ipv6Address::from_bytes(&info.ipi6_addr.s6_addr);
+				local_addr = (!addr.is_unspecified()).then_some(IpAddress::Ipv6(addr));
+			}
+			_ => {}
+		}
+
+		offset += cmsg_align(header.cmsg_len);
+	}
+
+	Ok(local_addr)
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_recv(fd: i32, buf: *mut u8, len: usize, flags: i32) -> isize {
+	let slice = unsafe { core::slice::from_raw_parts_mut(buf, len) };
+	let flags = MsgFlags::from_bits_truncate(flags);
+	crate::fd::recvmsg(fd, &mut [slice], flags).map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|meta| received_len(&meta, flags),
+	)
 }
 
 #[hermit_macro::system]
@@ -619,28 +1216,30 @@ pub unsafe extern "C" fn sys_sendto(
 	fd: i32,
 	buf: *const u8,
 	len: usize,
-	_flags: i32,
+	flags: i32,
 	addr: *const sockaddr,
 	addr_len: socklen_t,
 ) -> isize {
-	let endpoint = if addr_len == size_of::<sockaddr_in>().try_into().unwrap() {
-		IpEndpoint::from(unsafe { *(addr as *const sockaddr_in) })
-	} else if addr_len == size_of::<sockaddr_in6>().try_into().unwrap() {
-		IpEndpoint::from(unsafe { *(addr as *const sockaddr_in6) })
+	let endpoint = if addr.is_null() {
+		None
 	} else {
-		return (-crate::errno::EINVAL).try_into().unwrap();
+		match unsafe { read_sockaddr(addr, addr_len) } {
+			Ok(endpoint) => Some(endpoint),
+			Err(e) => return (-e).try_into().unwrap(),
+		}
 	};
 	let slice = unsafe { core::slice::from_raw_parts(buf, len) };
-	let obj = get_object(fd);
 
-	obj.map_or_else(
+	crate::fd::sendmsg(
+		fd,
+		&[slice],
+		endpoint,
+		None,
+		MsgFlags::from_bits_truncate(flags),
+	)
+	.map_or_else(
 		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
-		|v| {
-			(*v).sendto(slice, endpoint).map_or_else(
-				|e| -num::ToPrimitive::to_isize(&e).unwrap(),
-				|v| v.try_into().unwrap(),
-			)
-		},
+		|v| v.try_into().unwrap(),
 	)
 }
 
@@ -649,46 +1248,210 @@ pub unsafe extern "C" fn sys_recvfrom(
 	fd: i32,
 	buf: *mut u8,
 	len: usize,
-	_flags: i32,
+	flags: i32,
 	addr: *mut sockaddr,
 	addrlen: *mut socklen_t,
 ) -> isize {
 	let slice = unsafe { core::slice::from_raw_parts_mut(buf, len) };
-	let obj = get_object(fd);
-	obj.map_or_else(
-		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
-		|v| {
-			(*v).recvfrom(slice).map_or_else(
-				|e| -num::ToPrimitive::to_isize(&e).unwrap(),
-				|(len, endpoint)| {
-					if !addr.is_null() && !addrlen.is_null() {
-						let addrlen = unsafe { &mut *addrlen };
+	let flags = MsgFlags::from_bits_truncate(flags);
 
-						match endpoint.addr {
-							IpAddress::Ipv4(_) => {
-								if *addrlen >= size_of::<sockaddr_in>().try_into().unwrap() {
-									let addr = unsafe { &mut *(addr as *mut sockaddr_in) };
-									*addr = sockaddr_in::from(endpoint);
-									*addrlen = size_of::<sockaddr_in>().try_into().unwrap();
-								} else {
-									return (-crate::errno::EINVAL).try_into().unwrap();
-								}
-							}
-							IpAddress::Ipv6(_) => {
-								if *addrlen >= size_of::<sockaddr_in6>().try_into().unwrap() {
-									let addr = unsafe { &mut *(addr as *mut sockaddr_in6) };
-									*addr = sockaddr_in6::from(endpoint);
-									*addrlen = size_of::<sockaddr_in6>().try_into().unwrap();
-								} else {
-									return (-crate::errno::EINVAL).try_into().unwrap();
-								}
-							}
+	crate::fd::recvmsg(fd, &mut [slice], flags).map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|meta| {
+			if !addr.is_null() && !addrlen.is_null() {
+				let addrlen = unsafe { &mut *addrlen };
+
+				match meta.endpoint {
+					Some(endpoint) => {
+						if let Err(e) = unsafe { write_sockaddr(endpoint, addr, addrlen) } {
+							return (-e).try_into().unwrap();
 						}
 					}
+					None => *addrlen = 0,
+				}
+			}
 
-					len.try_into().unwrap()
-				},
-			)
+			received_len(&meta, flags)
 		},
 	)
 }
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_sendmsg(fd: i32, msg: *const msghdr, flags: i32) -> isize {
+	if msg.is_null() {
+		return (-EINVAL).try_into().unwrap();
+	}
+
+	let msg = unsafe { &*msg };
+	if msg.msg_iovlen > IOV_MAX.try_into().unwrap() {
+		return (-EINVAL).try_into().unwrap();
+	}
+
+	let endpoint = if msg.msg_name.is_null() || msg.msg_namelen == 0 {
+		None
+	} else {
+		match unsafe { read_sockaddr(msg.msg_name.cast(), msg.msg_namelen) } {
+			Ok(endpoint) => Some(endpoint),
+			Err(e) => return (-e).try_into().unwrap(),
+		}
+	};
+	let local_addr = match unsafe { get_pktinfo(msg) } {
+		Ok(addr) => addr,
+		Err(e) => return (-e).try_into().unwrap(),
+	};
+
+	let iov: &[IoVec] = if msg.msg_iovlen == 0 {
+		&[]
+	} else {
+		unsafe { core::slice::from_raw_parts(msg.msg_iov, msg.msg_iovlen) }
+	};
+	let bufs: Vec<&[u8]> = iov
+		.iter()
+		.filter(|v| v.iov_len > 0)
+		.map(|v| unsafe { core::slice::from_raw_parts(v.iov_base, v.iov_len) })
+		.collect();
+
+	crate::fd::sendmsg(
+		fd,
+		&bufs,
+		endpoint,
+		local_addr,
+		MsgFlags::from_bits_truncate(flags),
+	)
+	.map_or_else(
+		|e| -num::ToPrimitive::to_isize(&e).unwrap(),
+		|v| v.try_into().unwrap(),
+	)
+}
+
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_recvmsg(fd: i32, msg: *mut msghdr, flags: i32) -> isize {
+	if msg.is_null() {
+		return (-EINVAL).try_into().unwrap();
+	}
+
+	let msg = unsafe { &mut *msg };
+	if msg.msg_iovlen > IOV_MAX.try_into().unwrap() {
+		return (-EINVAL).try_into().unwrap();
+	}
+
+	let iov: &[IoVec] = if msg.msg_iovlen == 0 {
+		&[]
+	} else {
+		unsafe { core::slice::from_raw_parts(msg.msg_iov, msg.msg_iovlen) }
+	};
+	let mut bufs: Vec<&mut [u8]> = iov
+		.iter()
+		.filter(|v| v.iov_len > 0)
+		.map(|v| unsafe { core::slice::from_raw_parts_mut(v.iov_base, v.iov_len) })
+		.collect();
+	let flags = MsgFlags::from_bits_truncate(flags);
+
+	let meta = match crate::fd::recvmsg(fd, &mut bufs, flags) {
+		Ok(meta) => meta,
+		Err(e) => return -num::ToPrimitive::to_isize(&e).unwrap(),
+	};
+
+	msg.msg_flags = 0;
+	if meta.datagram_len > meta.len {
+		msg.msg_flags |= MSG_TRUNC;
+	}
+
+	if !msg.msg_name.is_null() {
+		match meta.endpoint {
+			Some(endpoint) => {
+				if let Err(e) =
+					unsafe { write_sockaddr(endpoint, msg.msg_name.cast(), &mut msg.msg_namelen) }
+				{
+					return (-e).try_into().unwrap();
+				}
+			}
+			None => msg.msg_namelen = 0,
+		}
+	}
+
+	let ifindex = meta
+		.device
+		.map_or(0, |device| u32::try_from(device).unwrap());
+	match meta.local_addr {
+		Some(IpAddress::Ipv4(addr)) => {
+			let info = in_pktinfo {
+				ipi_ifindex: ifindex.try_into().unwrap(),
+				ipi_spec_dst: in_addr { s_addr: addr.0 },
+				ipi_addr: in_addr { s_addr: addr.0 },
+			};
+			unsafe { put_cmsg(msg, IPPROTO_IP, IP_PKTINFO, info) }
+		}
+		Some(IpAddress::Ipv6(addr)) => {
+			let info = in6_pktinfo {
+				ipi6_addr: in6_addr { s6_addr: addr.0 },
+				ipi6_ifindex: ifindex,
+			};
+			unsafe { put_cmsg(msg, IPPROTO_IPV6, IPV6_PKTINFO, info) }
+		}
+		None => msg.msg_controllen = 0,
+	}
+
+	received_len(&meta, flags)
+}
+
+/// Returns the statistics of the `index`-th network interface. The
+/// interfaces are numbered from zero and the loopback interface is the
+/// last one. Returns `-ENODEV`, if the interface doesn't exist.
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_getifstats(index: u32, stats: *mut if_stats) -> i32 {
+	if stats.is_null() {
+		return -EINVAL;
+	}
+
+	let mut guard = NIC.lock();
+	let Ok(nic) = guard.as_nic_mut() else {
+		return -ENODEV;
+	};
+	let Some((name, mtu, counters)) = nic.device_stats(index.try_into().unwrap()) else {
+		return -ENODEV;
+	};
+	drop(guard);
+
+	let mut ifs_name = [0u8; IFNAMSIZ];
+	let len = name.len().min(IFNAMSIZ - 1);
+	ifs_name[..len].copy_from_slice(&name.as_bytes()[..len]);
+
+	unsafe {
+		*stats = if_stats {
+			ifs_name,
+			ifs_mtu: mtu.try_into().unwrap_or(u32::MAX),
+			ifs_rx_packets: counters.rx_packets,
+			ifs_rx_bytes: counters.rx_bytes,
+			ifs_rx_errors: counters.rx_errors,
+			ifs_rx_dropped: counters.rx_dropped,
+			ifs_tx_packets: counters.tx_packets,
+			ifs_tx_bytes: counters.tx_bytes,
+			ifs_tx_errors: counters.tx_errors,
+			ifs_tx_dropped: counters.tx_dropped,
+		};
+	}
+
+	0
+}
+
+/// Writes a report of the network interfaces, their addresses, routes and
+/// counters and of the open sockets to `buf`, which is similar to the output
+/// of `netstat`. The report isn't terminated by zero and is truncated to
+/// `len` bytes. Returns the length of the complete report.
+#[hermit_macro::system]
+pub unsafe extern "C" fn sys_netstat(buf: *mut u8, len: usize) -> isize {
+	if buf.is_null() && len > 0 {
+		return (-EINVAL).try_into().unwrap();
+	}
+
+	let report = stats::report();
+	let count = report.len().min(len);
+	if count > 0 {
+		unsafe {
+			ptr::copy_nonoverlapping(report.as_ptr(), buf, count);
+		}
+	}
+
+	report.len().try_into().unwrap()
+}